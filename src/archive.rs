@@ -0,0 +1,139 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Simple framed archive format used to export & import value logs:
+//!
+//! ```text
+//! [magic; 8]
+//! [segment count; u64] [segment id; u64]*   <- manifest
+//! ([segment id; u64] [len; u64] [data; len] [xxh3 checksum; u64])*
+//! [trailer magic; 8]
+//! ```
+
+use crate::{coding::DecodeError, fs::Fs, id::SegmentId};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+pub const ARCHIVE_MAGIC: &[u8] = &[b'V', b'L', b'O', b'G', b'A', b'R', b'C', 1];
+pub const ARCHIVE_TRAILER_MAGIC: &[u8] = b"VLOGAEND";
+
+/// Copies exactly `len` bytes, returning their checksum.
+fn copy_checksummed<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    mut len: u64,
+) -> std::io::Result<u64> {
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    let mut buf = vec![0; 64 * 1_024];
+
+    while len > 0 {
+        // NOTE: Truncation is fine because buffer is small
+        #[allow(clippy::cast_possible_truncation)]
+        let n = len.min(buf.len() as u64) as usize;
+
+        #[allow(clippy::indexing_slicing)]
+        let chunk = &mut buf[..n];
+
+        reader.read_exact(chunk)?;
+        hasher.update(chunk);
+        writer.write_all(chunk)?;
+
+        len -= n as u64;
+    }
+
+    Ok(hasher.digest())
+}
+
+/// Writes the given segments into an archive.
+pub fn write_archive<W: Write>(
+    fs: &dyn Fs,
+    writer: &mut W,
+    segments: &[(SegmentId, &Path)],
+) -> crate::Result<()> {
+    writer.write_all(ARCHIVE_MAGIC)?;
+
+    writer.write_u64::<BigEndian>(segments.len() as u64)?;
+    for (id, _) in segments {
+        writer.write_u64::<BigEndian>(*id)?;
+    }
+
+    for (id, path) in segments {
+        let mut file = fs.open(path)?;
+        let len = file.seek(std::io::SeekFrom::End(0))?;
+        file.rewind()?;
+
+        log::trace!("Exporting segment #{id} ({len} bytes)");
+
+        writer.write_u64::<BigEndian>(*id)?;
+        writer.write_u64::<BigEndian>(len)?;
+
+        let checksum = copy_checksummed(&mut file, writer, len)?;
+        writer.write_u64::<BigEndian>(checksum)?;
+    }
+
+    writer.write_all(ARCHIVE_TRAILER_MAGIC)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads an archive, writing its segments into the given folder.
+///
+/// Returns the segment IDs of the archived manifest.
+pub fn read_archive<R: Read>(
+    fs: &dyn Fs,
+    reader: &mut R,
+    segments_folder: &Path,
+) -> crate::Result<Vec<SegmentId>> {
+    let mut magic = [0; ARCHIVE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic != ARCHIVE_MAGIC {
+        return Err(crate::Error::Decode(DecodeError::InvalidHeader("Archive")));
+    }
+
+    let cnt = reader.read_u64::<BigEndian>()?;
+
+    let mut ids = vec![];
+    for _ in 0..cnt {
+        ids.push(reader.read_u64::<BigEndian>()?);
+    }
+
+    for _ in 0..cnt {
+        let id = reader.read_u64::<BigEndian>()?;
+        let len = reader.read_u64::<BigEndian>()?;
+
+        if !ids.contains(&id) {
+            return Err(crate::Error::Decode(DecodeError::InvalidHeader(
+                "ArchiveSegment",
+            )));
+        }
+
+        log::trace!("Importing segment #{id} ({len} bytes)");
+
+        let mut file = fs.create(&segments_folder.join(id.to_string()))?;
+        let checksum = copy_checksummed(reader, &mut file, len)?;
+
+        if checksum != reader.read_u64::<BigEndian>()? {
+            return Err(crate::Error::ChecksumMismatch);
+        }
+
+        file.flush()?;
+        file.sync_all()?;
+    }
+
+    let mut magic = [0; ARCHIVE_TRAILER_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic != ARCHIVE_TRAILER_MAGIC {
+        return Err(crate::Error::Decode(DecodeError::InvalidHeader(
+            "ArchiveTrailer",
+        )));
+    }
+
+    Ok(ids)
+}
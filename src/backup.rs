@@ -0,0 +1,34 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{fs::Fs, id::SegmentId};
+use std::path::{Path, PathBuf};
+
+/// Result of an incremental backup
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct BackupReport {
+    /// All segments that are part of the value log at the time of the backup
+    ///
+    /// This should be passed to the next incremental backup.
+    pub segment_ids: Vec<SegmentId>,
+
+    /// Segments that were not part of the previous backup, and their file paths
+    /// inside the value log
+    pub new_segments: Vec<(SegmentId, PathBuf)>,
+
+    /// Amount of bytes copied into the backup destination
+    pub copied_bytes: u64,
+}
+
+/// Copies a file and fsyncs the copy, returning the amount of bytes copied.
+pub fn copy_file(fs: &dyn Fs, src: &Path, dst: &Path) -> std::io::Result<u64> {
+    let mut reader = fs.open(src)?;
+    let mut writer = fs.create(dst)?;
+
+    let bytes = std::io::copy(&mut reader, &mut writer)?;
+    writer.sync_all()?;
+
+    Ok(bytes)
+}
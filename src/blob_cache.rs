@@ -2,8 +2,9 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{value::UserValue, value_log::ValueLogId, ValueHandle};
-use quick_cache::{sync::Cache, Equivalent, Weighter};
+use crate::{sync::AtomicU64, value::UserValue, value_log::ValueLogId, ValueHandle};
+use quick_cache::{sync::Cache, Equivalent, Lifecycle, Weighter};
+use std::sync::{atomic::Ordering, Arc};
 
 type Item = UserValue;
 
@@ -32,18 +33,90 @@ impl Weighter<CacheKey, Item> for BlobWeighter {
     }
 }
 
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    inserted_bytes: AtomicU64,
+    evictions: AtomicU64,
+    evicted_bytes: AtomicU64,
+}
+
+/// Counts evicted blobs
+#[derive(Clone)]
+struct EvictionCounter(Arc<Counters>);
+
+impl Lifecycle<CacheKey, Item> for EvictionCounter {
+    type RequestState = ();
+
+    fn begin_request(&self) -> Self::RequestState {}
+
+    fn on_evict(&self, (): &mut Self::RequestState, _: CacheKey, blob: Item) {
+        self.0.evictions.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .evicted_bytes
+            .fetch_add(blob.len() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Blob cache statistics, see [`ValueLog::cache_stats`](crate::ValueLog::cache_stats)
+///
+/// Counters are cumulative since the cache was created. If a cache is shared
+/// between value logs, they include the accesses of all of them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct BlobCacheStats {
+    /// Capacity in bytes
+    pub capacity: u64,
+
+    /// Bytes of cached blobs
+    pub size: u64,
+
+    /// Amount of cached blobs
+    pub len: usize,
+
+    /// Amount of lookups that found the blob
+    pub hits: u64,
+
+    /// Amount of lookups that did not find the blob
+    pub misses: u64,
+
+    /// Amount of inserted blobs
+    pub insertions: u64,
+
+    /// Bytes of inserted blobs
+    pub inserted_bytes: u64,
+
+    /// Amount of evicted blobs, including blobs that were too large to be cached
+    pub evictions: u64,
+
+    /// Bytes of evicted blobs
+    pub evicted_bytes: u64,
+}
+
 /// Blob cache, in which blobs are cached in-memory
 /// after being retrieved from disk
 ///
 /// This speeds up consecutive accesses to the same blobs, improving
 /// read performance for hot data.
+///
+/// Blobs are keyed by their value handle, and the capacity is weighed in cached bytes.
+/// By default, blobs are cached after decompression (and after reverting
+/// transforms & encryption). If a value cache is configured (see [`Config::value_cache`](crate::Config::value_cache)),
+/// which is a second [`BlobCache`], the blob cache holds blobs as stored in their segment,
+/// and the value cache holds the decompressed values.
 pub struct BlobCache {
     // NOTE: rustc_hash performed best: https://fjall-rs.github.io/post/fjall-2-1
     /// Concurrent cache implementation
-    data: Cache<CacheKey, Item, BlobWeighter, rustc_hash::FxBuildHasher>,
+    data: Cache<CacheKey, Item, BlobWeighter, rustc_hash::FxBuildHasher, EvictionCounter>,
 
     /// Capacity in bytes
     capacity: u64,
+
+    /// Access statistics
+    counters: Arc<Counters>,
 }
 
 impl std::fmt::Debug for BlobCache {
@@ -56,7 +129,7 @@ impl BlobCache {
     /// Creates a new block cache with roughly `n` bytes of capacity.
     #[must_use]
     pub fn with_capacity_bytes(bytes: u64) -> Self {
-        use quick_cache::sync::DefaultLifecycle;
+        let counters = Arc::new(Counters::default());
 
         #[allow(clippy::default_trait_access)]
         let quick_cache = Cache::with(
@@ -64,21 +137,51 @@ impl BlobCache {
             bytes,
             BlobWeighter,
             Default::default(),
-            DefaultLifecycle::default(),
+            EvictionCounter(counters.clone()),
         );
 
         Self {
             data: quick_cache,
             capacity: bytes,
+            counters,
         }
     }
 
     pub(crate) fn insert(&self, key: CacheKey, value: UserValue) {
+        self.counters.insertions.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .inserted_bytes
+            .fetch_add(value.len() as u64, Ordering::Relaxed);
+
         self.data.insert(key, value);
     }
 
     pub(crate) fn get(&self, vlog_id: ValueLogId, vhandle: &ValueHandle) -> Option<Item> {
-        self.data.get(&(vlog_id, vhandle))
+        let item = self.data.get(&(vlog_id, vhandle));
+
+        if item.is_some() {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        item
+    }
+
+    /// Returns the size & access statistics of the cache.
+    #[must_use]
+    pub fn stats(&self) -> BlobCacheStats {
+        BlobCacheStats {
+            capacity: self.capacity,
+            size: self.size(),
+            len: self.len(),
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            insertions: self.counters.insertions.load(Ordering::Relaxed),
+            inserted_bytes: self.counters.inserted_bytes.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            evicted_bytes: self.counters.evicted_bytes.load(Ordering::Relaxed),
+        }
     }
 
     /// Returns the cache capacity in bytes.
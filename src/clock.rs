@@ -0,0 +1,68 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::sync::AtomicU64;
+use std::{sync::atomic::Ordering, time::Duration};
+
+/// Source of time for the value log
+///
+/// Segment timestamps, retention, TTL-based GC, read statistics and
+/// throttling all go through the configured clock, see [`Config::clock`](crate::Config::clock).
+pub trait Clock: Send + Sync {
+    /// Returns the current time in milliseconds since the Unix epoch.
+    fn now_millis(&self) -> u64;
+
+    /// Blocks the current thread for the given duration.
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Clock that uses the system time
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        crate::time::unix_timestamp_millis()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Useful to test retention & TTL behavior without sleeping.
+/// Sleeping advances the clock instead of blocking.
+#[derive(Debug, Default)]
+pub struct ManualClock(AtomicU64);
+
+impl ManualClock {
+    /// Creates a clock that starts at the given time (in milliseconds since the Unix epoch).
+    #[must_use]
+    pub fn new(now_millis: u64) -> Self {
+        Self(AtomicU64::new(now_millis))
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        // NOTE: Milliseconds fit into a u64 for a very long time
+        #[allow(clippy::cast_possible_truncation)]
+        self.0
+            .fetch_add(duration.as_millis() as u64, Ordering::AcqRel);
+    }
+
+    /// Sets the current time (in milliseconds since the Unix epoch).
+    pub fn set(&self, now_millis: u64) {
+        self.0.store(now_millis, Ordering::Release);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -2,10 +2,20 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{blob_cache::BlobCache, compression::Compressor};
-use std::sync::Arc;
+use crate::{
+    blob_cache::BlobCache,
+    clock::{Clock, SystemClock},
+    compression::Compressor,
+    corruption::CorruptionCallback,
+    fs::{Fs, IoClass, PageCacheAdvice, PageCacheHints, StdFs},
+    progress::ProgressCallback,
+    Encryptor, GcPolicy, KeyPartitioner, Replicator, SegmentSource, Transform,
+};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 /// Value log configuration
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config<C: Compressor + Clone> {
     /// Target size of vLog segments
     pub(crate) segment_size_bytes: u64,
@@ -13,8 +23,110 @@ pub struct Config<C: Compressor + Clone> {
     /// Blob cache to use
     pub(crate) blob_cache: Arc<BlobCache>,
 
+    /// Cache of decompressed values, if enabled
+    pub(crate) value_cache: Option<Arc<BlobCache>>,
+
     /// Compression to use
     pub(crate) compression: C,
+
+    /// Encryption to use
+    pub(crate) encryption: Option<Arc<dyn Encryptor>>,
+
+    /// Custom stages values are passed through between compression & encryption
+    pub(crate) transforms: Vec<Arc<dyn Transform>>,
+
+    /// Fallback source for segments that are missing locally
+    pub(crate) segment_source: Option<Arc<dyn SegmentSource>>,
+
+    /// File system to store data in
+    pub(crate) fs: Arc<dyn Fs>,
+
+    /// Source of time
+    pub(crate) clock: Arc<dyn Clock>,
+
+    /// Receiver of segment list changes
+    pub(crate) replicator: Option<Arc<dyn Replicator>>,
+
+    /// Receiver of progress of long-running operations
+    pub(crate) progress: Option<ProgressCallback>,
+
+    /// Receiver of detected corruptions
+    pub(crate) corruption_callback: Option<CorruptionCallback>,
+
+    /// Whether to hint the file system to discard segment data before deleting segments
+    pub(crate) discard_on_drop: bool,
+
+    /// Page cache advice given for every class of I/O operations
+    pub(crate) page_cache_hints: PageCacheHints,
+
+    /// Amount of data shards finished segments are split into for parity, if enabled
+    pub(crate) parity_shards: Option<u8>,
+
+    /// Folder to store parity files in, instead of the value log folder
+    pub(crate) parity_folder: Option<PathBuf>,
+
+    /// Values larger than this are split into chunks of this size
+    pub(crate) blob_chunk_size: Option<u32>,
+
+    /// Every n-th key in a segment is stored as a whole, the keys in between are delta-encoded
+    pub(crate) key_restart_interval: Option<u32>,
+
+    /// Every n-th written blob is read back before its segment is registered
+    pub(crate) paranoid_sample_interval: Option<u32>,
+
+    /// Maximum amount of bytes the value log's blobs may occupy
+    pub(crate) max_disk_usage: Option<u64>,
+
+    /// Whether to drop stale segments when the disk usage quota is exceeded
+    pub(crate) emergency_gc: bool,
+
+    /// Maximum age of segments, older segments are garbage collected
+    pub(crate) retention: Option<Duration>,
+
+    /// Whether reading a blob of an expired segment fails, instead of resolving to `None`
+    pub(crate) expired_reads_error: bool,
+
+    /// Whether relocated blobs are written in key order
+    pub(crate) key_ordered_rollover: bool,
+
+    /// Whether relocations are recorded in remap tables, instead of being passed to the index
+    pub(crate) remap_relocations: bool,
+
+    /// Groups relocated blobs into segments by partition
+    pub(crate) rollover_partitioner: Option<KeyPartitioner>,
+
+    /// Time dropped segments are kept in the trash folder, before being deleted
+    pub(crate) trash_window: Option<Duration>,
+
+    /// Time after which a segment's read rate has decayed to half
+    pub(crate) read_rate_half_life: Duration,
+
+    /// Read rates (reads per second) below which segments are cold, and above which they are hot
+    pub(crate) temperature_thresholds: (f64, f64),
+
+    /// Compression that cold segments of a minimum age are rewritten with during maintenance
+    pub(crate) cold_compression: Option<(C, Duration)>,
+
+    /// Compression and segment size of the old generation, which garbage collection promotes blobs to
+    pub(crate) old_generation: Option<(C, u64)>,
+
+    /// Garbage collection policy applied during maintenance
+    pub(crate) gc_policy: Option<GcPolicy>,
+
+    /// Amount of relocations that are buffered before passing them to the index writer
+    pub(crate) relocation_batch_size: usize,
+
+    /// Time after which idle segment file handles are closed, zero disables caching them
+    pub(crate) idle_file_timeout: Duration,
+
+    /// Maximum amount of memory the value log should use
+    pub(crate) memory_budget: Option<u64>,
+
+    /// Amount of past GC operations that are kept
+    pub(crate) gc_history_capacity: usize,
+
+    /// Whether the GC history survives reopening the value log
+    pub(crate) persist_gc_history: bool,
 }
 
 impl<C: Compressor + Clone + Default> Default for Config<C> {
@@ -24,7 +136,41 @@ impl<C: Compressor + Clone + Default> Default for Config<C> {
             blob_cache: Arc::new(BlobCache::with_capacity_bytes(
                 /* 16 MiB */ 16 * 1_024 * 1_024,
             )),
+            value_cache: None,
             compression: C::default(),
+            encryption: None,
+            transforms: Vec::new(),
+            segment_source: None,
+            fs: Arc::new(StdFs),
+            clock: Arc::new(SystemClock),
+            replicator: None,
+            progress: None,
+            corruption_callback: None,
+            discard_on_drop: false,
+            page_cache_hints: PageCacheHints::default(),
+            parity_shards: None,
+            parity_folder: None,
+            blob_chunk_size: None,
+            key_restart_interval: None,
+            paranoid_sample_interval: None,
+            max_disk_usage: None,
+            emergency_gc: false,
+            retention: None,
+            expired_reads_error: false,
+            trash_window: None,
+            key_ordered_rollover: false,
+            rollover_partitioner: None,
+            remap_relocations: false,
+            read_rate_half_life: Duration::from_secs(/* 5 minutes */ 5 * 60),
+            temperature_thresholds: (0.01, 1.0),
+            cold_compression: None,
+            old_generation: None,
+            gc_policy: None,
+            relocation_batch_size: 1_000,
+            idle_file_timeout: Duration::from_secs(60),
+            memory_budget: None,
+            gc_history_capacity: 64,
+            persist_gc_history: false,
         }
     }
 }
@@ -37,6 +183,33 @@ impl<C: Compressor + Clone> Config<C> {
         self
     }
 
+    /// Sets the encryption scheme, which is applied after compression.
+    ///
+    /// New segments are encrypted with the encryptor's current key version.
+    /// Once set, the encryptor needs to stay configured, otherwise
+    /// reading encrypted segments fails with [`Error::Decrypt`](crate::Error::Decrypt).
+    ///
+    /// Defaults to `None`.
+    #[must_use]
+    pub fn encryption(mut self, encryptor: Arc<dyn Encryptor>) -> Self {
+        self.encryption = Some(encryptor);
+        self
+    }
+
+    /// Adds a custom stage to the value transformation pipeline.
+    ///
+    /// Transforms are applied in the order they are added, after compression
+    /// and before encryption. Every segment records the transforms it was written with,
+    /// so a transform needs to stay configured as long as such segments exist,
+    /// otherwise reading them fails with [`Error::MissingTransform`](crate::Error::MissingTransform).
+    ///
+    /// Defaults to no transforms.
+    #[must_use]
+    pub fn transform(mut self, transform: Arc<dyn Transform>) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
     /// Sets the blob cache.
     ///
     /// You can create a global [`BlobCache`] and share it between multiple
@@ -49,6 +222,26 @@ impl<C: Compressor + Clone> Config<C> {
         self
     }
 
+    /// Sets a cache of decompressed values, which is looked up before the blob cache.
+    ///
+    /// Without a value cache, the blob cache holds values after reverting compression
+    /// (and transforms & encryption). With a value cache, the blob cache holds blobs
+    /// as they are stored in their segment instead, and hot values are kept decoded in
+    /// the value cache, so they are not decompressed again on every read.
+    /// Both caches have their own capacity.
+    ///
+    /// Values that are stored as is are only held by the blob cache,
+    /// chunked values (see [`Config::blob_chunk_size`]) only by the value cache.
+    ///
+    /// Like the blob cache, the value cache can be shared between multiple value logs.
+    ///
+    /// Default = none
+    #[must_use]
+    pub fn value_cache(mut self, value_cache: Arc<BlobCache>) -> Self {
+        self.value_cache = Some(value_cache);
+        self
+    }
+
     /// Sets the maximum size of value log segments.
     ///
     /// This heavily influences space amplification, as
@@ -62,4 +255,469 @@ impl<C: Compressor + Clone> Config<C> {
         self.segment_size_bytes = bytes;
         self
     }
+
+    /// Sets a fallback source to read segments from when their
+    /// file is missing locally (e.g. while restoring from a backup).
+    ///
+    /// Defaults to `None`.
+    #[must_use]
+    pub fn segment_source(mut self, source: Arc<dyn SegmentSource>) -> Self {
+        self.segment_source = Some(source);
+        self
+    }
+
+    /// Sets the file system implementation all file operations are performed through.
+    ///
+    /// This can be used to run the value log on top of a simulated file system for testing.
+    ///
+    /// Defaults to [`StdFs`].
+    #[must_use]
+    pub fn fs(mut self, fs: Arc<dyn Fs>) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    /// Sets the clock that segment timestamps, retention, TTL-based garbage collection,
+    /// read statistics and GC throttling are based on.
+    ///
+    /// This can be used to test time-based behavior deterministically, see [`ManualClock`](crate::ManualClock).
+    ///
+    /// Defaults to [`SystemClock`].
+    #[must_use]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets a replicator that is notified about registered and dropped segments.
+    ///
+    /// Defaults to `None`.
+    #[must_use]
+    pub fn replicator(mut self, replicator: Arc<dyn Replicator>) -> Self {
+        self.replicator = Some(replicator);
+        self
+    }
+
+    /// Sets a callback that periodically receives the progress of long-running
+    /// operations ([`ValueLog::scan_for_stats`](crate::ValueLog::scan_for_stats),
+    /// [`ValueLog::verify`](crate::ValueLog::verify) and segment rewrites).
+    ///
+    /// Defaults to `None`.
+    #[must_use]
+    pub fn progress_callback(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Sets a callback that receives corruptions detected by reads and
+    /// [`ValueLog::verify`](crate::ValueLog::verify), with the affected segment and,
+    /// if known, the affected blob's offset and key.
+    ///
+    /// This allows marking the affected keys as unreadable, instead of
+    /// failing to read them forever. The operation still returns its error.
+    ///
+    /// Defaults to `None`.
+    #[must_use]
+    pub fn corruption_callback(mut self, callback: CorruptionCallback) -> Self {
+        self.corruption_callback = Some(callback);
+        self
+    }
+
+    /// If enabled, the file system is hinted to discard the data of segments
+    /// (see [`Fs::discard`]) before they are deleted.
+    ///
+    /// This can help thin-provisioned and SSD-backed storage reclaim space sooner.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn discard_on_drop(mut self, enabled: bool) -> Self {
+        self.discard_on_drop = enabled;
+        self
+    }
+
+    /// If set, a parity file is written for every finished segment, so damaged
+    /// segments can be repaired using [`ValueLog::repair_segment`](crate::ValueLog::repair_segment).
+    ///
+    /// Each segment is split into `data_shards` shards, which are combined (using XOR) into a parity shard,
+    /// so the parity takes up about `1 / data_shards` of the segment's size.
+    /// Damage is repairable as long as it does not affect the same position in two shards,
+    /// so any contiguous damaged range that is at least one (4 KiB) block smaller
+    /// than a shard can be repaired.
+    ///
+    /// With 1 data shard, the parity is a full copy, which also allows restoring
+    /// segment files that are lost completely.
+    ///
+    /// Setting 0 disables parity.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn parity_shards(mut self, data_shards: u8) -> Self {
+        self.parity_shards = (data_shards > 0).then_some(data_shards);
+        self
+    }
+
+    /// Sets the folder parity files are stored in (see [`Config::parity_shards`]).
+    ///
+    /// Storing parity on another disk protects against losing segment files with their parity.
+    /// The folder should not be shared with other value logs.
+    ///
+    /// Defaults to a `parity` folder inside the value log folder.
+    #[must_use]
+    pub fn parity_folder<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.parity_folder = Some(path.into());
+        self
+    }
+
+    /// If set, values larger than `bytes` are split into chunks of `bytes`,
+    /// which are compressed & encrypted separately, but stored as a single blob.
+    ///
+    /// This is transparent to readers, the value handle points to the whole value.
+    /// It avoids compressing (and decompressing) very large values in one go,
+    /// which some compression schemes cannot do.
+    ///
+    /// Setting 0 disables chunking.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn blob_chunk_size(mut self, bytes: u32) -> Self {
+        self.blob_chunk_size = (bytes > 0).then_some(bytes);
+        self
+    }
+
+    /// If set, keys in segments are delta-encoded: only the part of a key that differs
+    /// from the previous key is stored. Every `entries`-th key is stored as a whole
+    /// (a restart point), to bound the work needed to resolve a key.
+    ///
+    /// This shrinks segments with long, similar keys.
+    ///
+    /// Setting 0 disables delta encoding.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn key_restart_interval(mut self, entries: u32) -> Self {
+        self.key_restart_interval = (entries > 0).then_some(entries);
+        self
+    }
+
+    /// If set, segment writers remember the offset, key & checksum of every
+    /// `sample_interval`-th blob they write (starting with the first one).
+    /// Before the segments are registered (by [`ValueLog::register_writer`](crate::ValueLog::register_writer)
+    /// or garbage collection), the sampled blobs are read back and checked to parse
+    /// and match what was written, so bugs in the writer or the disk format
+    /// are caught before the segments are added to the manifest.
+    ///
+    /// Registering fails with an I/O error of kind [`std::io::ErrorKind::InvalidData`]
+    /// if a blob does not match.
+    ///
+    /// Setting 0 disables the checks.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn paranoid_checks(mut self, sample_interval: u32) -> Self {
+        self.paranoid_sample_interval = (sample_interval > 0).then_some(sample_interval);
+        self
+    }
+
+    /// Sets the maximum amount of bytes the value log's blobs may occupy on disk.
+    ///
+    /// Writes that would exceed the quota fail with [`Error::QuotaExceeded`](crate::Error::QuotaExceeded),
+    /// unless [`Config::emergency_gc`] can free enough space.
+    /// Until a writer is registered, its writes count with their uncompressed size.
+    ///
+    /// Rewriting segments during garbage collection is not limited by the quota,
+    /// because it is needed to free space.
+    ///
+    /// Setting 0 disables the quota.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn max_disk_usage(mut self, bytes: u64) -> Self {
+        self.max_disk_usage = (bytes > 0).then_some(bytes);
+        self
+    }
+
+    /// If enabled, stale segments are dropped (see [`ValueLog::drop_stale_segments`](crate::ValueLog::drop_stale_segments))
+    /// when a write would exceed the disk usage quota (see [`Config::max_disk_usage`]),
+    /// before failing the write.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn emergency_gc(mut self, enabled: bool) -> Self {
+        self.emergency_gc = enabled;
+        self
+    }
+
+    /// Sets the maximum age of segments.
+    ///
+    /// Segments that were created longer than `max_age` ago are picked for garbage collection
+    /// (see [`ValueLog::apply_gc_strategy`](crate::ValueLog::apply_gc_strategy)), regardless of their stale ratio,
+    /// and their blobs are not rewritten, so they are dropped together with the segment.
+    ///
+    /// Value handles that point to expired blobs resolve to `None`, even before
+    /// garbage collection reclaims the segment (see [`Config::expired_reads_error`]).
+    ///
+    /// Because a segment is only collected as a whole, items live for at least `max_age`.
+    /// Creation times are only recorded while retention (or another age-based feature)
+    /// is configured, so segments that were written without it never expire.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn retention(mut self, max_age: Duration) -> Self {
+        self.retention = Some(max_age);
+        self
+    }
+
+    /// If enabled, reading a blob of an expired segment (see [`Config::retention`])
+    /// returns [`Error::Expired`](crate::Error::Expired) instead of `None`,
+    /// so callers can tell expired values apart from missing ones.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn expired_reads_error(mut self, enabled: bool) -> Self {
+        self.expired_reads_error = enabled;
+        self
+    }
+
+    /// If enabled, garbage collection writes the live blobs it relocates in key order,
+    /// so the new segments cover tight key ranges, which benefits range scans.
+    ///
+    /// Without it, blobs are only written in key order if the rewritten segments were.
+    /// Because the live blobs need to be collected and sorted first,
+    /// their keys and values are kept in memory during the rewrite.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn key_ordered_rollover(mut self, enabled: bool) -> Self {
+        self.key_ordered_rollover = enabled;
+        self
+    }
+
+    /// Groups the live blobs garbage collection relocates by the partition the given function
+    /// maps their keys to, so related keys end up co-located in the same new segments.
+    ///
+    /// Every partition starts a new segment, and blobs are written in key order
+    /// within each partition. Without a partitioner, [`Config::key_ordered_rollover`]
+    /// groups blobs by key range only.
+    ///
+    /// Like key-ordered rollover, the live blobs need to be collected first,
+    /// so they are read twice, and their keys are kept in memory during the rewrite.
+    ///
+    /// Default = none
+    #[must_use]
+    pub fn rollover_partitioner(mut self, partitioner: KeyPartitioner) -> Self {
+        self.rollover_partitioner = Some(partitioner);
+        self
+    }
+
+    /// If enabled, garbage collection does not pass the new locations of relocated blobs
+    /// to the index. Instead, they are recorded in a remap table per rewritten segment,
+    /// which reads consult transparently, so old value handles keep resolving.
+    ///
+    /// This removes the index writes from garbage collection. The index can be
+    /// brought up to date lazily (e.g. when idle) using
+    /// [`ValueLog::apply_remaps`](crate::ValueLog::apply_remaps),
+    /// or per key using [`ValueLog::remapped_handle`](crate::ValueLog::remapped_handle).
+    ///
+    /// Remap tables are kept in memory (including the keys of all remapped blobs)
+    /// and on disk until they are applied. They are part of checkpoints,
+    /// but incremental backups, exports and metadata snapshots fail with
+    /// [`Error::PendingRemaps`](crate::Error::PendingRemaps) until they are applied.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn remap_relocations(mut self, enabled: bool) -> Self {
+        self.remap_relocations = enabled;
+        self
+    }
+
+    /// Keeps dropped segments in a trash folder for the given window, instead of deleting them,
+    /// so they can be restored using [`ValueLog::undrop_segment`](crate::ValueLog::undrop_segment).
+    ///
+    /// Segments that were dropped longer than `window` ago are deleted whenever stale segments
+    /// are dropped (see [`ValueLog::drop_stale_segments`](crate::ValueLog::drop_stale_segments)),
+    /// or explicitly using [`ValueLog::purge_trash`](crate::ValueLog::purge_trash).
+    /// Trashed segments do not count towards the disk usage quota (see [`Config::max_disk_usage`]).
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn trash_window(mut self, window: Duration) -> Self {
+        self.trash_window = Some(window);
+        self
+    }
+
+    /// Sets the half-life of segment read rates (see [`ValueLog::temperature`](crate::ValueLog::temperature)).
+    ///
+    /// Reads count less the older they are: after `half_life`, a read only counts half.
+    /// A shorter half-life reacts faster to changing access patterns.
+    ///
+    /// Default = 5 minutes
+    #[must_use]
+    pub fn read_rate_half_life(mut self, half_life: Duration) -> Self {
+        self.read_rate_half_life = half_life;
+        self
+    }
+
+    /// Sets the read rates (in reads per second) that segment temperatures are classified by
+    /// (see [`ValueLog::temperature`](crate::ValueLog::temperature)).
+    ///
+    /// Segments that are read less often than `cold_below` are cold,
+    /// segments that are read more often than `hot_above` are hot.
+    ///
+    /// Default = cold below 0.01, hot above 1.0
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thresholds are invalid.
+    #[must_use]
+    pub fn temperature_thresholds(mut self, cold_below: f64, hot_above: f64) -> Self {
+        assert!(
+            cold_below.is_finite() && hot_above.is_finite() && cold_below <= hot_above,
+            "invalid temperature thresholds"
+        );
+        self.temperature_thresholds = (cold_below, hot_above);
+        self
+    }
+
+    /// If set, [`ValueLog::run_maintenance`](crate::ValueLog::run_maintenance) rewrites
+    /// cold segments (see [`ValueLog::temperature`](crate::ValueLog::temperature)) that were created
+    /// at least `min_age` ago using `compressor`, which is usually a stronger (slower) codec
+    /// than the one set by [`Config::compression`].
+    ///
+    /// Values are always decompressed using the compressor set by [`Config::compression`],
+    /// so it needs to be able to decompress the output of `compressor` as well
+    /// (e.g. the same codec with a higher compression level).
+    ///
+    /// Recompressed segments are tagged (see [`SegmentWriter::with_tag`](crate::SegmentWriter::with_tag))
+    /// with `vlog.compression=cold`, so they are not recompressed again.
+    /// Segments that were written while no age-based feature was configured do not record
+    /// their creation time (see [`Config::retention`]), so they are never recompressed.
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn cold_compression(mut self, compressor: C, min_age: Duration) -> Self {
+        self.cold_compression = Some((compressor, min_age));
+        self
+    }
+
+    /// Organizes segments into a young and an old generation.
+    ///
+    /// Fresh writes go into young segments, which use the regular (ideally cheap) compression,
+    /// see [`Config::compression`]. Blobs that survive garbage collection are promoted to old
+    /// segments, which are written with the given (stronger) compressor and segment size target.
+    /// Because most blobs tend to die young, young segments are rewritten first
+    /// by [`ValueLog::run_maintenance`](crate::ValueLog::run_maintenance).
+    ///
+    /// Like with [`Config::cold_compression`], the regular compressor needs to be able
+    /// to decompress values written by the old generation's compressor.
+    ///
+    /// Promoted segments are tagged (see [`SegmentWriter::with_tag`](crate::SegmentWriter::with_tag))
+    /// with `vlog.generation=old`, see [`ValueLog::segment_generation`](crate::ValueLog::segment_generation).
+    ///
+    /// Default = disabled
+    #[must_use]
+    pub fn old_generation(mut self, compressor: C, segment_size_bytes: u64) -> Self {
+        self.old_generation = Some((compressor, segment_size_bytes));
+        self
+    }
+
+    /// Sets the page cache advice (see [`FsFile::advise`](crate::FsFile::advise)) given
+    /// for a class of I/O operations, or disables it if `None`.
+    ///
+    /// By default, scans & garbage collection read sequentially, prefetched blobs are loaded ahead,
+    /// and segments rewritten by garbage collection are evicted from the page cache,
+    /// so garbage collection does not push out data that is read frequently
+    /// (see [`IoClass`] for the defaults).
+    #[must_use]
+    pub fn page_cache_advice(mut self, class: IoClass, advice: Option<PageCacheAdvice>) -> Self {
+        self.page_cache_hints.set(class, advice);
+        self
+    }
+
+    /// Sets the garbage collection policy that [`ValueLog::run_maintenance`](crate::ValueLog::run_maintenance)
+    /// (and the background maintenance thread) applies.
+    ///
+    /// Default = no garbage collection during maintenance
+    #[must_use]
+    pub fn gc_policy(mut self, policy: GcPolicy) -> Self {
+        self.gc_policy = Some(policy);
+        self
+    }
+
+    /// Sets the amount of relocations that garbage collection buffers before
+    /// passing them, sorted by key, to the index writer
+    /// (see [`IndexWriter::relocate_batch`](crate::IndexWriter::relocate_batch)).
+    ///
+    /// Default = 1,000
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch size is 0.
+    #[must_use]
+    pub fn relocation_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "relocation batch size must be > 0");
+        self.relocation_batch_size = batch_size;
+        self
+    }
+
+    /// Sets the time after which idle segment file handles are closed.
+    ///
+    /// Segment files are opened lazily on their first point read, and the handle
+    /// is reused by following reads, until it was not used for `timeout`
+    /// (see [`ValueLog::close_idle_files`](crate::ValueLog::close_idle_files)).
+    ///
+    /// A timeout of zero disables caching file handles, so every read opens the file.
+    ///
+    /// Default = 60 seconds
+    #[must_use]
+    pub fn idle_file_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_file_timeout = timeout;
+        self
+    }
+
+    /// Sets the amount of memory the value log should fit into, including the blob & value caches,
+    /// and the buffers of segment readers & writers (see [`ValueLog::memory_usage`](crate::ValueLog::memory_usage)).
+    ///
+    /// Reader & writer buffers are needed for I/O, so the caches yield:
+    /// blobs are not cached while caching them would exceed the budget.
+    ///
+    /// Default = unlimited
+    #[must_use]
+    pub fn memory_budget(mut self, bytes: u64) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Sets the amount of past GC operations that are kept
+    /// (see [`ValueLog::gc_history`](crate::ValueLog::gc_history)).
+    ///
+    /// Default = 64
+    #[must_use]
+    pub fn gc_history_capacity(mut self, capacity: usize) -> Self {
+        self.gc_history_capacity = capacity;
+        self
+    }
+
+    /// If enabled, the GC history is persisted after every GC operation,
+    /// so it survives reopening the value log.
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn persist_gc_history(mut self, enabled: bool) -> Self {
+        self.persist_gc_history = enabled;
+        self
+    }
+
+    /// Returns `true` if new segments record their creation time, which is needed
+    /// by the retention, cold compression and the GC policy's minimum age.
+    ///
+    /// Recording it requires a newer segment metadata format, so segments
+    /// written without these features stay readable by older 1.x versions.
+    pub(crate) fn records_creation_time(&self) -> bool {
+        self.retention.is_some()
+            || self.cold_compression.is_some()
+            || self
+                .gc_policy
+                .as_ref()
+                .is_some_and(|x| x.min_age_limit().is_some())
+    }
 }
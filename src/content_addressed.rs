@@ -0,0 +1,158 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    dedup::{ChunkHash, Chunker, DedupValueLog, DedupWriter},
+    Compressor, GcReport, GcStrategy, UserValue, ValueLog,
+};
+
+/// Hash of a value's content (xxh3, 128 bit), which is used as its handle
+pub type ContentHash = ChunkHash;
+
+/// A value log where values are addressed by the hash of their content
+///
+/// Writing a value returns its [`ContentHash`], and identical values are stored only once.
+/// This is useful for immutable artifact stores, where the hash can be
+/// handed out as a stable identifier of the artifact.
+///
+/// Every write of a value takes a reference to it, which needs to be released
+/// using [`ContentAddressedValueLog::release`] once the value is not needed anymore.
+/// Garbage collection only keeps values that are still referenced.
+///
+/// Values are identified by a non-cryptographic 128-bit hash, so this should not
+/// be used with adversarial inputs.
+#[derive(Clone)]
+pub struct ContentAddressedValueLog<C: Compressor + Clone>(DedupValueLog<C>);
+
+impl<C: Compressor + Clone> ContentAddressedValueLog<C> {
+    /// Adds content addressing on top of a value log, recovering its index.
+    ///
+    /// The value log should only be written to through the returned [`ContentAddressedValueLog`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the index is corrupt.
+    pub fn open(value_log: ValueLog<C>) -> crate::Result<Self> {
+        // NOTE: Values are never split into chunks, so the chunker is unused
+        DedupValueLog::open(value_log, Chunker::default()).map(Self)
+    }
+
+    /// Returns the underlying value log.
+    #[must_use]
+    pub fn value_log(&self) -> &ValueLog<C> {
+        self.0.value_log()
+    }
+
+    /// Returns the amount of unique values that are referenced.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.chunk_count()
+    }
+
+    /// Returns `true` if no values are referenced.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if a value with the given hash is stored.
+    #[must_use]
+    pub fn contains(&self, hash: ContentHash) -> bool {
+        self.refcount(hash) > 0
+    }
+
+    /// Returns the amount of references to the given value.
+    #[must_use]
+    pub fn refcount(&self, hash: ContentHash) -> u64 {
+        self.0.refcount(hash)
+    }
+
+    /// Initializes a new writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_writer(&self) -> crate::Result<ContentAddressedWriter<C>> {
+        self.0.get_writer().map(ContentAddressedWriter)
+    }
+
+    /// Registers a [`ContentAddressedWriter`], making its new values available.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn register_writer(&self, writer: ContentAddressedWriter<C>) -> crate::Result<()> {
+        self.0.register_writer(writer.0)
+    }
+
+    /// Retrieves the value with the given hash.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get(&self, hash: ContentHash) -> crate::Result<Option<UserValue>> {
+        self.0.get_chunk(hash)
+    }
+
+    /// Drops one reference to each of the given values.
+    ///
+    /// Values that are not referenced anymore become stale.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn release(&self, hashes: &[ContentHash]) -> crate::Result<()> {
+        self.0.release_chunks(hashes.iter().copied())
+    }
+
+    /// Scans all segments to collect GC statistics, where a value
+    /// is alive as long as it is referenced.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_for_stats(&self) -> crate::Result<GcReport> {
+        self.0.scan_for_stats()
+    }
+
+    /// Applies a GC strategy, relocating referenced values.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn apply_gc_strategy(&self, strategy: &impl GcStrategy<C>) -> crate::Result<u64> {
+        self.0.apply_gc_strategy(strategy)
+    }
+
+    /// Rewrites all segments, relocating referenced values,
+    /// and drops the rewritten segments.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn major_compact(&self) -> crate::Result<u64> {
+        self.0.major_compact()
+    }
+}
+
+/// Segment writer of a [`ContentAddressedValueLog`]
+#[allow(clippy::module_name_repetitions)]
+pub struct ContentAddressedWriter<C: Compressor + Clone>(DedupWriter<C>);
+
+impl<C: Compressor + Clone> ContentAddressedWriter<C> {
+    /// Writes a value, returning its hash.
+    ///
+    /// If the value is already stored, it is not written again.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn write<V: AsRef<[u8]>>(&mut self, value: V) -> crate::Result<ContentHash> {
+        self.0.write_chunk(value.as_ref())
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::{any::Any, cell::RefCell, sync::Arc};
+
+thread_local! {
+    static CURRENT: RefCell<Option<RequestContext>> = const { RefCell::new(None) };
+}
+
+/// Opaque context of the user request that triggered an operation,
+/// e.g. a request ID or a tracing span
+///
+/// Operations that are run within [`RequestContext::scope`] can retrieve the context
+/// using [`RequestContext::current`], e.g. in a custom [`Fs`](crate::Fs), a
+/// [`log`] implementation or a callback, to correlate logs & metrics of segment I/O
+/// with the request.
+///
+/// The context is propagated into work the value log hands off to other threads:
+/// parallel scans, writers submitted to the background flush thread, and background
+/// maintenance (which runs within the context [`ValueLog::start_background_maintenance`](crate::ValueLog::start_background_maintenance)
+/// was called in).
+#[derive(Clone)]
+pub struct RequestContext(Arc<dyn Any + Send + Sync>);
+
+impl std::fmt::Debug for RequestContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RequestContext")
+    }
+}
+
+/// Restores the previous context when a scope is left
+struct ScopeGuard(Option<RequestContext>);
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
+
+impl RequestContext {
+    /// Wraps a value as a request context.
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Returns the wrapped value, if it is of type `T`.
+    #[must_use]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    /// Returns `true` if both contexts wrap the same value.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the context of the current thread, if it runs within a scope.
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Runs the function with this context as the context of the current thread.
+    pub fn scope<R, F: FnOnce() -> R>(self, f: F) -> R {
+        let prev = CURRENT.with(|current| current.borrow_mut().replace(self));
+        let _guard = ScopeGuard(prev);
+        f()
+    }
+
+    /// Runs the function within the given context, or the current one if `None`.
+    pub(crate) fn scope_opt<R, F: FnOnce() -> R>(ctx: Option<Self>, f: F) -> R {
+        match ctx {
+            Some(ctx) => ctx.scope(f),
+            None => f(),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Helpers to damage segment files in a controlled way, to test that corruptions
+//! are detected (see [`Error::category`](crate::Error::category)) and repaired
+
+use crate::{
+    format::{self, RecordDescription},
+    Compressor, ValueHandle, ValueLog,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+fn invalid_input(msg: &str) -> crate::Error {
+    crate::Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))
+}
+
+/// Locates the record a value handle points to.
+///
+/// Returns the segment file path, the record and the offset of its payload
+/// (the stored value, or the length-prefixed chunks of chunked blobs).
+fn locate_blob<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    vhandle: &ValueHandle,
+) -> crate::Result<(PathBuf, RecordDescription, u64)> {
+    let segment = value_log
+        .manifest
+        .get_segment(vhandle.segment_id)
+        .ok_or_else(|| invalid_input("segment does not exist"))?;
+
+    let description = format::describe_segment(&std::fs::read(&segment.path)?)?;
+
+    let record = description
+        .records
+        .into_iter()
+        .find(|x| x.offset == vhandle.offset)
+        .ok_or_else(|| invalid_input("value handle does not point to a blob"))?;
+
+    let header_len = record.header.encode()?.len() as u64;
+    let payload_offset = record.offset + header_len;
+
+    Ok((segment.path.clone(), record, payload_offset))
+}
+
+fn open_for_write(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().read(true).write(true).open(path)
+}
+
+/// Flips all bits of a byte of a blob's payload, which follows the blob header.
+///
+/// `pos` is relative to the payload: the stored (possibly compressed) value,
+/// or for chunked blobs, the length-prefixed chunks.
+/// Flipping a byte of a stored value results in a checksum mismatch,
+/// see [`ValueLog::verify`].
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs, or the position is outside the payload.
+pub fn flip_blob_byte<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    vhandle: &ValueHandle,
+    pos: u64,
+) -> crate::Result<()> {
+    let (path, record, payload_offset) = locate_blob(value_log, vhandle)?;
+
+    let offset = payload_offset + pos;
+
+    if offset >= record.offset + record.len {
+        return Err(invalid_input("position is outside of the blob"));
+    }
+
+    let mut file = open_for_write(&path)?;
+    let mut byte = [0];
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut byte)?;
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&byte.map(|b| !b))?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// Overwrites the checksum of a blob with zeroes,
+/// which results in a checksum mismatch, see [`ValueLog::verify`].
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+pub fn zero_blob_checksum<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    vhandle: &ValueHandle,
+) -> crate::Result<()> {
+    let (path, record, _) = locate_blob(value_log, vhandle)?;
+
+    // NOTE: The checksum directly follows the header magic
+    let offset = record.offset + record.header.layout.header().len() as u64;
+
+    let mut file = open_for_write(&path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&0_u64.to_be_bytes())?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// Truncates a segment file in the middle of a blob's payload, simulating a torn segment.
+///
+/// Reading the blob (or any later one) fails with an unexpected EOF,
+/// and the segment's trailer is lost, so the value log cannot be reopened
+/// until the segment is repaired, see [`ValueLog::repair_segment`].
+///
+/// Returns the new length of the segment file.
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+pub fn truncate_segment_mid_blob<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    vhandle: &ValueHandle,
+) -> crate::Result<u64> {
+    let (path, record, payload_offset) = locate_blob(value_log, vhandle)?;
+
+    let record_end = record.offset + record.len;
+    let len = payload_offset + (record_end - payload_offset) / 2;
+
+    let file = open_for_write(&path)?;
+    file.set_len(len)?;
+    file.sync_all()?;
+
+    Ok(len)
+}
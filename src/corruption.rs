@@ -0,0 +1,38 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{id::SegmentId, UserKey};
+use std::sync::Arc;
+
+/// Operation that detected a corruption
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CorruptionSource {
+    /// Resolving a value handle (e.g. [`crate::ValueLog::get`])
+    Read,
+
+    /// [`crate::ValueLog::verify`]
+    Verify,
+}
+
+/// Corruption that was detected in a segment, see [`Config::corruption_callback`](crate::Config::corruption_callback)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CorruptionReport {
+    /// The operation that detected the corruption
+    pub source: CorruptionSource,
+
+    /// Segment that is corrupted
+    pub segment_id: SegmentId,
+
+    /// Offset of the affected blob in the segment file, if known
+    ///
+    /// If the blob's header cannot be read, the segment cannot be scanned
+    /// beyond this offset either.
+    pub offset: Option<u64>,
+
+    /// Key of the affected blob, if known
+    pub key: Option<UserKey>,
+}
+
+/// Callback that receives detected corruptions
+pub type CorruptionCallback = Arc<dyn Fn(&CorruptionReport) + Send + Sync>;
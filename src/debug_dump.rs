@@ -0,0 +1,164 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    manifest::SegmentTags, pipeline::Stage, BlobCacheStats, GcHistoryEntry, ManifestSummary,
+    MemoryUsage, SegmentSummary,
+};
+use std::{path::PathBuf, time::Duration};
+
+/// Point-in-time dump of a value log's internal state, see [`ValueLog::debug_dump`](crate::ValueLog::debug_dump)
+///
+/// With the `serde` feature enabled, it can be serialized (e.g. as JSON)
+/// and attached to bug reports.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DebugDump {
+    /// Base folder of the value log
+    pub path: PathBuf,
+
+    /// Statistics of the segment list
+    pub manifest: ManifestSummary,
+
+    /// Details of every segment, ordered by segment ID
+    pub segments: Vec<SegmentDebugInfo>,
+
+    /// Configuration of the value log
+    pub config: ConfigDump,
+
+    /// Blob cache statistics
+    pub blob_cache: BlobCacheStats,
+
+    /// Value cache statistics, if a value cache is configured
+    pub value_cache: Option<BlobCacheStats>,
+
+    /// Memory usage
+    pub memory: MemoryUsage,
+
+    /// Operations that are in progress
+    pub in_flight: InFlightOperations,
+
+    /// Past GC operations, oldest first
+    pub gc_history: Vec<GcHistoryEntry>,
+}
+
+/// Details of a segment, as part of a [`DebugDump`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SegmentDebugInfo {
+    /// Segment statistics
+    pub summary: SegmentSummary,
+
+    /// Segment file path
+    pub path: PathBuf,
+
+    /// Time the segment was created at, in milliseconds since the Unix epoch
+    pub created_at: Option<u64>,
+
+    /// Stages its values were written with
+    pub pipeline: Vec<Stage>,
+
+    /// Whether the segment is marked as stale
+    pub is_stale: bool,
+
+    /// Whether the segment is pinned
+    pub is_pinned: bool,
+
+    /// User-defined tags
+    pub tags: SegmentTags,
+
+    /// Amount of blob reads served by the segment
+    pub reads: u64,
+
+    /// Time of the last read, in milliseconds since the Unix epoch
+    pub last_read_at: Option<u64>,
+
+    /// Whether the segment's file handle is open
+    pub is_file_open: bool,
+}
+
+/// Configuration of a value log, as part of a [`DebugDump`]
+///
+/// Pluggable components (compressor, file system, ...) are omitted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ConfigDump {
+    /// Target size of segments
+    pub segment_size_bytes: u64,
+
+    /// Whether values are encrypted
+    pub encryption: bool,
+
+    /// IDs of the configured transforms
+    pub transforms: Vec<u8>,
+
+    /// Amount of parity data shards, if parity is enabled
+    pub parity_shards: Option<u8>,
+
+    /// Size of value chunks, if chunking is enabled
+    pub blob_chunk_size: Option<u32>,
+
+    /// Key restart interval, if keys are delta-encoded
+    pub key_restart_interval: Option<u32>,
+
+    /// Disk usage quota
+    pub max_disk_usage: Option<u64>,
+
+    /// Whether stale segments are dropped when the quota is exceeded
+    pub emergency_gc: bool,
+
+    /// Maximum age of segments
+    pub retention: Option<Duration>,
+
+    /// Minimum age of cold segments that are recompressed, if enabled
+    pub cold_compression_age: Option<Duration>,
+
+    /// Segment size target of the old generation, if enabled
+    pub old_generation_segment_size: Option<u64>,
+
+    /// Whether a GC policy is configured
+    pub gc_policy: bool,
+
+    /// Amount of relocations passed to the index writer at once
+    pub relocation_batch_size: usize,
+
+    /// Time after which idle segment file handles are closed
+    pub idle_file_timeout: Duration,
+
+    /// Memory budget
+    pub memory_budget: Option<u64>,
+
+    /// Whether segment data is discarded before deleting segments
+    pub discard_on_drop: bool,
+}
+
+/// Operations that are in progress, as part of a [`DebugDump`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct InFlightOperations {
+    /// Whether a rollover, GC or another segment list rewrite is running
+    pub rollover: bool,
+
+    /// Whether maintenance is paused
+    pub maintenance_paused: bool,
+
+    /// Whether the background maintenance thread was started
+    pub background_maintenance: bool,
+
+    /// Whether the background flush thread was started
+    pub background_flush: bool,
+
+    /// Bytes written by writers that are not registered yet
+    ///
+    /// Only tracked if a disk usage quota is configured.
+    pub pending_writer_bytes: u64,
+
+    /// Amount of open segment file handles
+    pub open_files: usize,
+
+    /// Whether the value log is closed
+    pub closed: bool,
+}
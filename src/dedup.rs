@@ -0,0 +1,659 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::DecodeError, Compressor, GcReport, GcStrategy, IndexReader, IndexWriter, SegmentWriter,
+    SizedValueHandle, UserValue, ValueHandle, ValueLog,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    collections::hash_map::Entry,
+    io::{Read, Write},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// File that stores the chunk index of a deduplicated value log
+pub const DEDUP_INDEX_FILE: &str = "dedup_index";
+
+const DEDUP_INDEX_MAGIC: &[u8] = &[b'V', b'L', b'D', b'E', b'D', b'U', b'P', 1];
+
+/// Hash of a chunk's content (xxh3, 128 bit)
+pub type ChunkHash = u128;
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut state: u64 = 0;
+    let mut idx = 0;
+
+    // NOTE: splitmix64, so the table is stable across builds
+    while idx < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        // NOTE: idx is always in bounds
+        #[allow(clippy::indexing_slicing)]
+        {
+            table[idx] = z ^ (z >> 31);
+        }
+
+        idx += 1;
+    }
+
+    table
+}
+
+static GEAR: [u64; 256] = gear_table();
+
+/// Splits values into content-defined chunks
+///
+/// Chunk boundaries are picked using a rolling (gear) hash over the content,
+/// so inserting or removing bytes only changes the chunks around the edit,
+/// and identical regions of different values result in identical chunks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chunker {
+    min_size: usize,
+    max_size: usize,
+    shift: u32,
+}
+
+impl Default for Chunker {
+    fn default() -> Self {
+        Self::new(
+            /* 2 KiB */ 2 * 1_024,
+            /* 8 KiB */ 8 * 1_024,
+            /* 64 KiB */ 64 * 1_024,
+        )
+    }
+}
+
+impl Chunker {
+    /// Creates a chunker with the given minimum, average and maximum chunk size.
+    ///
+    /// The average size is rounded up to the next power of two, and the maximum size
+    /// is raised to the minimum size, if needed.
+    #[must_use]
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Self {
+        let avg_size = avg_size.max(2).next_power_of_two();
+        let min_size = min_size.max(1);
+
+        Self {
+            min_size,
+            max_size: max_size.max(min_size),
+            shift: u64::BITS - avg_size.trailing_zeros(),
+        }
+    }
+
+    /// Splits the bytes into chunks.
+    #[must_use]
+    pub fn split<'a>(&self, mut bytes: &'a [u8]) -> Vec<&'a [u8]> {
+        let mut chunks = vec![];
+
+        while !bytes.is_empty() {
+            let (chunk, rest) = bytes.split_at(self.next_boundary(bytes));
+            chunks.push(chunk);
+            bytes = rest;
+        }
+
+        chunks
+    }
+
+    fn next_boundary(&self, bytes: &[u8]) -> usize {
+        if bytes.len() <= self.min_size {
+            return bytes.len();
+        }
+
+        let end = bytes.len().min(self.max_size);
+        let mut hash = 0u64;
+
+        for (idx, &byte) in bytes.iter().enumerate().take(end).skip(self.min_size) {
+            // NOTE: A u8 is always in bounds of the table
+            #[allow(clippy::indexing_slicing)]
+            {
+                hash = (hash << 1).wrapping_add(GEAR[usize::from(byte)]);
+            }
+
+            // NOTE: The upper bits depend on the last 64 bytes
+            if hash >> self.shift == 0 {
+                return idx + 1;
+            }
+        }
+
+        end
+    }
+}
+
+/// Handle of a deduplicated value, listing the hashes of its chunks
+///
+/// Chunks are referenced by their content hash instead of their location,
+/// so composite handles stay valid when garbage collection moves chunks.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CompositeHandle {
+    /// Hashes of the value's chunks, in order
+    pub chunks: Vec<ChunkHash>,
+
+    /// Size of the value
+    pub size: u64,
+}
+
+impl CompositeHandle {
+    /// Serializes the handle, so it can be stored in an index.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 4 + self.chunks.len() * 16);
+        bytes.extend_from_slice(&self.size.to_be_bytes());
+
+        // NOTE: Truncation is OK, a value cannot have more than u32 chunks
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.extend_from_slice(&(self.chunks.len() as u32).to_be_bytes());
+
+        for hash in &self.chunks {
+            bytes.extend_from_slice(&hash.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserializes a handle that was serialized using [`CompositeHandle::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the bytes are not a valid handle.
+    pub fn from_bytes(mut bytes: &[u8]) -> crate::Result<Self> {
+        let size = bytes.read_u64::<BigEndian>().map_err(DecodeError::from)?;
+        let chunk_count = bytes.read_u32::<BigEndian>().map_err(DecodeError::from)?;
+
+        let chunks = (0..chunk_count)
+            .map(|_| bytes.read_u128::<BigEndian>())
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(DecodeError::from)?;
+
+        if !bytes.is_empty() {
+            return Err(crate::Error::Decode(DecodeError::InvalidHeader(
+                "CompositeHandle",
+            )));
+        }
+
+        Ok(Self { chunks, size })
+    }
+}
+
+struct ChunkEntry {
+    vhandle: ValueHandle,
+    size: u32,
+    refcount: u64,
+}
+
+fn chunk_hash(key: &[u8]) -> Option<ChunkHash> {
+    key.try_into().ok().map(ChunkHash::from_be_bytes)
+}
+
+/// Reference-counted chunks, keyed by their hash
+///
+/// Acts as the index of the chunk blobs, so garbage collection
+/// only keeps chunks that are still referenced.
+#[derive(Clone, Default)]
+struct ChunkIndex(Arc<Mutex<crate::HashMap<ChunkHash, ChunkEntry>>>);
+
+impl ChunkIndex {
+    fn lock(&self) -> MutexGuard<'_, crate::HashMap<ChunkHash, ChunkEntry>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes a reference to the chunk, if it exists.
+    fn acquire(&self, hash: ChunkHash) -> bool {
+        self.lock().get_mut(&hash).is_some_and(|entry| {
+            entry.refcount += 1;
+            true
+        })
+    }
+
+    /// Drops a reference to the chunk, removing it when it is not referenced anymore.
+    fn release(chunks: &mut crate::HashMap<ChunkHash, ChunkEntry>, hash: ChunkHash) {
+        if let Entry::Occupied(mut entry) = chunks.entry(hash) {
+            entry.get_mut().refcount = entry.get().refcount.saturating_sub(1);
+
+            if entry.get().refcount == 0 {
+                entry.remove();
+            }
+        }
+    }
+
+    fn encode_into<W: Write>(
+        chunks: &crate::HashMap<ChunkHash, ChunkEntry>,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        writer.write_all(DEDUP_INDEX_MAGIC)?;
+        writer.write_u64::<BigEndian>(chunks.len() as u64)?;
+
+        for (hash, entry) in chunks {
+            writer.write_u128::<BigEndian>(*hash)?;
+            writer.write_u64::<BigEndian>(entry.vhandle.segment_id)?;
+            writer.write_u64::<BigEndian>(entry.vhandle.offset)?;
+            writer.write_u32::<BigEndian>(entry.size)?;
+            writer.write_u64::<BigEndian>(entry.refcount)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut magic = [0u8; DEDUP_INDEX_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+
+        if magic != DEDUP_INDEX_MAGIC {
+            return Err(DecodeError::InvalidHeader("DedupIndex"));
+        }
+
+        let len = reader.read_u64::<BigEndian>()?;
+        let mut chunks = crate::HashMap::default();
+
+        for _ in 0..len {
+            let hash = reader.read_u128::<BigEndian>()?;
+            let segment_id = reader.read_u64::<BigEndian>()?;
+            let offset = reader.read_u64::<BigEndian>()?;
+            let size = reader.read_u32::<BigEndian>()?;
+            let refcount = reader.read_u64::<BigEndian>()?;
+
+            chunks.insert(
+                hash,
+                ChunkEntry {
+                    vhandle: ValueHandle { segment_id, offset },
+                    size,
+                    refcount,
+                },
+            );
+        }
+
+        Ok(Self(Arc::new(Mutex::new(chunks))))
+    }
+}
+
+impl IndexReader for ChunkIndex {
+    fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+        Ok(self.get_with_size(key)?.map(|x| x.vhandle))
+    }
+
+    fn get_with_size(&self, key: &[u8]) -> std::io::Result<Option<SizedValueHandle>> {
+        let Some(hash) = chunk_hash(key) else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .lock()
+            .get(&hash)
+            .map(|entry| entry.vhandle.clone().with_size(entry.size)))
+    }
+}
+
+impl IndexWriter for ChunkIndex {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        if let Some(hash) = chunk_hash(key) {
+            // NOTE: Only moves chunks, references are counted by the writers
+            if let Some(entry) = self.lock().get_mut(&hash) {
+                entry.vhandle = vhandle;
+                entry.size = size;
+            }
+        }
+        Ok(())
+    }
+
+    fn relocate_indirect(
+        &mut self,
+        key: &[u8],
+        expected: &ValueHandle,
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        if let Some(hash) = chunk_hash(key) {
+            match self.lock().get_mut(&hash) {
+                Some(entry) if entry.vhandle == *expected => {
+                    entry.vhandle = vhandle;
+                    entry.size = size;
+                }
+                _ => {
+                    log::trace!("Skipping relocation of chunk {hash:x}, it was released");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A value log that splits values into content-defined chunks,
+/// and stores every unique chunk only once
+///
+/// Chunks are stored as blobs keyed by their hash, and are reference counted.
+/// Writing a value returns a [`CompositeHandle`], which needs to be released using
+/// [`DedupValueLog::release`] once the value is not referenced anymore.
+/// Garbage collection only keeps chunks that are still referenced.
+///
+/// The chunk index is persisted in the value log's folder whenever it changes.
+///
+/// Chunks are identified by a non-cryptographic 128-bit hash, so this should not
+/// be used with adversarial inputs.
+#[derive(Clone)]
+pub struct DedupValueLog<C: Compressor + Clone> {
+    value_log: ValueLog<C>,
+    chunker: Chunker,
+    chunks: ChunkIndex,
+}
+
+impl<C: Compressor + Clone> DedupValueLog<C> {
+    /// Adds deduplication on top of a value log, recovering its chunk index.
+    ///
+    /// The value log should only be written to through the returned [`DedupValueLog`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the chunk index is corrupt.
+    pub fn open(value_log: ValueLog<C>, chunker: Chunker) -> crate::Result<Self> {
+        let path = value_log.path.join(DEDUP_INDEX_FILE);
+
+        let chunks = if value_log.config.fs.exists(&path)? {
+            let bytes = value_log.config.fs.read(&path)?;
+            ChunkIndex::decode_from(&mut &bytes[..])?
+        } else {
+            ChunkIndex::default()
+        };
+
+        Ok(Self {
+            value_log,
+            chunker,
+            chunks,
+        })
+    }
+
+    /// Returns the underlying value log.
+    #[must_use]
+    pub fn value_log(&self) -> &ValueLog<C> {
+        &self.value_log
+    }
+
+    /// Returns the amount of unique chunks that are referenced.
+    #[must_use]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.lock().len()
+    }
+
+    /// Returns the amount of references to the given chunk.
+    #[must_use]
+    pub fn refcount(&self, hash: ChunkHash) -> u64 {
+        self.chunks
+            .lock()
+            .get(&hash)
+            .map_or(0, |entry| entry.refcount)
+    }
+
+    fn persist(&self, chunks: &crate::HashMap<ChunkHash, ChunkEntry>) -> crate::Result<()> {
+        let mut bytes = vec![];
+        ChunkIndex::encode_into(chunks, &mut bytes)?;
+
+        self.value_log
+            .config
+            .fs
+            .rewrite_atomic(&self.value_log.path.join(DEDUP_INDEX_FILE), &bytes)?;
+
+        Ok(())
+    }
+
+    /// Initializes a new writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_writer(&self) -> crate::Result<DedupWriter<C>> {
+        Ok(DedupWriter {
+            writer: self.value_log.get_writer()?,
+            chunker: self.chunker.clone(),
+            new_chunks: crate::HashMap::default(),
+            acquired: AcquiredChunks {
+                chunks: self.chunks.clone(),
+                hashes: vec![],
+            },
+        })
+    }
+
+    /// Registers a [`DedupWriter`], making its new chunks available.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn register_writer(&self, writer: DedupWriter<C>) -> crate::Result<()> {
+        let DedupWriter {
+            writer,
+            new_chunks,
+            mut acquired,
+            ..
+        } = writer;
+
+        // IMPORTANT: Chunks need to be persisted before they are referenced
+        self.value_log.register_writer(writer)?;
+
+        let mut chunks = self.chunks.lock();
+
+        for (hash, entry) in new_chunks {
+            match chunks.entry(hash) {
+                Entry::Occupied(mut existing) => {
+                    // NOTE: Another writer stored the same chunk in the meantime,
+                    // so our copy is never referenced and will be garbage collected
+                    existing.get_mut().refcount += entry.refcount;
+                }
+                Entry::Vacant(vacant) => {
+                    vacant.insert(entry);
+                }
+            }
+        }
+
+        acquired.hashes.clear();
+
+        self.persist(&chunks)
+    }
+
+    /// Resolves a composite handle.
+    ///
+    /// Returns `None` if any of its chunks does not exist anymore.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get(&self, handle: &CompositeHandle) -> crate::Result<Option<UserValue>> {
+        // NOTE: Truncation is OK, the value was held in memory when it was written
+        #[allow(clippy::cast_possible_truncation)]
+        let mut value = Vec::with_capacity(handle.size as usize);
+
+        for hash in &handle.chunks {
+            let Some(chunk) = self.get_chunk(*hash)? else {
+                return Ok(None);
+            };
+
+            value.extend_from_slice(&chunk);
+        }
+
+        Ok(Some(value.into()))
+    }
+
+    /// Resolves a single chunk.
+    pub(crate) fn get_chunk(&self, hash: ChunkHash) -> crate::Result<Option<UserValue>> {
+        let Some(vhandle) = self.chunks.get(&hash.to_be_bytes())? else {
+            return Ok(None);
+        };
+
+        self.value_log.get(&vhandle)
+    }
+
+    /// Drops the references of the given values to their chunks.
+    ///
+    /// Chunks that are not referenced anymore become stale.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn release(&self, handles: &[CompositeHandle]) -> crate::Result<()> {
+        self.release_chunks(handles.iter().flat_map(|x| &x.chunks).copied())
+    }
+
+    /// Drops one reference to each of the given chunks.
+    pub(crate) fn release_chunks<I: IntoIterator<Item = ChunkHash>>(
+        &self,
+        hashes: I,
+    ) -> crate::Result<()> {
+        let mut chunks = self.chunks.lock();
+
+        for hash in hashes {
+            ChunkIndex::release(&mut chunks, hash);
+        }
+
+        self.persist(&chunks)
+    }
+
+    /// Scans all segments to collect GC statistics, where a chunk
+    /// is alive as long as it is referenced.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_for_stats(&self) -> crate::Result<GcReport> {
+        self.value_log.scan_index_for_stats(&self.chunks)
+    }
+
+    /// Applies a GC strategy, relocating referenced chunks.
+    ///
+    /// Like [`ValueLog::apply_gc_strategy`], the rewritten segments are only marked
+    /// as stale, and need to be dropped using [`ValueLog::drop_stale_segments`].
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn apply_gc_strategy(&self, strategy: &impl GcStrategy<C>) -> crate::Result<u64> {
+        let freed =
+            self.value_log
+                .apply_gc_strategy(strategy, &self.chunks, self.chunks.clone())?;
+
+        // IMPORTANT: The new chunk locations need to be persisted
+        // before the old segments are dropped
+        self.persist(&self.chunks.lock())?;
+
+        Ok(freed)
+    }
+
+    /// Rewrites all segments, relocating referenced chunks,
+    /// and drops the rewritten segments (see [`ValueLog::major_compact`]).
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn major_compact(&self) -> crate::Result<u64> {
+        let size_before = self.value_log.manifest.disk_space_used();
+
+        let ids = self.value_log.manifest.list_segment_ids();
+        self.value_log
+            .rollover(&ids, &self.chunks, self.chunks.clone())?;
+
+        // IMPORTANT: The chunk index needs to be durable before the old segments are dropped
+        self.persist(&self.chunks.lock())?;
+        self.value_log.drop_stale_segments()?;
+
+        Ok(size_before.saturating_sub(self.value_log.manifest.disk_space_used()))
+    }
+}
+
+/// References to existing chunks, which are dropped again
+/// if the writer is never registered
+struct AcquiredChunks {
+    chunks: ChunkIndex,
+    hashes: Vec<ChunkHash>,
+}
+
+impl Drop for AcquiredChunks {
+    fn drop(&mut self) {
+        if self.hashes.is_empty() {
+            return;
+        }
+
+        let mut chunks = self.chunks.lock();
+
+        for hash in &self.hashes {
+            ChunkIndex::release(&mut chunks, *hash);
+        }
+    }
+}
+
+/// Segment writer of a [`DedupValueLog`]
+#[allow(clippy::module_name_repetitions)]
+pub struct DedupWriter<C: Compressor + Clone> {
+    writer: SegmentWriter<C>,
+    chunker: Chunker,
+    new_chunks: crate::HashMap<ChunkHash, ChunkEntry>,
+    acquired: AcquiredChunks,
+}
+
+impl<C: Compressor + Clone> DedupWriter<C> {
+    /// Writes a value, only storing chunks that do not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn write<V: AsRef<[u8]>>(&mut self, value: V) -> crate::Result<CompositeHandle> {
+        let value = value.as_ref();
+        let mut chunks = vec![];
+
+        for chunk in self.chunker.split(value) {
+            chunks.push(self.write_chunk(chunk)?);
+        }
+
+        Ok(CompositeHandle {
+            chunks,
+            size: value.len() as u64,
+        })
+    }
+
+    /// Takes a reference to the chunk, storing it if it does not exist yet.
+    pub(crate) fn write_chunk(&mut self, chunk: &[u8]) -> crate::Result<ChunkHash> {
+        let hash = xxhash_rust::xxh3::xxh3_128(chunk);
+
+        if let Some(entry) = self.new_chunks.get_mut(&hash) {
+            entry.refcount += 1;
+            return Ok(hash);
+        }
+
+        // NOTE: The reference is taken right away, so the
+        // chunk cannot be released before we are registered
+        if self.acquired.chunks.acquire(hash) {
+            self.acquired.hashes.push(hash);
+            return Ok(hash);
+        }
+
+        let vhandle = self.writer.get_next_value_handle();
+        self.writer.write(hash.to_be_bytes(), chunk)?;
+
+        // NOTE: Truncation is OK, chunks are smaller than the maximum value size
+        #[allow(clippy::cast_possible_truncation)]
+        self.new_chunks.insert(
+            hash,
+            ChunkEntry {
+                vhandle,
+                size: chunk.len() as u32,
+                refcount: 1,
+            },
+        );
+
+        Ok(hash)
+    }
+}
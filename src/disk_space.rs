@@ -0,0 +1,44 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+/// Breakdown of the disk space used by a value log, see [`ValueLog::disk_space_breakdown`](crate::ValueLog::disk_space_breakdown)
+///
+/// Stale and live blob bytes are estimated from the segments' GC statistics,
+/// so they are only as accurate as the last scan.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DiskSpaceBreakdown {
+    /// Amount of (compressed) blob bytes that are still referenced
+    pub live_blob_bytes: u64,
+
+    /// Amount of (compressed) blob bytes that are not referenced anymore
+    ///
+    /// Bytes that were deallocated by punching holes are not included.
+    pub stale_blob_bytes: u64,
+
+    /// Bytes of segment files that are not blob data: blob headers,
+    /// checksums, keys, segment metadata and trailers
+    pub format_overhead_bytes: u64,
+
+    /// Size of the manifest file
+    pub manifest_bytes: u64,
+
+    /// Bytes of segment files that are not referenced by the manifest
+    ///
+    /// These are written by writers that are not registered yet, or were left behind
+    /// by writers that were dropped or a crash, and are deleted on recovery.
+    pub quarantined_bytes: u64,
+}
+
+impl DiskSpaceBreakdown {
+    /// Returns the sum of all categories.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.live_blob_bytes
+            + self.stale_blob_bytes
+            + self.format_overhead_bytes
+            + self.manifest_bytes
+            + self.quarantined_bytes
+    }
+}
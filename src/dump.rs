@@ -0,0 +1,265 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Record-level access to segment files, for inspection tools
+//!
+//! A [`SegmentDump`] reads a segment file by its path, without opening the value log
+//! it belongs to, and yields every blob record with its offset, layout, sizes and checksum.
+//! Values are only decoded if the pipeline the segment was written with
+//! is provided, see [`SegmentDump::decode_with`].
+//!
+//! ```
+//! # use value_log::{dump::SegmentDump, Config, ValueLog};
+//! #
+//! # #[derive(Clone, Default)]
+//! # struct MyCompressor;
+//! #
+//! # impl value_log::Compressor for MyCompressor {
+//! #    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! #
+//! #    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! # }
+//! # fn main() -> value_log::Result<()> {
+//! # let folder = tempfile::tempdir()?;
+//! # let value_log = ValueLog::open(folder.path(), Config::<MyCompressor>::default())?;
+//! # let mut writer = value_log.get_writer()?;
+//! # writer.write("a", "hello")?;
+//! # value_log.register_writer(writer)?;
+//! # let path = value_log.manifest.list_segments().pop().unwrap().path.clone();
+//! let dump = SegmentDump::open(&path)?.decode_with(&Config::<MyCompressor>::default())?;
+//! println!("{} blobs", dump.metadata().item_count);
+//!
+//! for record in dump {
+//!     let record = record?;
+//!     println!("{:?} @ {}: {}B stored, valid = {}", record.key, record.offset, record.stored_len, record.is_checksum_valid);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    coding::DecodeError,
+    format::{self, BlobLayout, RecordDescription},
+    id::SegmentId,
+    pipeline::Pipeline,
+    Compressor, Config, SegmentMetadata, Slice, UserKey, UserValue,
+};
+use std::{path::Path, vec::IntoIter};
+
+type Decoder = Box<dyn Fn(Vec<u8>) -> crate::Result<Vec<u8>> + Send + Sync>;
+
+/// Blob record of a segment file, see [`SegmentDump`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct DumpRecord {
+    /// Offset of the record in the segment file
+    pub offset: u64,
+
+    /// Size of the record on disk, including its header
+    pub len: u64,
+
+    /// Layout of the record
+    pub layout: BlobLayout,
+
+    /// Key of the blob, with its delta-encoded prefix resolved
+    pub key: UserKey,
+
+    /// Amount of stored (possibly compressed) value bytes
+    pub stored_len: u64,
+
+    /// Amount of chunks the value is stored in, 0 if the blob is not chunked
+    pub chunk_count: u32,
+
+    /// Stored checksum, covering the key and the stored value
+    pub checksum: u64,
+
+    /// Whether the stored checksum matches the key and stored value
+    pub is_checksum_valid: bool,
+
+    /// Decoded value, if the segment's pipeline was provided (see [`SegmentDump::decode_with`])
+    pub value: Option<UserValue>,
+}
+
+/// Iterator over the blob records of a segment file
+///
+/// See the [module documentation](self) for more.
+pub struct SegmentDump {
+    bytes: Vec<u8>,
+    segment_id: SegmentId,
+    metadata: SegmentMetadata,
+    records: IntoIter<RecordDescription>,
+    prev_key: Option<UserKey>,
+    decoder: Option<Decoder>,
+}
+
+impl SegmentDump {
+    /// Reads a segment file.
+    ///
+    /// The segment ID (which may be needed to decode values) is taken from the file name.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the file is not a valid segment file.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let path = path.as_ref();
+
+        let segment_id = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.parse::<SegmentId>().ok())
+            .unwrap_or_default();
+
+        Self::from_bytes(segment_id, std::fs::read(path)?)
+    }
+
+    /// Parses the contents of a segment file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the bytes are not a valid segment file.
+    pub fn from_bytes(segment_id: SegmentId, bytes: Vec<u8>) -> crate::Result<Self> {
+        let description = format::describe_segment(&bytes)?;
+
+        Ok(Self {
+            bytes,
+            segment_id,
+            metadata: description.metadata,
+            records: description.records.into_iter(),
+            prev_key: None,
+            decoder: None,
+        })
+    }
+
+    /// Decodes values using the compressor, transforms and encryptor of the given config,
+    /// according to the pipeline recorded in the segment's metadata.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a stage of the segment's pipeline is not configured.
+    pub fn decode_with<C: Compressor + Clone + Send + Sync + 'static>(
+        mut self,
+        config: &Config<C>,
+    ) -> crate::Result<Self> {
+        let pipeline = Pipeline::resolve(config, self.segment_id, &self.metadata.pipeline)?;
+        let segment_id = self.segment_id;
+
+        self.decoder = Some(Box::new(move |value| pipeline.revert(segment_id, value)));
+
+        Ok(self)
+    }
+
+    /// Returns the ID of the segment.
+    #[must_use]
+    pub fn segment_id(&self) -> SegmentId {
+        self.segment_id
+    }
+
+    /// Returns the segment's metadata, which includes the pipeline (compression,
+    /// transforms, encryption) its values were written with.
+    #[must_use]
+    pub fn metadata(&self) -> &SegmentMetadata {
+        &self.metadata
+    }
+
+    fn resolve_key(&mut self, record: &RecordDescription) -> crate::Result<UserKey> {
+        let suffix = &record.header.key;
+
+        let key = if record.header.layout.prefixed {
+            let prefix = self
+                .prev_key
+                .as_deref()
+                .and_then(|x| x.get(..usize::from(record.header.shared_prefix_len)))
+                .ok_or(crate::Error::Decode(DecodeError::InvalidHeader("Blob")))?;
+
+            Slice::from([prefix, suffix].concat())
+        } else {
+            Slice::from(&**suffix)
+        };
+
+        self.prev_key = Some(key.clone());
+
+        Ok(key)
+    }
+
+    fn dump_record(&mut self, record: &RecordDescription) -> crate::Result<DumpRecord> {
+        let key = self.resolve_key(record)?;
+
+        let stored = usize::try_from(record.offset + record.len)
+            .ok()
+            .and_then(|end| self.bytes.get(..end))
+            .unwrap_or_default();
+
+        // NOTE: Chunks are prefixed by their length, the value directly follows the header
+        let stored_parts = if record.header.layout.chunked {
+            let mut end = stored.len();
+
+            let mut chunks = record
+                .chunk_lens
+                .iter()
+                .rev()
+                .map(|&len| {
+                    let start = end.saturating_sub(len as usize);
+                    let chunk = stored.get(start..end).unwrap_or_default();
+                    end = start.saturating_sub(std::mem::size_of::<u32>());
+                    chunk
+                })
+                .collect::<Vec<_>>();
+
+            chunks.reverse();
+            chunks
+        } else {
+            vec![stored
+                .get(stored.len().saturating_sub(record.header.len as usize)..)
+                .unwrap_or_default()]
+        };
+
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        hasher.update(&key);
+        for part in &stored_parts {
+            hasher.update(part);
+        }
+
+        let value = match &self.decoder {
+            Some(decoder) => {
+                let mut value = vec![];
+
+                for part in &stored_parts {
+                    value.extend(decoder(part.to_vec())?);
+                }
+
+                Some(Slice::from(value))
+            }
+            None => None,
+        };
+
+        Ok(DumpRecord {
+            offset: record.offset,
+            len: record.len,
+            layout: record.header.layout,
+            key,
+            stored_len: stored_parts.iter().map(|x| x.len() as u64).sum(),
+            chunk_count: if record.header.layout.chunked {
+                record.header.len
+            } else {
+                0
+            },
+            checksum: record.header.checksum,
+            is_checksum_valid: hasher.digest() == record.header.checksum,
+            value,
+        })
+    }
+}
+
+impl Iterator for SegmentDump {
+    type Item = crate::Result<DumpRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        Some(self.dump_record(&record))
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::id::SegmentId;
+
+/// Trait that allows encrypting blobs at rest
+///
+/// Values are encrypted after compression, and decrypted before decompression.
+/// Keys are stored in plaintext, because they are needed to look up blobs in the index.
+///
+/// Every segment records the version of the key its blobs are encrypted with,
+/// so the encryptor needs to be able to decrypt data of every key version that
+/// is still in use.
+///
+/// The segment ID is passed in so implementations can derive per-segment keys.
+/// Because every blob is encrypted on its own, implementations that need a unique
+/// nonce per encryption should store the nonce inside the returned bytes.
+pub trait Encryptor: Send + Sync {
+    /// Returns the version of the key new segments are encrypted with.
+    fn key_version(&self) -> u32;
+
+    /// Encrypts a (compressed) value that is written into the given segment.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value could not be encrypted.
+    fn encrypt(
+        &self,
+        segment_id: SegmentId,
+        key_version: u32,
+        bytes: &[u8],
+    ) -> crate::Result<Vec<u8>>;
+
+    /// Decrypts a value that was read from the given segment.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value could not be decrypted.
+    fn decrypt(
+        &self,
+        segment_id: SegmentId,
+        key_version: u32,
+        bytes: &[u8],
+    ) -> crate::Result<Vec<u8>>;
+}
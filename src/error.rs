@@ -4,8 +4,101 @@
 
 use crate::{
     coding::{DecodeError, EncodeError},
+    id::SegmentId,
     version::Version,
 };
+use std::path::PathBuf;
+
+/// Describes the I/O operation an error occurred in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IoContext {
+    /// Operation that failed
+    pub operation: &'static str,
+
+    /// File that was accessed
+    pub path: Option<PathBuf>,
+
+    /// Segment that was accessed
+    pub segment_id: Option<SegmentId>,
+
+    /// Offset in the file that was accessed
+    pub offset: Option<u64>,
+}
+
+impl IoContext {
+    /// Creates a context for the given operation.
+    #[must_use]
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the file that was accessed.
+    #[must_use]
+    pub fn path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Sets the segment that was accessed.
+    #[must_use]
+    pub fn segment_id(mut self, segment_id: SegmentId) -> Self {
+        self.segment_id = Some(segment_id);
+        self
+    }
+
+    /// Sets the offset in the file that was accessed.
+    #[must_use]
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl std::fmt::Display for IoContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.operation)?;
+
+        if let Some(segment_id) = self.segment_id {
+            write!(f, " segment #{segment_id}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, " ({})", path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Broad category of an [`Error`]
+///
+/// New error variants are assigned to one of these categories,
+/// so matching on the category does not break when variants are added.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// An I/O operation failed
+    Io,
+
+    /// Data on disk is invalid (e.g. a checksum mismatch or a truncated file)
+    Corruption,
+
+    /// The value log is used in an unsupported way
+    /// (e.g. an incompatible data format version)
+    Config,
+
+    /// The value log (or a resource it needs) is not available anymore
+    Resource,
+
+    /// Lost a race with another operation on the value log
+    Concurrency,
+}
 
 /// Represents errors that can occur in the value log
 #[derive(Debug)]
@@ -14,6 +107,15 @@ pub enum Error {
     /// I/O error
     Io(std::io::Error),
 
+    /// I/O error, with information about the operation that failed
+    IoWithContext {
+        /// Underlying I/O error
+        source: std::io::Error,
+
+        /// Operation that failed
+        ctx: IoContext,
+    },
+
     /// Invalid data format version
     InvalidVersion(Option<Version>),
 
@@ -28,18 +130,130 @@ pub enum Error {
 
     /// Decompression failed
     Decompress,
-    // TODO:
-    // /// Checksum check failed
-    // ChecksumMismatch,
+
+    /// Encryption failed
+    Encrypt,
+
+    /// Decryption failed, or an encrypted segment was read without an encryptor
+    Decrypt,
+
+    /// Segment was written with a transform (see [`Config::transform`](crate::Config::transform))
+    /// that is not configured
+    MissingTransform(u8),
+
+    /// Checksum check failed
+    ChecksumMismatch,
+
+    /// Segment writer was not created by this value log, or was created
+    /// before the value log's segment list was replaced
+    StaleWriter,
+
+    /// Value log was closed
+    Closed,
+
+    /// There is no value log in the directory
+    NotFound,
+
+    /// There already is a value log in the directory
+    AlreadyExists,
+
+    /// The value log is already opened, possibly by another process
+    ///
+    /// Only detected if the file system supports locking (see [`Fs::try_lock`](crate::Fs::try_lock)).
+    Locked,
+
+    /// The value log's segment list (manifest) cannot be read
+    CorruptManifest,
+
+    /// Writing would exceed the disk usage quota (see [`Config::max_disk_usage`](crate::Config::max_disk_usage))
+    QuotaExceeded,
+
+    /// Segment is damaged beyond what its parity can repair,
+    /// or it has no (intact) parity
+    Unrepairable(SegmentId),
+
+    /// Blob belongs to a segment that is older than the configured retention
+    /// (see [`Config::expired_reads_error`](crate::Config::expired_reads_error))
+    Expired(SegmentId),
+
+    /// The value log has remapped blobs whose new locations were not applied to the index yet,
+    /// so it cannot be backed up or exported (see [`ValueLog::apply_remaps`](crate::ValueLog::apply_remaps))
+    PendingRemaps,
+}
+
+impl Error {
+    /// Attaches context to an I/O error.
+    ///
+    /// Other errors (and I/O errors that already have context) are returned as is.
+    #[must_use]
+    pub fn with_context(self, ctx: IoContext) -> Self {
+        match self {
+            Self::Io(source) => Self::IoWithContext { source, ctx },
+            e => e,
+        }
+    }
+
+    /// Returns the category of the error.
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Io(e) | Self::IoWithContext { source: e, .. } => match e.kind() {
+                std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::InvalidData => {
+                    ErrorCategory::Corruption
+                }
+                std::io::ErrorKind::OutOfMemory => ErrorCategory::Resource,
+                _ => ErrorCategory::Io,
+            },
+            Self::Encode(_) => ErrorCategory::Io,
+            Self::Decode(_)
+            | Self::Decompress
+            | Self::Decrypt
+            | Self::ChecksumMismatch
+            | Self::CorruptManifest
+            | Self::Unrepairable(_) => ErrorCategory::Corruption,
+            Self::InvalidVersion(_)
+            | Self::Compress
+            | Self::Encrypt
+            | Self::MissingTransform(_)
+            | Self::NotFound
+            | Self::AlreadyExists
+            | Self::PendingRemaps => ErrorCategory::Config,
+            Self::Closed | Self::QuotaExceeded | Self::Expired(_) => ErrorCategory::Resource,
+            Self::StaleWriter | Self::Locked => ErrorCategory::Concurrency,
+        }
+    }
+
+    /// Returns the underlying I/O error, if this is an I/O error.
+    #[must_use]
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::Io(e) | Self::IoWithContext { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ValueLogError: {self:?}")
+        match self {
+            Self::IoWithContext { source, ctx } => {
+                write!(f, "ValueLogError: failed to {ctx}: {source}")
+            }
+            _ => write!(f, "ValueLogError: {self:?}"),
+        }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) | Self::IoWithContext { source: e, .. } => Some(e),
+            Self::Encode(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
@@ -0,0 +1,207 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Failure injection points, which can only be triggered with the `failpoints` feature enabled
+//!
+//! Tests configure failure points within a [`FailScenario`], to deterministically
+//! simulate I/O errors (or panics) at specific points of writes, fsyncs,
+//! manifest updates and rollovers.
+//!
+//! Without the feature, the injection points compile to nothing.
+//!
+//! ```
+//! # #[cfg(feature = "failpoints")]
+//! # {
+//! use value_log::failpoints::{FailPoint, FailScenario, ROLLOVER_RELOCATE};
+//!
+//! let scenario = FailScenario::setup();
+//!
+//! // Fail relocating the third blob of the next rollover
+//! scenario.configure(ROLLOVER_RELOCATE, FailPoint::error().skip(2).times(1));
+//! # }
+//! ```
+
+#[cfg(feature = "failpoints")]
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Writing a blob into a segment
+pub const SEGMENT_WRITE: &str = "segment::write";
+
+/// Making a finished segment file durable
+pub const SEGMENT_FSYNC: &str = "segment::fsync";
+
+/// Rewriting the segment manifest
+pub const MANIFEST_WRITE: &str = "manifest::write";
+
+/// Rollover: relocating a live blob into the new segment(s)
+pub const ROLLOVER_RELOCATE: &str = "rollover::relocate";
+
+/// Rollover: registering the new segment(s), after all blobs were relocated
+pub const ROLLOVER_REGISTER: &str = "rollover::register";
+
+/// Rollover: finishing the index write batch, after the new segment(s) were registered
+pub const ROLLOVER_INDEX_FINISH: &str = "rollover::index_finish";
+
+/// Action taken when a failure point triggers
+#[cfg(feature = "failpoints")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Action {
+    Error,
+    Panic,
+}
+
+/// Configuration of a failure point
+#[cfg(feature = "failpoints")]
+#[derive(Clone, Debug)]
+pub struct FailPoint {
+    action: Action,
+    skip: usize,
+    times: Option<usize>,
+    hits: usize,
+}
+
+#[cfg(feature = "failpoints")]
+impl FailPoint {
+    fn new(action: Action) -> Self {
+        Self {
+            action,
+            skip: 0,
+            times: None,
+            hits: 0,
+        }
+    }
+
+    /// Makes the failure point return an I/O error.
+    #[must_use]
+    pub fn error() -> Self {
+        Self::new(Action::Error)
+    }
+
+    /// Makes the failure point panic, e.g. to simulate a crash.
+    #[must_use]
+    pub fn panic() -> Self {
+        Self::new(Action::Panic)
+    }
+
+    /// Lets the first `n` hits pass, before the failure point triggers.
+    ///
+    /// Default = 0
+    #[must_use]
+    pub fn skip(mut self, n: usize) -> Self {
+        self.skip = n;
+        self
+    }
+
+    /// Lets the failure point trigger only `n` times, after which it passes again.
+    ///
+    /// Default = unlimited
+    #[must_use]
+    pub fn times(mut self, n: usize) -> Self {
+        self.times = Some(n);
+        self
+    }
+}
+
+#[cfg(feature = "failpoints")]
+static REGISTRY: Mutex<BTreeMap<String, FailPoint>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "failpoints")]
+static SCENARIO: Mutex<()> = Mutex::new(());
+
+#[cfg(feature = "failpoints")]
+fn registry() -> MutexGuard<'static, BTreeMap<String, FailPoint>> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Exclusive access to the failure points
+///
+/// Failure points are process-wide, so scenarios are serialized: setting up a scenario
+/// blocks until the previous one is dropped. All failure points are removed when
+/// the scenario is set up and dropped.
+#[cfg(feature = "failpoints")]
+pub struct FailScenario {
+    _guard: MutexGuard<'static, ()>,
+}
+
+#[cfg(feature = "failpoints")]
+impl FailScenario {
+    /// Sets up a new scenario without any failure points.
+    #[must_use]
+    pub fn setup() -> Self {
+        let guard = SCENARIO.lock().unwrap_or_else(PoisonError::into_inner);
+        registry().clear();
+
+        Self { _guard: guard }
+    }
+
+    /// Configures a failure point, replacing its previous configuration.
+    pub fn configure(&self, name: &str, fail_point: FailPoint) {
+        registry().insert(name.into(), fail_point);
+    }
+
+    /// Removes a failure point.
+    pub fn remove(&self, name: &str) {
+        registry().remove(name);
+    }
+
+    /// Returns how often a configured failure point was hit (whether it triggered or not).
+    #[must_use]
+    pub fn hits(&self, name: &str) -> usize {
+        registry().get(name).map_or(0, |x| x.hits)
+    }
+}
+
+#[cfg(feature = "failpoints")]
+impl Drop for FailScenario {
+    fn drop(&mut self) {
+        registry().clear();
+    }
+}
+
+/// Evaluates a failure point, returning an error if it triggers.
+#[cfg(feature = "failpoints")]
+pub(crate) fn eval(name: &str) -> std::io::Result<()> {
+    let mut registry = registry();
+
+    let Some(fail_point) = registry.get_mut(name) else {
+        return Ok(());
+    };
+
+    fail_point.hits += 1;
+
+    if fail_point.skip > 0 {
+        fail_point.skip -= 1;
+        return Ok(());
+    }
+
+    match &mut fail_point.times {
+        Some(0) => return Ok(()),
+        Some(n) => *n -= 1,
+        None => {}
+    }
+
+    let action = fail_point.action;
+    drop(registry);
+
+    log::debug!("Failure point {name} triggered");
+
+    match action {
+        Action::Error => Err(std::io::Error::other(format!(
+            "failure point {name} triggered"
+        ))),
+        Action::Panic => panic!("failure point {name} triggered"),
+    }
+}
+
+/// Evaluates a failure point, returning an error if it triggers.
+#[cfg(not(feature = "failpoints"))]
+#[inline]
+#[allow(clippy::unnecessary_wraps)]
+pub fn eval(name: &str) -> std::io::Result<()> {
+    let _ = name;
+    Ok(())
+}
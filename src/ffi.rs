@@ -0,0 +1,438 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! C ABI bindings.
+//!
+//! Value logs, segment writers and returned values are passed as opaque handles,
+//! which need to be released using their respective `*_close`, `*_discard` or `*_free` function.
+//!
+//! Functions return [`VLOG_OK`] on success, [`VLOG_NOT_FOUND`] if an item does not exist,
+//! and [`VLOG_ERROR`] on failure (details are logged using the `log` crate).
+//! Panics are caught at the boundary, and reported as failures (or NULL).
+//!
+//! Value logs opened through the C ABI do not use compression.
+//!
+//! To link against the bindings, build the crate as a `staticlib` or `cdylib`, e.g.:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+
+// NOTE: Dealing with raw pointers handed to us over the C ABI is inherently unsafe
+#![allow(unsafe_code)]
+
+use crate::{Compressor, Config, IndexReader, IndexWriter, SegmentWriter, ValueHandle, ValueLog};
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    panic::AssertUnwindSafe,
+    ptr::null_mut,
+};
+
+/// The operation succeeded
+pub const VLOG_OK: c_int = 0;
+
+/// The requested item does not exist
+pub const VLOG_NOT_FOUND: c_int = 1;
+
+/// The operation failed
+pub const VLOG_ERROR: c_int = -1;
+
+/// Compressor of value logs opened through the C ABI (does not compress)
+#[derive(Clone, Default)]
+pub struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> crate::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> crate::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Opaque value log handle
+pub struct VlogHandle(ValueLog<NoCompressor>);
+
+/// Opaque segment writer handle
+pub struct VlogWriterHandle(SegmentWriter<NoCompressor>);
+
+/// C representation of a [`ValueHandle`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VlogValueHandle {
+    /// Segment ID
+    pub segment_id: u64,
+
+    /// Offset in file
+    pub offset: u64,
+}
+
+impl From<ValueHandle> for VlogValueHandle {
+    fn from(value: ValueHandle) -> Self {
+        Self {
+            segment_id: value.segment_id,
+            offset: value.offset,
+        }
+    }
+}
+
+impl From<VlogValueHandle> for ValueHandle {
+    fn from(value: VlogValueHandle) -> Self {
+        Self {
+            segment_id: value.segment_id,
+            offset: value.offset,
+        }
+    }
+}
+
+/// External index, implemented using C callbacks
+///
+/// Every callback receives `ctx` as its first argument.
+#[repr(C)]
+pub struct VlogIndex {
+    /// Opaque pointer passed to the callbacks
+    pub ctx: *mut c_void,
+
+    /// Looks up the value handle of a key, writing it into `out`.
+    ///
+    /// Needs to return [`VLOG_OK`], [`VLOG_NOT_FOUND`] or [`VLOG_ERROR`].
+    pub get: unsafe extern "C" fn(
+        ctx: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        out: *mut VlogValueHandle,
+    ) -> c_int,
+
+    /// Inserts a value handle into the index write batch.
+    ///
+    /// Needs to return [`VLOG_OK`] or [`VLOG_ERROR`].
+    pub insert_indirect: unsafe extern "C" fn(
+        ctx: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        vhandle: VlogValueHandle,
+        size: u32,
+    ) -> c_int,
+
+    /// Finishes the index write batch.
+    ///
+    /// Needs to return [`VLOG_OK`] or [`VLOG_ERROR`].
+    pub finish: unsafe extern "C" fn(ctx: *mut c_void) -> c_int,
+}
+
+struct CallbackIndex<'a>(&'a VlogIndex);
+
+impl IndexReader for CallbackIndex<'_> {
+    fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+        let mut out = VlogValueHandle::default();
+
+        // SAFETY: The caller of `vlog_rollover` guarantees the callbacks are valid
+        match unsafe { (self.0.get)(self.0.ctx, key.as_ptr(), key.len(), &mut out) } {
+            VLOG_OK => Ok(Some(out.into())),
+            VLOG_NOT_FOUND => Ok(None),
+            _ => Err(std::io::Error::other("index get callback failed")),
+        }
+    }
+}
+
+impl IndexWriter for CallbackIndex<'_> {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        // SAFETY: The caller of `vlog_rollover` guarantees the callbacks are valid
+        match unsafe {
+            (self.0.insert_indirect)(self.0.ctx, key.as_ptr(), key.len(), vhandle.into(), size)
+        } {
+            VLOG_OK => Ok(()),
+            _ => Err(std::io::Error::other("index insert callback failed")),
+        }
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        // SAFETY: The caller of `vlog_rollover` guarantees the callbacks are valid
+        match unsafe { (self.0.finish)(self.0.ctx) } {
+            VLOG_OK => Ok(()),
+            _ => Err(std::io::Error::other("index finish callback failed")),
+        }
+    }
+}
+
+/// Runs the body of an exported function, returning `on_panic` if it panics,
+/// because panics must not unwind across the C ABI.
+fn catch_panic<T, F: FnOnce() -> T>(name: &str, on_panic: T, f: F) -> T {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        log::error!("{name}: panicked");
+        on_panic
+    })
+}
+
+/// Builds a byte slice from a pointer & length, allowing empty slices to be passed as NULL.
+unsafe fn slice_from_raw<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Opens or recovers a value log in the given directory.
+///
+/// Returns NULL on error.
+///
+/// # Safety
+///
+/// `path` needs to be a valid, NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_open(path: *const c_char) -> *mut VlogHandle {
+    catch_panic("vlog_open", null_mut(), || {
+        if path.is_null() {
+            return null_mut();
+        }
+
+        let Ok(path) = CStr::from_ptr(path).to_str() else {
+            log::error!("vlog_open: path is not valid UTF-8");
+            return null_mut();
+        };
+
+        match ValueLog::open(path, Config::default()) {
+            Ok(value_log) => Box::into_raw(Box::new(VlogHandle(value_log))),
+            Err(e) => {
+                log::error!("vlog_open: {e:?}");
+                null_mut()
+            }
+        }
+    })
+}
+
+/// Closes a value log handle.
+///
+/// # Safety
+///
+/// `vlog` needs to be NULL or a handle returned by [`vlog_open`] that has not been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_close(vlog: *mut VlogHandle) {
+    catch_panic("vlog_close", (), || {
+        if !vlog.is_null() {
+            drop(Box::from_raw(vlog));
+        }
+    });
+}
+
+/// Initializes a new segment writer.
+///
+/// Returns NULL on error.
+///
+/// # Safety
+///
+/// `vlog` needs to be a valid value log handle.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_writer_create(vlog: *const VlogHandle) -> *mut VlogWriterHandle {
+    catch_panic("vlog_writer_create", null_mut(), || {
+        let Some(vlog) = vlog.as_ref() else {
+            return null_mut();
+        };
+
+        match vlog.0.get_writer() {
+            Ok(writer) => Box::into_raw(Box::new(VlogWriterHandle(writer))),
+            Err(e) => {
+                log::error!("vlog_writer_create: {e:?}");
+                null_mut()
+            }
+        }
+    })
+}
+
+/// Writes an item, storing its value handle into `out_vhandle`.
+///
+/// The key needs to be 1 - 65535 bytes long.
+///
+/// # Safety
+///
+/// `writer` needs to be a valid segment writer handle, `key` and `value` need to point
+/// to at least `key_len` and `value_len` bytes respectively, and `out_vhandle` needs to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_writer_write(
+    writer: *mut VlogWriterHandle,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+    out_vhandle: *mut VlogValueHandle,
+) -> c_int {
+    catch_panic("vlog_writer_write", VLOG_ERROR, || {
+        let Some(writer) = writer.as_mut() else {
+            return VLOG_ERROR;
+        };
+
+        // NOTE: Check the invariants the writer would otherwise panic on,
+        // so they are reported as proper errors
+        if key.is_null() || key_len == 0 || key_len > u16::MAX.into() {
+            log::error!("vlog_writer_write: invalid key");
+            return VLOG_ERROR;
+        }
+        if u32::try_from(value_len).is_err() {
+            log::error!("vlog_writer_write: value is too large");
+            return VLOG_ERROR;
+        }
+
+        let key = slice_from_raw(key, key_len);
+        let value = slice_from_raw(value, value_len);
+
+        let vhandle = writer.0.get_next_value_handle();
+
+        if let Err(e) = writer.0.write(key, value) {
+            log::error!("vlog_writer_write: {e:?}");
+            return VLOG_ERROR;
+        }
+
+        if let Some(out) = out_vhandle.as_mut() {
+            *out = vhandle.into();
+        }
+
+        VLOG_OK
+    })
+}
+
+/// Discards a segment writer without registering it.
+///
+/// # Safety
+///
+/// `writer` needs to be NULL or a valid segment writer handle, which is consumed.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_writer_discard(writer: *mut VlogWriterHandle) {
+    catch_panic("vlog_writer_discard", (), || {
+        if !writer.is_null() {
+            drop(Box::from_raw(writer));
+        }
+    });
+}
+
+/// Registers a segment writer, making its items readable.
+///
+/// # Safety
+///
+/// `vlog` needs to be a valid value log handle, and `writer` a valid segment writer handle.
+/// The writer handle is consumed, even if an error is returned.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_register_writer(
+    vlog: *const VlogHandle,
+    writer: *mut VlogWriterHandle,
+) -> c_int {
+    catch_panic("vlog_register_writer", VLOG_ERROR, || {
+        if writer.is_null() {
+            return VLOG_ERROR;
+        }
+        let writer = Box::from_raw(writer);
+
+        let Some(vlog) = vlog.as_ref() else {
+            return VLOG_ERROR;
+        };
+
+        match vlog.0.register_writer(writer.0) {
+            Ok(()) => VLOG_OK,
+            Err(e) => {
+                log::error!("vlog_register_writer: {e:?}");
+                VLOG_ERROR
+            }
+        }
+    })
+}
+
+/// Resolves a value handle.
+///
+/// On success, a newly allocated buffer is stored into `out_value` and `out_len`,
+/// which needs to be released using [`vlog_value_free`].
+///
+/// # Safety
+///
+/// `vlog` needs to be a valid value log handle, and `out_value` and `out_len` need to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_get(
+    vlog: *const VlogHandle,
+    vhandle: VlogValueHandle,
+    out_value: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    catch_panic("vlog_get", VLOG_ERROR, || {
+        let Some(vlog) = vlog.as_ref() else {
+            return VLOG_ERROR;
+        };
+        if out_value.is_null() || out_len.is_null() {
+            return VLOG_ERROR;
+        }
+
+        match vlog.0.get(&vhandle.into()) {
+            Ok(Some(value)) => {
+                let value: Box<[u8]> = value.to_vec().into_boxed_slice();
+                *out_len = value.len();
+                *out_value = Box::into_raw(value).cast::<u8>();
+                VLOG_OK
+            }
+            Ok(None) => VLOG_NOT_FOUND,
+            Err(e) => {
+                log::error!("vlog_get: {e:?}");
+                VLOG_ERROR
+            }
+        }
+    })
+}
+
+/// Releases a value returned by [`vlog_get`].
+///
+/// # Safety
+///
+/// `value` and `len` need to be NULL or exactly as returned by [`vlog_get`], and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_value_free(value: *mut u8, len: usize) {
+    catch_panic("vlog_value_free", (), || {
+        if !value.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                value, len,
+            )));
+        }
+    });
+}
+
+/// Rewrites the given segments, updating the external index through its callbacks.
+///
+/// Returns the amount of disk space (compressed data) freed, or -1 on error.
+///
+/// # Safety
+///
+/// `vlog` needs to be a valid value log handle, `ids` needs to point to at least
+/// `ids_len` segment IDs, and `index` needs to point to valid callbacks.
+#[no_mangle]
+pub unsafe extern "C" fn vlog_rollover(
+    vlog: *const VlogHandle,
+    ids: *const u64,
+    ids_len: usize,
+    index: *const VlogIndex,
+) -> i64 {
+    catch_panic("vlog_rollover", VLOG_ERROR.into(), || {
+        let (Some(vlog), Some(index)) = (vlog.as_ref(), index.as_ref()) else {
+            return VLOG_ERROR.into();
+        };
+
+        let ids = if ids_len == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(ids, ids_len)
+        };
+
+        match vlog
+            .0
+            .rollover(ids, &CallbackIndex(index), CallbackIndex(index))
+        {
+            Ok(bytes_freed) => i64::try_from(bytes_freed).unwrap_or(i64::MAX),
+            Err(e) => {
+                log::error!("vlog_rollover: {e:?}");
+                VLOG_ERROR.into()
+            }
+        }
+    })
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::{io::Write, path::Path};
+
+/// Atomically rewrites a file
+pub fn rewrite_atomic<P: AsRef<Path>>(path: P, content: &[u8]) -> std::io::Result<()> {
+    let path = path.as_ref();
+
+    #[allow(clippy::expect_used)]
+    let folder = path.parent().expect("should have a parent");
+
+    #[cfg(not(feature = "wasi"))]
+    {
+        let mut temp_file = tempfile::NamedTempFile::new_in(folder)?;
+        temp_file.write_all(content)?;
+        temp_file.persist(path)?;
+    }
+
+    #[cfg(feature = "wasi")]
+    {
+        // NOTE: WASI runtimes generally do not support the file handle
+        // gymnastics `NamedTempFile::persist` relies on, so we write a sibling file
+        // and rename it into place ourselves
+        #[allow(clippy::expect_used)]
+        let file_name = path.file_name().expect("should have a file name");
+
+        // NOTE: Concurrent rewrites of the same file must not share a temp file
+        let temp_path = folder.join(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            unique_suffix(),
+        ));
+
+        {
+            let mut temp_file = std::fs::File::create(&temp_path)?;
+            temp_file.write_all(content)?;
+            temp_file.sync_all()?;
+        }
+
+        std::fs::rename(&temp_path, path)?;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        // TODO: Not sure if the fsync is really required, but just for the sake of it...
+        // TODO: also not sure why it fails on Windows...
+        let file = std::fs::File::open(path)?;
+        file.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Returns a suffix for temp file names that is unique within the process,
+/// and (very likely) across processes.
+///
+/// Uses the current time instead of the process ID, which is not available in WASI.
+#[cfg(feature = "wasi")]
+fn unique_suffix() -> String {
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+
+    let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+    format!("{nanos:x}-{count}")
+}
+
+/// Fsyncs a directory, making sure newly created or deleted
+/// directory entries are durable.
+///
+/// This is a no-op on Windows, and when the `wasi` feature is enabled,
+/// as most WASI runtimes do not allow opening directories as files.
+#[cfg(not(any(target_os = "windows", feature = "wasi")))]
+pub fn fsync_directory<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    let folder = std::fs::File::open(path.as_ref())?;
+    folder.sync_all()
+}
+
+/// Fsyncs a directory, which is a no-op on Windows and WASI (see above).
+// NOTE: Keeps the fallible signature of the other platforms
+#[cfg(any(target_os = "windows", feature = "wasi"))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn fsync_directory<P: AsRef<Path>>(_path: P) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use test_log::test;
+
+    #[test]
+    fn test_atomic_rewrite() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let path = dir.path().join("test.txt");
+        {
+            let mut file = File::create(&path)?;
+            write!(file, "asdasdasdasdasd")?;
+        }
+
+        rewrite_atomic(&path, b"newcontent")?;
+
+        let content = std::fs::read_to_string(&path)?;
+        assert_eq!("newcontent", content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_atomic_rewrite_concurrent() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("test.txt");
+
+        std::thread::scope(|scope| {
+            let threads = (0..8)
+                .map(|idx| {
+                    let path = &path;
+                    scope.spawn(move || {
+                        (0..20).try_for_each(|_| rewrite_atomic(path, format!("{idx}").as_bytes()))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            threads
+                .into_iter()
+                .try_for_each(|x| x.join().expect("should join"))
+        })?;
+
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.parse::<u8>().is_ok_and(|x| x < 8));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Entry points into the deserializers of the on-disk formats
+//!
+//! The parsers handle bytes read from disk, which may be corrupted or crafted,
+//! so they are exposed for fuzzing. Invalid input results in an error, never a panic.
+//!
+//! The `describe_*` functions additionally report the structure of a file
+//! (format versions, record boundaries, layout flags), so compatibility tests
+//! can compare files written by different crate versions against golden files.
+//!
+//! With the `arbitrary` feature enabled, the format types implement
+//! `arbitrary::Arbitrary`, so fuzz targets can generate structured input and
+//! check that encoding & parsing round-trips.
+
+use crate::{
+    coding::Decode,
+    coding::DecodeError,
+    id::SegmentId,
+    manifest::{
+        decode_manifest, decode_manifest_sections, encode_manifest, SegmentAttributes, SegmentTags,
+    },
+    segment::{
+        meta::{is_metadata_header, Metadata},
+        reader::{parse_blob_header as parse_blob_layout, read_key, read_record_header},
+        trailer::{TRAILER_MAGIC, TRAILER_SIZE},
+        writer::BLOB_HEADER_MAGIC,
+    },
+    ReadCounters,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+pub use crate::segment::writer::BlobLayout;
+
+/// Header of a blob record, which is followed by the (possibly compressed) value
+///
+/// For chunked blobs, the header is followed by the chunks, each prefixed by its length.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct BlobHeader {
+    /// Layout of the record
+    pub layout: BlobLayout,
+
+    /// Checksum of the key and (possibly compressed) value
+    pub checksum: u64,
+
+    /// Length of the prefix the key shares with the previous blob's key,
+    /// if the key is delta-encoded
+    pub shared_prefix_len: u16,
+
+    /// Stored key bytes, which is only the key's suffix if it is delta-encoded
+    pub key: Vec<u8>,
+
+    /// Size of the value, or the amount of chunks for chunked blobs
+    pub len: u32,
+}
+
+impl BlobHeader {
+    /// Serializes the header into the blob record format.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is longer than 65535 bytes.
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        let key_len = u16::try_from(self.key.len()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "key is too long")
+        })?;
+
+        let mut bytes = Vec::with_capacity(BLOB_HEADER_MAGIC.len() + 16 + self.key.len());
+        bytes.extend_from_slice(self.layout.header());
+        bytes.write_u64::<BigEndian>(self.checksum)?;
+
+        if self.layout.prefixed {
+            bytes.write_u16::<BigEndian>(self.shared_prefix_len)?;
+        }
+
+        bytes.write_u16::<BigEndian>(key_len)?;
+        bytes.extend_from_slice(&self.key);
+        bytes.write_u32::<BigEndian>(self.len)?;
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for BlobHeader {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let layout: BlobLayout = u.arbitrary()?;
+        let key_len = u.int_in_range(0..=usize::from(u16::MAX))?;
+
+        Ok(Self {
+            layout,
+            checksum: u.arbitrary()?,
+            shared_prefix_len: if layout.prefixed { u.arbitrary()? } else { 0 },
+            key: u.bytes(key_len)?.to_vec(),
+            len: u.arbitrary()?,
+        })
+    }
+}
+
+/// Parses the header of a blob record at the start of `bytes`.
+///
+/// Returns the header and the amount of bytes it occupies.
+///
+/// # Errors
+///
+/// Will return `Err` if the bytes are not a valid blob header.
+pub fn parse_blob_header(bytes: &[u8]) -> crate::Result<(BlobHeader, usize)> {
+    let mut reader = Cursor::new(bytes);
+
+    let mut magic = [0; BLOB_HEADER_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    let layout = parse_blob_layout(&magic)?;
+
+    let checksum = reader.read_u64::<BigEndian>()?;
+    let (key, shared_prefix_len) = read_key(&mut reader, layout, None)?;
+    let len = reader.read_u32::<BigEndian>()?;
+
+    // NOTE: The position is at most bytes.len()
+    #[allow(clippy::cast_possible_truncation)]
+    let consumed = reader.position() as usize;
+
+    Ok((
+        BlobHeader {
+            layout,
+            checksum,
+            shared_prefix_len,
+            key: key.to_vec(),
+            len,
+        },
+        consumed,
+    ))
+}
+
+/// Contents of a manifest file
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ManifestContents {
+    /// IDs of the registered segments
+    pub segment_ids: Vec<SegmentId>,
+
+    /// Pinned segments
+    pub pinned: BTreeSet<SegmentId>,
+
+    /// User-defined tags of segments
+    pub tags: BTreeMap<SegmentId, SegmentTags>,
+
+    /// Persisted read counters of segments
+    pub read_counters: BTreeMap<SegmentId, ReadCounters>,
+}
+
+impl ManifestContents {
+    /// Serializes the contents into the manifest file format.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a tag is longer than 65535 bytes,
+    /// or a segment has more than 65535 tags.
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        let attributes = SegmentAttributes {
+            pinned: self.pinned.clone(),
+            tags: self.tags.clone(),
+            read_counters: self.read_counters.clone(),
+        };
+
+        Ok(encode_manifest(&self.segment_ids, &attributes)?)
+    }
+}
+
+/// Parses the contents of a manifest file.
+///
+/// # Errors
+///
+/// Will return `Err` if the bytes are not a valid manifest.
+pub fn parse_manifest(bytes: &[u8]) -> crate::Result<ManifestContents> {
+    let (segment_ids, attributes) =
+        decode_manifest(bytes).map_err(|_| crate::Error::CorruptManifest)?;
+
+    Ok(ManifestContents {
+        segment_ids,
+        pinned: attributes.pinned,
+        tags: attributes.tags,
+        read_counters: attributes.read_counters,
+    })
+}
+
+/// Parses segment metadata, which is stored in a segment file's trailer.
+///
+/// # Errors
+///
+/// Will return `Err` if the bytes are not valid segment metadata.
+pub fn parse_segment_metadata(bytes: &[u8]) -> crate::Result<Metadata> {
+    Ok(Metadata::decode_from(&mut Cursor::new(bytes))?)
+}
+
+/// Returns the format version of a header magic, which is stored in its last byte.
+fn magic_version(magic: &[u8]) -> u8 {
+    magic.last().copied().unwrap_or_default()
+}
+
+/// Blob record inside a segment file, see [`describe_segment`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordDescription {
+    /// Offset of the record in the segment file
+    pub offset: u64,
+
+    /// Size of the record, including its header
+    pub len: u64,
+
+    /// Format version of the record header
+    pub version: u8,
+
+    /// Record header
+    pub header: BlobHeader,
+
+    /// Stored size of every chunk, if the blob is chunked
+    pub chunk_lens: Vec<u32>,
+}
+
+/// Structure of a segment file, see [`describe_segment`]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SegmentDescription {
+    /// Blob records, in file order
+    pub records: Vec<RecordDescription>,
+
+    /// Ranges of punched holes between records
+    pub holes: Vec<Range<u64>>,
+
+    /// Offset of the segment metadata
+    pub metadata_offset: u64,
+
+    /// Format version of the segment metadata
+    pub metadata_version: u8,
+
+    /// Segment metadata
+    pub metadata: Metadata,
+
+    /// Offset of the fixed-size trailer
+    pub trailer_offset: u64,
+
+    /// Format version of the trailer
+    pub trailer_version: u8,
+}
+
+/// Parses a whole segment file, describing its records, metadata and trailer.
+///
+/// # Errors
+///
+/// Will return `Err` if the bytes are not a valid segment file.
+pub fn describe_segment(bytes: &[u8]) -> crate::Result<SegmentDescription> {
+    let trailer_offset = bytes
+        .len()
+        .checked_sub(TRAILER_SIZE)
+        .ok_or(crate::Error::Decode(DecodeError::InvalidTrailer))?;
+
+    let trailer = bytes.get(trailer_offset..).unwrap_or_default();
+
+    if !trailer.ends_with(TRAILER_MAGIC) {
+        return Err(crate::Error::Decode(DecodeError::InvalidTrailer));
+    }
+
+    let metadata_offset = Cursor::new(trailer).read_u64::<BigEndian>()?;
+
+    let metadata_bytes = usize::try_from(metadata_offset)
+        .ok()
+        .and_then(|offset| bytes.get(offset..trailer_offset))
+        .ok_or(crate::Error::Decode(DecodeError::InvalidTrailer))?;
+
+    let metadata = parse_segment_metadata(metadata_bytes)?;
+    let metadata_version = metadata_bytes
+        .get(..BLOB_HEADER_MAGIC.len())
+        .map(magic_version)
+        .unwrap_or_default();
+
+    let mut records = vec![];
+    let mut holes = vec![];
+
+    let mut reader = Cursor::new(bytes.get(..trailer_offset).unwrap_or_default());
+
+    loop {
+        let start = reader.position();
+
+        let mut magic = [0; BLOB_HEADER_MAGIC.len()];
+        let skipped = read_record_header(&mut reader, &mut magic)?;
+        let offset = start + skipped;
+
+        if skipped > 0 {
+            holes.push(start..offset);
+        }
+
+        if is_metadata_header(&magic) {
+            if offset != metadata_offset {
+                return Err(crate::Error::Decode(DecodeError::InvalidTrailer));
+            }
+            break;
+        }
+
+        let record = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| reader.get_ref().get(offset..))
+            .unwrap_or_default();
+
+        let (header, header_len) = parse_blob_header(record)?;
+        reader.seek(SeekFrom::Start(offset + header_len as u64))?;
+
+        let chunk_lens = if header.layout.chunked {
+            let mut chunk_lens = vec![];
+
+            for _ in 0..header.len {
+                let len = reader.read_u32::<BigEndian>()?;
+                reader.seek(SeekFrom::Current(i64::from(len)))?;
+                chunk_lens.push(len);
+            }
+
+            chunk_lens
+        } else {
+            reader.seek(SeekFrom::Current(i64::from(header.len)))?;
+            vec![]
+        };
+
+        // NOTE: Seeking past the end of the metadata is caught by the next header read,
+        // but a record may not overlap the metadata
+        if reader.position() > metadata_offset {
+            return Err(crate::Error::Decode(DecodeError::InvalidHeader("Blob")));
+        }
+
+        records.push(RecordDescription {
+            offset,
+            len: reader.position() - offset,
+            version: magic_version(&magic),
+            header,
+            chunk_lens,
+        });
+    }
+
+    Ok(SegmentDescription {
+        records,
+        holes,
+        metadata_offset,
+        metadata_version,
+        metadata,
+        trailer_offset: trailer_offset as u64,
+        trailer_version: magic_version(TRAILER_MAGIC),
+    })
+}
+
+/// Section of a manifest file, see [`describe_manifest`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ManifestSectionKind {
+    /// IDs of the registered segments
+    Segments,
+
+    /// Pinned segments
+    Pinned,
+
+    /// User-defined tags of segments
+    Tags,
+
+    /// Persisted read counters of segments
+    ReadCounters,
+}
+
+/// Section of a manifest file, see [`describe_manifest`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ManifestSection {
+    /// Contents of the section
+    pub kind: ManifestSectionKind,
+
+    /// Byte range of the section in the manifest file
+    pub range: Range<u64>,
+
+    /// Amount of entries in the section
+    pub entry_count: u64,
+}
+
+/// Parses a manifest file, describing its sections.
+///
+/// Manifests written by older versions only contain a prefix of the sections.
+///
+/// # Errors
+///
+/// Will return `Err` if the bytes are not a valid manifest.
+pub fn describe_manifest(bytes: &[u8]) -> crate::Result<Vec<ManifestSection>> {
+    let (segment_ids, attributes, section_ends) =
+        decode_manifest_sections(bytes).map_err(|_| crate::Error::CorruptManifest)?;
+
+    let sections = [
+        (ManifestSectionKind::Segments, segment_ids.len()),
+        (ManifestSectionKind::Pinned, attributes.pinned.len()),
+        (ManifestSectionKind::Tags, attributes.tags.len()),
+        (
+            ManifestSectionKind::ReadCounters,
+            attributes.read_counters.len(),
+        ),
+    ];
+
+    let mut start = 0;
+
+    Ok(sections
+        .into_iter()
+        .zip(section_ends)
+        .map(|((kind, entry_count), end)| {
+            let range = start..end;
+            start = end;
+
+            ManifestSection {
+                kind,
+                range,
+                entry_count: entry_count as u64,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,469 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::file::{fsync_directory, rewrite_atomic};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Hint about how a file range is going to be accessed (see [`FsFile::advise`])
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PageCacheAdvice {
+    /// No special access pattern
+    Normal,
+
+    /// The range is read sequentially, so it can be read ahead aggressively
+    Sequential,
+
+    /// The range is going to be read soon, so it can be loaded into the page cache
+    WillNeed,
+
+    /// The range is not going to be read anymore, so it can be evicted from the page cache
+    DontNeed,
+}
+
+/// Class of I/O operations that page cache hints are configured for
+/// (see [`Config::page_cache_advice`](crate::Config::page_cache_advice))
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum IoClass {
+    /// Segments are scanned, e.g. by iterators or [`ValueLog::scan_for_stats`](crate::ValueLog::scan_for_stats)
+    ///
+    /// Default = [`PageCacheAdvice::Sequential`]
+    Scan,
+
+    /// Segments are read to be rewritten by garbage collection
+    ///
+    /// Default = [`PageCacheAdvice::Sequential`]
+    Rollover,
+
+    /// Segments were rewritten by garbage collection, so they are stale
+    ///
+    /// Default = [`PageCacheAdvice::DontNeed`]
+    RolloverFinished,
+
+    /// Blobs after a point read are prefetched (see [`ValueLog::get_with_prefetch`](crate::ValueLog::get_with_prefetch))
+    ///
+    /// Default = [`PageCacheAdvice::WillNeed`]
+    Prefetch,
+}
+
+/// Page cache hints of every [`IoClass`]
+#[derive(Copy, Clone, Debug)]
+pub struct PageCacheHints {
+    scan: Option<PageCacheAdvice>,
+    rollover: Option<PageCacheAdvice>,
+    rollover_finished: Option<PageCacheAdvice>,
+    prefetch: Option<PageCacheAdvice>,
+}
+
+impl Default for PageCacheHints {
+    fn default() -> Self {
+        Self {
+            scan: Some(PageCacheAdvice::Sequential),
+            rollover: Some(PageCacheAdvice::Sequential),
+            rollover_finished: Some(PageCacheAdvice::DontNeed),
+            prefetch: Some(PageCacheAdvice::WillNeed),
+        }
+    }
+}
+
+impl PageCacheHints {
+    /// Returns the advice given for the operation class.
+    pub fn get(self, class: IoClass) -> Option<PageCacheAdvice> {
+        match class {
+            IoClass::Scan => self.scan,
+            IoClass::Rollover => self.rollover,
+            IoClass::RolloverFinished => self.rollover_finished,
+            IoClass::Prefetch => self.prefetch,
+        }
+    }
+
+    /// Sets the advice given for the operation class.
+    pub fn set(&mut self, class: IoClass, advice: Option<PageCacheAdvice>) {
+        let slot = match class {
+            IoClass::Scan => &mut self.scan,
+            IoClass::Rollover => &mut self.rollover,
+            IoClass::RolloverFinished => &mut self.rollover_finished,
+            IoClass::Prefetch => &mut self.prefetch,
+        };
+        *slot = advice;
+    }
+}
+
+/// Applies the page cache advice (if any) to a file range.
+///
+/// Advice is only a hint, so errors are ignored.
+pub fn advise(file: &dyn FsFile, offset: u64, len: u64, advice: Option<PageCacheAdvice>) {
+    if let Some(advice) = advice {
+        if let Err(e) = file.advise(offset, len, advice) {
+            log::trace!("Failed to apply page cache advice {advice:?}: {e:?}");
+        }
+    }
+}
+
+/// A file handle returned by a [`Fs`]
+pub trait FsFile: Read + Write + Seek + Send {
+    /// Flushes all data and metadata of the file to durable storage.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn sync_all(&self) -> std::io::Result<()>;
+
+    /// Hints how a byte range of the file is going to be accessed.
+    /// A length of 0 extends the range to the end of the file.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn advise(&self, offset: u64, len: u64, advice: PageCacheAdvice) -> std::io::Result<()> {
+        let _ = (offset, len, advice);
+        Ok(())
+    }
+}
+
+impl FsFile for File {
+    fn sync_all(&self) -> std::io::Result<()> {
+        Self::sync_all(self)
+    }
+
+    /// Applies the advice using `posix_fadvise`.
+    #[cfg(target_os = "linux")]
+    fn advise(&self, offset: u64, len: u64, advice: PageCacheAdvice) -> std::io::Result<()> {
+        use rustix::fs::{fadvise, Advice};
+
+        let advice = match advice {
+            PageCacheAdvice::Normal => Advice::Normal,
+            PageCacheAdvice::Sequential => Advice::Sequential,
+            PageCacheAdvice::WillNeed => Advice::WillNeed,
+            PageCacheAdvice::DontNeed => Advice::DontNeed,
+        };
+
+        fadvise(self, offset, std::num::NonZeroU64::new(len), advice)?;
+        Ok(())
+    }
+}
+
+/// Exclusive lock on a file, which is released when dropped (see [`Fs::try_lock`])
+pub trait FsLock: Send + Sync {}
+
+impl FsLock for File {}
+
+/// Lock of file systems that do not support locking
+struct NoLock;
+
+impl FsLock for NoLock {}
+
+/// Storage abstraction the value log performs all file system operations through
+///
+/// The default implementation, [`StdFs`], uses `std::fs`.
+/// Custom implementations can be used to run the value log on top of
+/// simulated or fault-injecting file systems.
+pub trait Fs: Send + Sync {
+    /// Creates (or truncates) a file for writing.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>>;
+
+    /// Opens an existing file for reading.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>>;
+
+    /// Returns `true` if the path exists.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn exists(&self, path: &Path) -> std::io::Result<bool>;
+
+    /// Removes a file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn remove_file(&self, path: &Path) -> std::io::Result<()>;
+
+    /// Recursively creates a directory.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()>;
+
+    /// Lists the files (not directories) inside a directory.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>>;
+
+    /// Creates a hard link `dst` pointing to the same file as `src`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()>;
+
+    /// Atomically replaces the content of a file.
+    ///
+    /// After returning `Ok`, the new content needs to be durable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()>;
+
+    /// Makes directory entries (created or deleted files) durable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()>;
+
+    /// Reads the entire content of a file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        let mut file = self.open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the amount of disk space that is allocated by a file.
+    ///
+    /// The default implementation returns the file's length.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn allocated_size(&self, path: &Path) -> std::io::Result<u64> {
+        self.open(path)?.seek(SeekFrom::End(0))
+    }
+
+    /// Hints that the data of a file is not needed anymore, because it is about to be deleted.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn discard(&self, path: &Path) -> std::io::Result<()> {
+        let _ = path;
+        Ok(())
+    }
+
+    /// Deallocates a byte range of a file, without changing its size.
+    ///
+    /// Afterwards, the range reads as zeroes.
+    ///
+    /// The default implementation returns an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn punch_hole(&self, path: &Path, offset: u64, len: u64) -> std::io::Result<()> {
+        let _ = (path, offset, len);
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "file system does not support punching holes",
+        ))
+    }
+
+    /// Returns the amount of free disk space (in bytes) that is available
+    /// on the file system the given path is stored on.
+    ///
+    /// The default implementation returns an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        let _ = path;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "file system does not report free space",
+        ))
+    }
+
+    /// Takes an exclusive lock on a file, creating it if it does not exist.
+    ///
+    /// The lock is held until the returned lock is dropped.
+    /// Returns `None` if the file is already locked, possibly by another process.
+    ///
+    /// The default implementation does not lock anything, so opening a value log
+    /// never fails with [`Error::Locked`](crate::Error::Locked).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn try_lock(&self, path: &Path) -> std::io::Result<Option<Box<dyn FsLock>>> {
+        let _ = path;
+        Ok(Some(Box::new(NoLock)))
+    }
+}
+
+/// [`Fs`] implementation backed by `std::fs`
+///
+/// Locking (see [`Fs::try_lock`]) is supported on Unix and Windows.
+/// On other targets (e.g. WASI), nothing is locked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFs;
+
+impl StdFs {
+    #[cfg(any(unix, windows))]
+    fn open_lock_file(path: &Path) -> std::io::Result<File> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+    }
+}
+
+impl Fs for StdFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        Ok(Box::new(File::create(path)?))
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        path.try_exists()
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+
+        for dirent in std::fs::read_dir(path)? {
+            let dirent = dirent?;
+
+            if dirent.file_type()?.is_file() {
+                files.push(dirent.path());
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        std::fs::hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        fsync_directory(path)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    #[cfg(unix)]
+    fn allocated_size(&self, path: &Path) -> std::io::Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        // NOTE: st_blocks is always counted in 512 byte units
+        Ok(std::fs::metadata(path)?.blocks() * 512)
+    }
+
+    #[cfg(not(unix))]
+    fn allocated_size(&self, path: &Path) -> std::io::Result<u64> {
+        Ok(std::fs::metadata(path)?.len())
+    }
+
+    /// Evicts the file from the page cache (`posix_fadvise(DONTNEED)`).
+    ///
+    /// Discarding the blocks on the device is left to the file system,
+    /// e.g. when it is mounted with online discard.
+    #[cfg(target_os = "linux")]
+    fn discard(&self, path: &Path) -> std::io::Result<()> {
+        use rustix::fs::{fadvise, Advice};
+
+        let file = File::open(path)?;
+        fadvise(&file, 0, None, Advice::DontNeed)?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn punch_hole(&self, path: &Path, offset: u64, len: u64) -> std::io::Result<()> {
+        use rustix::fs::{fallocate, FallocateFlags};
+
+        let file = std::fs::OpenOptions::new().write(true).open(path)?;
+
+        fallocate(
+            &file,
+            FallocateFlags::PUNCH_HOLE | FallocateFlags::KEEP_SIZE,
+            offset,
+            len,
+        )?;
+
+        file.sync_all()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        let stat = rustix::fs::statvfs(path)?;
+        Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+    }
+
+    /// Locks the file using `flock`.
+    ///
+    /// The lock is tied to the file handle, so it is released if the process dies.
+    #[cfg(unix)]
+    fn try_lock(&self, path: &Path) -> std::io::Result<Option<Box<dyn FsLock>>> {
+        use rustix::{
+            fs::{flock, FlockOperation},
+            io::Errno,
+        };
+
+        let file = Self::open_lock_file(path)?;
+
+        match flock(&file, FlockOperation::NonBlockingLockExclusive) {
+            Ok(()) => Ok(Some(Box::new(file))),
+            Err(e) if e == Errno::WOULDBLOCK => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Locks the file using `LockFileEx`.
+    ///
+    /// The lock is tied to the file handle, so it is released if the process dies.
+    #[cfg(windows)]
+    fn try_lock(&self, path: &Path) -> std::io::Result<Option<Box<dyn FsLock>>> {
+        use fs2::FileExt;
+
+        let file = Self::open_lock_file(path)?;
+
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(Box::new(file))),
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
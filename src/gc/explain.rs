@@ -0,0 +1,74 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::id::SegmentId;
+use std::time::Duration;
+
+/// Reason a segment was or was not selected for garbage collection
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GcReason {
+    /// Selected, because its stale ratio exceeds the threshold
+    /// (which may be adjusted by the free disk space watermarks)
+    StaleThresholdExceeded {
+        /// Effective stale ratio threshold
+        threshold: f32,
+    },
+
+    /// Selected, because rewriting it is needed to reach the space amplification target
+    SpaceAmpTarget {
+        /// Space amplification target
+        target: f32,
+    },
+
+    /// Selected, because it is older than the configured retention
+    Expired,
+
+    /// Not selected, because it is pinned
+    Pinned,
+
+    /// Not selected, because it is fully stale, so it only needs to be dropped
+    FullyStale,
+
+    /// Not selected, because it is younger than the policy's minimum age
+    TooYoung {
+        /// Minimum age of the policy
+        min_age: Duration,
+    },
+
+    /// Not selected, because more segments were selected than
+    /// the policy allows jobs per maintenance run
+    JobLimit,
+
+    /// Not selected, because its stale ratio does not exceed the threshold,
+    /// and it is not needed to reach the space amplification target
+    BelowThreshold {
+        /// Effective stale ratio threshold, if the policy has one
+        threshold: Option<f32>,
+    },
+
+    /// Not selected, because no GC policy is configured
+    NoPolicy,
+}
+
+/// Explanation of the garbage collection choice for a segment,
+/// see [`ValueLog::explain_gc_choice`](crate::ValueLog::explain_gc_choice)
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct GcDecision {
+    /// Segment ID
+    pub segment_id: SegmentId,
+
+    /// Whether the segment would be rewritten by the next maintenance run
+    pub selected: bool,
+
+    /// Why the segment was or was not selected
+    pub reason: GcReason,
+
+    /// Percent of stale blobs in the segment
+    pub stale_ratio: f32,
+
+    /// Time since the segment was created, if the segment records its creation time
+    pub age: Option<Duration>,
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{id::SegmentId, sync::Mutex};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    collections::VecDeque,
+    io::{Cursor, Read},
+    sync::PoisonError,
+    time::Duration,
+};
+
+/// File (inside the value log folder) the GC history is persisted in
+pub const GC_HISTORY_FILE: &str = "gc_history";
+
+/// Kind of a garbage collection operation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GcOperation {
+    /// Live blobs of some segments were rewritten into new segment(s)
+    Rollover,
+
+    /// Stale segments were dropped
+    Drop,
+}
+
+/// Record of a past garbage collection operation, see [`ValueLog::gc_history`](crate::ValueLog::gc_history)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct GcHistoryEntry {
+    /// Kind of the operation
+    pub operation: GcOperation,
+
+    /// Time the operation started at, in milliseconds since the Unix epoch
+    pub started_at: u64,
+
+    /// Time the operation took
+    pub duration: Duration,
+
+    /// IDs of the segments that were rewritten or dropped
+    pub input_segment_ids: Vec<SegmentId>,
+
+    /// IDs of the segments that were created
+    pub output_segment_ids: Vec<SegmentId>,
+
+    /// Amount of (compressed) blob bytes that were reclaimed
+    ///
+    /// For rollovers, this is the size difference between the rewritten and the new
+    /// segments, which is reclaimed once the rewritten segments are dropped.
+    pub bytes_reclaimed: u64,
+}
+
+const TAG_ROLLOVER: u8 = 0;
+const TAG_DROP: u8 = 1;
+
+fn write_ids(bytes: &mut Vec<u8>, ids: &[SegmentId]) -> std::io::Result<()> {
+    bytes.write_u64::<BigEndian>(ids.len() as u64)?;
+
+    for id in ids {
+        bytes.write_u64::<BigEndian>(*id)?;
+    }
+
+    Ok(())
+}
+
+fn read_ids<R: Read>(reader: &mut R) -> std::io::Result<Vec<SegmentId>> {
+    let cnt = reader.read_u64::<BigEndian>()?;
+
+    (0..cnt)
+        .map(|_| reader.read_u64::<BigEndian>())
+        .collect::<std::io::Result<Vec<_>>>()
+}
+
+/// Bounded log of past garbage collection operations, the oldest entries are evicted first
+pub struct GcHistory {
+    entries: Mutex<VecDeque<GcHistoryEntry>>,
+    capacity: usize,
+}
+
+impl GcHistory {
+    /// Creates an empty history that holds up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Returns the entries, oldest first.
+    pub fn entries(&self) -> Vec<GcHistoryEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Appends an entry, evicting the oldest entries if the history is full.
+    pub fn push(&self, entry: GcHistoryEntry) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+
+        entries.push_back(entry);
+
+        while entries.len() > self.capacity {
+            entries.pop_front();
+        }
+
+        drop(entries);
+    }
+
+    /// Serializes the history into its file format.
+    pub fn encode(&self) -> std::io::Result<Vec<u8>> {
+        let entries = self.entries();
+
+        let mut bytes = Vec::new();
+        bytes.write_u64::<BigEndian>(entries.len() as u64)?;
+
+        for entry in &entries {
+            bytes.write_u8(match entry.operation {
+                GcOperation::Rollover => TAG_ROLLOVER,
+                GcOperation::Drop => TAG_DROP,
+            })?;
+            bytes.write_u64::<BigEndian>(entry.started_at)?;
+
+            // NOTE: A GC operation does not take 500 million years
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.write_u64::<BigEndian>(entry.duration.as_micros() as u64)?;
+
+            write_ids(&mut bytes, &entry.input_segment_ids)?;
+            write_ids(&mut bytes, &entry.output_segment_ids)?;
+            bytes.write_u64::<BigEndian>(entry.bytes_reclaimed)?;
+        }
+
+        Ok(bytes)
+    }
+
+    /// Deserializes a persisted history, keeping its newest `capacity` entries.
+    pub fn decode(bytes: &[u8], capacity: usize) -> std::io::Result<Self> {
+        let mut reader = Cursor::new(bytes);
+        let history = Self::new(capacity);
+
+        let cnt = reader.read_u64::<BigEndian>()?;
+
+        for _ in 0..cnt {
+            let operation = match reader.read_u8()? {
+                TAG_ROLLOVER => GcOperation::Rollover,
+                TAG_DROP => GcOperation::Drop,
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "invalid GC operation tag",
+                    ))
+                }
+            };
+
+            history.push(GcHistoryEntry {
+                operation,
+                started_at: reader.read_u64::<BigEndian>()?,
+                duration: Duration::from_micros(reader.read_u64::<BigEndian>()?),
+                input_segment_ids: read_ids(&mut reader)?,
+                output_segment_ids: read_ids(&mut reader)?,
+                bytes_reclaimed: reader.read_u64::<BigEndian>()?,
+            });
+        }
+
+        Ok(history)
+    }
+}
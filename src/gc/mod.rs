@@ -2,9 +2,17 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+pub mod explain;
+pub mod history;
+pub mod policy;
 pub mod report;
 
 use crate::{id::SegmentId, Compressor, ValueLog};
+use std::sync::Arc;
+
+/// Maps a key to the partition its blob is relocated into,
+/// see [`Config::rollover_partitioner`](crate::Config::rollover_partitioner)
+pub type KeyPartitioner = Arc<dyn Fn(&[u8]) -> u64 + Send + Sync>;
 
 /// GC strategy
 #[allow(clippy::module_name_repetitions)]
@@ -36,9 +44,7 @@ impl<C: Compressor + Clone> GcStrategy<C> for StaleThresholdStrategy {
     fn pick(&self, value_log: &ValueLog<C>) -> Vec<SegmentId> {
         value_log
             .manifest
-            .segments
-            .read()
-            .expect("lock is poisoned")
+            .read_segments()
             .values()
             .filter(|x| x.stale_ratio() > self.0)
             .map(|x| x.id)
@@ -63,7 +69,7 @@ impl SpaceAmpStrategy {
 }
 
 impl<C: Compressor + Clone> GcStrategy<C> for SpaceAmpStrategy {
-    #[allow(clippy::cast_precision_loss, clippy::significant_drop_tightening)]
+    #[allow(clippy::cast_precision_loss)]
     fn pick(&self, value_log: &ValueLog<C>) -> Vec<SegmentId> {
         let space_amp_target = self.0;
         let current_space_amp = value_log.space_amp();
@@ -74,13 +80,9 @@ impl<C: Compressor + Clone> GcStrategy<C> for SpaceAmpStrategy {
         } else {
             log::debug!("Selecting segments to GC, space_amp_target={space_amp_target}");
 
-            let lock = value_log
-                .manifest
-                .segments
-                .read()
-                .expect("lock is poisoned");
+            let snapshot = value_log.manifest.read_segments();
 
-            let mut segments = lock
+            let mut segments = snapshot
                 .values()
                 .filter(|x| x.stale_ratio() > 0.0)
                 .collect::<Vec<_>>();
@@ -0,0 +1,203 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{GcStrategy, SpaceAmpStrategy, StaleThresholdStrategy};
+use crate::{id::SegmentId, Compressor, ValueLog};
+use std::time::Duration;
+
+/// Declarative garbage collection policy, which is applied by
+/// [`ValueLog::run_maintenance`] (see [`Config::gc_policy`](crate::Config::gc_policy))
+///
+/// A segment is picked if it exceeds the stale ratio threshold, or if it is needed
+/// to reach the space amplification target. Segments are rewritten one by one,
+/// so every rewritten segment is a separate job.
+///
+/// The policy can also be used as a [`GcStrategy`].
+#[derive(Clone, Debug, Default)]
+pub struct GcPolicy {
+    stale_ratio: Option<f32>,
+    space_amp: Option<f32>,
+    max_jobs: Option<usize>,
+    throttle: Duration,
+    min_age: Option<Duration>,
+    low_watermark: Option<(u64, f32)>,
+    high_watermark: Option<(u64, f32)>,
+}
+
+impl GcPolicy {
+    /// Picks segments that have more than the given ratio of stale blobs
+    /// (see [`StaleThresholdStrategy`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratio is invalid.
+    #[must_use]
+    pub fn stale_threshold(mut self, ratio: f32) -> Self {
+        assert!(
+            ratio.is_finite() && ratio.is_sign_positive(),
+            "invalid stale ratio"
+        );
+        self.stale_ratio = Some(ratio.min(1.0));
+        self
+    }
+
+    /// Picks segments until the space amplification target is reached
+    /// (see [`SpaceAmpStrategy`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the space amp factor is < 1.0.
+    #[must_use]
+    pub fn space_amp_target(mut self, ratio: f32) -> Self {
+        assert!(ratio >= 1.0, "invalid space amp ratio");
+        self.space_amp = Some(ratio);
+        self
+    }
+
+    /// Sets the maximum amount of segments rewritten per maintenance run.
+    ///
+    /// Garbage collection jobs never run concurrently within a value log,
+    /// so this bounds the work (and I/O) of a single run.
+    ///
+    /// Default = unlimited
+    #[must_use]
+    pub fn max_jobs(mut self, jobs: usize) -> Self {
+        self.max_jobs = Some(jobs);
+        self
+    }
+
+    /// Sets the pause between two garbage collection jobs, to limit
+    /// the I/O garbage collection competes with reads & writes for.
+    ///
+    /// Default = no pause
+    #[must_use]
+    pub fn throttle(mut self, pause: Duration) -> Self {
+        self.throttle = pause;
+        self
+    }
+
+    /// Only picks segments that were created at least `min_age` ago, because
+    /// young segments often become more stale soon, so rewriting them early is wasteful.
+    ///
+    /// Segments that do not record their creation time are considered old enough.
+    /// Creation times are recorded while the policy is set using [`Config::gc_policy`](crate::Config::gc_policy).
+    /// See [`Config::retention`](crate::Config::retention) to limit the age of segments instead.
+    ///
+    /// Default = no limit
+    #[must_use]
+    pub fn min_age(mut self, min_age: Duration) -> Self {
+        self.min_age = Some(min_age);
+        self
+    }
+
+    /// Makes garbage collection more aggressive when the disk runs out of space:
+    /// while less than `free_bytes` are available, segments that have more than
+    /// the given ratio of stale blobs are picked, instead of the [stale ratio threshold](GcPolicy::stale_threshold).
+    ///
+    /// Free space is queried using [`Fs::available_space`](crate::Fs::available_space);
+    /// if it is unknown, the watermarks are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratio is invalid.
+    #[must_use]
+    pub fn low_watermark(mut self, free_bytes: u64, stale_ratio: f32) -> Self {
+        assert!(
+            stale_ratio.is_finite() && stale_ratio.is_sign_positive(),
+            "invalid stale ratio"
+        );
+        self.low_watermark = Some((free_bytes, stale_ratio.min(1.0)));
+        self
+    }
+
+    /// Relaxes garbage collection when there is plenty of disk space:
+    /// while more than `free_bytes` are available, segments that have more than
+    /// the given ratio of stale blobs are picked, instead of the [stale ratio threshold](GcPolicy::stale_threshold).
+    ///
+    /// See [`GcPolicy::low_watermark`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratio is invalid.
+    #[must_use]
+    pub fn high_watermark(mut self, free_bytes: u64, stale_ratio: f32) -> Self {
+        assert!(
+            stale_ratio.is_finite() && stale_ratio.is_sign_positive(),
+            "invalid stale ratio"
+        );
+        self.high_watermark = Some((free_bytes, stale_ratio.min(1.0)));
+        self
+    }
+
+    /// Returns the stale ratio threshold, depending on the free disk space.
+    pub(crate) fn effective_stale_ratio<C: Compressor + Clone>(
+        &self,
+        value_log: &ValueLog<C>,
+    ) -> Option<f32> {
+        if self.low_watermark.is_none() && self.high_watermark.is_none() {
+            return self.stale_ratio;
+        }
+
+        let free_bytes = match value_log.available_space() {
+            Ok(free_bytes) => free_bytes,
+            Err(e) => {
+                log::debug!("Could not query free disk space, ignoring watermarks: {e:?}");
+                return self.stale_ratio;
+            }
+        };
+
+        match (self.low_watermark, self.high_watermark) {
+            (Some((low, ratio)), _) if free_bytes < low => {
+                log::debug!("Free disk space ({free_bytes}B) is below low watermark ({low}B)");
+                Some(ratio)
+            }
+            (_, Some((high, ratio))) if free_bytes > high => Some(ratio),
+            _ => self.stale_ratio,
+        }
+    }
+
+    pub(crate) fn space_amp(&self) -> Option<f32> {
+        self.space_amp
+    }
+
+    pub(crate) fn min_age_limit(&self) -> Option<Duration> {
+        self.min_age
+    }
+
+    pub(crate) fn job_limit(&self) -> usize {
+        self.max_jobs.unwrap_or(usize::MAX)
+    }
+
+    pub(crate) fn throttle_pause(&self) -> Duration {
+        self.throttle
+    }
+}
+
+impl<C: Compressor + Clone> GcStrategy<C> for GcPolicy {
+    fn pick(&self, value_log: &ValueLog<C>) -> Vec<SegmentId> {
+        let mut segment_ids = self
+            .effective_stale_ratio(value_log)
+            .map(|ratio| StaleThresholdStrategy::new(ratio).pick(value_log))
+            .unwrap_or_default();
+
+        if let Some(ratio) = self.space_amp {
+            for segment_id in SpaceAmpStrategy::new(ratio).pick(value_log) {
+                if !segment_ids.contains(&segment_id) {
+                    segment_ids.push(segment_id);
+                }
+            }
+        }
+
+        if let Some(min_age) = self.min_age {
+            segment_ids.retain(|id| {
+                value_log
+                    .manifest
+                    .get_segment(*id)
+                    .is_some_and(|x| x.meta.created_at.is_none() || x.is_expired(min_age))
+            });
+        }
+
+        segment_ids
+    }
+}
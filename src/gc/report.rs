@@ -2,7 +2,83 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use std::path::PathBuf;
+use crate::id::SegmentId;
+use std::{path::PathBuf, time::Duration};
+
+/// Report of dropping stale segments
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct DropReport {
+    /// IDs of the dropped segments
+    pub segment_ids: Vec<SegmentId>,
+
+    /// Amount of (compressed) blob bytes that were dropped
+    pub bytes_freed: u64,
+
+    /// Amount of disk space that was allocated by the dropped segment files,
+    /// as reported by the file system
+    ///
+    /// This includes segment metadata, and excludes holes that were punched
+    /// into the segment files before.
+    pub disk_bytes_freed: u64,
+}
+
+/// Report of a maintenance run, see [`ValueLog::run_maintenance`](crate::ValueLog::run_maintenance)
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MaintenanceReport {
+    /// IDs of the segments that were rewritten by garbage collection
+    /// (see [`Config::gc_policy`](crate::Config::gc_policy))
+    pub gc_segment_ids: Vec<SegmentId>,
+
+    /// IDs of the cold segments that were rewritten with the cold compressor
+    /// (see [`Config::cold_compression`](crate::Config::cold_compression))
+    pub recompressed_segment_ids: Vec<SegmentId>,
+
+    /// Amount of (compressed) blob bytes the recompressed segments shrunk by,
+    /// once they are dropped
+    pub recompression_bytes_saved: u64,
+}
+
+/// Garbage collection statistics of a single segment, see [`Segment::gc_report`](crate::segment::Segment::gc_report)
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct SegmentGcReport {
+    /// Segment ID
+    pub segment_id: SegmentId,
+
+    /// Percent of stale blobs in the segment
+    pub stale_ratio: f32,
+
+    /// Amount of stored blobs
+    pub item_count: u64,
+
+    /// Amount of stale blobs
+    pub stale_items: u64,
+
+    /// Amount of live blobs
+    pub live_items: u64,
+
+    /// Amount of stored (uncompressed) bytes
+    pub total_bytes: u64,
+
+    /// Amount of stale (uncompressed) bytes
+    pub stale_bytes: u64,
+
+    /// Amount of live (uncompressed) bytes
+    pub live_bytes: u64,
+
+    /// Amount of (compressed) blob bytes on disk
+    pub compressed_bytes: u64,
+
+    /// Estimated amount of (compressed) blob bytes that rewriting the segment would free
+    pub reclaimable_bytes: u64,
+
+    /// Time since the segment was created, if the segment records its creation time
+    pub age: Option<Duration>,
+}
 
 /// Statistics report for garbage collection
 #[derive(Debug)]
@@ -0,0 +1,34 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::manifest::SegmentTags;
+
+/// Tag of segments that garbage collection promoted to the old generation
+pub const PROMOTED_TAG: (&str, &str) = ("vlog.generation", "old");
+
+/// Generation of a segment (see [`Config::old_generation`](crate::Config::old_generation))
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Generation {
+    /// The segment contains freshly written blobs
+    Young,
+
+    /// The segment contains blobs that survived garbage collection
+    Old,
+}
+
+impl Generation {
+    /// Classifies a segment by its tags.
+    pub(crate) fn from_tags(tags: Option<&SegmentTags>) -> Self {
+        let is_promoted = tags
+            .and_then(|tags| tags.get(PROMOTED_TAG.0))
+            .is_some_and(|value| value == PROMOTED_TAG.1);
+
+        if is_promoted {
+            Self::Old
+        } else {
+            Self::Young
+        }
+    }
+}
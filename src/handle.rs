@@ -6,9 +6,12 @@ use crate::id::SegmentId;
 use std::hash::Hash;
 
 /// A value handle points into the value log
+///
+/// Value handles are ordered by segment ID, then offset.
 #[allow(clippy::module_name_repetitions)]
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ValueHandle {
     /// Segment ID
     pub segment_id: SegmentId,
@@ -16,3 +19,67 @@ pub struct ValueHandle {
     /// Offset in file
     pub offset: u64,
 }
+
+impl std::fmt::Display for ValueHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.segment_id, self.offset)
+    }
+}
+
+impl ValueHandle {
+    /// Packs the value handle into a single integer, with the
+    /// segment ID in the upper and the offset in the lower 64 bits.
+    ///
+    /// Packed value handles are ordered the same way as value handles.
+    #[must_use]
+    pub fn to_u128(&self) -> u128 {
+        (u128::from(self.segment_id) << 64) | u128::from(self.offset)
+    }
+
+    /// Unpacks a value handle that was packed using [`ValueHandle::to_u128`].
+    #[must_use]
+    pub fn from_u128(packed: u128) -> Self {
+        // NOTE: Truncation is intended, the halves are unpacked separately
+        #[allow(clippy::cast_possible_truncation)]
+        Self {
+            segment_id: (packed >> 64) as u64,
+            offset: packed as u64,
+        }
+    }
+
+    /// Attaches the size of the value the handle points to.
+    #[must_use]
+    pub fn with_size(self, size: u32) -> SizedValueHandle {
+        SizedValueHandle {
+            vhandle: self,
+            size,
+        }
+    }
+}
+
+/// A value handle, together with the (uncompressed) size of the value it points to
+///
+/// This is what an index typically stores for a key.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct SizedValueHandle {
+    /// Value handle
+    pub vhandle: ValueHandle,
+
+    /// Size of the value
+    pub size: u32,
+}
+
+impl std::fmt::Display for SizedValueHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({} bytes)", self.vhandle, self.size)
+    }
+}
+
+impl From<SizedValueHandle> for ValueHandle {
+    fn from(value: SizedValueHandle) -> Self {
+        value.vhandle
+    }
+}
@@ -2,7 +2,39 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::ValueHandle;
+use crate::{id::SegmentId, HashMap, SizedValueHandle, UserKey, ValueHandle};
+
+/// Information about a relocated blob, supplied by the value log
+/// (see [`Writer::relocate_indirect_with_meta`])
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RelocationMeta {
+    /// Checksum of the blob (xxh3 of its key and stored value), as read from its old segment
+    pub checksum: u64,
+
+    /// Size of the stored (possibly compressed) value in the segment file
+    pub stored_size: u32,
+}
+
+/// Relocated blob, as passed to [`Writer::relocate_batch`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Relocation {
+    /// Key of the blob
+    pub key: UserKey,
+
+    /// Old location of the blob, which the index should still point to
+    pub expected: ValueHandle,
+
+    /// New location of the blob
+    pub vhandle: ValueHandle,
+
+    /// Size of the (uncompressed) value
+    pub size: u32,
+
+    /// Information about the relocated blob
+    pub meta: RelocationMeta,
+}
 
 /// Trait that allows reading from an external index
 ///
@@ -18,6 +50,56 @@ pub trait Reader {
     ///
     /// Will return `Err` if an IO error occurs.
     fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>>;
+
+    /// Returns a value handle for a given key, together with the value size
+    /// that was passed to [`Writer::insert_indirect`].
+    ///
+    /// This is used by [`ValueLog::scan_index_for_stats`](crate::ValueLog::scan_index_for_stats).
+    ///
+    /// The default implementation returns an [`Unsupported`](std::io::ErrorKind::Unsupported)
+    /// error, for indexes that do not store value sizes.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn get_with_size(&self, key: &[u8]) -> std::io::Result<Option<SizedValueHandle>> {
+        let _ = key;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "index does not store value sizes",
+        ))
+    }
+}
+
+/// Snapshot of the live value handles that point into a set of segments,
+/// collected from a single pass over the index
+pub struct LiveHandles(HashMap<UserKey, ValueHandle>);
+
+impl LiveHandles {
+    /// Collects the handles that (once resolved) point into one of the given segments.
+    pub fn collect<I: Iterator<Item = std::io::Result<(UserKey, ValueHandle)>>>(
+        iter: I,
+        segment_ids: &[SegmentId],
+        resolve: impl Fn(&ValueHandle) -> ValueHandle,
+    ) -> std::io::Result<Self> {
+        let mut handles = HashMap::default();
+
+        for item in iter {
+            let (key, vhandle) = item?;
+
+            if segment_ids.contains(&resolve(&vhandle).segment_id) {
+                handles.insert(key, vhandle);
+            }
+        }
+
+        Ok(Self(handles))
+    }
+}
+
+impl Reader for LiveHandles {
+    fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+        Ok(self.0.get(key).cloned())
+    }
 }
 
 /// Trait that allows writing into an external index
@@ -38,6 +120,80 @@ pub trait Writer {
         size: u32,
     ) -> std::io::Result<()>;
 
+    /// Inserts a relocated value handle into the index write batch.
+    ///
+    /// This is used by garbage collection when moving a blob into a new segment.
+    /// The index should only apply the write if the key still points to `expected`
+    /// (the blob's old location) when the write batch is applied. Otherwise the key was
+    /// overwritten by a user write while the blob was being relocated, and the relocation
+    /// must not clobber the newer value.
+    ///
+    /// The default implementation does not check `expected`, which is only safe if
+    /// no writes happen while garbage collection is running.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn relocate_indirect(
+        &mut self,
+        key: &[u8],
+        expected: &ValueHandle,
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        let _ = expected;
+        self.insert_indirect(key, vhandle, size)
+    }
+
+    /// Inserts a relocated value handle into the index write batch, together with
+    /// information about the blob that the value log knows about.
+    ///
+    /// This is what garbage collection calls; the default implementation discards
+    /// `meta` and calls [`Writer::relocate_indirect`]. Because `expected` identifies the
+    /// index entry that is being relocated, any index-specific information
+    /// (e.g. a sequence number) can be carried over from that entry.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn relocate_indirect_with_meta(
+        &mut self,
+        key: &[u8],
+        expected: &ValueHandle,
+        vhandle: ValueHandle,
+        size: u32,
+        meta: &RelocationMeta,
+    ) -> std::io::Result<()> {
+        let _ = meta;
+        self.relocate_indirect(key, expected, vhandle, size)
+    }
+
+    /// Inserts a batch of relocated value handles into the index write batch,
+    /// sorted by key (see [`Writer::relocate_indirect_with_meta`]).
+    ///
+    /// Garbage collection buffers relocations and passes them in batches
+    /// (see [`Config::relocation_batch_size`](crate::Config::relocation_batch_size)),
+    /// so indexes can apply them more efficiently than one by one, e.g. with a single
+    /// ordered pass. The default implementation calls [`Writer::relocate_indirect_with_meta`]
+    /// for every relocation.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn relocate_batch(&mut self, batch: &[Relocation]) -> std::io::Result<()> {
+        for item in batch {
+            self.relocate_indirect_with_meta(
+                &item.key,
+                &item.expected,
+                item.vhandle.clone(),
+                item.size,
+                &item.meta,
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Finishes the write batch.
     ///
     /// # Errors
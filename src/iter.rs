@@ -0,0 +1,86 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    segment::merge::MergeReader,
+    value::{UserKey, UserValue},
+    Compressor, ValueHandle,
+};
+use std::ops::Bound;
+
+/// Iterator over the blobs of a value log, in key order
+///
+/// Created by [`crate::ValueLog::scan_blobs`], [`crate::ValueLog::scan_latest_blobs`]
+/// and [`crate::ValueLog::scan_range`].
+#[allow(clippy::module_name_repetitions)]
+pub struct BlobIter<C: Compressor + Clone> {
+    inner: MergeReader<C>,
+    range: (Bound<UserKey>, Bound<UserKey>),
+}
+
+impl<C: Compressor + Clone> BlobIter<C> {
+    pub(crate) fn new(inner: MergeReader<C>) -> Self {
+        Self {
+            inner,
+            range: (Bound::Unbounded, Bound::Unbounded),
+        }
+    }
+
+    /// Only yields blobs whose key is inside the given range.
+    pub(crate) fn with_range(mut self, range: (Bound<UserKey>, Bound<UserKey>)) -> Self {
+        self.range = range;
+        self
+    }
+}
+
+pub fn as_slice_bound(bound: &Bound<UserKey>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(x) => Bound::Included(x),
+        Bound::Excluded(x) => Bound::Excluded(x),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl<C: Compressor + Clone> Iterator for BlobIter<C> {
+    type Item = crate::Result<(UserKey, ValueHandle, UserValue)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = match self.inner.next_entry()? {
+                Ok(item) => item,
+                Err(e) => return Some(Err(e)),
+            };
+
+            let key: &[u8] = &item.key;
+
+            let before_start = match &self.range.0 {
+                Bound::Included(start) => key < &**start,
+                Bound::Excluded(start) => key <= &**start,
+                Bound::Unbounded => false,
+            };
+
+            if before_start {
+                continue;
+            }
+
+            let past_end = match &self.range.1 {
+                Bound::Included(end) => key > &**end,
+                Bound::Excluded(end) => key >= &**end,
+                Bound::Unbounded => false,
+            };
+
+            // NOTE: Items are sorted, so there cannot be any more items in range
+            if past_end {
+                return None;
+            }
+
+            let vhandle = ValueHandle {
+                segment_id: item.segment_id,
+                offset: item.offset,
+            };
+
+            return Some(Ok((item.key, vhandle, item.value)));
+        }
+    }
+}
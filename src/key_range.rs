@@ -10,7 +10,7 @@ use crate::{
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::{
     io::{Read, Write},
-    ops::Deref,
+    ops::{Bound, Deref, RangeBounds},
 };
 
 /// A key range in the format of [min, max] (inclusive on both sides)
@@ -32,6 +32,66 @@ impl KeyRange {
     pub fn new(range: (UserKey, UserKey)) -> Self {
         Self(range)
     }
+
+    /// Returns `true` if the key range overlaps with the given bounds.
+    pub fn overlaps_with_bounds<R: RangeBounds<[u8]>>(&self, bounds: &R) -> bool {
+        let (min, max) = &self.0;
+
+        let after_start = match bounds.start_bound() {
+            Bound::Included(start) => &**max >= start,
+            Bound::Excluded(start) => &**max > start,
+            Bound::Unbounded => true,
+        };
+
+        let before_end = match bounds.end_bound() {
+            Bound::Included(end) => &**min <= end,
+            Bound::Excluded(end) => &**min < end,
+            Bound::Unbounded => true,
+        };
+
+        after_start && before_end
+    }
+
+    /// Returns `true` if the key is contained in the key range.
+    #[must_use]
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        let (min, max) = &self.0;
+        key >= &**min && key <= &**max
+    }
+
+    /// Returns `true` if the key range overlaps with the other key range.
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let (min, max) = &self.0;
+        let (other_min, other_max) = &other.0;
+        min <= other_max && max >= other_min
+    }
+
+    /// Returns the key range that is covered by both key ranges,
+    /// or `None` if they do not overlap.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let (min, max) = &self.0;
+        let (other_min, other_max) = &other.0;
+
+        Some(Self::new((
+            min.max(other_min).clone(),
+            max.min(other_max).clone(),
+        )))
+    }
+
+    /// Returns the smallest key range that covers both key ranges.
+    #[must_use]
+    pub fn merge(&self, other: &Self) -> Self {
+        let (min, max) = &self.0;
+        let (other_min, other_max) = &other.0;
+
+        Self::new((min.min(other_min).clone(), max.max(other_max).clone()))
+    }
 }
 
 impl Encode for KeyRange {
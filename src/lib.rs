@@ -98,28 +98,96 @@
 #![warn(clippy::expect_used)]
 #![allow(clippy::missing_const_for_fn)]
 #![warn(clippy::multiple_crate_versions)]
-// the bytes feature uses unsafe to improve from_reader performance; so we need to relax this lint
-#![cfg_attr(feature = "bytes", deny(unsafe_code))]
-#![cfg_attr(not(feature = "bytes"), forbid(unsafe_code))]
+// the bytes feature uses unsafe to improve from_reader performance, and the ffi feature
+// needs unsafe to deal with raw pointers; so we need to relax this lint
+#![cfg_attr(any(feature = "bytes", feature = "ffi"), deny(unsafe_code))]
+#![cfg_attr(not(any(feature = "bytes", feature = "ffi")), forbid(unsafe_code))]
 
+mod archive;
+mod backup;
 mod blob_cache;
+mod clock;
 mod coding;
 mod compression;
 mod config;
+mod content_addressed;
+mod context;
+mod corruption;
+
+#[cfg(feature = "test_utils")]
+mod corrupt;
+mod debug_dump;
+mod dedup;
+mod disk_space;
+mod encryption;
 mod error;
+mod file;
+mod fs;
 mod gc;
+mod generation;
 mod handle;
 mod id;
 mod index;
+mod iter;
 mod key_range;
+mod maintenance;
 mod manifest;
+mod memory;
+mod metrics;
 mod mock;
+mod open_options;
+mod parity;
 mod path;
+mod pipeline;
+mod progress;
+mod remap;
+mod replication;
+mod sharded;
 mod slice;
+mod snapshot;
+mod source;
+mod summary;
+mod sync;
+mod temperature;
+mod time;
+mod trash;
 
 #[doc(hidden)]
 pub mod scanner;
 
+pub mod format;
+
+pub mod dump;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "failpoints")]
+pub mod failpoints;
+
+#[cfg(not(feature = "failpoints"))]
+mod failpoints;
+
+#[cfg(feature = "metrics")]
+pub use metrics::{LatencyStats, Statistics};
+
+#[cfg(feature = "simulation")]
+pub mod sim;
+
+#[cfg(feature = "workload")]
+pub mod workload;
+
+/// Utilities to test applications built on top of the value log
+///
+/// The corruption helpers modify segment files in place, bypassing the configured
+/// file system, so they need the value log to use [`StdFs`].
+/// Blobs that are already in the blob cache are still served from the cache.
+#[cfg(feature = "test_utils")]
+pub mod test_utils {
+    pub use crate::corrupt::{flip_blob_byte, truncate_segment_mid_blob, zero_blob_checksum};
+    pub use crate::mock::{MockIndex, MockIndexWriter};
+}
+
 mod segment;
 mod value;
 mod value_log;
@@ -128,23 +196,59 @@ mod version;
 pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, xxhash_rust::xxh3::Xxh3Builder>;
 
 pub use {
-    blob_cache::BlobCache,
+    backup::BackupReport,
+    blob_cache::{BlobCache, BlobCacheStats},
+    clock::{Clock, ManualClock, SystemClock},
     compression::Compressor,
     config::Config,
-    error::{Error, Result},
-    gc::report::GcReport,
-    gc::{GcStrategy, SpaceAmpStrategy, StaleThresholdStrategy},
-    handle::ValueHandle,
-    index::{Reader as IndexReader, Writer as IndexWriter},
-    segment::multi_writer::MultiWriter as SegmentWriter,
+    content_addressed::{ContentAddressedValueLog, ContentAddressedWriter, ContentHash},
+    context::RequestContext,
+    corruption::{CorruptionCallback, CorruptionReport, CorruptionSource},
+    debug_dump::{ConfigDump, DebugDump, InFlightOperations, SegmentDebugInfo},
+    dedup::{ChunkHash, Chunker, CompositeHandle, DedupValueLog, DedupWriter},
+    disk_space::DiskSpaceBreakdown,
+    encryption::Encryptor,
+    error::{Error, ErrorCategory, IoContext, Result},
+    fs::{Fs, FsFile, FsLock, IoClass, PageCacheAdvice, StdFs},
+    gc::explain::{GcDecision, GcReason},
+    gc::history::{GcHistoryEntry, GcOperation},
+    gc::policy::GcPolicy,
+    gc::report::{DropReport, GcReport, MaintenanceReport, SegmentGcReport},
+    gc::{GcStrategy, KeyPartitioner, SpaceAmpStrategy, StaleThresholdStrategy},
+    generation::Generation,
+    handle::{SizedValueHandle, ValueHandle},
+    index::{Reader as IndexReader, Relocation, RelocationMeta, Writer as IndexWriter},
+    iter::BlobIter,
+    maintenance::MaintenancePause,
+    manifest::SegmentTags,
+    memory::MemoryUsage,
+    open_options::OpenOptions,
+    pipeline::{Stage as PipelineStage, Transform},
+    progress::{Operation, Progress, ProgressCallback},
+    replication::Replicator,
+    segment::{
+        builder::SegmentBuilder, filtered_reader::ScanFilter, meta::Metadata as SegmentMetadata,
+        meta_reader::BlobMeta, multi_writer::MultiWriter as SegmentWriter,
+    },
+    sharded::{ShardedValueLog, ShardedWriter},
     slice::Slice,
+    source::SegmentSource,
+    summary::{ManifestSummary, SegmentSummary},
+    temperature::{ReadCounters, Temperature},
+    trash::TrashedSegment,
     value::{UserKey, UserValue},
     value_log::ValueLog,
     version::Version,
 };
 
 #[doc(hidden)]
-pub use segment::{reader::Reader as SegmentReader, Segment};
+pub use segment::{
+    filtered_reader::FilteredReader,
+    meta_reader::{HandleReader, KeyReader, MetaReader},
+    reader::Reader as SegmentReader,
+    rev_reader::RevReader,
+    Segment,
+};
 
 #[doc(hidden)]
 pub use mock::{MockIndex, MockIndexWriter};
@@ -0,0 +1,21 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{value_log::ValueLogInner, Compressor};
+use std::sync::Weak;
+
+/// Pauses maintenance of a value log until it is dropped
+/// (see [`ValueLog::pause_maintenance`](crate::ValueLog::pause_maintenance))
+#[must_use = "maintenance is resumed when the guard is dropped"]
+pub struct MaintenancePause<C: Compressor + Clone> {
+    pub(crate) value_log: Weak<ValueLogInner<C>>,
+}
+
+impl<C: Compressor + Clone> Drop for MaintenancePause<C> {
+    fn drop(&mut self) {
+        if let Some(value_log) = self.value_log.upgrade() {
+            value_log.release_maintenance_pause();
+        }
+    }
+}
@@ -3,47 +3,282 @@
 // (found in the LICENSE-* files in the repository)
 
 use crate::{
+    clock::Clock,
+    failpoints,
+    fs::Fs,
+    gc::report::SegmentGcReport,
     id::SegmentId,
     key_range::KeyRange,
-    segment::{gc_stats::GcStats, meta::Metadata, trailer::SegmentFileTrailer},
-    Compressor, HashMap, Segment, SegmentWriter as MultiWriter,
+    metrics::{LatencyOp, Metrics, Timer},
+    segment::{
+        file_slot::FileSlot, gc_stats::GcStats, meta::Metadata, trailer::SegmentFileTrailer,
+        writer::Writer,
+    },
+    sync::{ArcSwap, AtomicU64, Mutex},
+    temperature::{ReadCounters, ReadStats},
+    Compressor, HashMap, IoContext, ManifestSummary, Segment, SegmentSummary,
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::{
-    io::{Cursor, Write},
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read},
     marker::PhantomData,
     path::{Path, PathBuf},
-    sync::{Arc, RwLock},
+    sync::{Arc, PoisonError},
 };
 
 pub const VLOG_MARKER: &str = ".vlog";
 pub const SEGMENTS_FOLDER: &str = "segments";
-const MANIFEST_FILE: &str = "vlog_manifest";
+pub const MANIFEST_FILE: &str = "vlog_manifest";
 
-/// Atomically rewrites a file
-fn rewrite_atomic<P: AsRef<Path>>(path: P, content: &[u8]) -> std::io::Result<()> {
-    let path = path.as_ref();
-    let folder = path.parent().expect("should have a parent");
+/// File that is locked while the value log is open, so it is not opened twice
+pub const LOCK_FILE: &str = ".lock";
 
-    let mut temp_file = tempfile::NamedTempFile::new_in(folder)?;
-    temp_file.write_all(content)?;
-    temp_file.persist(path)?;
+type SegmentMap<C> = HashMap<SegmentId, Arc<Segment<C>>>;
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        // TODO: Not sure if the fsync is really required, but just for the sake of it...
-        // TODO: also not sure why it fails on Windows...
-        let file = std::fs::File::open(path)?;
-        file.sync_all()?;
+/// Change to the segment list, which is persisted using a single manifest write
+pub struct ManifestEdit<C: Compressor + Clone> {
+    /// Finished segment writers whose segments are registered
+    pub add: Vec<Writer<C>>,
+
+    /// Segments that are dropped
+    pub remove: Vec<SegmentId>,
+}
+
+/// Result of a staged manifest edit, filled in once it has been persisted (or failed to)
+type CommitSlot = Arc<Mutex<Option<std::io::Result<()>>>>;
+
+/// Manifest edits that are applied, but not persisted yet
+struct StagedEdits<C: Compressor + Clone> {
+    /// Segment list & per-segment state with all staged edits applied
+    state: Option<(SegmentMap<C>, SegmentAttributes)>,
+
+    /// Result slots of the callers whose edits are staged
+    waiters: Vec<CommitSlot>,
+}
+
+impl<C: Compressor + Clone> Default for StagedEdits<C> {
+    fn default() -> Self {
+        Self {
+            state: None,
+            waiters: Vec::new(),
+        }
+    }
+}
+
+/// Manifest edit that is staged, but not necessarily persisted yet
+///
+/// Needs to be passed to [`SegmentManifest::commit`].
+#[must_use]
+pub struct StagedEdit(CommitSlot);
+
+fn fill_slot(slot: &CommitSlot, result: std::io::Result<()>) {
+    *slot.lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+}
+
+fn take_slot(slot: &CommitSlot) -> Option<std::io::Result<()>> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner).take()
+}
+
+/// Per-segment state that is persisted in the manifest, besides the segment list
+#[derive(Clone, Debug, Default)]
+pub struct SegmentAttributes {
+    /// Segments that are never picked for GC or dropped
+    pub pinned: BTreeSet<SegmentId>,
+
+    /// User-defined tags of segments, attached when they were registered
+    pub tags: BTreeMap<SegmentId, SegmentTags>,
+
+    /// Read counters of segments, as of the last time they were persisted
+    pub read_counters: BTreeMap<SegmentId, ReadCounters>,
+}
+
+/// User-defined key/value tags of a segment
+pub type SegmentTags = BTreeMap<String, String>;
+
+impl SegmentAttributes {
+    /// Removes the attributes of segments that are not part of the segment list.
+    fn retain<C: Compressor + Clone>(&mut self, segments: &SegmentMap<C>) {
+        self.pinned.retain(|id| segments.contains_key(id));
+        self.tags.retain(|id, _| segments.contains_key(id));
+        self.read_counters.retain(|id, _| segments.contains_key(id));
     }
+}
 
+fn write_string(bytes: &mut Vec<u8>, s: &str) -> std::io::Result<()> {
+    // NOTE: Tag length is checked when a tag is added, but tags
+    // may also come from an untrusted source (see `format::ManifestContents`)
+    let len = u16::try_from(s.len()).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "segment tag is too long")
+    })?;
+    bytes.write_u16::<BigEndian>(len)?;
+    bytes.extend_from_slice(s.as_bytes());
     Ok(())
 }
 
+fn read_string<R: Read>(reader: &mut R) -> std::io::Result<String> {
+    let len = reader.read_u16::<BigEndian>()?;
+
+    let mut buf = vec![0; usize::from(len)];
+    reader.read_exact(&mut buf)?;
+
+    String::from_utf8(buf)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Serializes the segment list & per-segment state into the manifest file format.
+pub fn encode_manifest(
+    segment_ids: &[SegmentId],
+    attributes: &SegmentAttributes,
+) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    let cnt = segment_ids.len() as u64;
+    bytes.write_u64::<BigEndian>(cnt)?;
+
+    for id in segment_ids {
+        bytes.write_u64::<BigEndian>(*id)?;
+    }
+
+    bytes.write_u64::<BigEndian>(attributes.pinned.len() as u64)?;
+
+    for id in &attributes.pinned {
+        bytes.write_u64::<BigEndian>(*id)?;
+    }
+
+    bytes.write_u64::<BigEndian>(attributes.tags.len() as u64)?;
+
+    for (id, tags) in &attributes.tags {
+        bytes.write_u64::<BigEndian>(*id)?;
+
+        // NOTE: Tag count is checked when a tag is added
+        let tag_cnt = u16::try_from(tags.len()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "too many segment tags")
+        })?;
+        bytes.write_u16::<BigEndian>(tag_cnt)?;
+
+        for (key, value) in tags {
+            write_string(&mut bytes, key)?;
+            write_string(&mut bytes, value)?;
+        }
+    }
+
+    bytes.write_u64::<BigEndian>(attributes.read_counters.len() as u64)?;
+
+    for (id, counters) in &attributes.read_counters {
+        bytes.write_u64::<BigEndian>(*id)?;
+        bytes.write_u64::<BigEndian>(counters.reads)?;
+        bytes.write_u64::<BigEndian>(counters.last_read_at.unwrap_or_default())?;
+        bytes.write_f64::<BigEndian>(counters.score)?;
+    }
+
+    Ok(bytes)
+}
+
+/// Deserializes the manifest file format into segment IDs and segment attributes.
+pub fn decode_manifest(bytes: &[u8]) -> std::io::Result<(Vec<SegmentId>, SegmentAttributes)> {
+    decode_manifest_sections(bytes).map(|(ids, attributes, _)| (ids, attributes))
+}
+
+/// Deserializes the manifest file format, additionally returning the end offset
+/// of every section that is present (segment list, pinned segments, tags, read counters).
+///
+/// Manifests written by older versions do not contain the trailing sections.
+pub fn decode_manifest_sections(
+    bytes: &[u8],
+) -> std::io::Result<(Vec<SegmentId>, SegmentAttributes, Vec<u64>)> {
+    let len = bytes.len() as u64;
+    let mut section_ends = vec![];
+
+    let mut ids = vec![];
+    let mut attributes = SegmentAttributes::default();
+
+    let mut cursor = Cursor::new(bytes);
+
+    let cnt = cursor.read_u64::<BigEndian>()?;
+
+    for _ in 0..cnt {
+        ids.push(cursor.read_u64::<BigEndian>()?);
+    }
+    section_ends.push(cursor.position());
+
+    if cursor.position() < len {
+        let cnt = cursor.read_u64::<BigEndian>()?;
+
+        for _ in 0..cnt {
+            attributes.pinned.insert(cursor.read_u64::<BigEndian>()?);
+        }
+        section_ends.push(cursor.position());
+    }
+
+    if cursor.position() < len {
+        let cnt = cursor.read_u64::<BigEndian>()?;
+
+        for _ in 0..cnt {
+            let id = cursor.read_u64::<BigEndian>()?;
+            let tag_cnt = cursor.read_u16::<BigEndian>()?;
+
+            let mut tags = SegmentTags::new();
+
+            for _ in 0..tag_cnt {
+                let key = read_string(&mut cursor)?;
+                let value = read_string(&mut cursor)?;
+                tags.insert(key, value);
+            }
+
+            attributes.tags.insert(id, tags);
+        }
+        section_ends.push(cursor.position());
+    }
+
+    if cursor.position() < len {
+        let cnt = cursor.read_u64::<BigEndian>()?;
+
+        for _ in 0..cnt {
+            let id = cursor.read_u64::<BigEndian>()?;
+            let reads = cursor.read_u64::<BigEndian>()?;
+            let last_read_at = cursor.read_u64::<BigEndian>()?;
+            let score = cursor.read_f64::<BigEndian>()?;
+
+            attributes.read_counters.insert(
+                id,
+                ReadCounters {
+                    reads,
+                    last_read_at: (last_read_at > 0).then_some(last_read_at),
+                    score,
+                },
+            );
+        }
+        section_ends.push(cursor.position());
+    }
+
+    Ok((ids, attributes, section_ends))
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct SegmentManifestInner<C: Compressor + Clone> {
     path: PathBuf,
-    pub segments: RwLock<HashMap<SegmentId, Arc<Segment<C>>>>,
+    fs: Arc<dyn Fs>,
+    clock: Arc<dyn Clock>,
+
+    /// Immutable snapshot of the segment list, which is replaced as a whole
+    /// on every change, so readers never block
+    pub segments: ArcSwap<SegmentMap<C>>,
+
+    /// Persisted per-segment state, which is replaced as a whole on every change
+    attributes: ArcSwap<SegmentAttributes>,
+
+    /// Bytes occupied by the blobs of all segments, updated on every change
+    disk_space_used: AtomicU64,
+
+    /// Edits waiting to be persisted by the next manifest write
+    staged: Mutex<StagedEdits<C>>,
+
+    /// Serializes manifest writes
+    write_lock: Mutex<()>,
+
+    /// Latency histograms of the value log
+    metrics: Arc<Metrics>,
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -60,29 +295,29 @@ impl<C: Compressor + Clone> std::ops::Deref for SegmentManifest<C> {
 
 impl<C: Compressor + Clone> SegmentManifest<C> {
     fn remove_unfinished_segments<P: AsRef<Path>>(
+        fs: &dyn Fs,
         folder: P,
         registered_ids: &[u64],
     ) -> crate::Result<()> {
-        for dirent in std::fs::read_dir(folder)? {
-            let dirent = dirent?;
+        for path in fs.list_files(folder.as_ref())? {
+            let Some(file_name) = path.file_name() else {
+                continue;
+            };
 
             // IMPORTANT: Skip .DS_Store files when using MacOS
-            if dirent.file_name() == ".DS_Store" {
+            if file_name == ".DS_Store" {
                 continue;
             }
 
-            if dirent.file_type()?.is_file() {
-                let segment_id = dirent
-                    .file_name()
-                    .to_str()
-                    .expect("should be valid utf-8")
-                    .parse::<u64>()
-                    .expect("should be valid segment ID");
-
-                if !registered_ids.contains(&segment_id) {
-                    log::trace!("Deleting unfinished vLog segment {segment_id}");
-                    std::fs::remove_file(dirent.path())?;
-                }
+            let segment_id = file_name
+                .to_str()
+                .expect("should be valid utf-8")
+                .parse::<u64>()
+                .expect("should be valid segment ID");
+
+            if !registered_ids.contains(&segment_id) {
+                log::trace!("Deleting unfinished vLog segment {segment_id}");
+                fs.remove_file(&path)?;
             }
         }
 
@@ -90,33 +325,45 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
     }
 
     /// Parses segment IDs from manifest file
-    fn load_ids_from_disk<P: AsRef<Path>>(path: P) -> crate::Result<Vec<SegmentId>> {
+    pub(crate) fn load_ids_from_disk<P: AsRef<Path>>(
+        fs: &dyn Fs,
+        path: P,
+    ) -> crate::Result<Vec<SegmentId>> {
+        Self::load_from_disk(fs, path).map(|(ids, _)| ids)
+    }
+
+    /// Parses segment IDs and segment attributes from manifest file
+    ///
+    /// Segment attributes are stored after the segment IDs, so manifests
+    /// written before they existed simply have none.
+    fn load_from_disk<P: AsRef<Path>>(
+        fs: &dyn Fs,
+        path: P,
+    ) -> crate::Result<(Vec<SegmentId>, SegmentAttributes)> {
         let path = path.as_ref();
         log::debug!("Loading manifest from {}", path.display());
 
-        let bytes = std::fs::read(path)?;
-
-        let mut ids = vec![];
+        let bytes = fs.read(path)?;
 
-        let mut cursor = Cursor::new(bytes);
-
-        let cnt = cursor.read_u64::<BigEndian>()?;
-
-        for _ in 0..cnt {
-            ids.push(cursor.read_u64::<BigEndian>()?);
-        }
-
-        Ok(ids)
+        decode_manifest(&bytes).map_err(|e| {
+            log::error!("Manifest at {} is corrupt: {e:?}", path.display());
+            crate::Error::CorruptManifest
+        })
     }
 
     /// Recovers a value log from disk
-    pub(crate) fn recover<P: AsRef<Path>>(folder: P) -> crate::Result<Self> {
+    pub(crate) fn recover<P: AsRef<Path>>(
+        folder: P,
+        fs: Arc<dyn Fs>,
+        clock: Arc<dyn Clock>,
+        metrics: Arc<Metrics>,
+    ) -> crate::Result<Self> {
         let folder = folder.as_ref();
         let manifest_path = folder.join(MANIFEST_FILE);
 
         log::info!("Recovering vLog at {folder:?}");
 
-        let ids = Self::load_ids_from_disk(&manifest_path)?;
+        let (ids, mut attributes) = Self::load_from_disk(&*fs, &manifest_path)?;
         let cnt = ids.len();
 
         let progress_mod = match cnt {
@@ -128,7 +375,7 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
         log::debug!("Recovering {cnt} vLog segments from {folder:?}");
 
         let segments_folder = folder.join(SEGMENTS_FOLDER);
-        Self::remove_unfinished_segments(&segments_folder, &ids)?;
+        Self::remove_unfinished_segments(&*fs, &segments_folder, &ids)?;
 
         let segments = {
             let mut map =
@@ -138,7 +385,9 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
                 log::trace!("Recovering segment #{id:?}");
 
                 let path = segments_folder.join(id.to_string());
-                let trailer = SegmentFileTrailer::from_file(&path)?;
+                let trailer = SegmentFileTrailer::from_file(&*fs, &path).map_err(|e| {
+                    e.with_context(IoContext::new("recover").segment_id(id).path(&path))
+                })?;
 
                 map.insert(
                     id,
@@ -147,6 +396,15 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
                         path,
                         meta: trailer.metadata,
                         gc_stats: GcStats::default(),
+                        read_stats: attributes
+                            .read_counters
+                            .get(&id)
+                            .copied()
+                            .map(ReadStats::from)
+                            .unwrap_or_default(),
+                        file_slot: FileSlot::default(),
+                        fs: fs.clone(),
+                        clock: clock.clone(),
                         _phantom: PhantomData,
                     }),
                 );
@@ -159,70 +417,264 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
             map
         };
 
+        attributes.retain(&segments);
+
         Ok(Self(Arc::new(SegmentManifestInner {
             path: manifest_path,
-            segments: RwLock::new(segments),
+            fs,
+            clock,
+            attributes: ArcSwap::from_pointee(attributes),
+            disk_space_used: AtomicU64::new(Self::sum_disk_space(&segments)),
+            segments: ArcSwap::from_pointee(segments),
+            staged: Mutex::default(),
+            write_lock: Mutex::default(),
+            metrics,
         })))
     }
 
-    pub(crate) fn create_new<P: AsRef<Path>>(folder: P) -> crate::Result<Self> {
+    pub(crate) fn create_new<P: AsRef<Path>>(
+        folder: P,
+        fs: Arc<dyn Fs>,
+        clock: Arc<dyn Clock>,
+        metrics: Arc<Metrics>,
+    ) -> crate::Result<Self> {
         let path = folder.as_ref().join(MANIFEST_FILE);
 
         let m = Self(Arc::new(SegmentManifestInner {
             path,
-            segments: RwLock::new(HashMap::default()),
+            fs,
+            clock,
+            segments: ArcSwap::from_pointee(HashMap::default()),
+            attributes: ArcSwap::from_pointee(SegmentAttributes::default()),
+            disk_space_used: AtomicU64::new(0),
+            staged: Mutex::default(),
+            write_lock: Mutex::default(),
+            metrics,
         }));
-        Self::write_to_disk(&m.path, &[])?;
+        Self::write_to_disk(&*m.fs, &m.path, &[])?;
 
         Ok(m)
     }
 
+    /// Returns a snapshot of the segment list.
+    ///
+    /// This never blocks, even while the segment list is being changed.
+    pub(crate) fn read_segments(&self) -> Arc<SegmentMap<C>> {
+        self.segments.load_full()
+    }
+
     /// Modifies the level manifest atomically.
-    pub(crate) fn atomic_swap<F: FnOnce(&mut HashMap<SegmentId, Arc<Segment<C>>>)>(
+    pub(crate) fn atomic_swap<F: FnOnce(&mut SegmentMap<C>)>(&self, f: F) -> crate::Result<()> {
+        self.atomic_swap_with_attributes(|recipe, _| f(recipe))
+    }
+
+    /// Modifies the level manifest and the persisted per-segment state atomically.
+    ///
+    /// Edits of concurrent callers are persisted together, in a single manifest write
+    /// (group commit). The change is visible to readers once it is durable.
+    pub(crate) fn atomic_swap_with_attributes<
+        F: FnOnce(&mut SegmentMap<C>, &mut SegmentAttributes),
+    >(
         &self,
         f: F,
     ) -> crate::Result<()> {
-        let mut prev_segments = self.segments.write().expect("lock is poisoned");
+        let edit = self.stage(f);
+        self.commit(edit)
+    }
 
-        // NOTE: Create a copy of the levels we can operate on
-        // without mutating the current level manifest
-        // If persisting to disk fails, this way the level manifest
-        // is unchanged
-        let mut working_copy = prev_segments.clone();
+    /// Applies an edit to the staged state of the level manifest, without persisting it.
+    ///
+    /// Edits are applied in the order they are staged.
+    pub(crate) fn stage<F: FnOnce(&mut SegmentMap<C>, &mut SegmentAttributes)>(
+        &self,
+        f: F,
+    ) -> StagedEdit {
+        let slot = CommitSlot::default();
 
-        f(&mut working_copy);
+        {
+            // NOTE: The locks guard no data that can be left inconsistent
+            // (the segment list is only ever replaced as a whole),
+            // so if another thread panicked while holding them, they are simply recovered
+            let mut staged = self.staged.lock().unwrap_or_else(PoisonError::into_inner);
+
+            // NOTE: Edits are applied to a copy of the current state,
+            // so the level manifest is unchanged until the edits are persisted
+            let (segments, attributes) = staged.state.get_or_insert_with(|| {
+                (
+                    (*self.segments.load_full()).clone(),
+                    (*self.attributes.load_full()).clone(),
+                )
+            });
+
+            f(segments, attributes);
+
+            staged.waiters.push(slot.clone());
+        }
 
-        let ids = working_copy.keys().copied().collect::<Vec<_>>();
+        StagedEdit(slot)
+    }
 
-        Self::write_to_disk(&self.path, &ids)?;
-        *prev_segments = working_copy;
+    /// Waits until a staged edit is persisted, persisting it (and all other staged edits)
+    /// if no other caller is doing so already.
+    ///
+    /// The edit is visible to readers once this returns successfully.
+    pub(crate) fn commit(&self, edit: StagedEdit) -> crate::Result<()> {
+        let StagedEdit(slot) = edit;
+
+        let lock = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // NOTE: If another caller got the lock first, it has persisted our edit as well
+        if let Some(result) = take_slot(&slot) {
+            return result.map_err(Into::into);
+        }
 
-        // NOTE: Lock needs to live until end of function because
-        // writing to disk needs to be exclusive
-        drop(prev_segments);
+        self.commit_staged();
 
-        log::trace!("Swapped vLog segment list to: {ids:?}");
+        // NOTE: Lock needs to live until the staged edits are published,
+        // because writing to disk needs to be exclusive
+        drop(lock);
 
-        Ok(())
+        take_slot(&slot).unwrap_or(Ok(())).map_err(Into::into)
+    }
+
+    /// Persists & publishes all staged edits, filling in the result of every waiting caller.
+    ///
+    /// Needs to be called while holding the write lock.
+    fn commit_staged(&self) {
+        let (segments, mut attributes, waiters) = {
+            let mut staged = self.staged.lock().unwrap_or_else(PoisonError::into_inner);
+
+            let Some((segments, attributes)) = &staged.state else {
+                return;
+            };
+
+            let (segments, attributes) = (segments.clone(), attributes.clone());
+            (segments, attributes, std::mem::take(&mut staged.waiters))
+        };
+
+        let ids = segments.keys().copied().collect::<Vec<_>>();
+
+        attributes.retain(&segments);
+
+        log::trace!("Writing segment manifest to {}", self.path.display());
+
+        let timer = Timer::start();
+
+        let result = failpoints::eval(failpoints::MANIFEST_WRITE)
+            .and_then(|()| encode_manifest(&ids, &attributes))
+            .and_then(|bytes| self.fs.rewrite_atomic(&self.path, &bytes));
+
+        self.metrics.record(LatencyOp::Fsync, timer);
+
+        match result {
+            Ok(()) => {
+                self.attributes.store(Arc::new(attributes));
+                self.disk_space_used.store(
+                    Self::sum_disk_space(&segments),
+                    std::sync::atomic::Ordering::Release,
+                );
+                self.segments.store(Arc::new(segments));
+
+                {
+                    let mut staged = self.staged.lock().unwrap_or_else(PoisonError::into_inner);
+
+                    // NOTE: If no edits were staged in the meantime,
+                    // the staged state is identical to the published one
+                    if staged.waiters.is_empty() {
+                        staged.state = None;
+                    }
+                }
+
+                log::trace!(
+                    "Swapped vLog segment list to: {ids:?} ({} edit(s))",
+                    waiters.len(),
+                );
+
+                for slot in &waiters {
+                    fill_slot(slot, Ok(()));
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to write vLog manifest: {e:?}");
+
+                // NOTE: Edits that were staged in the meantime build on the failed ones,
+                // so they are discarded as well
+                let mut staged = self.staged.lock().unwrap_or_else(PoisonError::into_inner);
+                staged.state = None;
+
+                for slot in waiters.iter().chain(&staged.waiters) {
+                    fill_slot(slot, Err(std::io::Error::new(e.kind(), e.to_string())));
+                }
+
+                staged.waiters.clear();
+            }
+        }
+    }
+
+    /// Returns a snapshot of the persisted per-segment state.
+    pub(crate) fn attributes(&self) -> Arc<SegmentAttributes> {
+        self.attributes.load_full()
+    }
+
+    /// Modifies the persisted per-segment state atomically.
+    pub(crate) fn update_attributes<F: FnOnce(&mut SegmentAttributes)>(
+        &self,
+        f: F,
+    ) -> crate::Result<()> {
+        self.atomic_swap_with_attributes(|_, attributes| f(attributes))
+    }
+
+    /// Returns `true` if the segment is pinned.
+    #[must_use]
+    pub fn is_pinned(&self, id: SegmentId) -> bool {
+        self.attributes().pinned.contains(&id)
     }
 
     pub fn drop_segments(&self, ids: &[u64]) -> crate::Result<()> {
-        self.atomic_swap(|recipe| {
-            recipe.retain(|x, _| !ids.contains(x));
+        self.apply(ManifestEdit {
+            add: vec![],
+            remove: ids.to_vec(),
+        })?;
+        Ok(())
+    }
+
+    /// Registers the segments of a finished writer, returning the IDs of the new segments.
+    pub fn register(&self, writers: Vec<Writer<C>>) -> crate::Result<Vec<SegmentId>> {
+        self.apply(ManifestEdit {
+            add: writers,
+            remove: vec![],
         })
     }
 
-    pub fn register(&self, writer: MultiWriter<C>) -> crate::Result<()> {
-        let writers = writer.finish()?;
+    /// Registers & drops segments using a single durable manifest write,
+    /// returning the IDs of the new segments.
+    ///
+    /// Readers see either none or all of the changes.
+    pub fn apply(&self, edit: ManifestEdit<C>) -> crate::Result<Vec<SegmentId>> {
+        let (segment_ids, edit) = self.stage_apply(edit);
+        self.commit(edit)?;
+        Ok(segment_ids)
+    }
 
-        self.atomic_swap(move |recipe| {
-            for writer in writers {
+    /// Stages a change to the segment list (see [`SegmentManifest::apply`]),
+    /// returning the IDs of the new segments.
+    pub(crate) fn stage_apply(&self, edit: ManifestEdit<C>) -> (Vec<SegmentId>, StagedEdit) {
+        let ManifestEdit { add, remove } = edit;
+        let mut segment_ids = Vec::with_capacity(add.len());
+
+        let edit = self.stage(|recipe, attributes| {
+            recipe.retain(|x, _| !remove.contains(x));
+
+            for writer in add {
                 if writer.item_count == 0 {
                     log::debug!(
                         "Writer at {:?} has written no data, deleting empty vLog segment file",
                         writer.path
                     );
-                    if let Err(e) = std::fs::remove_file(&writer.path) {
+                    if let Err(e) = self.fs.remove_file(&writer.path) {
                         log::warn!(
                             "Could not delete empty vLog segment file at {:?}: {e:?}",
                             writer.path
@@ -232,6 +684,11 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
                 }
 
                 let segment_id = writer.segment_id;
+                segment_ids.push(segment_id);
+
+                if !writer.tags.is_empty() {
+                    attributes.tags.insert(segment_id, writer.tags);
+                }
 
                 recipe.insert(
                     segment_id,
@@ -256,8 +713,15 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
                                     .clone()
                                     .expect("should have written at least 1 item"),
                             )),
+                            key_version: writer.pipeline.key_version(),
+                            pipeline: writer.pipeline.stages(),
+                            created_at: writer.created_at,
                         },
                         gc_stats: GcStats::default(),
+                        read_stats: ReadStats::default(),
+                        file_slot: FileSlot::default(),
+                        fs: self.fs.clone(),
+                        clock: self.clock.clone(),
                         _phantom: PhantomData,
                     }),
                 );
@@ -268,28 +732,74 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
                     writer.uncompressed_bytes,
                 );
             }
-        })?;
+        });
 
         // NOTE: If we crash before before finishing the index write, it's fine
         // because all new segments will be unreferenced, and thus can be dropped because stale
 
-        Ok(())
+        (segment_ids, edit)
     }
 
-    fn write_to_disk<P: AsRef<Path>>(path: P, segment_ids: &[SegmentId]) -> crate::Result<()> {
-        let path = path.as_ref();
-        log::trace!("Writing segment manifest to {}", path.display());
+    /// Registers a segment file that is already located in the segments folder.
+    pub(crate) fn register_file(
+        &self,
+        segment_id: SegmentId,
+        path: PathBuf,
+        meta: Metadata,
+    ) -> crate::Result<()> {
+        self.register_files(vec![(segment_id, path, meta)])
+    }
 
-        let mut bytes = Vec::new();
+    /// Registers segment files that are already located in the segments folder,
+    /// using a single manifest write.
+    pub(crate) fn register_files(
+        &self,
+        files: Vec<(SegmentId, PathBuf, Metadata)>,
+    ) -> crate::Result<()> {
+        self.atomic_swap(move |recipe| {
+            for (segment_id, path, meta) in files {
+                log::debug!(
+                    "Ingested segment #{segment_id:?} ({} items, {} userdata bytes)",
+                    meta.item_count,
+                    meta.total_uncompressed_bytes,
+                );
 
-        let cnt = segment_ids.len() as u64;
-        bytes.write_u64::<BigEndian>(cnt)?;
+                recipe.insert(
+                    segment_id,
+                    Arc::new(Segment {
+                        id: segment_id,
+                        path,
+                        meta,
+                        gc_stats: GcStats::default(),
+                        read_stats: ReadStats::default(),
+                        file_slot: FileSlot::default(),
+                        fs: self.fs.clone(),
+                        clock: self.clock.clone(),
+                        _phantom: PhantomData,
+                    }),
+                );
+            }
+        })
+    }
 
-        for id in segment_ids {
-            bytes.write_u64::<BigEndian>(*id)?;
-        }
+    pub(crate) fn write_to_disk<P: AsRef<Path>>(
+        fs: &dyn Fs,
+        path: P,
+        segment_ids: &[SegmentId],
+    ) -> crate::Result<()> {
+        Self::write_with_attributes(fs, path, segment_ids, &SegmentAttributes::default())
+    }
 
-        rewrite_atomic(path, &bytes)?;
+    pub(crate) fn write_with_attributes<P: AsRef<Path>>(
+        fs: &dyn Fs,
+        path: P,
+        segment_ids: &[SegmentId],
+        attributes: &SegmentAttributes,
+    ) -> crate::Result<()> {
+        let path = path.as_ref();
+        log::trace!("Writing segment manifest to {}", path.display());
+
+        fs.rewrite_atomic(path, &encode_manifest(segment_ids, attributes)?)?;
 
         Ok(())
     }
@@ -297,59 +807,73 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
     /// Gets a segment
     #[must_use]
     pub fn get_segment(&self, id: SegmentId) -> Option<Arc<Segment<C>>> {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
-            .get(&id)
-            .cloned()
+        self.read_segments().get(&id).cloned()
     }
 
     /// Lists all segment IDs
     #[doc(hidden)]
     #[must_use]
     pub fn list_segment_ids(&self) -> Vec<SegmentId> {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
-            .keys()
-            .copied()
-            .collect()
+        self.read_segments().keys().copied().collect()
     }
 
     /// Lists all segments
     #[must_use]
     pub fn list_segments(&self) -> Vec<Arc<Segment<C>>> {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
+        self.read_segments().values().cloned().collect()
+    }
+
+    /// Returns point-in-time statistics of all segments.
+    #[must_use]
+    pub fn summary(&self) -> ManifestSummary {
+        ManifestSummary::new(
+            self.read_segments()
+                .values()
+                .map(|x| SegmentSummary::from(&**x))
+                .collect(),
+        )
+    }
+
+    /// Returns the garbage collection reports of all segments,
+    /// sorted by reclaimable bytes (descending).
+    #[must_use]
+    pub fn gc_reports(&self) -> Vec<SegmentGcReport> {
+        let mut reports = self
+            .read_segments()
             .values()
-            .cloned()
-            .collect()
+            .map(|x| x.gc_report())
+            .collect::<Vec<_>>();
+
+        reports.sort_by(|a, b| {
+            b.reclaimable_bytes
+                .cmp(&a.reclaimable_bytes)
+                .then(a.segment_id.cmp(&b.segment_id))
+        });
+
+        reports
     }
 
     /// Counts segments
     #[must_use]
     pub fn len(&self) -> usize {
-        self.segments.read().expect("lock is poisoned").len()
+        self.read_segments().len()
+    }
+
+    fn sum_disk_space(segments: &SegmentMap<C>) -> u64 {
+        segments.values().map(|x| x.meta.compressed_bytes).sum()
     }
 
     /// Returns the amount of bytes on disk that are occupied by blobs.
     #[must_use]
     pub fn disk_space_used(&self) -> u64 {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
-            .values()
-            .map(|x| x.meta.compressed_bytes)
-            .sum::<u64>()
+        self.disk_space_used
+            .load(std::sync::atomic::Ordering::Acquire)
     }
 
     /// Returns the amount of stale bytes
     #[must_use]
     pub fn total_bytes(&self) -> u64 {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
+        self.read_segments()
             .values()
             .map(|x| x.meta.total_uncompressed_bytes)
             .sum::<u64>()
@@ -358,9 +882,7 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
     /// Returns the amount of stale bytes
     #[must_use]
     pub fn stale_bytes(&self) -> u64 {
-        self.segments
-            .read()
-            .expect("lock is poisoned")
+        self.read_segments()
             .values()
             .map(|x| x.gc_stats.stale_bytes())
             .sum::<u64>()
@@ -405,29 +927,3 @@ impl<C: Compressor + Clone> SegmentManifest<C> {
         total_bytes as f32 / alive_bytes as f32
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
-    use test_log::test;
-
-    #[test]
-    fn test_atomic_rewrite() -> crate::Result<()> {
-        let dir = tempfile::tempdir()?;
-
-        let path = dir.path().join("test.txt");
-        {
-            let mut file = File::create(&path)?;
-            write!(file, "asdasdasdasdasd")?;
-        }
-
-        rewrite_atomic(&path, b"newcontent")?;
-
-        let content = std::fs::read_to_string(&path)?;
-        assert_eq!("newcontent", content);
-
-        Ok(())
-    }
-}
@@ -0,0 +1,101 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::sync::AtomicU64;
+use std::sync::{atomic::Ordering, Arc};
+
+/// Size of the buffer of every segment reader & writer
+pub const IO_BUFFER_SIZE: u64 = /* 8 KiB */ 8 * 1_024;
+
+/// Point-in-time memory usage of a value log (see [`ValueLog::memory_usage`](crate::ValueLog::memory_usage))
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MemoryUsage {
+    /// Bytes of blobs held by the blob cache
+    ///
+    /// If the blob cache is shared, this includes the blobs of other value logs.
+    pub blob_cache: u64,
+
+    /// Bytes of values held by the value cache (see [`Config::value_cache`](crate::Config::value_cache))
+    ///
+    /// If the value cache is shared, this includes the values of other value logs.
+    pub value_cache: u64,
+
+    /// Bytes of buffers of open segment readers
+    pub read_buffers: u64,
+
+    /// Bytes of buffers of segment writers that are not registered yet
+    pub write_buffers: u64,
+
+    /// Configured memory budget (see [`Config::memory_budget`](crate::Config::memory_budget))
+    pub budget: Option<u64>,
+}
+
+impl MemoryUsage {
+    /// Returns the total amount of used bytes.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.blob_cache + self.value_cache + self.read_buffers + self.write_buffers
+    }
+
+    /// Returns `true` if the usage exceeds the memory budget.
+    #[must_use]
+    pub fn is_over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.total() > budget)
+    }
+}
+
+/// Counters of memory that is used besides the caches
+#[derive(Clone, Default)]
+pub struct MemoryTracker {
+    read_buffers: Arc<AtomicU64>,
+    write_buffers: Arc<AtomicU64>,
+}
+
+impl MemoryTracker {
+    /// Reserves memory of a reader's buffer, until the reservation is dropped.
+    pub fn reserve_read_buffer(&self) -> MemoryReservation {
+        MemoryReservation::new(self.read_buffers.clone(), IO_BUFFER_SIZE)
+    }
+
+    /// Reserves memory of a writer's buffer, until the reservation is dropped.
+    pub fn reserve_write_buffer(&self) -> MemoryReservation {
+        MemoryReservation::new(self.write_buffers.clone(), IO_BUFFER_SIZE)
+    }
+
+    /// Returns the bytes used by reader buffers.
+    pub fn read_buffers(&self) -> u64 {
+        self.read_buffers.load(Ordering::Acquire)
+    }
+
+    /// Returns the bytes used by writer buffers.
+    pub fn write_buffers(&self) -> u64 {
+        self.write_buffers.load(Ordering::Acquire)
+    }
+}
+
+/// Memory accounted for in a [`MemoryTracker`], which is released on drop
+pub struct MemoryReservation {
+    counter: Arc<AtomicU64>,
+    bytes: u64,
+}
+
+impl MemoryReservation {
+    fn new(counter: Arc<AtomicU64>, bytes: u64) -> Self {
+        counter.fetch_add(bytes, Ordering::AcqRel);
+        Self { counter, bytes }
+    }
+
+    /// Reserves more memory.
+    pub fn grow(&mut self, bytes: u64) {
+        self.counter.fetch_add(bytes, Ordering::AcqRel);
+        self.bytes += bytes;
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Latency histograms, which are only recorded with the `metrics` feature enabled
+//!
+//! Without the feature, recording compiles to nothing.
+
+#[cfg(feature = "metrics")]
+use crate::sync::AtomicU64;
+
+#[cfg(feature = "metrics")]
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+/// Operation whose latency is recorded
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LatencyOp {
+    /// Resolving a value handle
+    Get,
+
+    /// Writing a blob into a segment
+    SegmentWrite,
+
+    /// Making a segment file or the manifest durable
+    Fsync,
+
+    /// Rollover step: reading & rewriting the live blobs
+    RolloverRelocate,
+
+    /// Rollover step: passing relocations to the index writer
+    RolloverIndex,
+
+    /// Rollover step: finishing & registering the new segments
+    RolloverCommit,
+}
+
+#[cfg(feature = "metrics")]
+const OP_COUNT: usize = 6;
+
+/// Started latency measurement, see [`Metrics::record`]
+#[derive(Copy, Clone)]
+pub struct Timer {
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl Timer {
+    /// Starts measuring.
+    #[inline]
+    pub fn start() -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Latency histograms of a value log
+#[derive(Default)]
+pub struct Metrics {
+    #[cfg(feature = "metrics")]
+    histograms: [Histogram; OP_COUNT],
+}
+
+impl Metrics {
+    /// Records the time elapsed since the timer was started.
+    #[inline]
+    #[cfg_attr(not(feature = "metrics"), allow(clippy::unused_self))]
+    pub fn record(&self, op: LatencyOp, timer: Timer) {
+        #[cfg(feature = "metrics")]
+        self.histogram(op).record(timer.start.elapsed());
+
+        #[cfg(not(feature = "metrics"))]
+        let _ = (op, timer);
+    }
+
+    #[cfg(feature = "metrics")]
+    fn histogram(&self, op: LatencyOp) -> &Histogram {
+        let idx = match op {
+            LatencyOp::Get => 0,
+            LatencyOp::SegmentWrite => 1,
+            LatencyOp::Fsync => 2,
+            LatencyOp::RolloverRelocate => 3,
+            LatencyOp::RolloverIndex => 4,
+            LatencyOp::RolloverCommit => 5,
+        };
+
+        // NOTE: The index is always in bounds
+        #[allow(clippy::indexing_slicing)]
+        &self.histograms[idx]
+    }
+
+    /// Returns the latency distributions of all operations.
+    #[cfg(feature = "metrics")]
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            get: self.histogram(LatencyOp::Get).snapshot(),
+            segment_write: self.histogram(LatencyOp::SegmentWrite).snapshot(),
+            fsync: self.histogram(LatencyOp::Fsync).snapshot(),
+            rollover_relocate: self.histogram(LatencyOp::RolloverRelocate).snapshot(),
+            rollover_index: self.histogram(LatencyOp::RolloverIndex).snapshot(),
+            rollover_commit: self.histogram(LatencyOp::RolloverCommit).snapshot(),
+        }
+    }
+}
+
+/// Values below this are counted exactly
+#[cfg(feature = "metrics")]
+const LINEAR_BUCKETS: u64 = 16;
+
+/// Every power of two above [`LINEAR_BUCKETS`] is split into this many buckets,
+/// so the relative error of a quantile is at most 12.5%
+#[cfg(feature = "metrics")]
+const SUB_BUCKETS: u64 = 8;
+
+#[cfg(feature = "metrics")]
+#[allow(clippy::cast_possible_truncation)]
+const BUCKET_COUNT: usize = (LINEAR_BUCKETS + (64 - 4) * SUB_BUCKETS) as usize;
+
+/// Returns the bucket of a latency (in nanoseconds).
+#[cfg(feature = "metrics")]
+fn bucket_of(nanos: u64) -> usize {
+    let bucket = if nanos < LINEAR_BUCKETS {
+        nanos
+    } else {
+        let exponent = u64::from(nanos.ilog2());
+        let sub = (nanos >> (exponent - 3)) & (SUB_BUCKETS - 1);
+
+        LINEAR_BUCKETS + (exponent - 4) * SUB_BUCKETS + sub
+    };
+
+    // NOTE: The bucket is < BUCKET_COUNT
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        bucket as usize
+    }
+}
+
+/// Returns the highest latency (in nanoseconds) that falls into a bucket.
+#[cfg(feature = "metrics")]
+fn bucket_upper_bound(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+
+    if bucket < LINEAR_BUCKETS {
+        return bucket;
+    }
+
+    let exponent = (bucket - LINEAR_BUCKETS) / SUB_BUCKETS + 4;
+    let sub = (bucket - LINEAR_BUCKETS) % SUB_BUCKETS;
+
+    let lower = (SUB_BUCKETS + sub) << (exponent - 3);
+    lower.saturating_add((1 << (exponent - 3)) - 1)
+}
+
+/// Lock-free log-linear latency histogram
+#[cfg(feature = "metrics")]
+struct Histogram {
+    buckets: Box<[AtomicU64]>,
+    max: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKET_COUNT).map(|_| AtomicU64::new(0)).collect(),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    fn record(&self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+
+        if let Some(bucket) = self.buckets.get(bucket_of(nanos)) {
+            bucket.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyStats {
+        let counts = self
+            .buckets
+            .iter()
+            .map(|x| x.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+
+        let count = counts.iter().sum::<u64>();
+        let max = self.max.load(Ordering::Relaxed);
+
+        let quantile = |q: f64| {
+            // NOTE: The rank is <= count
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_precision_loss,
+                clippy::cast_sign_loss
+            )]
+            let rank = ((count as f64 * q).ceil() as u64).max(1);
+
+            let mut seen = 0;
+
+            for (bucket, bucket_count) in counts.iter().enumerate() {
+                seen += bucket_count;
+
+                if seen >= rank {
+                    return Duration::from_nanos(bucket_upper_bound(bucket).min(max));
+                }
+            }
+
+            Duration::from_nanos(max)
+        };
+
+        if count == 0 {
+            return LatencyStats::default();
+        }
+
+        LatencyStats {
+            count,
+            p50: quantile(0.5),
+            p99: quantile(0.99),
+            p999: quantile(0.999),
+            max: Duration::from_nanos(max),
+        }
+    }
+}
+
+/// Latency distribution of an operation
+///
+/// Quantiles are approximated, with a relative error of at most 12.5%.
+#[cfg(feature = "metrics")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct LatencyStats {
+    /// Amount of recorded operations
+    pub count: u64,
+
+    /// Median latency
+    pub p50: Duration,
+
+    /// 99th percentile latency
+    pub p99: Duration,
+
+    /// 99.9th percentile latency
+    pub p999: Duration,
+
+    /// Highest latency
+    pub max: Duration,
+}
+
+/// Latency distributions of a value log's operations, see [`ValueLog::statistics`](crate::ValueLog::statistics)
+#[cfg(feature = "metrics")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Statistics {
+    /// Resolving value handles (see [`ValueLog::get`](crate::ValueLog::get))
+    pub get: LatencyStats,
+
+    /// Writing blobs into segments
+    pub segment_write: LatencyStats,
+
+    /// Making segment files & the manifest durable
+    pub fsync: LatencyStats,
+
+    /// Rollover step: reading & rewriting the live blobs of the rewritten segments
+    pub rollover_relocate: LatencyStats,
+
+    /// Rollover step: passing a batch of relocations to the index writer
+    pub rollover_index: LatencyStats,
+
+    /// Rollover step: finishing & registering the new segments
+    pub rollover_commit: LatencyStats,
+}
@@ -2,46 +2,203 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{value::UserKey, IndexReader, IndexWriter, ValueHandle};
+use crate::{
+    value::UserKey, Compressor, IndexReader, IndexWriter, SizedValueHandle, ValueHandle, ValueLog,
+};
 use std::{
     collections::BTreeMap,
-    sync::{Arc, RwLock},
+    ops::RangeBounds,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
 };
 
-type MockIndexInner = RwLock<BTreeMap<UserKey, (ValueHandle, u32)>>;
+type MockIndexItems = BTreeMap<UserKey, (ValueHandle, u32)>;
+
+type MockIndexInner = RwLock<MockIndexItems>;
+
+type InvariantCheck = Arc<dyn Fn(&MockIndex) -> crate::Result<()> + Send + Sync>;
 
-/// Mock in-memory index
+/// In-memory index, mapping keys to value handles and value sizes
+///
+/// Can be used to test applications or GC strategies without an actual LSM-tree.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Default)]
-pub struct MockIndex(Arc<MockIndexInner>);
+pub struct MockIndex {
+    items: Arc<MockIndexInner>,
+    overwrites: Arc<AtomicU64>,
+
+    /// Invariant check that runs whenever a write batch is finished
+    check: Option<InvariantCheck>,
+}
 
 impl std::ops::Deref for MockIndex {
     type Target = MockIndexInner;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.items
     }
 }
 
 impl MockIndex {
-    /// Remove item
+    /// Creates an empty index that checks its invariants against the value log
+    /// (see [`MockIndex::check_invariants`]) whenever a write batch is finished,
+    /// including the relocation batches of garbage collection.
+    ///
+    /// Write batches need to be finished after their writers are registered,
+    /// like they would be with an actual index.
+    /// Checks stop once the value log is dropped.
+    ///
+    /// If the value log cannot be read while checking, finishing the write batch fails.
+    ///
+    /// # Panics
+    ///
+    /// Finishing a write batch panics if an invariant is violated.
+    #[must_use]
+    pub fn checked<C: Compressor + Clone + Send + Sync + 'static>(value_log: &ValueLog<C>) -> Self {
+        let value_log = value_log.downgrade();
+
+        Self {
+            check: Some(Arc::new(move |index: &Self| {
+                ValueLog::upgrade(&value_log)
+                    .map_or(Ok(()), |value_log| index.check_invariants(&value_log))
+            })),
+            ..Default::default()
+        }
+    }
+
+    /// Read-locks the items.
+    ///
+    /// If another thread panicked while holding the lock, the lock is recovered,
+    /// because every modification is a single map operation.
+    fn read_items(&self) -> RwLockReadGuard<'_, MockIndexItems> {
+        self.items.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Write-locks the items, see [`MockIndex::read_items`].
+    fn write_items(&self) -> RwLockWriteGuard<'_, MockIndexItems> {
+        self.items.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Checks that the index is consistent with the value log:
+    ///
+    /// - no value handle points into a segment that is not (or no longer) part of the value log
+    /// - every value handle resolves
+    /// - the size of every value matches the indexed size
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an invariant is violated.
+    pub fn check_invariants<C: Compressor + Clone>(
+        &self,
+        value_log: &ValueLog<C>,
+    ) -> crate::Result<()> {
+        for (key, vhandle, size) in self.range(..) {
+            let vhandle = value_log.remapped_handle(&vhandle).unwrap_or(vhandle);
+
+            assert!(
+                value_log.manifest.get_segment(vhandle.segment_id).is_some(),
+                "value handle {vhandle:?} of key {key:?} points into a segment that is not part of the value log",
+            );
+
+            let Some(value) = value_log.get(&vhandle)? else {
+                panic!("value handle {vhandle:?} of key {key:?} does not resolve");
+            };
+
+            assert_eq!(
+                value.len() as u64,
+                u64::from(size),
+                "value size of key {key:?} does not match index",
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Removes an item.
     pub fn remove(&self, key: &[u8]) {
-        self.0.write().expect("lock is poisoned").remove(key);
+        self.write_items().remove(key);
+    }
+
+    /// Returns the amount of items.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.read_items().len()
+    }
+
+    /// Returns `true` if the index is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the amount of times an existing key was pointed to a new value handle.
+    ///
+    /// This includes relocations performed by garbage collection.
+    #[must_use]
+    pub fn overwrite_count(&self) -> u64 {
+        self.overwrites.load(Ordering::Relaxed)
+    }
+
+    /// Returns all items inside the given key range, in key order.
+    #[must_use]
+    pub fn range<R: RangeBounds<[u8]>>(&self, range: R) -> Vec<(UserKey, ValueHandle, u32)> {
+        self.read_items()
+            .range::<[u8], R>(range)
+            .map(|(k, (vhandle, size))| (k.clone(), vhandle.clone(), *size))
+            .collect()
+    }
+
+    /// Checks that every value handle in the index resolves to a
+    /// value of the indexed size.
+    ///
+    /// Returns the amount of items that do not resolve.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn verify<C: Compressor + Clone>(&self, value_log: &ValueLog<C>) -> crate::Result<usize> {
+        let mut sum = 0;
+
+        for (key, vhandle, size) in self.range(..) {
+            match value_log.get(&vhandle)? {
+                Some(value) if value.len() as u64 == u64::from(size) => {}
+                _ => {
+                    log::error!("Value handle {vhandle:?} of key {key:?} does not resolve");
+                    sum += 1;
+                }
+            }
+        }
+
+        Ok(sum)
     }
 }
 
 impl IndexReader for MockIndex {
     fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
         Ok(self
-            .read()
-            .expect("lock is poisoned")
+            .read_items()
             .get(key)
             .map(|(vhandle, _)| vhandle)
             .cloned())
     }
+
+    fn get_with_size(&self, key: &[u8]) -> std::io::Result<Option<SizedValueHandle>> {
+        Ok(self
+            .read_items()
+            .get(key)
+            .map(|(vhandle, size)| vhandle.clone().with_size(*size)))
+    }
 }
 
-/// Used for tests only
+/// Writer for a [`MockIndex`]
+///
+/// Items are inserted immediately, not when the write batch is finished.
 #[allow(clippy::module_name_repetitions)]
 pub struct MockIndexWriter(pub MockIndex);
 
@@ -52,14 +209,39 @@ impl IndexWriter for MockIndexWriter {
         value: ValueHandle,
         size: u32,
     ) -> std::io::Result<()> {
-        self.0
-            .write()
-            .expect("lock is poisoned")
-            .insert(key.into(), (value, size));
+        let prev = self.0.write_items().insert(key.into(), (value, size));
+
+        if prev.is_some() {
+            self.0.overwrites.fetch_add(1, Ordering::Relaxed);
+        }
+
         Ok(())
     }
 
-    fn finish(&mut self) -> std::io::Result<()> {
+    fn relocate_indirect(
+        &mut self,
+        key: &[u8],
+        expected: &ValueHandle,
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        match self.0.write_items().get_mut(key) {
+            Some(item) if item.0 == *expected => {
+                *item = (vhandle, size);
+                self.0.overwrites.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {
+                log::trace!("Skipping relocation of {key:?}, it was overwritten");
+            }
+        }
+
         Ok(())
     }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        match &self.0.check {
+            Some(check) => check(&self.0).map_err(std::io::Error::other),
+            None => Ok(()),
+        }
+    }
 }
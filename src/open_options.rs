@@ -0,0 +1,109 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{manifest::VLOG_MARKER, Compressor, Config, ValueLog};
+use std::path::PathBuf;
+
+/// Options that control whether a value log is created or recovered when opening it
+///
+/// By default, the value log is recovered if it exists, and created otherwise,
+/// like [`ValueLog::open`].
+#[derive(Clone, Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct OpenOptions {
+    create: bool,
+    create_new: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            create: true,
+            create_new: false,
+        }
+    }
+}
+
+impl OpenOptions {
+    /// Creates the default open options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether a new value log is created if none exists in the directory.
+    ///
+    /// If `false`, opening fails with [`Error::NotFound`](crate::Error::NotFound)
+    /// if there is no value log.
+    ///
+    /// Default = true
+    #[must_use]
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets whether a new value log has to be created.
+    ///
+    /// If `true`, opening fails with [`Error::AlreadyExists`](crate::Error::AlreadyExists)
+    /// if there already is a value log in the directory. Overrides [`OpenOptions::create`].
+    ///
+    /// Default = false
+    #[must_use]
+    pub fn create_new(mut self, create_new: bool) -> Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Opens a value log in the given directory.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::NotFound`](crate::Error::NotFound) or
+    /// [`Error::AlreadyExists`](crate::Error::AlreadyExists), depending on the options.
+    ///
+    /// Will return [`Error::Locked`](crate::Error::Locked) if the value log is already
+    /// opened, possibly by another process. This relies on the file system's locking
+    /// (see [`Fs::try_lock`](crate::Fs::try_lock)): if it does not support locking
+    /// (e.g. [`StdFs`](crate::StdFs) on WASI), the value log is not protected
+    /// against being opened twice.
+    ///
+    /// Will return [`Error::InvalidVersion`](crate::Error::InvalidVersion) if the value log
+    /// was written in an unsupported data format version, and
+    /// [`Error::CorruptManifest`](crate::Error::CorruptManifest) if its segment list
+    /// cannot be read.
+    pub fn open<C: Compressor + Clone, P: Into<PathBuf>>(
+        &self,
+        path: P,
+        config: Config<C>,
+    ) -> crate::Result<ValueLog<C>> {
+        let path = path.into();
+        let fs = config.fs.clone();
+
+        let marker_path = path.join(VLOG_MARKER);
+
+        if !self.create && !self.create_new && !fs.exists(&marker_path)? {
+            return Err(crate::Error::NotFound);
+        }
+
+        fs.create_dir_all(&path)?;
+
+        // IMPORTANT: Lock before checking for the marker, so another process
+        // cannot create or open the value log in between
+        let dir_lock = ValueLog::<C>::lock_directory(&*fs, &path)?;
+
+        if fs.exists(&marker_path)? {
+            if self.create_new {
+                return Err(crate::Error::AlreadyExists);
+            }
+            ValueLog::recover(path, config, dir_lock)
+        } else if self.create || self.create_new {
+            ValueLog::create_new(path, config, dir_lock)
+        } else {
+            Err(crate::Error::NotFound)
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{fs::Fs, id::SegmentId};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{BufReader, Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Default folder (inside the value log folder) parity files are stored in
+pub const PARITY_FOLDER: &str = "parity";
+
+const PARITY_MAGIC: &[u8] = &[b'V', b'L', b'P', b'A', b'R', b'T', b'Y', 1];
+
+/// Granularity that corruption is detected & repaired at
+const PARITY_BLOCK_SIZE: u32 = 4_096;
+
+fn invalid_data(msg: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+fn to_usize(n: u64) -> std::io::Result<usize> {
+    usize::try_from(n).map_err(|_| invalid_data("parity file is too large"))
+}
+
+fn block(data: &[u8], idx: usize, block_size: usize) -> &[u8] {
+    let start = (idx * block_size).min(data.len());
+    let end = (start + block_size).min(data.len());
+    data.get(start..end).unwrap_or_default()
+}
+
+fn block_mut(data: &mut [u8], idx: usize, block_size: usize) -> &mut [u8] {
+    let start = (idx * block_size).min(data.len());
+    let end = (start + block_size).min(data.len());
+    data.get_mut(start..end).unwrap_or_default()
+}
+
+fn xor_into(dst: &mut [u8], src: &[u8]) {
+    for (a, b) in dst.iter_mut().zip(src) {
+        *a ^= b;
+    }
+}
+
+/// XOR parity of a segment file
+///
+/// The file is split into `data_shards` equally sized shards, which are
+/// combined (using XOR) into a single parity shard, so one damaged block per stripe
+/// (the blocks at the same position in every shard) can be reconstructed.
+///
+/// Every block is checksummed, so damaged blocks can be located.
+pub struct Parity {
+    file_len: u64,
+    data_shards: u8,
+    block_size: usize,
+
+    /// Checksum of every block of the segment file
+    block_checksums: Vec<u64>,
+
+    /// Checksum of every block of the parity shard
+    shard_checksums: Vec<u64>,
+
+    /// Parity shard
+    shard: Vec<u8>,
+}
+
+impl Parity {
+    /// Computes the parity of a file, reading it sequentially.
+    pub fn compute<R: Read>(
+        mut reader: R,
+        file_len: u64,
+        data_shards: u8,
+    ) -> std::io::Result<Self> {
+        let block_size = PARITY_BLOCK_SIZE as usize;
+        let total_blocks = to_usize(file_len.div_ceil(u64::from(PARITY_BLOCK_SIZE)))?;
+        let shard_blocks = total_blocks.div_ceil(usize::from(data_shards.max(1)));
+
+        let mut block_checksums = Vec::with_capacity(total_blocks);
+        let mut parity = vec![0; shard_blocks * block_size];
+        let mut buf = vec![0; block_size];
+        let mut remaining = file_len;
+
+        for idx in 0..total_blocks {
+            let len = to_usize(remaining.min(u64::from(PARITY_BLOCK_SIZE)))?;
+            remaining -= len as u64;
+
+            let block = buf.get_mut(..len).unwrap_or_default();
+            reader.read_exact(block)?;
+            block_checksums.push(xxh3_64(block));
+
+            xor_into(
+                block_mut(&mut parity, idx % shard_blocks, block_size),
+                block,
+            );
+        }
+
+        let parity_checksums = parity.chunks(block_size).map(xxh3_64).collect();
+
+        Ok(Self {
+            file_len,
+            data_shards,
+            block_size,
+            block_checksums,
+            shard_checksums: parity_checksums,
+            shard: parity,
+        })
+    }
+
+    /// Computes the parity of a file.
+    pub fn compute_file(fs: &dyn Fs, path: &Path, data_shards: u8) -> std::io::Result<Self> {
+        let mut file = fs.open(path)?;
+        let file_len = file.seek(SeekFrom::End(0))?;
+        file.seek(SeekFrom::Start(0))?;
+
+        Self::compute(BufReader::new(file), file_len, data_shards)
+    }
+
+    /// Serializes the parity into its file format.
+    pub fn encode(&self) -> std::io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(
+            self.shard.len() + (self.block_checksums.len() + self.shard_checksums.len()) * 8 + 64,
+        );
+
+        bytes.write_all(PARITY_MAGIC)?;
+        bytes.write_u64::<BigEndian>(self.file_len)?;
+        bytes.write_u8(self.data_shards)?;
+
+        // NOTE: Block size is always small
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.write_u32::<BigEndian>(self.block_size as u32)?;
+
+        for checksum in self.block_checksums.iter().chain(&self.shard_checksums) {
+            bytes.write_u64::<BigEndian>(*checksum)?;
+        }
+
+        bytes.write_all(&self.shard)?;
+
+        let checksum = xxh3_64(&bytes);
+        bytes.write_u64::<BigEndian>(checksum)?;
+
+        Ok(bytes)
+    }
+
+    /// Deserializes a parity file.
+    pub fn decode(bytes: &[u8]) -> std::io::Result<Self> {
+        let Some(split) = bytes.len().checked_sub(8) else {
+            return Err(invalid_data("parity file is truncated"));
+        };
+        let (bytes, mut trailer) = bytes.split_at(split);
+
+        if xxh3_64(bytes) != trailer.read_u64::<BigEndian>()? {
+            return Err(invalid_data("parity file checksum mismatch"));
+        }
+
+        let mut cursor = Cursor::new(bytes);
+
+        let mut magic = [0; PARITY_MAGIC.len()];
+        cursor.read_exact(&mut magic)?;
+
+        if magic != PARITY_MAGIC {
+            return Err(invalid_data("invalid parity file header"));
+        }
+
+        let file_len = cursor.read_u64::<BigEndian>()?;
+        let data_shards = cursor.read_u8()?;
+        let block_size = cursor.read_u32::<BigEndian>()?;
+
+        if data_shards == 0 || block_size == 0 {
+            return Err(invalid_data("invalid parity file header"));
+        }
+
+        let total_blocks = to_usize(file_len.div_ceil(u64::from(block_size)))?;
+        let shard_blocks = total_blocks.div_ceil(usize::from(data_shards));
+        let block_size = block_size as usize;
+
+        let block_checksums = (0..total_blocks)
+            .map(|_| cursor.read_u64::<BigEndian>())
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let parity_checksums = (0..shard_blocks)
+            .map(|_| cursor.read_u64::<BigEndian>())
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let mut parity = vec![0; shard_blocks * block_size];
+        cursor.read_exact(&mut parity)?;
+
+        Ok(Self {
+            file_len,
+            data_shards,
+            block_size,
+            block_checksums,
+            shard_checksums: parity_checksums,
+            shard: parity,
+        })
+    }
+
+    /// Amount of data shards the file was split into
+    pub fn data_shards(&self) -> u8 {
+        self.data_shards
+    }
+
+    /// Repairs damaged blocks of the file in place.
+    ///
+    /// Returns the amount of bytes repaired, and whether the parity shard itself is damaged.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Unrepairable`](crate::Error::Unrepairable) if a stripe
+    /// has more than one damaged block.
+    pub fn repair(&self, segment_id: SegmentId, data: &mut Vec<u8>) -> crate::Result<(u64, bool)> {
+        let file_len = to_usize(self.file_len)?;
+        let block_size = self.block_size;
+
+        // NOTE: Truncated files are padded, so the missing blocks fail their checksum
+        let mut repaired = data.len().saturating_sub(file_len) as u64;
+        data.resize(file_len, 0);
+
+        let shard_blocks = self.shard_checksums.len();
+        let mut parity_damaged = false;
+
+        for (pos, (parity_block, parity_checksum)) in self
+            .shard
+            .chunks_exact(block_size)
+            .zip(&self.shard_checksums)
+            .enumerate()
+        {
+            let stripe = (0..usize::from(self.data_shards))
+                .map(|shard| shard * shard_blocks + pos)
+                .take_while(|idx| *idx < self.block_checksums.len())
+                .collect::<Vec<_>>();
+
+            let damaged = stripe
+                .iter()
+                .copied()
+                .filter(|idx| {
+                    Some(&xxh3_64(block(data, *idx, block_size))) != self.block_checksums.get(*idx)
+                })
+                .collect::<Vec<_>>();
+
+            let parity_ok = xxh3_64(parity_block) == *parity_checksum;
+
+            match (damaged.as_slice(), parity_ok) {
+                ([], true) => {}
+                ([], false) => parity_damaged = true,
+                ([idx], true) => {
+                    let mut reconstructed = parity_block.to_vec();
+
+                    for other in stripe.iter().filter(|x| *x != idx) {
+                        xor_into(&mut reconstructed, block(data, *other, block_size));
+                    }
+
+                    let dst = block_mut(data, *idx, block_size);
+                    let reconstructed = reconstructed.get(..dst.len()).unwrap_or_default();
+
+                    if Some(&xxh3_64(reconstructed)) != self.block_checksums.get(*idx) {
+                        return Err(crate::Error::Unrepairable(segment_id));
+                    }
+
+                    dst.copy_from_slice(reconstructed);
+                    repaired += dst.len() as u64;
+                }
+                _ => {
+                    log::error!(
+                        "Segment #{segment_id} has {} damaged block(s) in stripe {pos} (parity intact: {parity_ok})",
+                        damaged.len(),
+                    );
+                    return Err(crate::Error::Unrepairable(segment_id));
+                }
+            }
+        }
+
+        Ok((repaired, parity_damaged))
+    }
+}
+
+/// Computes and persists the parity file of a segment.
+pub fn write_parity_file(
+    fs: &dyn Fs,
+    folder: &Path,
+    segment_id: SegmentId,
+    segment_path: &Path,
+    data_shards: u8,
+) -> crate::Result<()> {
+    let parity = Parity::compute_file(fs, segment_path, data_shards)?;
+
+    fs.create_dir_all(folder)?;
+    fs.rewrite_atomic(&folder.join(segment_id.to_string()), &parity.encode()?)?;
+
+    log::trace!("Wrote parity of segment #{segment_id}");
+
+    Ok(())
+}
+
+/// Deletes parity files of segments that are not part of the value log anymore.
+pub fn remove_orphaned_parity_files(
+    fs: &dyn Fs,
+    folder: &Path,
+    segment_ids: &[SegmentId],
+) -> crate::Result<()> {
+    if !fs.exists(folder)? {
+        return Ok(());
+    }
+
+    for path in fs.list_files(folder)? {
+        let Some(segment_id) = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.parse::<SegmentId>().ok())
+        else {
+            continue;
+        };
+
+        if !segment_ids.contains(&segment_id) {
+            log::trace!("Deleting orphaned parity file of segment #{segment_id}");
+            fs.remove_file(&path)?;
+        }
+    }
+
+    Ok(())
+}
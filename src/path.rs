@@ -8,8 +8,19 @@ use std::path::{Path, PathBuf};
 #[allow(clippy::module_name_repetitions)]
 pub fn absolute_path<P: AsRef<Path>>(path: P) -> PathBuf {
     // TODO: replace with https://doc.rust-lang.org/std/path/fn.absolute.html once stable
-    path.as_ref()
-        .absolutize()
-        .expect("should be absolute path")
-        .into()
+    #[cfg(not(feature = "wasi"))]
+    {
+        path.as_ref()
+            .absolutize()
+            .expect("should be absolute path")
+            .into()
+    }
+
+    // NOTE: Most WASI runtimes have no working directory, paths are
+    // resolved against preopened directories instead, so keep relative paths as-is
+    #[cfg(feature = "wasi")]
+    {
+        let path = path.as_ref();
+        path.absolutize().map_or_else(|_| path.into(), Into::into)
+    }
 }
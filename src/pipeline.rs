@@ -0,0 +1,258 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    compression::Compressor,
+    id::SegmentId,
+    Config, Encryptor,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+};
+
+/// Trait for a custom stage of the value transformation pipeline
+///
+/// Values pass through the pipeline when they are written:
+/// they are compressed, then passed through every configured transform
+/// (see [`Config::transform`]), then encrypted.
+/// The blob checksum is always calculated over the final, stored bytes.
+///
+/// Every segment records which stages were applied to its values
+/// (see [`SegmentMetadata::pipeline`](crate::SegmentMetadata::pipeline)),
+/// so reads revert exactly those stages, in reverse order.
+pub trait Transform: Send + Sync {
+    /// Returns the ID of the transform.
+    ///
+    /// The ID is recorded in every segment the transform was applied to,
+    /// so it needs to be unique and must never change.
+    fn id(&self) -> u8;
+
+    /// Transforms a (compressed) value that is written into the given segment.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value could not be transformed.
+    fn apply(&self, segment_id: SegmentId, bytes: &[u8]) -> crate::Result<Vec<u8>>;
+
+    /// Reverts the transformation of a value that was read from the given segment.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value could not be restored.
+    fn revert(&self, segment_id: SegmentId, bytes: &[u8]) -> crate::Result<Vec<u8>>;
+}
+
+/// Stage of the value transformation pipeline, as recorded in a segment
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Stage {
+    /// Values are compressed using the configured [`Compressor`]
+    Compression,
+
+    /// Values are transformed using the configured [`Transform`] with the given ID
+    Transform(u8),
+
+    /// Values are encrypted using the given key version of the configured [`Encryptor`]
+    Encryption {
+        /// Version of the key the values are encrypted with
+        key_version: u32,
+    },
+}
+
+const TAG_COMPRESSION: u8 = 0;
+const TAG_TRANSFORM: u8 = 1;
+const TAG_ENCRYPTION: u8 = 2;
+
+impl Encode for Stage {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        match self {
+            Self::Compression => writer.write_u8(TAG_COMPRESSION)?,
+            Self::Transform(id) => {
+                writer.write_u8(TAG_TRANSFORM)?;
+                writer.write_u8(*id)?;
+            }
+            Self::Encryption { key_version } => {
+                writer.write_u8(TAG_ENCRYPTION)?;
+                writer.write_u32::<BigEndian>(*key_version)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Stage {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        match reader.read_u8()? {
+            TAG_COMPRESSION => Ok(Self::Compression),
+            TAG_TRANSFORM => Ok(Self::Transform(reader.read_u8()?)),
+            TAG_ENCRYPTION => Ok(Self::Encryption {
+                key_version: reader.read_u32::<BigEndian>()?,
+            }),
+            tag => Err(DecodeError::InvalidTag(("PipelineStage", tag))),
+        }
+    }
+}
+
+/// Returns the key version of the encryption stage, if any.
+pub fn key_version(stages: &[Stage]) -> Option<u32> {
+    stages.iter().find_map(|stage| match stage {
+        Stage::Encryption { key_version } => Some(*key_version),
+        _ => None,
+    })
+}
+
+#[derive(Clone)]
+enum Step<C: Compressor + Clone> {
+    Compression(C),
+    Transform(Arc<dyn Transform>),
+    Encryption(Arc<dyn Encryptor>, u32),
+}
+
+/// Value transformation pipeline with its stages resolved to their implementations
+#[derive(Clone)]
+pub struct Pipeline<C: Compressor + Clone> {
+    steps: Vec<Step<C>>,
+}
+
+impl<C: Compressor + Clone> Default for Pipeline<C> {
+    fn default() -> Self {
+        Self { steps: vec![] }
+    }
+}
+
+impl<C: Compressor + Clone> Pipeline<C> {
+    /// Builds the pipeline new segments are written with.
+    ///
+    /// If a key version is given, values are encrypted with it,
+    /// instead of the encryptor's current key version.
+    pub fn for_writing(config: &Config<C>, key_version: Option<u32>) -> Self {
+        let mut steps = vec![Step::Compression(config.compression.clone())];
+
+        steps.extend(config.transforms.iter().cloned().map(Step::Transform));
+
+        if let Some(encryptor) = &config.encryption {
+            let key_version = key_version.unwrap_or_else(|| encryptor.key_version());
+            steps.push(Step::Encryption(encryptor.clone(), key_version));
+        }
+
+        Self { steps }
+    }
+
+    /// Resolves the pipeline a segment was written with.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a stage is not configured.
+    pub fn resolve(
+        config: &Config<C>,
+        segment_id: SegmentId,
+        stages: &[Stage],
+    ) -> crate::Result<Self> {
+        let steps = stages
+            .iter()
+            .map(|stage| match stage {
+                Stage::Compression => Ok(Step::Compression(config.compression.clone())),
+                Stage::Transform(id) => config
+                    .transforms
+                    .iter()
+                    .find(|x| x.id() == *id)
+                    .cloned()
+                    .map(Step::Transform)
+                    .ok_or_else(|| {
+                        log::error!(
+                            "Segment #{segment_id} was written with transform {id}, but it is not configured",
+                        );
+                        crate::Error::MissingTransform(*id)
+                    }),
+                Stage::Encryption { key_version } => config
+                    .encryption
+                    .clone()
+                    .map(|encryptor| Step::Encryption(encryptor, *key_version))
+                    .ok_or_else(|| {
+                        log::error!(
+                            "Segment #{segment_id} is encrypted, but no encryptor is configured",
+                        );
+                        crate::Error::Decrypt
+                    }),
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self { steps })
+    }
+
+    /// Sets (or removes) the compression stage, which always comes first.
+    #[must_use]
+    pub fn with_compression(mut self, compressor: Option<C>) -> Self {
+        self.steps.retain(|x| !matches!(x, Step::Compression(_)));
+
+        if let Some(compressor) = compressor {
+            self.steps.insert(0, Step::Compression(compressor));
+        }
+
+        self
+    }
+
+    /// Returns the stages of the pipeline, in the order they are applied.
+    pub fn stages(&self) -> Vec<Stage> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Compression(_) => Stage::Compression,
+                Step::Transform(transform) => Stage::Transform(transform.id()),
+                Step::Encryption(_, key_version) => Stage::Encryption {
+                    key_version: *key_version,
+                },
+            })
+            .collect()
+    }
+
+    /// Returns the key version of the encryption stage, if any.
+    pub fn key_version(&self) -> Option<u32> {
+        self.steps.iter().find_map(|step| match step {
+            Step::Encryption(_, key_version) => Some(*key_version),
+            _ => None,
+        })
+    }
+
+    /// Returns `true` if values are stored as is.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Passes a value (or chunk) through every stage.
+    pub fn apply(&self, segment_id: SegmentId, value: &[u8]) -> crate::Result<Vec<u8>> {
+        let mut value = value.to_vec();
+
+        for step in &self.steps {
+            value = match step {
+                Step::Compression(compressor) => compressor.compress(&value)?,
+                Step::Transform(transform) => transform.apply(segment_id, &value)?,
+                Step::Encryption(encryptor, key_version) => {
+                    encryptor.encrypt(segment_id, *key_version, &value)?
+                }
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// Reverts every stage of a stored value (or chunk), in reverse order.
+    pub fn revert(&self, segment_id: SegmentId, mut value: Vec<u8>) -> crate::Result<Vec<u8>> {
+        for step in self.steps.iter().rev() {
+            value = match step {
+                Step::Compression(compressor) => compressor.decompress(&value)?,
+                Step::Transform(transform) => transform.revert(segment_id, &value)?,
+                Step::Encryption(encryptor, key_version) => {
+                    encryptor.decrypt(segment_id, *key_version, &value)?
+                }
+            };
+        }
+
+        Ok(value)
+    }
+}
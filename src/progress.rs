@@ -0,0 +1,92 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::id::SegmentId;
+use std::sync::Arc;
+
+/// Amount of items after which progress is reported
+const REPORT_INTERVAL: u64 = 1_000;
+
+/// Long-running operation that reports its progress
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// [`crate::ValueLog::scan_for_stats`]
+    ScanForStats,
+
+    /// [`crate::ValueLog::verify`]
+    Verify,
+
+    /// Rewriting segments (rollover, GC)
+    Rollover,
+}
+
+/// Progress of a long-running operation
+#[derive(Clone, Debug)]
+pub struct Progress {
+    /// The operation in progress
+    pub operation: Operation,
+
+    /// Amount of items processed so far
+    pub items_processed: u64,
+
+    /// Amount of (value) bytes processed so far
+    pub bytes_processed: u64,
+
+    /// Segment of the most recently processed item
+    pub segment_id: Option<SegmentId>,
+
+    /// `true` if the operation has finished
+    pub done: bool,
+}
+
+/// Callback that receives progress of long-running operations
+pub type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// Tracks the progress of an operation, calling the callback (if any)
+/// every couple of items and when the segment changes.
+pub struct ProgressTracker<'a> {
+    callback: Option<&'a ProgressCallback>,
+    progress: Progress,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub fn new(callback: Option<&'a ProgressCallback>, operation: Operation) -> Self {
+        Self {
+            callback,
+            progress: Progress {
+                operation,
+                items_processed: 0,
+                bytes_processed: 0,
+                segment_id: None,
+                done: false,
+            },
+        }
+    }
+
+    pub fn advance(&mut self, segment_id: SegmentId, bytes: u64) {
+        let Some(callback) = self.callback else {
+            return;
+        };
+
+        let segment_changed = self
+            .progress
+            .segment_id
+            .is_some_and(|prev| prev != segment_id);
+
+        self.progress.items_processed += 1;
+        self.progress.bytes_processed += bytes;
+        self.progress.segment_id = Some(segment_id);
+
+        if segment_changed || self.progress.items_processed % REPORT_INTERVAL == 0 {
+            callback(&self.progress);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(callback) = self.callback {
+            self.progress.done = true;
+            callback(&self.progress);
+        }
+    }
+}
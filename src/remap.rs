@@ -0,0 +1,275 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    fs::Fs,
+    id::SegmentId,
+    index::{Relocation, RelocationMeta},
+    sync::ArcSwap,
+    Slice, ValueHandle,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Folder (inside the value log folder) remap tables are stored in,
+/// see [`Config::remap_relocations`](crate::Config::remap_relocations)
+pub const REMAP_FOLDER: &str = "remap";
+
+const REMAP_MAGIC: &[u8] = &[b'V', b'L', b'R', b'E', b'M', b'A', b'P', 1];
+
+/// Remap table of a rewritten segment, mapping the offsets of its relocated blobs
+/// to their new locations
+type RemapTable = BTreeMap<u64, Relocation>;
+
+fn invalid_data(msg: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+fn encode_table(table: &RemapTable) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+
+    bytes.write_all(REMAP_MAGIC)?;
+    bytes.write_u64::<BigEndian>(table.len() as u64)?;
+
+    for (offset, relocation) in table {
+        let key_len = u16::try_from(relocation.key.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "key too long"))?;
+
+        bytes.write_u64::<BigEndian>(*offset)?;
+        bytes.write_u64::<BigEndian>(relocation.vhandle.segment_id)?;
+        bytes.write_u64::<BigEndian>(relocation.vhandle.offset)?;
+        bytes.write_u32::<BigEndian>(relocation.size)?;
+        bytes.write_u64::<BigEndian>(relocation.meta.checksum)?;
+        bytes.write_u32::<BigEndian>(relocation.meta.stored_size)?;
+        bytes.write_u16::<BigEndian>(key_len)?;
+        bytes.write_all(&relocation.key)?;
+    }
+
+    let checksum = xxh3_64(&bytes);
+    bytes.write_u64::<BigEndian>(checksum)?;
+
+    Ok(bytes)
+}
+
+fn decode_table(segment_id: SegmentId, bytes: &[u8]) -> std::io::Result<RemapTable> {
+    let Some(split) = bytes.len().checked_sub(8) else {
+        return Err(invalid_data("remap file is truncated"));
+    };
+    let (bytes, mut trailer) = bytes.split_at(split);
+
+    if xxh3_64(bytes) != trailer.read_u64::<BigEndian>()? {
+        return Err(invalid_data("remap file checksum mismatch"));
+    }
+
+    let mut cursor = Cursor::new(bytes);
+
+    let mut magic = [0; REMAP_MAGIC.len()];
+    cursor.read_exact(&mut magic)?;
+
+    if magic != REMAP_MAGIC {
+        return Err(invalid_data("invalid remap file header"));
+    }
+
+    let count = cursor.read_u64::<BigEndian>()?;
+    let mut table = RemapTable::new();
+
+    for _ in 0..count {
+        let offset = cursor.read_u64::<BigEndian>()?;
+        let vhandle = ValueHandle {
+            segment_id: cursor.read_u64::<BigEndian>()?,
+            offset: cursor.read_u64::<BigEndian>()?,
+        };
+        let size = cursor.read_u32::<BigEndian>()?;
+        let checksum = cursor.read_u64::<BigEndian>()?;
+        let stored_size = cursor.read_u32::<BigEndian>()?;
+
+        let mut key = vec![0; usize::from(cursor.read_u16::<BigEndian>()?)];
+        cursor.read_exact(&mut key)?;
+
+        table.insert(
+            offset,
+            Relocation {
+                key: Slice::from(key),
+                expected: ValueHandle { segment_id, offset },
+                vhandle,
+                size,
+                meta: RelocationMeta {
+                    checksum,
+                    stored_size,
+                },
+            },
+        );
+    }
+
+    Ok(table)
+}
+
+/// Remap tables of segments whose blobs were relocated without updating the index
+///
+/// Reads of value handles that point into a remapped segment are redirected
+/// to the blob's new location. A blob may be relocated multiple times
+/// before the index is updated, so remaps are followed transitively.
+pub struct Remaps(ArcSwap<HashMap<SegmentId, Arc<RemapTable>>>);
+
+impl Remaps {
+    /// Loads the remap tables in the given folder.
+    pub fn recover(fs: &dyn Fs, folder: &Path) -> crate::Result<Self> {
+        let mut tables = HashMap::new();
+
+        if fs.exists(folder)? {
+            for path in fs.list_files(folder)? {
+                let Some(segment_id) = path
+                    .file_name()
+                    .and_then(|x| x.to_str())
+                    .and_then(|x| x.parse::<SegmentId>().ok())
+                else {
+                    continue;
+                };
+
+                let table = decode_table(segment_id, &fs.read(&path)?)?;
+                tables.insert(segment_id, Arc::new(table));
+            }
+        }
+
+        log::debug!("Recovered {} remap tables", tables.len());
+
+        Ok(Self(ArcSwap::from_pointee(tables)))
+    }
+
+    /// Returns the amount of remapped blobs.
+    pub fn len(&self) -> usize {
+        self.0.load_full().values().map(|x| x.len()).sum()
+    }
+
+    /// Returns the final location of a relocated blob, or `None` if the
+    /// value handle is not remapped.
+    pub fn resolve(&self, vhandle: &ValueHandle) -> Option<ValueHandle> {
+        let tables = self.0.load_full();
+
+        if tables.is_empty() {
+            return None;
+        }
+
+        let mut resolved = None;
+        let mut current = vhandle;
+
+        // NOTE: Blobs are always relocated into newer segments, so remaps cannot form
+        // a cycle, and a chain cannot be longer than the amount of tables
+        for _ in 0..tables.len() {
+            let Some(relocation) = tables
+                .get(&current.segment_id)
+                .and_then(|x| x.get(&current.offset))
+            else {
+                break;
+            };
+
+            current = &relocation.vhandle;
+            resolved = Some(relocation.vhandle.clone());
+        }
+
+        resolved
+    }
+
+    /// Persists the remap tables of rewritten segments, and starts redirecting reads.
+    ///
+    /// The `expected` value handle of every relocation is the blob's old location.
+    pub fn insert(
+        &self,
+        fs: &dyn Fs,
+        folder: &Path,
+        relocations: Vec<Relocation>,
+    ) -> crate::Result<()> {
+        let mut new_tables = HashMap::<SegmentId, RemapTable>::new();
+
+        for relocation in relocations {
+            new_tables
+                .entry(relocation.expected.segment_id)
+                .or_default()
+                .insert(relocation.expected.offset, relocation);
+        }
+
+        if new_tables.is_empty() {
+            return Ok(());
+        }
+
+        fs.create_dir_all(folder)?;
+
+        for (segment_id, table) in &new_tables {
+            fs.rewrite_atomic(&folder.join(segment_id.to_string()), &encode_table(table)?)?;
+        }
+        fs.sync_directory(folder)?;
+
+        let mut tables = (*self.0.load_full()).clone();
+        tables.extend(new_tables.into_iter().map(|(id, x)| (id, Arc::new(x))));
+        self.0.store(Arc::new(tables));
+
+        Ok(())
+    }
+
+    /// Returns the relocations that bring the index up to date, each pointing to
+    /// the final location of its blob, together with the IDs of the remapped segments.
+    pub fn pending(&self) -> (Vec<SegmentId>, Vec<Relocation>) {
+        let tables = self.0.load_full();
+
+        let relocations = tables
+            .values()
+            .flat_map(|table| table.values())
+            .map(|relocation| {
+                let mut relocation = relocation.clone();
+
+                if let Some(vhandle) = self.resolve(&relocation.vhandle) {
+                    relocation.vhandle = vhandle;
+                }
+
+                relocation
+            })
+            .collect();
+
+        (tables.keys().copied().collect(), relocations)
+    }
+
+    /// Deletes the remap tables of the given segments.
+    pub fn remove(
+        &self,
+        fs: &dyn Fs,
+        folder: &Path,
+        segment_ids: &[SegmentId],
+    ) -> crate::Result<()> {
+        let mut tables = (*self.0.load_full()).clone();
+
+        for segment_id in segment_ids {
+            if tables.remove(segment_id).is_some() {
+                fs.remove_file(&folder.join(segment_id.to_string()))?;
+            }
+        }
+
+        self.0.store(Arc::new(tables));
+
+        Ok(())
+    }
+
+    /// Writes all remap tables into the given folder.
+    pub fn write_to(&self, fs: &dyn Fs, folder: &Path) -> crate::Result<()> {
+        let tables = self.0.load_full();
+
+        if tables.is_empty() {
+            return Ok(());
+        }
+
+        fs.create_dir_all(folder)?;
+
+        for (segment_id, table) in tables.iter() {
+            fs.rewrite_atomic(&folder.join(segment_id.to_string()), &encode_table(table)?)?;
+        }
+        fs.sync_directory(folder)?;
+
+        Ok(())
+    }
+}
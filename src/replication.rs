@@ -0,0 +1,23 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{id::SegmentId, segment::meta::Metadata};
+use std::path::Path;
+
+/// Receives changes to the segment list, enabling physical replication of the value log
+///
+/// Callbacks are invoked synchronously, after the manifest change is durable,
+/// while holding the value log's rollover lock. This means a newly registered segment
+/// cannot be dropped by garbage collection before [`Replicator::segment_registered`] returns,
+/// so the segment file can be shipped inside the callback.
+///
+/// Callbacks should not call back into the value log, as that could deadlock.
+pub trait Replicator: Send + Sync {
+    /// Called after a segment has been registered in the value log.
+    fn segment_registered(&self, segment_id: SegmentId, path: &Path, metadata: &Metadata);
+
+    /// Called after segments have been removed from the value log,
+    /// before their files are deleted.
+    fn segments_dropped(&self, segment_ids: &[SegmentId]);
+}
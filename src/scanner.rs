@@ -2,8 +2,8 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{id::SegmentId, ValueHandle};
-use std::{collections::BTreeMap, sync::MutexGuard};
+use crate::{id::SegmentId, sync::MutexGuard, ValueHandle};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Default)]
 pub struct SegmentCounter {
@@ -0,0 +1,99 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::writer::Writer;
+use crate::{compression::Compressor, fs::StdFs};
+use std::path::{Path, PathBuf};
+
+/// Builds a single, valid segment file outside of any value log
+///
+/// This can be used by offline (e.g. ETL) jobs to prepare segment files,
+/// which can later be added to a value log using [`crate::ValueLog::ingest_segment`].
+///
+/// The segment ID is only assigned on ingestion, so blobs are addressed by their offset.
+#[allow(clippy::module_name_repetitions)]
+pub struct SegmentBuilder<C: Compressor + Clone> {
+    inner: Writer<C>,
+}
+
+impl<C: Compressor + Clone> SegmentBuilder<C> {
+    /// Creates a new segment file at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self {
+            inner: Writer::new(&StdFs, path, 0)?,
+        })
+    }
+
+    /// Sets the compression method.
+    ///
+    /// The segment records whether its values are compressed, but the compression method
+    /// needs to match the compression of the value log the segment will be ingested into.
+    #[must_use]
+    pub fn use_compression(mut self, compressor: C) -> Self {
+        self.inner = self.inner.use_compression(Some(compressor));
+        self
+    }
+
+    /// Returns the offset of the next written blob.
+    ///
+    /// Combined with the segment ID returned by [`crate::ValueLog::ingest_segment`],
+    /// this forms the [`crate::ValueHandle`] of the blob.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.inner.offset()
+    }
+
+    /// Returns the amount of items written.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.inner.item_count
+    }
+
+    /// Returns `true` if no items have been written.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes an item, returning the amount of bytes the value takes on disk.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key length is empty or greater than 2^16, or the value length is greater than 2^32.
+    pub fn write<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> crate::Result<u32> {
+        self.inner.write(key.as_ref(), value.as_ref())
+    }
+
+    /// Writes the segment metadata & trailer, and syncs the file to disk.
+    ///
+    /// Returns the path of the finished segment file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or no items have been written.
+    pub fn finish(mut self) -> crate::Result<PathBuf> {
+        if self.is_empty() {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot build empty segment",
+            )));
+        }
+
+        self.inner.flush()?;
+
+        Ok(self.inner.path)
+    }
+}
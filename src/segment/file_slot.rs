@@ -0,0 +1,142 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    fs::{Fs, FsFile},
+    sync::Mutex,
+};
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+#[derive(Default)]
+struct SlotState {
+    /// Cached handle, and the time it was last used at
+    file: Option<(Box<dyn FsFile>, Instant)>,
+
+    /// Increased whenever the handle is closed explicitly, so handles
+    /// that were checked out before are not put back
+    epoch: u64,
+}
+
+/// Lazily opened file handle of a segment, which is reused by point reads
+///
+/// The handle is only opened on the first read, and closed once it was idle
+/// for some time (see [`Config::idle_file_timeout`](crate::Config::idle_file_timeout)).
+/// Concurrent readers that find the slot empty open their own handle,
+/// so reads never wait for each other.
+#[derive(Clone, Default)]
+pub struct FileSlot(Arc<Mutex<SlotState>>);
+
+impl FileSlot {
+    fn lock(&self) -> crate::sync::MutexGuard<'_, SlotState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes the cached handle (or opens a new one) for a read.
+    ///
+    /// The handle is put back into the slot when the returned file is dropped.
+    pub fn checkout(
+        &self,
+        fs: &dyn Fs,
+        path: &Path,
+        idle_timeout: Duration,
+    ) -> std::io::Result<PooledFile> {
+        let (cached, epoch) = {
+            let mut lock = self.lock();
+            (lock.file.take(), lock.epoch)
+        };
+
+        let file = match cached {
+            Some((file, last_used)) if last_used.elapsed() < idle_timeout => file,
+            _ => fs.open(path)?,
+        };
+
+        Ok(PooledFile {
+            file: Some(file),
+            slot: self.clone(),
+            epoch,
+        })
+    }
+
+    /// Closes the cached handle if it was idle for at least `idle_timeout`.
+    ///
+    /// Returns `true` if a handle was closed.
+    pub fn close_if_idle(&self, idle_timeout: Duration) -> bool {
+        let mut lock = self.lock();
+
+        let is_idle = lock
+            .file
+            .as_ref()
+            .is_some_and(|(_, last_used)| last_used.elapsed() >= idle_timeout);
+
+        if is_idle {
+            lock.file = None;
+        }
+
+        is_idle
+    }
+
+    /// Closes the cached handle, e.g. before the file is deleted or replaced.
+    pub fn close(&self) {
+        let mut lock = self.lock();
+        lock.file = None;
+        lock.epoch += 1;
+    }
+
+    /// Returns `true` if a handle is cached.
+    pub fn is_open(&self) -> bool {
+        self.lock().file.is_some()
+    }
+}
+
+/// File handle borrowed from a [`FileSlot`]
+pub struct PooledFile {
+    file: Option<Box<dyn FsFile>>,
+    slot: FileSlot,
+    epoch: u64,
+}
+
+impl PooledFile {
+    /// Calls `f` with the underlying file handle.
+    pub fn with_file<F: FnOnce(&dyn FsFile)>(&self, f: F) {
+        if let Some(file) = &self.file {
+            f(&**file);
+        }
+    }
+
+    fn file(&mut self) -> std::io::Result<&mut Box<dyn FsFile>> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| std::io::Error::other("file handle was returned"))
+    }
+}
+
+impl Read for PooledFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file()?.read(buf)
+    }
+}
+
+impl Seek for PooledFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.file()?.seek(pos)
+    }
+}
+
+impl Drop for PooledFile {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            let mut lock = self.slot.lock();
+
+            // NOTE: If another reader returned its handle first, this one is simply closed
+            if lock.file.is_none() && lock.epoch == self.epoch {
+                lock.file = Some((file, Instant::now()));
+            }
+        }
+    }
+}
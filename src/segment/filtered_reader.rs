@@ -0,0 +1,114 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{
+    meta::is_metadata_header,
+    meta_reader::skip_chunks,
+    reader::{parse_blob_header, read_chunks, read_key, read_record_header},
+    writer::BLOB_HEADER_MAGIC,
+};
+use crate::{coding::DecodeError, fs::FsFile, value::UserKey, Slice, UserValue};
+use byteorder::{BigEndian, ReadBytesExt};
+use std::io::BufReader;
+
+macro_rules! fail_iter {
+    ($e:expr) => {
+        match $e {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e.into())),
+        }
+    };
+}
+
+/// Decides what a filtered scan does with a blob, based on its key
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScanFilter {
+    /// Read the value and yield the blob
+    Yield,
+
+    /// Skip over the value without reading it
+    Skip,
+
+    /// Stop the scan
+    Stop,
+}
+
+/// Reads through a segment in order, only reading the values of blobs
+/// that are accepted by a filter.
+#[allow(clippy::module_name_repetitions)]
+pub struct FilteredReader<F: FnMut(&[u8]) -> ScanFilter> {
+    inner: BufReader<Box<dyn FsFile>>,
+    filter: F,
+    is_terminated: bool,
+    prev_key: Option<UserKey>,
+}
+
+impl<F: FnMut(&[u8]) -> ScanFilter> FilteredReader<F> {
+    pub(crate) fn new(inner: BufReader<Box<dyn FsFile>>, filter: F) -> Self {
+        Self {
+            inner,
+            filter,
+            is_terminated: false,
+            prev_key: None,
+        }
+    }
+}
+
+impl<F: FnMut(&[u8]) -> ScanFilter> Iterator for FilteredReader<F> {
+    type Item = crate::Result<(UserKey, UserValue, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.is_terminated {
+            let layout = {
+                let mut buf = [0; BLOB_HEADER_MAGIC.len()];
+                if fail_iter!(read_record_header(&mut self.inner, &mut buf)) > 0 {
+                    self.prev_key = None;
+                }
+
+                if is_metadata_header(&buf) {
+                    self.is_terminated = true;
+                    return None;
+                }
+
+                fail_iter!(parse_blob_header(&buf))
+            };
+
+            let checksum = fail_iter!(self.inner.read_u64::<BigEndian>());
+
+            if layout.prefixed && self.prev_key.is_none() {
+                return Some(Err(crate::Error::Decode(DecodeError::InvalidHeader(
+                    "Blob",
+                ))));
+            }
+
+            let (key, _) = fail_iter!(read_key(&mut self.inner, layout, self.prev_key.as_deref()));
+            self.prev_key = Some(key.clone());
+
+            // NOTE: For chunked values, this is the chunk count
+            let val_len = fail_iter!(self.inner.read_u32::<BigEndian>());
+
+            match (self.filter)(&key) {
+                ScanFilter::Yield if layout.chunked => {
+                    let (val, _) = fail_iter!(read_chunks(&mut self.inner, val_len, Ok));
+                    return Some(Ok((key, Slice::from(val), checksum)));
+                }
+                ScanFilter::Yield => {
+                    let val = fail_iter!(Slice::from_reader(&mut self.inner, val_len as usize));
+                    return Some(Ok((key, val, checksum)));
+                }
+                ScanFilter::Skip if layout.chunked => {
+                    fail_iter!(skip_chunks(&mut self.inner, val_len));
+                }
+                ScanFilter::Skip => {
+                    fail_iter!(self.inner.seek_relative(i64::from(val_len)));
+                }
+                ScanFilter::Stop => {
+                    self.is_terminated = true;
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -2,13 +2,35 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use std::sync::atomic::AtomicU64;
+use crate::sync::AtomicU64;
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct GcStats {
     pub(crate) stale_items: AtomicU64,
     pub(crate) stale_bytes: AtomicU64,
+    pub(crate) punched_bytes: AtomicU64,
+}
+
+impl std::fmt::Debug for GcStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GcStats")
+            .field("stale_items", &self.stale_items())
+            .field("stale_bytes", &self.stale_bytes())
+            .field("punched_bytes", &self.punched_bytes())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for GcStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "stale_items={} stale_bytes={}",
+            self.stale_items(),
+            self.stale_bytes()
+        )
+    }
 }
 
 impl GcStats {
@@ -31,4 +53,16 @@ impl GcStats {
     pub fn stale_bytes(&self) -> u64 {
         self.stale_bytes.load(std::sync::atomic::Ordering::Acquire)
     }
+
+    pub(crate) fn add_punched_bytes(&self, x: u64) {
+        self.punched_bytes
+            .fetch_add(x, std::sync::atomic::Ordering::AcqRel);
+    }
+
+    /// Returns the amount of bytes of the segment file that were deallocated by
+    /// punching holes, since the segment was loaded
+    pub fn punched_bytes(&self) -> u64 {
+        self.punched_bytes
+            .load(std::sync::atomic::Ordering::Acquire)
+    }
 }
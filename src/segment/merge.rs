@@ -18,12 +18,13 @@ macro_rules! fail_iter {
 type IteratorIndex = usize;
 
 #[derive(Debug)]
-struct IteratorValue {
+pub struct IteratorValue {
     index: IteratorIndex,
-    key: UserKey,
-    value: UserValue,
-    segment_id: SegmentId,
-    checksum: u64,
+    pub key: UserKey,
+    pub value: UserValue,
+    pub segment_id: SegmentId,
+    pub offset: u64,
+    pub checksum: u64,
 }
 
 impl PartialEq for IteratorValue {
@@ -50,13 +51,24 @@ impl Ord for IteratorValue {
 pub struct MergeReader<C: Compressor + Clone> {
     readers: Vec<SegmentReader<C>>,
     heap: IntervalHeap<IteratorValue>,
+    dedup: bool,
 }
 
 impl<C: Compressor + Clone> MergeReader<C> {
     /// Initializes a new merging reader
     pub fn new(readers: Vec<SegmentReader<C>>) -> Self {
         let heap = IntervalHeap::with_capacity(readers.len());
-        Self { readers, heap }
+        Self {
+            readers,
+            heap,
+            dedup: true,
+        }
+    }
+
+    /// Also yields older versions of a key, instead of only the newest one.
+    pub(crate) fn without_dedup(mut self) -> Self {
+        self.dedup = false;
+        self
     }
 
     fn advance_reader(&mut self, idx: usize) -> crate::Result<()> {
@@ -65,12 +77,14 @@ impl<C: Compressor + Clone> MergeReader<C> {
         if let Some(value) = reader.next() {
             let (k, v, checksum) = value?;
             let segment_id = reader.segment_id;
+            let offset = reader.last_offset();
 
             self.heap.push(IteratorValue {
                 index: idx,
                 key: k,
                 value: v,
                 segment_id,
+                offset,
                 checksum,
             });
         }
@@ -87,10 +101,9 @@ impl<C: Compressor + Clone> MergeReader<C> {
     }
 }
 
-impl<C: Compressor + Clone> Iterator for MergeReader<C> {
-    type Item = crate::Result<(UserKey, UserValue, SegmentId, u64)>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<C: Compressor + Clone> MergeReader<C> {
+    /// Returns the next item, including its position.
+    pub(crate) fn next_entry(&mut self) -> Option<crate::Result<IteratorValue>> {
         if self.heap.is_empty() {
             fail_iter!(self.push_next());
         }
@@ -98,6 +111,10 @@ impl<C: Compressor + Clone> Iterator for MergeReader<C> {
         if let Some(head) = self.heap.pop_min() {
             fail_iter!(self.advance_reader(head.index));
 
+            if !self.dedup {
+                return Some(Ok(head));
+            }
+
             // Discard old items
             while let Some(next) = self.heap.pop_min() {
                 if next.key == head.key {
@@ -110,9 +127,18 @@ impl<C: Compressor + Clone> Iterator for MergeReader<C> {
                 }
             }
 
-            return Some(Ok((head.key, head.value, head.segment_id, head.checksum)));
+            return Some(Ok(head));
         }
 
         None
     }
 }
+
+impl<C: Compressor + Clone> Iterator for MergeReader<C> {
+    type Item = crate::Result<(UserKey, UserValue, SegmentId, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = fail_iter!(self.next_entry()?);
+        Some(Ok((item.key, item.value, item.segment_id, item.checksum)))
+    }
+}
@@ -5,12 +5,33 @@
 use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
     key_range::KeyRange,
+    pipeline::{key_version, Stage},
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
 pub const METADATA_HEADER_MAGIC: &[u8] = &[b'V', b'L', b'O', b'G', b'S', b'M', b'D', 1];
 
+/// Metadata of encrypted segments, which additionally contains the key version
+pub const METADATA_HEADER_MAGIC_ENCRYPTED: &[u8] = &[b'V', b'L', b'O', b'G', b'S', b'M', b'D', 2];
+
+/// Metadata of segments written with any other pipeline,
+/// which additionally contains the pipeline's stages
+pub const METADATA_HEADER_MAGIC_PIPELINE: &[u8] = &[b'V', b'L', b'O', b'G', b'S', b'M', b'D', 3];
+
+/// Metadata of segments that record their creation time,
+/// which additionally contains the pipeline's stages and the creation time
+pub const METADATA_HEADER_MAGIC_CREATED: &[u8] = &[b'V', b'L', b'O', b'G', b'S', b'M', b'D', 4];
+
+/// Returns `true` if the bytes are the start of the segment metadata.
+pub fn is_metadata_header(bytes: &[u8]) -> bool {
+    bytes == METADATA_HEADER_MAGIC
+        || bytes == METADATA_HEADER_MAGIC_ENCRYPTED
+        || bytes == METADATA_HEADER_MAGIC_PIPELINE
+        || bytes == METADATA_HEADER_MAGIC_CREATED
+}
+
+/// Segment statistics, stored in the segment file's trailer
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Metadata {
@@ -25,12 +46,49 @@ pub struct Metadata {
 
     /// Key range
     pub key_range: KeyRange,
+
+    /// Version of the key the segment's blobs are encrypted with,
+    /// or `None` if the segment is not encrypted
+    pub key_version: Option<u32>,
+
+    /// Stages of the value transformation pipeline the segment's blobs were written with,
+    /// in the order they were applied
+    pub pipeline: Vec<Stage>,
+
+    /// Time the segment was created at, in milliseconds since the Unix epoch,
+    /// or `None` if the segment was written before creation times were recorded
+    ///
+    /// Segments that are rewritten by garbage collection keep
+    /// the creation time of their oldest source segment.
+    pub created_at: Option<u64>,
+}
+
+impl Metadata {
+    /// Returns the header magic of the metadata's format.
+    ///
+    /// Segments without a creation time that were written with the default pipelines
+    /// keep the original format.
+    ///
+    /// The creation time is only recorded if an age-based feature is configured
+    /// (see [`Config::retention`](crate::Config::retention)), so other segments stay readable by older versions.
+    fn header(&self) -> &'static [u8] {
+        if self.created_at.is_some() {
+            return METADATA_HEADER_MAGIC_CREATED;
+        }
+
+        match self.pipeline.as_slice() {
+            [Stage::Compression] => METADATA_HEADER_MAGIC,
+            [Stage::Compression, Stage::Encryption { .. }] => METADATA_HEADER_MAGIC_ENCRYPTED,
+            _ => METADATA_HEADER_MAGIC_PIPELINE,
+        }
+    }
 }
 
 impl Encode for Metadata {
     fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
         // Write header
-        writer.write_all(METADATA_HEADER_MAGIC)?;
+        let header = self.header();
+        writer.write_all(header)?;
 
         writer.write_u64::<BigEndian>(self.item_count)?;
         writer.write_u64::<BigEndian>(self.compressed_bytes)?;
@@ -38,6 +96,24 @@ impl Encode for Metadata {
 
         self.key_range.encode_into(writer)?;
 
+        if header == METADATA_HEADER_MAGIC_ENCRYPTED {
+            writer.write_u32::<BigEndian>(self.key_version.unwrap_or_default())?;
+        } else if header == METADATA_HEADER_MAGIC_PIPELINE
+            || header == METADATA_HEADER_MAGIC_CREATED
+        {
+            // NOTE: There are only a few stages
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_u8(self.pipeline.len() as u8)?;
+
+            for stage in &self.pipeline {
+                stage.encode_into(writer)?;
+            }
+
+            if let Some(created_at) = self.created_at {
+                writer.write_u64::<BigEndian>(created_at)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -48,7 +124,7 @@ impl Decode for Metadata {
         let mut magic = [0u8; METADATA_HEADER_MAGIC.len()];
         reader.read_exact(&mut magic)?;
 
-        if magic != METADATA_HEADER_MAGIC {
+        if !is_metadata_header(&magic) {
             return Err(DecodeError::InvalidHeader("SegmentMetadata"));
         }
 
@@ -58,11 +134,40 @@ impl Decode for Metadata {
 
         let key_range = KeyRange::decode_from(reader)?;
 
+        let pipeline = if magic == METADATA_HEADER_MAGIC_ENCRYPTED {
+            vec![
+                Stage::Compression,
+                Stage::Encryption {
+                    key_version: reader.read_u32::<BigEndian>()?,
+                },
+            ]
+        } else if magic == METADATA_HEADER_MAGIC_PIPELINE || magic == METADATA_HEADER_MAGIC_CREATED
+        {
+            let len = reader.read_u8()?;
+
+            (0..len)
+                .map(|_| Stage::decode_from(reader))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![Stage::Compression]
+        };
+
+        let created_at = if magic == METADATA_HEADER_MAGIC_CREATED {
+            Some(reader.read_u64::<BigEndian>()?)
+        } else {
+            None
+        };
+
+        let key_version = key_version(&pipeline);
+
         Ok(Self {
             item_count,
             compressed_bytes,
             total_uncompressed_bytes,
             key_range,
+            key_version,
+            pipeline,
+            created_at,
         })
     }
 }
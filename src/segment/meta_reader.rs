@@ -0,0 +1,213 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{
+    meta::is_metadata_header,
+    reader::{parse_blob_header, read_key, read_record_header},
+    writer::{record_len, BLOB_HEADER_MAGIC},
+};
+use crate::{coding::DecodeError, fs::FsFile, id::SegmentId, value::UserKey, ValueHandle};
+use byteorder::{BigEndian, ReadBytesExt};
+use std::io::BufReader;
+
+macro_rules! fail_iter {
+    ($e:expr) => {
+        match $e {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e.into())),
+        }
+    };
+}
+
+/// Location and size of a blob, without its value
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlobMeta {
+    /// Key of the blob
+    pub key: UserKey,
+
+    /// Offset of the blob in the segment file
+    pub offset: u64,
+
+    /// Size of the (possibly compressed) value on disk
+    ///
+    /// For chunked values, this is the size of all chunks.
+    pub value_size: u32,
+
+    /// Checksum of the key and (possibly compressed) value
+    pub checksum: u64,
+
+    /// Amount of chunks the value is split into, or 0 if it is not chunked
+    /// (see [`Config::blob_chunk_size`](crate::Config::blob_chunk_size))
+    pub chunk_count: u32,
+
+    /// Length of the prefix the key shares with the previous blob's key, which is not
+    /// stored again, or 0 if the key is stored as a whole
+    /// (see [`Config::key_restart_interval`](crate::Config::key_restart_interval))
+    pub shared_prefix_len: u16,
+}
+
+impl BlobMeta {
+    /// Returns the size of the whole blob record on disk, including its header.
+    ///
+    /// The record occupies the bytes `offset..(offset + record_len)` of the segment file.
+    #[must_use]
+    pub fn record_len(&self) -> u64 {
+        record_len(
+            self.key.len(),
+            self.shared_prefix_len,
+            u64::from(self.value_size),
+            self.chunk_count,
+        )
+    }
+}
+
+/// Skips over the chunks of a chunked blob, returning their total size.
+pub fn skip_chunks(
+    reader: &mut BufReader<Box<dyn FsFile>>,
+    chunk_count: u32,
+) -> crate::Result<u32> {
+    let mut value_size = 0u32;
+
+    for _ in 0..chunk_count {
+        let len = reader.read_u32::<BigEndian>()?;
+        reader.seek_relative(i64::from(len))?;
+
+        // NOTE: Chunked values are limited to 4 GiB when written
+        value_size = value_size
+            .checked_add(len)
+            .ok_or(crate::Error::Decode(DecodeError::InvalidHeader("Blob")))?;
+    }
+
+    Ok(value_size)
+}
+
+/// Reads through a segment in order, skipping over values.
+#[allow(clippy::module_name_repetitions)]
+pub struct MetaReader {
+    inner: BufReader<Box<dyn FsFile>>,
+    offset: u64,
+    is_terminated: bool,
+    prev_key: Option<UserKey>,
+}
+
+impl MetaReader {
+    pub(crate) fn new(inner: BufReader<Box<dyn FsFile>>) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            is_terminated: false,
+            prev_key: None,
+        }
+    }
+}
+
+impl Iterator for MetaReader {
+    type Item = crate::Result<BlobMeta>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_terminated {
+            return None;
+        }
+
+        let layout = {
+            let mut buf = [0; BLOB_HEADER_MAGIC.len()];
+            let skipped = fail_iter!(read_record_header(&mut self.inner, &mut buf));
+            self.offset += skipped;
+
+            if skipped > 0 {
+                self.prev_key = None;
+            }
+
+            if is_metadata_header(&buf) {
+                self.is_terminated = true;
+                return None;
+            }
+
+            fail_iter!(parse_blob_header(&buf))
+        };
+
+        let checksum = fail_iter!(self.inner.read_u64::<BigEndian>());
+
+        if layout.prefixed && self.prev_key.is_none() {
+            return Some(Err(crate::Error::Decode(DecodeError::InvalidHeader(
+                "Blob",
+            ))));
+        }
+
+        let (key, shared_prefix_len) =
+            fail_iter!(read_key(&mut self.inner, layout, self.prev_key.as_deref()));
+        self.prev_key = Some(key.clone());
+
+        let (value_size, chunk_count) = if layout.chunked {
+            let chunk_count = fail_iter!(self.inner.read_u32::<BigEndian>());
+            let value_size = fail_iter!(skip_chunks(&mut self.inner, chunk_count));
+            (value_size, chunk_count)
+        } else {
+            let value_size = fail_iter!(self.inner.read_u32::<BigEndian>());
+
+            // NOTE: Seeking relatively keeps the read buffer if the value is small
+            fail_iter!(self.inner.seek_relative(i64::from(value_size)));
+
+            (value_size, 0)
+        };
+
+        let meta = BlobMeta {
+            key,
+            offset: self.offset,
+            value_size,
+            checksum,
+            chunk_count,
+            shared_prefix_len,
+        };
+
+        self.offset += meta.record_len();
+
+        Some(Ok(meta))
+    }
+}
+
+/// Reads through a segment's keys and blob offsets in order, skipping over values.
+#[allow(clippy::module_name_repetitions)]
+pub struct KeyReader(MetaReader);
+
+impl KeyReader {
+    pub(crate) fn new(inner: MetaReader) -> Self {
+        Self(inner)
+    }
+}
+
+impl Iterator for KeyReader {
+    type Item = crate::Result<(UserKey, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.map(|meta| (meta.key, meta.offset)))
+    }
+}
+
+/// Reads through a segment's keys, value handles and value sizes in order, skipping over values.
+#[allow(clippy::module_name_repetitions)]
+pub struct HandleReader {
+    inner: MetaReader,
+    segment_id: SegmentId,
+}
+
+impl HandleReader {
+    pub(crate) fn new(inner: MetaReader, segment_id: SegmentId) -> Self {
+        Self { inner, segment_id }
+    }
+}
+
+impl Iterator for HandleReader {
+    type Item = crate::Result<(UserKey, ValueHandle, u32)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.inner.next()?.map(|meta| {
+            let vhandle = ValueHandle {
+                segment_id: self.segment_id,
+                offset: meta.offset,
+            };
+            (meta.key, vhandle, meta.value_size)
+        }))
+    }
+}
@@ -2,22 +2,37 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+pub mod builder;
+pub mod file_slot;
+pub mod filtered_reader;
 pub mod gc_stats;
 pub mod merge;
 pub mod meta;
+pub mod meta_reader;
 pub mod multi_writer;
 pub mod reader;
+pub mod rev_reader;
 pub mod trailer;
 pub mod writer;
 
-use crate::{id::SegmentId, Compressor};
+use crate::{
+    clock::Clock,
+    fs::{advise, Fs, FsFile, PageCacheAdvice},
+    gc::report::SegmentGcReport,
+    id::SegmentId,
+    temperature::ReadStats,
+    Compressor, IoContext,
+};
+use file_slot::FileSlot;
 use gc_stats::GcStats;
 use meta::Metadata;
-use std::{marker::PhantomData, path::PathBuf};
+use std::{
+    collections::BTreeSet, io::BufReader, marker::PhantomData, ops::Range, path::PathBuf,
+    sync::Arc, time::Duration,
+};
 
 /// A disk segment is an immutable, sorted, contiguous file
 /// that contains key-value pairs.
-#[derive(Debug)]
 pub struct Segment<C: Compressor + Clone> {
     /// Segment ID
     pub id: SegmentId,
@@ -31,17 +46,219 @@ pub struct Segment<C: Compressor + Clone> {
     /// Runtime stats for garbage collection
     pub gc_stats: GcStats,
 
+    /// Runtime read statistics, see [`ValueLog::temperature`](crate::ValueLog::temperature)
+    pub(crate) read_stats: ReadStats,
+
+    /// Lazily opened file handle, reused by point reads
+    pub(crate) file_slot: FileSlot,
+
+    /// File system the segment file is stored in
+    pub(crate) fs: Arc<dyn Fs>,
+
+    /// Clock the segment's age is measured with
+    pub(crate) clock: Arc<dyn Clock>,
+
     pub(crate) _phantom: PhantomData<C>,
 }
 
+impl<C: Compressor + Clone> std::fmt::Debug for Segment<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Segment")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .field("meta", &self.meta)
+            .field("gc_stats", &self.gc_stats)
+            .field("read_stats", &self.read_stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Compressor + Clone> std::fmt::Display for Segment<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", crate::SegmentSummary::from(self))
+    }
+}
+
 impl<C: Compressor + Clone> Segment<C> {
+    fn open_file(&self) -> crate::Result<Box<dyn FsFile>> {
+        self.fs.open(&self.path).map_err(|e| {
+            crate::Error::from(e)
+                .with_context(IoContext::new("open").segment_id(self.id).path(&self.path))
+        })
+    }
+
     /// Returns a scanner that can iterate through the segment.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
     pub fn scan(&self) -> crate::Result<reader::Reader<C>> {
-        reader::Reader::new(&self.path, self.id)
+        self.scan_with_advice(None)
+    }
+
+    /// Returns a scanner that can iterate through the segment,
+    /// giving the page cache advice (if any) for the whole file first.
+    pub(crate) fn scan_with_advice(
+        &self,
+        advice: Option<PageCacheAdvice>,
+    ) -> crate::Result<reader::Reader<C>> {
+        let file = self.open_file()?;
+        advise(&*file, 0, 0, advice);
+
+        Ok(reader::Reader::from_source(
+            self.id,
+            Box::new(BufReader::new(file)),
+        ))
+    }
+
+    /// Returns a scanner that iterates through the segment, calling the filter
+    /// with each blob's key to decide whether to read the value, skip over it,
+    /// or stop the scan.
+    ///
+    /// Values of skipped blobs are never read.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_filtered<F: FnMut(&[u8]) -> filtered_reader::ScanFilter>(
+        &self,
+        filter: F,
+    ) -> crate::Result<filtered_reader::FilteredReader<F>> {
+        let file = self.open_file()?;
+        Ok(filtered_reader::FilteredReader::new(
+            BufReader::new(file),
+            filter,
+        ))
+    }
+
+    /// Returns a scanner that iterates through the segment in reverse order,
+    /// starting with the last written blob.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_rev(&self) -> crate::Result<rev_reader::RevReader<C>> {
+        let blobs = self
+            .scan_meta()?
+            .map(|x| x.map(|x| (x.offset, x.key)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(rev_reader::RevReader::new(self.scan()?, blobs))
+    }
+
+    /// Returns a scanner that iterates through the segment's keys, value sizes,
+    /// offsets and checksums, skipping over the values.
+    ///
+    /// This is much cheaper than [`Segment::scan`] when the values are not needed.
+    ///
+    /// Each item also describes the exact location of its blob record in the segment file
+    /// (see [`BlobMeta::record_len`](crate::BlobMeta::record_len)), so external tools can
+    /// reference blobs without parsing the file format themselves.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_meta(&self) -> crate::Result<meta_reader::MetaReader> {
+        let file = self.open_file()?;
+        Ok(meta_reader::MetaReader::new(BufReader::new(file)))
+    }
+
+    /// Returns a scanner that iterates through the segment's keys and blob offsets,
+    /// skipping over the values.
+    ///
+    /// This is useful to check which blobs are still referenced by the index.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_keys(&self) -> crate::Result<meta_reader::KeyReader> {
+        self.scan_meta().map(meta_reader::KeyReader::new)
+    }
+
+    /// Returns a scanner that iterates through the segment's keys, value handles
+    /// and value sizes, skipping over the values.
+    ///
+    /// This can be used to (re-)build indexes that point into the value log.
+    ///
+    /// The value size is the size of the value as stored on disk, so if the segment
+    /// is compressed, it is the compressed size.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn handles(&self) -> crate::Result<meta_reader::HandleReader> {
+        self.scan_meta()
+            .map(|reader| meta_reader::HandleReader::new(reader, self.id))
+    }
+
+    /// Deallocates the given byte ranges of the segment file, without rewriting it.
+    ///
+    /// This frees the disk space of stale blobs, as an alternative to a rollover
+    /// for large segments that only contain a few stale spans.
+    /// Every range needs to consist of whole blob records (see [`Segment::scan_meta`]),
+    /// and must not be followed by a blob with a delta-encoded key
+    /// (see [`BlobMeta::shared_prefix_len`](crate::BlobMeta::shared_prefix_len)).
+    ///
+    /// Returns the amount of bytes that were punched.
+    ///
+    /// The blobs inside the ranges are lost, so the caller needs to ensure they are stale,
+    /// and that the segment is not scanned at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, a range does not align with blob records,
+    /// or the file system does not support punching holes.
+    pub fn punch_stale_ranges(&self, ranges: &[Range<u64>]) -> crate::Result<u64> {
+        let mut starts = BTreeSet::new();
+        let mut ends = BTreeSet::new();
+        let mut segment_end = 0;
+
+        for blob in self.scan_meta()? {
+            let blob = blob?;
+            starts.insert(blob.offset);
+
+            if blob.shared_prefix_len == 0 {
+                ends.insert(blob.offset);
+            }
+
+            segment_end = blob.offset + blob.record_len();
+        }
+
+        ends.insert(segment_end);
+
+        for range in ranges {
+            if range.is_empty() || !starts.contains(&range.start) || !ends.contains(&range.end) {
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "range {range:?} does not align with blob records of segment #{}",
+                        self.id,
+                    ),
+                )));
+            }
+        }
+
+        let mut punched_bytes = 0;
+
+        for range in ranges {
+            let len = range.end - range.start;
+
+            self.fs
+                .punch_hole(&self.path, range.start, len)
+                .map_err(|e| {
+                    crate::Error::from(e).with_context(
+                        IoContext::new("punch hole")
+                            .segment_id(self.id)
+                            .offset(range.start)
+                            .path(&self.path),
+                    )
+                })?;
+
+            self.gc_stats.add_punched_bytes(len);
+            punched_bytes += len;
+        }
+
+        Ok(punched_bytes)
     }
 
     /// Always returns `false` because a segment is never empty.
@@ -54,6 +271,15 @@ impl<C: Compressor + Clone> Segment<C> {
         self.meta.item_count
     }
 
+    /// Returns `true` if the segment was created longer than `max_age` ago.
+    ///
+    /// Segments that do not record their creation time never expire.
+    pub fn is_expired(&self, max_age: Duration) -> bool {
+        self.meta.created_at.is_some_and(|created_at| {
+            crate::time::is_older_than(created_at, max_age, self.clock.now_millis())
+        })
+    }
+
     /// Marks the segment as fully stale.
     pub(crate) fn mark_as_stale(&self) {
         self.gc_stats.set_stale_items(self.meta.item_count);
@@ -78,4 +304,42 @@ impl<C: Compressor + Clone> Segment<C> {
 
         dead / self.meta.item_count as f32
     }
+
+    /// Returns a point-in-time report of the segment's garbage collection statistics.
+    #[must_use]
+    pub fn gc_report(&self) -> SegmentGcReport {
+        let stale_items = self.gc_stats.stale_items().min(self.meta.item_count);
+        let total_bytes = self.meta.total_uncompressed_bytes;
+        let stale_bytes = self.gc_stats.stale_bytes().min(total_bytes);
+
+        // NOTE: Compression is assumed to be uniform across blobs
+        let reclaimable_bytes = if total_bytes == 0 {
+            0
+        } else {
+            let reclaimable = u128::from(self.meta.compressed_bytes) * u128::from(stale_bytes)
+                / u128::from(total_bytes);
+
+            // NOTE: Is <= compressed_bytes
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                reclaimable as u64
+            }
+        };
+
+        SegmentGcReport {
+            segment_id: self.id,
+            stale_ratio: self.stale_ratio(),
+            item_count: self.meta.item_count,
+            stale_items,
+            live_items: self.meta.item_count - stale_items,
+            total_bytes,
+            stale_bytes,
+            live_bytes: total_bytes - stale_bytes,
+            compressed_bytes: self.meta.compressed_bytes,
+            reclaimable_bytes,
+            age: self.meta.created_at.map(|created_at| {
+                Duration::from_millis(self.clock.now_millis().saturating_sub(created_at))
+            }),
+        }
+    }
 }
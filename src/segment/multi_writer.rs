@@ -4,13 +4,26 @@
 
 use super::writer::Writer;
 use crate::{
+    clock::Clock,
     compression::Compressor,
+    fs::{Fs, StdFs},
     id::{IdGenerator, SegmentId},
+    manifest::SegmentTags,
+    memory::{MemoryReservation, IO_BUFFER_SIZE},
+    metrics::{LatencyOp, Metrics, Timer},
+    pipeline::Pipeline,
+    value_log::{ValueLogId, ValueLogInner},
     ValueHandle,
 };
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
 
 /// Segment writer, may write multiple segments
+///
+/// A writer is `Send` (if its compressor is `Send + Sync`), so it can be moved to another thread,
+/// for example to flush data in the background. Multiple writers can be used in parallel.
 pub struct MultiWriter<C: Compressor + Clone> {
     folder: PathBuf,
     target_size: u64,
@@ -19,7 +32,40 @@ pub struct MultiWriter<C: Compressor + Clone> {
 
     id_generator: IdGenerator,
 
-    compression: Option<C>,
+    pipeline: Pipeline<C>,
+
+    chunk_size: Option<u32>,
+
+    restart_interval: Option<u32>,
+
+    /// Every n-th blob is read back before the segments are registered
+    sample_interval: Option<u32>,
+
+    /// Creation time to record in the segments, instead of the time they are created at
+    created_at: Option<u64>,
+
+    /// Clock the creation time of the segments is taken from, instead of the system time
+    clock: Option<Arc<dyn Clock>>,
+
+    /// User-defined tags that are attached to the segments when they are registered
+    tags: SegmentTags,
+
+    fs: Arc<dyn Fs>,
+
+    /// Value log ID & generation the writer was handed out for
+    pub(crate) lease: Option<(ValueLogId, u64)>,
+
+    /// Value log whose disk usage quota the writes count against
+    quota: Option<Weak<ValueLogInner<C>>>,
+
+    /// Disk space reserved for the writes so far
+    reserved_bytes: u64,
+
+    /// Memory accounted for the buffers of the segment writers
+    memory: Option<MemoryReservation>,
+
+    /// Latency histograms of the value log the writer was handed out by
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl<C: Compressor + Clone> MultiWriter<C> {
@@ -33,6 +79,20 @@ impl<C: Compressor + Clone> MultiWriter<C> {
         id_generator: IdGenerator,
         target_size: u64,
         folder: P,
+    ) -> std::io::Result<Self> {
+        Self::with_fs(id_generator, target_size, folder, Arc::new(StdFs))
+    }
+
+    /// Initializes a new segment writer that writes into the given file system.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub(crate) fn with_fs<P: AsRef<Path>>(
+        id_generator: IdGenerator,
+        target_size: u64,
+        folder: P,
+        fs: Arc<dyn Fs>,
     ) -> std::io::Result<Self> {
         let folder = folder.as_ref();
 
@@ -44,18 +104,168 @@ impl<C: Compressor + Clone> MultiWriter<C> {
             folder: folder.into(),
             target_size,
 
-            writers: vec![Writer::new(segment_path, segment_id)?],
+            writers: vec![Writer::new(&*fs, segment_path, segment_id)?],
+
+            pipeline: Pipeline::default(),
+
+            chunk_size: None,
+            restart_interval: None,
+            sample_interval: None,
+
+            created_at: None,
+            clock: None,
+
+            tags: SegmentTags::new(),
+
+            fs,
 
-            compression: None,
+            lease: None,
+
+            quota: None,
+            reserved_bytes: 0,
+            memory: None,
+            metrics: None,
         })
     }
 
+    /// Binds the writer to a value log generation.
+    #[must_use]
+    pub(crate) fn with_lease(mut self, vlog_id: ValueLogId, generation: u64) -> Self {
+        self.lease = Some((vlog_id, generation));
+        self
+    }
+
+    /// Makes the writes count against the value log's disk usage quota.
+    #[must_use]
+    pub(crate) fn with_quota(mut self, value_log: Weak<ValueLogInner<C>>) -> Self {
+        self.quota = Some(value_log);
+        self
+    }
+
+    /// Accounts the buffers of the segment writers, until the writer is dropped.
+    pub(crate) fn with_memory_reservation(mut self, memory: MemoryReservation) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Records write & fsync latencies into the value log's histograms.
+    pub(crate) fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        for writer in &mut self.writers {
+            writer.metrics = Some(metrics.clone());
+        }
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Releases the disk space reserved for the writes.
+    fn release_quota(&mut self) {
+        if let Some(value_log) = self.quota.as_ref().and_then(Weak::upgrade) {
+            value_log.release_disk_space(self.reserved_bytes);
+        }
+        self.reserved_bytes = 0;
+    }
+
     /// Sets the compression method
     #[must_use]
     #[doc(hidden)]
     pub fn use_compression(mut self, compressor: C) -> Self {
-        self.compression = Some(compressor.clone());
-        self.get_active_writer_mut().compression = Some(compressor);
+        self.pipeline = std::mem::take(&mut self.pipeline).with_compression(Some(compressor));
+        self.get_active_writer_mut().pipeline = self.pipeline.clone();
+        self
+    }
+
+    /// Sets the pipeline values are passed through before being stored.
+    #[must_use]
+    pub(crate) fn use_pipeline(mut self, pipeline: Pipeline<C>) -> Self {
+        self.pipeline.clone_from(&pipeline);
+        self.get_active_writer_mut().pipeline = pipeline;
+        self
+    }
+
+    /// Sets the size after which a new segment is started.
+    #[must_use]
+    pub(crate) fn use_target_size(mut self, target_size: u64) -> Self {
+        self.target_size = target_size;
+        self
+    }
+
+    /// Sets the chunk size that larger values are split into.
+    #[must_use]
+    pub(crate) fn use_chunking(mut self, chunk_size: Option<u32>) -> Self {
+        self.chunk_size = chunk_size;
+        self.get_active_writer_mut().chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the interval of keys that are not delta-encoded.
+    #[must_use]
+    pub(crate) fn use_key_restart_interval(mut self, restart_interval: Option<u32>) -> Self {
+        self.restart_interval = restart_interval;
+        self.get_active_writer_mut().restart_interval = restart_interval;
+        self
+    }
+
+    /// Remembers every n-th blob, so it can be read back before the segments are registered.
+    #[must_use]
+    pub(crate) fn use_sampling(mut self, sample_interval: Option<u32>) -> Self {
+        self.sample_interval = sample_interval;
+        self.get_active_writer_mut().sample_interval = sample_interval;
+        self
+    }
+
+    /// Sets the creation time (in milliseconds since the Unix epoch)
+    /// that is recorded in the written segments.
+    #[must_use]
+    pub(crate) fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self.get_active_writer_mut().created_at = Some(created_at);
+        self
+    }
+
+    /// Records the creation time of the written segments, taken from the given clock.
+    ///
+    /// Without it, the written segments do not record a creation time
+    /// (unless set by [`MultiWriter::with_created_at`]).
+    #[must_use]
+    pub(crate) fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        if self.created_at.is_none() {
+            self.get_active_writer_mut().created_at = Some(clock.now_millis());
+        }
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Adds a tag that is attached to every segment of the writer when it is registered,
+    /// e.g. to track where the data came from.
+    ///
+    /// Tags are persisted in the manifest, so they should be small.
+    /// Segments can be looked up by tag using [`ValueLog::list_segments_with_tag`](crate::ValueLog::list_segments_with_tag).
+    /// Segments written by garbage collection have no tags.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key or value is longer than 65535 bytes,
+    /// or the writer has 65535 tags already.
+    #[must_use]
+    pub fn with_tag<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        let value = value.into();
+
+        assert!(
+            u16::try_from(key.len()).is_ok() && u16::try_from(value.len()).is_ok(),
+            "segment tag is too long"
+        );
+        assert!(
+            self.tags.len() < usize::from(u16::MAX) || self.tags.contains_key(&key),
+            "too many segment tags"
+        );
+
+        self.tags.insert(key, value);
+
+        for writer in &mut self.writers {
+            writer.tags.clone_from(&self.tags);
+        }
+
         self
     }
 
@@ -102,14 +312,44 @@ impl<C: Compressor + Clone> MultiWriter<C> {
         let new_segment_id = self.id_generator.next();
         let segment_path = self.folder.join(new_segment_id.to_string());
 
-        let new_writer =
-            Writer::new(segment_path, new_segment_id)?.use_compression(self.compression.clone());
+        let mut new_writer = Writer::new(&*self.fs, segment_path, new_segment_id)?
+            .use_pipeline(self.pipeline.clone())
+            .use_chunking(self.chunk_size)
+            .use_key_restart_interval(self.restart_interval)
+            .use_sampling(self.sample_interval);
+
+        if let Some(created_at) = self.created_at {
+            new_writer.created_at = Some(created_at);
+        } else if let Some(clock) = &self.clock {
+            new_writer.created_at = Some(clock.now_millis());
+        }
+
+        new_writer.tags.clone_from(&self.tags);
+        new_writer.metrics.clone_from(&self.metrics);
 
         self.writers.push(new_writer);
 
+        if let Some(memory) = &mut self.memory {
+            memory.grow(IO_BUFFER_SIZE);
+        }
+
         Ok(())
     }
 
+    /// Finishes the current segment, so the next item is written into a new segment.
+    ///
+    /// Does nothing if the current segment is empty.
+    pub(crate) fn start_new_segment(&mut self) -> crate::Result<()> {
+        let writer = self.get_active_writer_mut();
+
+        if writer.item_count == 0 {
+            return Ok(());
+        }
+
+        writer.flush()?;
+        self.rotate()
+    }
+
     /// Writes an item.
     ///
     /// # Errors
@@ -120,9 +360,17 @@ impl<C: Compressor + Clone> MultiWriter<C> {
         key: K,
         value: V,
     ) -> crate::Result<u32> {
+        let timer = Timer::start();
+
         let key = key.as_ref();
         let value = value.as_ref();
 
+        if let Some(value_log) = self.quota.as_ref().and_then(Weak::upgrade) {
+            let bytes = (key.len() + value.len()) as u64;
+            value_log.reserve_disk_space(bytes)?;
+            self.reserved_bytes += bytes;
+        }
+
         let target_size = self.target_size;
 
         // Write actual value into segment
@@ -135,6 +383,10 @@ impl<C: Compressor + Clone> MultiWriter<C> {
             self.rotate()?;
         }
 
+        if let Some(metrics) = &self.metrics {
+            metrics.record(LatencyOp::SegmentWrite, timer);
+        }
+
         Ok(bytes_written)
     }
 
@@ -145,9 +397,18 @@ impl<C: Compressor + Clone> MultiWriter<C> {
             writer.flush()?;
         }
 
+        // NOTE: The written segments are accounted for by the value log once registered
+        self.release_quota();
+
         // IMPORTANT: We cannot finish the index writer here
         // The writers first need to be registered into the value log
 
-        Ok(self.writers)
+        Ok(std::mem::take(&mut self.writers))
+    }
+}
+
+impl<C: Compressor + Clone> Drop for MultiWriter<C> {
+    fn drop(&mut self) {
+        self.release_quota();
     }
 }
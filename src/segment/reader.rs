@@ -2,8 +2,14 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use super::{meta::METADATA_HEADER_MAGIC, writer::BLOB_HEADER_MAGIC};
-use crate::{coding::DecodeError, id::SegmentId, value::UserKey, Compressor, Slice, UserValue};
+use super::{
+    meta::is_metadata_header,
+    writer::{record_len, BlobLayout, BLOB_HEADER_MAGIC},
+};
+use crate::{
+    coding::DecodeError, id::SegmentId, memory::MemoryReservation, pipeline::Pipeline,
+    value::UserKey, Compressor, Slice, UserValue,
+};
 use byteorder::{BigEndian, ReadBytesExt};
 use std::{
     fs::File,
@@ -20,12 +26,196 @@ macro_rules! fail_iter {
     };
 }
 
+/// Reads the header of the next record, skipping over punched holes
+/// (see [`Segment::punch_stale_ranges`](crate::Segment::punch_stale_ranges)).
+///
+/// Returns the amount of skipped bytes.
+pub fn read_record_header<R: Read>(
+    reader: &mut R,
+    buf: &mut [u8; BLOB_HEADER_MAGIC.len()],
+) -> std::io::Result<u64> {
+    reader.read_exact(buf)?;
+
+    if buf.iter().any(|&b| b != 0) {
+        return Ok(0);
+    }
+
+    // NOTE: Holes always end at a record header, which never starts with a zero byte
+    let mut skipped = buf.len() as u64;
+
+    loop {
+        let byte = reader.read_u8()?;
+
+        if byte != 0 {
+            if let Some((first, rest)) = buf.split_first_mut() {
+                *first = byte;
+                reader.read_exact(rest)?;
+            }
+            return Ok(skipped);
+        }
+
+        skipped += 1;
+    }
+}
+
+/// Parses the layout of a blob record from its header,
+/// failing if it is not a blob header.
+pub fn parse_blob_header(buf: &[u8]) -> crate::Result<BlobLayout> {
+    BlobLayout::from_header(buf).ok_or(crate::Error::Decode(DecodeError::InvalidHeader("Blob")))
+}
+
+/// Reads a blob's key, which may be delta-encoded against the previous key.
+///
+/// Returns the key and the length of the prefix shared with the previous key.
+/// If the key is delta-encoded, but the previous key is not known, only its suffix is returned.
+pub fn read_key<R: Read>(
+    reader: &mut R,
+    layout: BlobLayout,
+    prev_key: Option<&[u8]>,
+) -> crate::Result<(UserKey, u16)> {
+    if !layout.prefixed {
+        let key_len = reader.read_u16::<BigEndian>()?;
+        return Ok((Slice::from_reader(reader, key_len as usize)?, 0));
+    }
+
+    let shared_prefix_len = reader.read_u16::<BigEndian>()?;
+    let suffix_len = reader.read_u16::<BigEndian>()?;
+
+    let mut suffix = vec![0; suffix_len as usize];
+    reader.read_exact(&mut suffix)?;
+
+    let Some(prev_key) = prev_key else {
+        return Ok((Slice::from(suffix), shared_prefix_len));
+    };
+
+    let Some(prefix) = prev_key.get(..usize::from(shared_prefix_len)) else {
+        return Err(crate::Error::Decode(DecodeError::InvalidHeader("Blob")));
+    };
+
+    let mut key = Vec::with_capacity(prefix.len() + suffix.len());
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(&suffix);
+
+    Ok((Slice::from(key), shared_prefix_len))
+}
+
+/// Reads the chunks of a chunked blob, passing each one through `decode`.
+///
+/// Returns the concatenated chunks, and the amount of stored bytes.
+pub fn read_chunks<R: Read, F: FnMut(Vec<u8>) -> crate::Result<Vec<u8>>>(
+    reader: &mut R,
+    chunk_count: u32,
+    mut decode: F,
+) -> crate::Result<(Vec<u8>, u64)> {
+    let mut value = vec![];
+    let mut stored_bytes = 0;
+
+    for _ in 0..chunk_count {
+        let len = reader.read_u32::<BigEndian>()?;
+
+        let mut chunk = vec![0; len as usize];
+        reader.read_exact(&mut chunk)?;
+        stored_bytes += u64::from(len);
+
+        value.extend(decode(chunk)?);
+    }
+
+    Ok((value, stored_bytes))
+}
+
+/// Caller-provided buffer a value is read into
+pub enum ValueBuffer<'a> {
+    /// The buffer is resized to the value's length
+    Vec(&'a mut Vec<u8>),
+
+    /// The value is written to the start of the buffer, which needs to be large enough
+    Slice(&'a mut [u8]),
+}
+
+impl ValueBuffer<'_> {
+    fn slice_prefix(buf: &mut [u8], len: usize) -> crate::Result<&mut [u8]> {
+        let buf_len = buf.len();
+
+        buf.get_mut(..len).ok_or_else(|| {
+            crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("buffer of {buf_len}B is too small for value of {len}B"),
+            ))
+        })
+    }
+
+    /// Reads a value of known length directly into the buffer.
+    fn read_from<R: Read>(&mut self, reader: &mut R, len: usize) -> crate::Result<usize> {
+        match self {
+            Self::Vec(buf) => {
+                buf.clear();
+                buf.reserve_exact(len);
+                buf.resize(len, 0);
+                reader.read_exact(buf)?;
+            }
+            Self::Slice(buf) => {
+                reader.read_exact(Self::slice_prefix(buf, len)?)?;
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Copies a value into the buffer.
+    pub fn copy_from(&mut self, value: &[u8]) -> crate::Result<usize> {
+        match self {
+            Self::Vec(buf) => {
+                buf.clear();
+                buf.extend_from_slice(value);
+            }
+            Self::Slice(buf) => {
+                Self::slice_prefix(buf, value.len())?.copy_from_slice(value);
+            }
+        }
+
+        Ok(value.len())
+    }
+}
+
+/// Header of the blob record a reader is positioned at
+struct RecordHeader {
+    layout: BlobLayout,
+    checksum: u64,
+    key: UserKey,
+    key_len: usize,
+    shared_prefix_len: u16,
+}
+
+/// Seekable byte stream a segment reader can parse blobs from
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
 /// Reads through a segment in order.
 pub struct Reader<C: Compressor + Clone> {
     pub(crate) segment_id: SegmentId,
-    inner: BufReader<File>,
+    inner: Box<dyn ReadSeek>,
+    offset: u64,
+    last_offset: u64,
     is_terminated: bool,
-    compression: Option<C>,
+
+    /// Stages to revert to restore stored values
+    pipeline: Pipeline<C>,
+
+    /// Key of the previous blob, to resolve delta-encoded keys
+    prev_key: Option<UserKey>,
+
+    /// If unset, delta-encoded keys are not required to be resolvable
+    resolve_keys: bool,
+
+    /// Memory accounted for the reader's buffer
+    memory: Option<MemoryReservation>,
+
+    /// If set, the last value is also kept as stored, before reverting the pipeline
+    keep_stored: bool,
+
+    /// Last value as stored, see [`Reader::keep_stored_values`]
+    stored_value: Option<UserValue>,
 }
 
 impl<C: Compressor + Clone> Reader<C> {
@@ -40,71 +230,241 @@ impl<C: Compressor + Clone> Reader<C> {
         Ok(Self::with_reader(segment_id, file_reader))
     }
 
-    pub(crate) fn get_offset(&mut self) -> std::io::Result<u64> {
-        self.inner.stream_position()
+    /// Returns the offset of the last returned blob in the segment file.
+    pub(crate) fn last_offset(&self) -> u64 {
+        self.last_offset
+    }
+
+    /// Repositions the reader at the given blob offset.
+    pub(crate) fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        self.inner.seek(std::io::SeekFrom::Start(offset))?;
+        self.offset = offset;
+        self.is_terminated = false;
+        self.prev_key = None;
+        Ok(())
+    }
+
+    /// Sets the offset the underlying byte stream is positioned at.
+    pub(crate) fn at_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
     }
 
     /// Initializes a new segment reader.
     #[must_use]
     pub fn with_reader(segment_id: SegmentId, file_reader: BufReader<File>) -> Self {
+        Self::from_source(segment_id, Box::new(file_reader))
+    }
+
+    /// Initializes a new segment reader over an arbitrary byte stream.
+    pub(crate) fn from_source(segment_id: SegmentId, inner: Box<dyn ReadSeek>) -> Self {
         Self {
             segment_id,
-            inner: file_reader,
+            inner,
+            offset: 0,
+            last_offset: 0,
             is_terminated: false,
-            compression: None,
+            pipeline: Pipeline::default(),
+            prev_key: None,
+            resolve_keys: true,
+            memory: None,
+            keep_stored: false,
+            stored_value: None,
         }
     }
 
-    pub(crate) fn use_compression(mut self, compressor: C) -> Self {
-        self.compression = Some(compressor);
+    /// Allows reading from blobs with delta-encoded keys without knowing the previous key,
+    /// for readers that only need values (e.g. when starting at a value handle).
+    ///
+    /// The keys of such blobs are returned incomplete.
+    pub(crate) fn without_key_resolution(mut self) -> Self {
+        self.resolve_keys = false;
         self
     }
-}
 
-impl<C: Compressor + Clone> Iterator for Reader<C> {
-    type Item = crate::Result<(UserKey, UserValue, u64)>;
+    /// Sets the pipeline the segment's blobs were written with.
+    pub(crate) fn use_pipeline(mut self, pipeline: Pipeline<C>) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Accounts the reader's buffer, until the reader is dropped.
+    pub(crate) fn with_memory_reservation(mut self, memory: MemoryReservation) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Keeps values as stored in the segment (before reverting the pipeline),
+    /// so they can be taken after reading them (see [`Reader::take_stored_value`]).
+    pub(crate) fn keep_stored_values(mut self, keep_stored: bool) -> Self {
+        self.keep_stored = keep_stored;
+        self
+    }
+
+    /// Returns `true` if values are kept as stored.
+    pub(crate) fn keeps_stored_values(&self) -> bool {
+        self.keep_stored
+    }
+
+    /// Takes the last value as stored in the segment.
+    ///
+    /// Returns `None` if stored values are not kept, the pipeline is empty,
+    /// or the last blob is chunked, as its chunks are decoded separately.
+    pub(crate) fn take_stored_value(&mut self) -> Option<UserValue> {
+        self.stored_value.take()
+    }
+
+    /// Reads the next blob, writing its value into the given buffer
+    /// instead of allocating a new one.
+    ///
+    /// Returns the key, the length of the value and the checksum.
+    pub(crate) fn next_into(
+        &mut self,
+        mut buf: ValueBuffer<'_>,
+    ) -> Option<crate::Result<(UserKey, usize, u64)>> {
+        let header = fail_iter!(self.next_header()?);
+
+        if header.layout.chunked {
+            let chunk_count = fail_iter!(self.inner.read_u32::<BigEndian>());
+
+            let (val, stored_bytes) =
+                fail_iter!(read_chunks(&mut self.inner, chunk_count, |chunk| {
+                    self.pipeline.revert(self.segment_id, chunk)
+                }));
+
+            self.offset += record_len(
+                header.key_len,
+                header.shared_prefix_len,
+                stored_bytes,
+                chunk_count,
+            );
+
+            let len = fail_iter!(buf.copy_from(&val));
+            return Some(Ok((header.key, len, header.checksum)));
+        }
+
+        let val_len = fail_iter!(self.inner.read_u32::<BigEndian>());
+
+        // NOTE: Values that are stored as is have their exact length in the header,
+        // so they can be read directly into the buffer
+        let len = if self.pipeline.is_empty() {
+            fail_iter!(buf.read_from(&mut self.inner, val_len as usize))
+        } else {
+            let mut val = vec![0; val_len as usize];
+            fail_iter!(self.inner.read_exact(&mut val));
+
+            let val = fail_iter!(self.pipeline.revert(self.segment_id, val));
+            fail_iter!(buf.copy_from(&val))
+        };
+
+        self.offset += record_len(header.key_len, header.shared_prefix_len, val_len.into(), 0);
+
+        Some(Ok((header.key, len, header.checksum)))
+    }
+
+    /// Reads the header and key of the next blob.
+    fn next_header(&mut self) -> Option<crate::Result<RecordHeader>> {
         if self.is_terminated {
             return None;
         }
 
-        {
+        let layout = {
             let mut buf = [0; BLOB_HEADER_MAGIC.len()];
-            fail_iter!(self.inner.read_exact(&mut buf));
+            let skipped = fail_iter!(read_record_header(&mut self.inner, &mut buf));
+            self.offset += skipped;
+            self.last_offset = self.offset;
+
+            // NOTE: Holes never end in the middle of a run of delta-encoded keys
+            if skipped > 0 {
+                self.prev_key = None;
+            }
 
-            if buf == METADATA_HEADER_MAGIC {
+            if is_metadata_header(&buf) {
                 self.is_terminated = true;
                 return None;
             }
 
-            if buf != BLOB_HEADER_MAGIC {
-                return Some(Err(crate::Error::Decode(DecodeError::InvalidHeader(
-                    "Blob",
-                ))));
-            }
-        }
+            fail_iter!(parse_blob_header(&buf))
+        };
 
         let checksum = fail_iter!(self.inner.read_u64::<BigEndian>());
 
-        let key_len = fail_iter!(self.inner.read_u16::<BigEndian>());
-        let key = fail_iter!(Slice::from_reader(&mut self.inner, key_len as usize));
+        let is_resolved = !layout.prefixed || self.prev_key.is_some();
+
+        if !is_resolved && self.resolve_keys {
+            return Some(Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot resolve delta-encoded key without reading the previous blob",
+            ))));
+        }
+
+        let (key, shared_prefix_len) =
+            fail_iter!(read_key(&mut self.inner, layout, self.prev_key.as_deref()));
+
+        let key_len = if is_resolved {
+            key.len()
+        } else {
+            usize::from(shared_prefix_len) + key.len()
+        };
+
+        self.prev_key = is_resolved.then(|| key.clone());
+
+        Some(Ok(RecordHeader {
+            layout,
+            checksum,
+            key,
+            key_len,
+            shared_prefix_len,
+        }))
+    }
+}
+
+impl<C: Compressor + Clone> Iterator for Reader<C> {
+    type Item = crate::Result<(UserKey, UserValue, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let RecordHeader {
+            layout,
+            checksum,
+            key,
+            key_len,
+            shared_prefix_len,
+        } = fail_iter!(self.next_header()?);
+
+        self.stored_value = None;
+
+        if layout.chunked {
+            let chunk_count = fail_iter!(self.inner.read_u32::<BigEndian>());
+
+            let (val, stored_bytes) =
+                fail_iter!(read_chunks(&mut self.inner, chunk_count, |chunk| {
+                    self.pipeline.revert(self.segment_id, chunk)
+                }));
+
+            self.offset += record_len(key_len, shared_prefix_len, stored_bytes, chunk_count);
+
+            return Some(Ok((key, Slice::from(val), checksum)));
+        }
 
         let val_len = fail_iter!(self.inner.read_u32::<BigEndian>());
-        let val = match &self.compression {
-            Some(compressor) => {
-                // TODO: https://github.com/PSeitz/lz4_flex/issues/166
-                let mut val = vec![0; val_len as usize];
-                fail_iter!(self.inner.read_exact(&mut val));
-                Slice::from(fail_iter!(compressor.decompress(&val)))
-            }
-            None => {
-                // NOTE: When not using compression, we can skip
-                // the intermediary heap allocation and read directly into a Slice
-                fail_iter!(Slice::from_reader(&mut self.inner, val_len as usize))
+        let val = if self.pipeline.is_empty() {
+            // NOTE: When not transforming values, we can skip
+            // the intermediary heap allocation and read directly into a Slice
+            fail_iter!(Slice::from_reader(&mut self.inner, val_len as usize))
+        } else {
+            // TODO: https://github.com/PSeitz/lz4_flex/issues/166
+            let mut val = vec![0; val_len as usize];
+            fail_iter!(self.inner.read_exact(&mut val));
+
+            if self.keep_stored {
+                self.stored_value = Some(Slice::from(&*val));
             }
+
+            Slice::from(fail_iter!(self.pipeline.revert(self.segment_id, val)))
         };
 
+        self.offset += record_len(key_len, shared_prefix_len, val_len.into(), 0);
+
         Some(Ok((key, val, checksum)))
     }
 }
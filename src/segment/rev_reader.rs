@@ -0,0 +1,46 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::reader::Reader;
+use crate::{value::UserKey, Compressor, UserValue};
+
+/// Reads through a segment in reverse order.
+///
+/// Because blobs are not back-linked, the blob offsets and keys are collected up front
+/// (skipping over values), so only those need to be buffered, not the values.
+///
+/// The buffered keys are already resolved, so delta-encoded keys do not
+/// need to be read in order.
+#[allow(clippy::module_name_repetitions)]
+pub struct RevReader<C: Compressor + Clone> {
+    inner: Reader<C>,
+    blobs: Vec<(u64, UserKey)>,
+}
+
+impl<C: Compressor + Clone> RevReader<C> {
+    pub(crate) fn new(inner: Reader<C>, blobs: Vec<(u64, UserKey)>) -> Self {
+        Self {
+            inner: inner.without_key_resolution(),
+            blobs,
+        }
+    }
+}
+
+impl<C: Compressor + Clone> Iterator for RevReader<C> {
+    type Item = crate::Result<(UserKey, UserValue, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, key) = self.blobs.pop()?;
+
+        if let Err(e) = self.inner.seek_to(offset) {
+            return Some(Err(e.into()));
+        }
+
+        Some(
+            self.inner
+                .next()?
+                .map(|(_, value, checksum)| (key, value, checksum)),
+        )
+    }
+}
@@ -3,10 +3,12 @@
 // (found in the LICENSE-* files in the repository)
 
 use super::meta::Metadata;
-use crate::coding::{Decode, DecodeError, Encode, EncodeError};
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    fs::Fs,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::{
-    fs::File,
     io::{BufReader, Read, Seek, Write},
     path::Path,
 };
@@ -22,8 +24,8 @@ pub struct SegmentFileTrailer {
 }
 
 impl SegmentFileTrailer {
-    pub fn from_file<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
-        let file = File::open(path)?;
+    pub fn from_file<P: AsRef<Path>>(fs: &dyn Fs, path: P) -> crate::Result<Self> {
+        let file = fs.open(path.as_ref())?;
         let mut reader = BufReader::new(file);
         reader.seek(std::io::SeekFrom::End(-(TRAILER_SIZE as i64)))?;
 
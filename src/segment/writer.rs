@@ -2,26 +2,110 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use super::{meta::Metadata, trailer::SegmentFileTrailer};
+use super::{meta::Metadata, reader::Reader, trailer::SegmentFileTrailer};
 use crate::{
-    coding::Encode, compression::Compressor, id::SegmentId, key_range::KeyRange, value::UserKey,
+    coding::Encode,
+    compression::Compressor,
+    failpoints,
+    fs::{Fs, FsFile},
+    id::SegmentId,
+    key_range::KeyRange,
+    manifest::SegmentTags,
+    metrics::{LatencyOp, Metrics, Timer},
+    pipeline::Pipeline,
+    value::UserKey,
 };
 use byteorder::{BigEndian, WriteBytesExt};
 use std::{
-    fs::File,
-    io::{BufWriter, Seek, Write},
+    io::{BufReader, BufWriter, Seek, Write},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 pub const BLOB_HEADER_MAGIC: &[u8] = &[b'V', b'L', b'G', b'B', b'L', b'O', b'B', 1];
 
+/// Header of a blob whose value is split into chunks
+///
+/// Instead of the value length, the record stores the chunk count,
+/// followed by every (separately compressed & encrypted) chunk, prefixed by its length.
+pub const CHUNKED_BLOB_HEADER_MAGIC: &[u8] = &[b'V', b'L', b'G', b'B', b'L', b'O', b'B', 2];
+
+/// Header of a blob whose key is delta-encoded against the previous blob's key
+///
+/// Instead of the key length, the record stores the length of the prefix shared
+/// with the previous key, followed by the length of the remaining suffix.
+pub const PREFIXED_BLOB_HEADER_MAGIC: &[u8] = &[b'V', b'L', b'G', b'B', b'L', b'O', b'B', 3];
+
+/// Header of a chunked blob whose key is delta-encoded against the previous blob's key
+pub const PREFIXED_CHUNKED_BLOB_HEADER_MAGIC: &[u8] =
+    &[b'V', b'L', b'G', b'B', b'L', b'O', b'B', 4];
+
+/// Layout of a blob record, as indicated by its header
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct BlobLayout {
+    /// The value is split into chunks
+    pub chunked: bool,
+
+    /// The key is delta-encoded against the previous blob's key
+    pub prefixed: bool,
+}
+
+impl BlobLayout {
+    /// Parses a record header, returning `None` if it is not a blob header.
+    #[must_use]
+    pub fn from_header(buf: &[u8]) -> Option<Self> {
+        [(false, false), (true, false), (false, true), (true, true)]
+            .into_iter()
+            .map(|(chunked, prefixed)| Self { chunked, prefixed })
+            .find(|layout| layout.header() == buf)
+    }
+
+    /// Returns the record header of the layout.
+    #[must_use]
+    pub fn header(self) -> &'static [u8] {
+        match (self.chunked, self.prefixed) {
+            (false, false) => BLOB_HEADER_MAGIC,
+            (true, false) => CHUNKED_BLOB_HEADER_MAGIC,
+            (false, true) => PREFIXED_BLOB_HEADER_MAGIC,
+            (true, true) => PREFIXED_CHUNKED_BLOB_HEADER_MAGIC,
+        }
+    }
+}
+
+/// Returns the size of a blob record on disk, including its header.
+///
+/// If the key shares a prefix with the previous key, only the remaining suffix is stored.
+#[must_use]
+pub fn record_len(
+    key_len: usize,
+    shared_prefix_len: u16,
+    value_size: u64,
+    chunk_count: u32,
+) -> u64 {
+    let stored_key_len = if shared_prefix_len > 0 {
+        std::mem::size_of::<u16>() + key_len.saturating_sub(shared_prefix_len.into())
+    } else {
+        key_len
+    };
+
+    (BLOB_HEADER_MAGIC.len()
+        + std::mem::size_of::<u64>()
+        + std::mem::size_of::<u16>()
+        + stored_key_len
+        + std::mem::size_of::<u32>()) as u64
+        + u64::from(chunk_count) * std::mem::size_of::<u32>() as u64
+        + value_size
+}
+
 /// Segment writer
 pub struct Writer<C: Compressor + Clone> {
     pub path: PathBuf,
     pub(crate) segment_id: SegmentId,
 
     #[allow(clippy::struct_field_names)]
-    active_writer: BufWriter<File>,
+    active_writer: BufWriter<Box<dyn FsFile>>,
 
     offset: u64,
 
@@ -32,7 +116,33 @@ pub struct Writer<C: Compressor + Clone> {
     pub(crate) first_key: Option<UserKey>,
     pub(crate) last_key: Option<UserKey>,
 
-    pub(crate) compression: Option<C>,
+    /// Stages values are passed through before being stored
+    pub(crate) pipeline: Pipeline<C>,
+
+    /// Values larger than this are split into chunks of this size
+    pub(crate) chunk_size: Option<u32>,
+
+    /// Every n-th key is stored as a whole, the keys in between are delta-encoded
+    pub(crate) restart_interval: Option<u32>,
+
+    /// Amount of delta-encoded keys written since the last whole key
+    entries_since_restart: u32,
+
+    /// Time the segment was created at, in milliseconds since the Unix epoch,
+    /// or `None` if it is not recorded
+    pub(crate) created_at: Option<u64>,
+
+    /// User-defined tags that are attached to the segment when it is registered
+    pub(crate) tags: SegmentTags,
+
+    /// Latency histograms of the value log the segment is written for
+    pub(crate) metrics: Option<Arc<Metrics>>,
+
+    /// Every n-th blob is remembered, to be read back by [`Writer::verify_samples`]
+    pub(crate) sample_interval: Option<u32>,
+
+    /// Offset, key & checksum of the remembered blobs
+    samples: Vec<(u64, UserKey, u64)>,
 }
 
 impl<C: Compressor + Clone> Writer<C> {
@@ -42,10 +152,14 @@ impl<C: Compressor + Clone> Writer<C> {
     ///
     /// Will return `Err` if an IO error occurs.
     #[doc(hidden)]
-    pub fn new<P: AsRef<Path>>(path: P, segment_id: SegmentId) -> std::io::Result<Self> {
+    pub fn new<P: AsRef<Path>>(
+        fs: &dyn Fs,
+        path: P,
+        segment_id: SegmentId,
+    ) -> std::io::Result<Self> {
         let path = path.as_ref();
 
-        let file = File::create(path)?;
+        let file = fs.create(path)?;
 
         Ok(Self {
             path: path.into(),
@@ -59,15 +173,48 @@ impl<C: Compressor + Clone> Writer<C> {
             first_key: None,
             last_key: None,
 
-            compression: None,
+            pipeline: Pipeline::default(),
+            chunk_size: None,
+            restart_interval: None,
+            entries_since_restart: 0,
+            created_at: None,
+            tags: SegmentTags::new(),
+            metrics: None,
+            sample_interval: None,
+            samples: vec![],
         })
     }
 
     pub fn use_compression(mut self, compressor: Option<C>) -> Self {
-        self.compression = compressor;
+        self.pipeline = self.pipeline.with_compression(compressor);
+        self
+    }
+
+    pub(crate) fn use_pipeline(mut self, pipeline: Pipeline<C>) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub(crate) fn use_chunking(mut self, chunk_size: Option<u32>) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    pub(crate) fn use_key_restart_interval(mut self, restart_interval: Option<u32>) -> Self {
+        self.restart_interval = restart_interval;
         self
     }
 
+    pub(crate) fn use_sampling(mut self, sample_interval: Option<u32>) -> Self {
+        self.sample_interval = sample_interval;
+        self
+    }
+
+    /// Returns the key version the segment is encrypted with.
+    pub(crate) fn key_version(&self) -> Option<u32> {
+        self.pipeline.key_version()
+    }
+
     /// Returns the current offset in the file.
     ///
     /// This can be used to index an item into an external `Index`.
@@ -94,19 +241,25 @@ impl<C: Compressor + Clone> Writer<C> {
     pub fn write(&mut self, key: &[u8], value: &[u8]) -> crate::Result<u32> {
         assert!(!key.is_empty());
         assert!(key.len() <= u16::MAX.into());
-        assert!(u32::try_from(value.len()).is_ok());
 
-        if self.first_key.is_none() {
-            self.first_key = Some(key.into());
+        failpoints::eval(failpoints::SEGMENT_WRITE)?;
+
+        if let Some(chunk_size) = self.chunk_size {
+            if value.len() > chunk_size as usize {
+                return self.write_chunked(key, value, chunk_size);
+            }
         }
-        self.last_key = Some(key.into());
 
-        self.uncompressed_bytes += value.len() as u64;
+        assert!(u32::try_from(value.len()).is_ok());
 
-        let value = match &self.compression {
-            Some(compressor) => compressor.compress(value)?,
-            None => value.to_vec(),
-        };
+        let uncompressed_len = value.len() as u64;
+
+        // NOTE: Encode first, so a failed write does not leave its key behind for delta-encoding
+        let value = self.encode_value(value)?;
+
+        let shared_prefix_len = self.shared_prefix_len(key);
+        self.track_key(key);
+        self.uncompressed_bytes += uncompressed_len;
 
         let mut hasher = xxhash_rust::xxh3::Xxh3::new();
         hasher.update(key);
@@ -118,19 +271,20 @@ impl<C: Compressor + Clone> Writer<C> {
         // so we can optimize rollover by avoiding
         // repeated compression & decompression
 
+        self.sample(key, checksum);
+
         // Write header
-        self.active_writer.write_all(BLOB_HEADER_MAGIC)?;
+        let layout = BlobLayout {
+            chunked: false,
+            prefixed: shared_prefix_len > 0,
+        };
+        self.active_writer.write_all(layout.header())?;
 
         // Write checksum
         self.active_writer.write_u64::<BigEndian>(checksum)?;
 
         // Write key
-
-        // NOTE: Truncation is okay and actually needed
-        #[allow(clippy::cast_possible_truncation)]
-        self.active_writer
-            .write_u16::<BigEndian>(key.len() as u16)?;
-        self.active_writer.write_all(key)?;
+        self.write_key(key, shared_prefix_len)?;
 
         // Write value
 
@@ -140,19 +294,7 @@ impl<C: Compressor + Clone> Writer<C> {
             .write_u32::<BigEndian>(value.len() as u32)?;
         self.active_writer.write_all(&value)?;
 
-        // Header
-        self.offset += BLOB_HEADER_MAGIC.len() as u64;
-
-        // Checksum
-        self.offset += std::mem::size_of::<u64>() as u64;
-
-        // Key
-        self.offset += std::mem::size_of::<u16>() as u64;
-        self.offset += key.len() as u64;
-
-        // Value
-        self.offset += std::mem::size_of::<u32>() as u64;
-        self.offset += value.len() as u64;
+        self.offset += record_len(key.len(), shared_prefix_len, value.len() as u64, 0);
 
         // Update metadata
         self.written_blob_bytes += value.len() as u64;
@@ -163,6 +305,195 @@ impl<C: Compressor + Clone> Writer<C> {
         Ok(value.len() as u32)
     }
 
+    /// Returns the length of the prefix the key shares with the previous key,
+    /// or 0 if the key needs to be stored as a whole (a restart point).
+    fn shared_prefix_len(&mut self, key: &[u8]) -> u16 {
+        let (Some(restart_interval), Some(prev_key)) = (self.restart_interval, &self.last_key)
+        else {
+            return 0;
+        };
+
+        if self.entries_since_restart + 1 >= restart_interval {
+            self.entries_since_restart = 0;
+            return 0;
+        }
+
+        let shared = prev_key.iter().zip(key).take_while(|(a, b)| a == b).count();
+
+        // NOTE: Keys are at most 2^16 bytes long
+        let shared = u16::try_from(shared).unwrap_or_default();
+
+        if shared == 0 {
+            self.entries_since_restart = 0;
+        } else {
+            self.entries_since_restart += 1;
+        }
+
+        shared
+    }
+
+    /// Writes the key, or only its suffix if it shares a prefix with the previous key.
+    fn write_key(&mut self, key: &[u8], shared_prefix_len: u16) -> std::io::Result<()> {
+        if shared_prefix_len > 0 {
+            self.active_writer
+                .write_u16::<BigEndian>(shared_prefix_len)?;
+        }
+
+        let suffix = key
+            .get(usize::from(shared_prefix_len)..)
+            .unwrap_or_default();
+
+        // NOTE: Truncation is okay, the key length is checked when writing
+        #[allow(clippy::cast_possible_truncation)]
+        self.active_writer
+            .write_u16::<BigEndian>(suffix.len() as u16)?;
+        self.active_writer.write_all(suffix)
+    }
+
+    fn track_key(&mut self, key: &[u8]) {
+        if self.first_key.is_none() {
+            self.first_key = Some(key.into());
+        }
+        self.last_key = Some(key.into());
+    }
+
+    /// Remembers the blob that is about to be written, if it is sampled.
+    fn sample(&mut self, key: &[u8], checksum: u64) {
+        if let Some(interval) = self.sample_interval {
+            if self.item_count % u64::from(interval) == 0 {
+                self.samples.push((self.offset, key.into(), checksum));
+            }
+        }
+    }
+
+    /// Reads back the sampled blobs of the finished segment file,
+    /// checking that they parse and match what was written.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or a blob does not match.
+    pub(crate) fn verify_samples(&self, fs: &dyn Fs) -> crate::Result<()> {
+        if self.samples.is_empty() {
+            return Ok(());
+        }
+
+        let file = BufReader::new(fs.open(&self.path)?);
+
+        // NOTE: Checksums are calculated over the stored value, so the pipeline is not reverted,
+        // and the reader only returns the suffix of delta-encoded keys
+        let mut reader =
+            Reader::<C>::from_source(self.segment_id, Box::new(file)).without_key_resolution();
+
+        for (offset, expected_key, expected_checksum) in &self.samples {
+            reader.seek_to(*offset)?;
+
+            let is_match = match reader.next() {
+                Some(Ok((key, value, checksum))) => {
+                    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+                    hasher.update(expected_key);
+                    hasher.update(&value);
+
+                    expected_key.ends_with(&key)
+                        && checksum == *expected_checksum
+                        && hasher.digest() == checksum
+                }
+                Some(Err(e)) => {
+                    log::error!(
+                        "Failed to read back blob at offset {offset} of segment #{}: {e:?}",
+                        self.segment_id,
+                    );
+                    false
+                }
+                None => false,
+            };
+
+            if !is_match {
+                log::error!(
+                    "Blob at offset {offset} of segment #{} does not match what was written",
+                    self.segment_id,
+                );
+
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "written blob does not match",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Passes a value (or chunk) through the pipeline.
+    fn encode_value(&self, value: &[u8]) -> crate::Result<Vec<u8>> {
+        self.pipeline.apply(self.segment_id, value)
+    }
+
+    /// Writes a value as a single blob, split into chunks that are compressed
+    /// & encrypted separately.
+    ///
+    /// The checksum covers the key and all (possibly compressed) chunks.
+    fn write_chunked(&mut self, key: &[u8], value: &[u8], chunk_size: u32) -> crate::Result<u32> {
+        let chunks = value
+            .chunks(chunk_size.max(1) as usize)
+            .map(|chunk| self.encode_value(chunk))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let stored_bytes = chunks.iter().map(|x| x.len() as u64).sum::<u64>();
+
+        let Ok(stored_bytes) = u32::try_from(stored_bytes) else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "chunked value is too large",
+            )));
+        };
+
+        let shared_prefix_len = self.shared_prefix_len(key);
+        self.track_key(key);
+        self.uncompressed_bytes += value.len() as u64;
+
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        hasher.update(key);
+        for chunk in &chunks {
+            hasher.update(chunk);
+        }
+        let checksum = hasher.digest();
+
+        self.sample(key, checksum);
+
+        let layout = BlobLayout {
+            chunked: true,
+            prefixed: shared_prefix_len > 0,
+        };
+        self.active_writer.write_all(layout.header())?;
+        self.active_writer.write_u64::<BigEndian>(checksum)?;
+        self.write_key(key, shared_prefix_len)?;
+
+        // NOTE: Truncation is okay, there are never more chunks than stored bytes
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.active_writer
+                .write_u32::<BigEndian>(chunks.len() as u32)?;
+
+            for chunk in &chunks {
+                self.active_writer
+                    .write_u32::<BigEndian>(chunk.len() as u32)?;
+                self.active_writer.write_all(chunk)?;
+            }
+
+            self.offset += record_len(
+                key.len(),
+                shared_prefix_len,
+                stored_bytes.into(),
+                chunks.len() as u32,
+            );
+        }
+
+        self.written_blob_bytes += u64::from(stored_bytes);
+        self.item_count += 1;
+
+        Ok(stored_bytes)
+    }
+
     pub(crate) fn flush(&mut self) -> crate::Result<()> {
         let metadata_ptr = self.active_writer.stream_position()?;
 
@@ -179,6 +510,9 @@ impl<C: Compressor + Clone> Writer<C> {
                     .clone()
                     .expect("should have written at least 1 item"),
             )),
+            key_version: self.key_version(),
+            pipeline: self.pipeline.stages(),
+            created_at: self.created_at,
         };
         metadata.encode_into(&mut self.active_writer)?;
 
@@ -189,7 +523,15 @@ impl<C: Compressor + Clone> Writer<C> {
         .encode_into(&mut self.active_writer)?;
 
         self.active_writer.flush()?;
-        self.active_writer.get_mut().sync_all()?;
+
+        failpoints::eval(failpoints::SEGMENT_FSYNC)?;
+
+        let timer = Timer::start();
+        self.active_writer.get_ref().sync_all()?;
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record(LatencyOp::Fsync, timer);
+        }
 
         Ok(())
     }
@@ -0,0 +1,274 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::DecodeError, Compressor, Config, SegmentWriter, UserValue, ValueHandle, ValueLog,
+};
+use std::path::{Path, PathBuf};
+
+/// File that stores the amount of shards, so the key partitioning
+/// cannot silently change when reopening a sharded value log
+pub const SHARDS_FILE: &str = "shards";
+
+fn shard_index_of(key: &[u8], shard_count: usize) -> usize {
+    // NOTE: Truncation is fine because the result is less than `shard_count`
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        (xxhash_rust::xxh3::xxh3_64(key) % shard_count as u64) as usize
+    }
+}
+
+/// A value log that is partitioned into multiple value logs (shards) by key hash
+///
+/// Every shard has its own segments, locks and GC, so writers and
+/// garbage collection of different shards do not contend with each other.
+///
+/// Because value handles do not contain the shard, reads need to be routed
+/// using the key the value handle was stored for.
+#[derive(Clone)]
+pub struct ShardedValueLog<C: Compressor + Clone> {
+    path: PathBuf,
+    shards: Vec<ValueLog<C>>,
+}
+
+impl<C: Compressor + Clone> ShardedValueLog<C> {
+    /// Creates or recovers a sharded value log in the given directory.
+    ///
+    /// All shards share the given config, including its blob cache.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log was
+    /// created with a different amount of shards.
+    // NOTE: Taken by value to mirror `ValueLog::open`
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn open<P: Into<PathBuf>>(
+        path: P,
+        shard_count: usize,
+        config: Config<C>,
+    ) -> crate::Result<Self> {
+        let path = path.into();
+        let fs = config.fs.clone();
+
+        if shard_count == 0 {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "shard count must be at least 1",
+            )));
+        }
+
+        fs.create_dir_all(&path)?;
+
+        let shards_file = path.join(SHARDS_FILE);
+
+        if fs.exists(&shards_file)? {
+            let bytes = fs.read(&shards_file)?;
+
+            let stored = bytes
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| crate::Error::Decode(DecodeError::InvalidHeader("Shards")))?;
+
+            if stored != shard_count as u64 {
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("value log has {stored} shards, but {shard_count} were requested"),
+                )));
+            }
+        } else {
+            fs.rewrite_atomic(&shards_file, &(shard_count as u64).to_be_bytes())?;
+        }
+
+        let shards = (0..shard_count)
+            .map(|idx| ValueLog::open(path.join(idx.to_string()), config.clone()))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self { path, shards })
+    }
+
+    /// Returns the base folder of the sharded value log.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the amount of shards.
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the index of the shard the key belongs to.
+    #[must_use]
+    pub fn shard_index(&self, key: &[u8]) -> usize {
+        shard_index_of(key, self.shard_count())
+    }
+
+    /// Returns the shard the key belongs to.
+    #[must_use]
+    pub fn shard(&self, key: &[u8]) -> &ValueLog<C> {
+        // NOTE: shard_index is always in bounds
+        #[allow(clippy::indexing_slicing)]
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// Returns all shards.
+    #[must_use]
+    pub fn shards(&self) -> &[ValueLog<C>] {
+        &self.shards
+    }
+
+    /// Resolves the value handle of the given key.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get(&self, key: &[u8], vhandle: &ValueHandle) -> crate::Result<Option<UserValue>> {
+        self.shard(key).get(vhandle)
+    }
+
+    /// Initializes a new writer, which writes into every shard.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_writer(&self) -> crate::Result<ShardedWriter<C>> {
+        let writers = self
+            .shards
+            .iter()
+            .map(ValueLog::get_writer)
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(ShardedWriter { writers })
+    }
+
+    /// Registers a [`ShardedWriter`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn register_writer(&self, writer: ShardedWriter<C>) -> crate::Result<()> {
+        for (shard, writer) in self.shards.iter().zip(writer.writers) {
+            shard.register_writer(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the amount of segments over all shards.
+    #[must_use]
+    pub fn segment_count(&self) -> usize {
+        self.shards.iter().map(ValueLog::segment_count).sum()
+    }
+
+    /// Returns the amount of bytes on disk over all shards.
+    #[must_use]
+    pub fn disk_space_used(&self) -> u64 {
+        self.shards
+            .iter()
+            .map(|x| x.manifest.disk_space_used())
+            .sum()
+    }
+
+    /// Returns the amount of uncompressed bytes over all shards.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.shards.iter().map(|x| x.manifest.total_bytes()).sum()
+    }
+
+    /// Returns the amount of stale (uncompressed) bytes over all shards.
+    #[must_use]
+    pub fn stale_bytes(&self) -> u64 {
+        self.shards.iter().map(|x| x.manifest.stale_bytes()).sum()
+    }
+
+    /// Returns the approximate space amplification over all shards.
+    ///
+    /// Returns 0.0 if there are no items or the entire value log is stale.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn space_amp(&self) -> f32 {
+        let total_bytes = self.total_bytes();
+        if total_bytes == 0 {
+            return 0.0;
+        }
+
+        let alive_bytes = total_bytes - self.stale_bytes();
+        if alive_bytes == 0 {
+            return 0.0;
+        }
+
+        total_bytes as f32 / alive_bytes as f32
+    }
+
+    /// Returns the indexes of shards that contain stale data,
+    /// ordered by their amount of stale bytes (most first).
+    ///
+    /// Shards can be garbage collected independently, so this can be used
+    /// to schedule GC runs of the shards that benefit the most first.
+    #[must_use]
+    pub fn gc_schedule(&self) -> Vec<usize> {
+        let mut shards = self
+            .shards
+            .iter()
+            .map(|x| x.manifest.stale_bytes())
+            .enumerate()
+            .filter(|(_, stale_bytes)| *stale_bytes > 0)
+            .collect::<Vec<_>>();
+
+        shards.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        shards.into_iter().map(|(idx, _)| idx).collect()
+    }
+
+    /// Drops stale segments of all shards.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn drop_stale_segments(&self) -> crate::Result<u64> {
+        self.shards.iter().map(ValueLog::drop_stale_segments).sum()
+    }
+}
+
+/// Segment writer of a [`ShardedValueLog`], routing every item to its shard
+#[allow(clippy::module_name_repetitions)]
+pub struct ShardedWriter<C: Compressor + Clone> {
+    writers: Vec<SegmentWriter<C>>,
+}
+
+impl<C: Compressor + Clone> ShardedWriter<C> {
+    fn writer(&self, key: &[u8]) -> &SegmentWriter<C> {
+        // NOTE: shard index is always in bounds
+        #[allow(clippy::indexing_slicing)]
+        &self.writers[shard_index_of(key, self.writers.len())]
+    }
+
+    /// Returns the [`ValueHandle`] for the next written blob of the given key.
+    ///
+    /// This can be used to index an item into an external `Index`.
+    #[must_use]
+    pub fn get_next_value_handle(&self, key: &[u8]) -> ValueHandle {
+        self.writer(key).get_next_value_handle()
+    }
+
+    /// Writes an item into its shard.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn write<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> crate::Result<u32> {
+        let key = key.as_ref();
+        let idx = shard_index_of(key, self.writers.len());
+
+        // NOTE: shard index is always in bounds
+        #[allow(clippy::indexing_slicing)]
+        self.writers[idx].write(key, value)
+    }
+}
@@ -0,0 +1,616 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Deterministic simulation harness with fault injection.
+//!
+//! [`SimFs`] is a [`Fs`] implementation that stores data in a real directory,
+//! but keeps track of which bytes have been made durable (fsynced). It can
+//! simulate crashes after a given amount of bytes have been written, torn writes
+//! and failing fsyncs. After a crash, [`SimFs::restart`] simulates a power loss
+//! by cutting off unsynced data at a (seeded) random point.
+//!
+//! ```
+//! # use value_log::{sim::{self, SimFs}, Config, MockIndex, ValueLog};
+//! # use std::sync::Arc;
+//! #
+//! # #[derive(Clone, Default)]
+//! # struct MyCompressor;
+//! #
+//! # impl value_log::Compressor for MyCompressor {
+//! #    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! #
+//! #    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! # }
+//! # fn main() -> value_log::Result<()> {
+//! # let folder = tempfile::tempdir()?;
+//! # let path = folder.path();
+//! let fs = Arc::new(SimFs::new(/* seed */ 42));
+//! let config = || Config::<MyCompressor>::default().fs(fs.clone());
+//!
+//! let value_log = ValueLog::open(path, config())?;
+//! fs.crash_after_bytes(100);
+//!
+//! let mut writer = value_log.get_writer()?;
+//! writer.write(b"a", b"a".repeat(1_000))?;
+//! assert!(value_log.register_writer(writer).is_err());
+//! drop(value_log);
+//!
+//! let value_log = sim::reopen(&fs, path, config())?;
+//! sim::verify_invariants(&value_log, &MockIndex::default())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Crash states can also be produced without [`SimFs`]: a [`DirSnapshot`] copies
+//! a value log folder at any point (e.g. from inside an index writer), and
+//! [`truncate_file`] cuts off files to simulate torn writes. After reopening,
+//! [`verify_recovery`] checks the recovery invariants.
+
+use crate::{
+    fs::{Fs, FsFile, StdFs},
+    manifest::SEGMENTS_FOLDER,
+    Compressor, Config, MockIndex, ValueLog,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+fn crash_error() -> std::io::Error {
+    std::io::Error::other("simulated crash")
+}
+
+fn fsync_error() -> std::io::Error {
+    std::io::Error::other("simulated fsync failure")
+}
+
+#[derive(Default)]
+struct FileState {
+    /// Logical file length
+    len: u64,
+
+    /// Length of the prefix that is durable
+    synced_len: u64,
+}
+
+struct SimState {
+    rng: u64,
+
+    crashed: bool,
+
+    bytes_written: u64,
+    crash_at: Option<u64>,
+
+    failing_fsyncs: usize,
+
+    files: HashMap<PathBuf, FileState>,
+}
+
+impl SimState {
+    /// xorshift64
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    fn check_crashed(&self) -> std::io::Result<()> {
+        if self.crashed {
+            Err(crash_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns how many bytes may be written before hitting the crash point.
+    fn remaining_bytes(&self) -> u64 {
+        self.crash_at
+            .map_or(u64::MAX, |at| at.saturating_sub(self.bytes_written))
+    }
+
+    fn take_fsync_failure(&mut self) -> bool {
+        if self.failing_fsyncs > 0 {
+            self.failing_fsyncs -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Simulated file system with fault injection
+///
+/// See the [module documentation](self) for more.
+pub struct SimFs(Arc<Mutex<SimState>>);
+
+impl SimFs {
+    /// Creates a new simulated file system.
+    ///
+    /// The seed determines where unsynced data is torn off when restarting after a crash.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self(Arc::new(Mutex::new(SimState {
+            // NOTE: xorshift needs a non-zero state
+            rng: seed.max(1),
+            crashed: false,
+            bytes_written: 0,
+            crash_at: None,
+            failing_fsyncs: 0,
+            files: HashMap::default(),
+        })))
+    }
+
+    #[allow(clippy::expect_used)]
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.0.lock().expect("lock is poisoned")
+    }
+
+    /// Simulates a crash after `n` more bytes have been written.
+    ///
+    /// The write that crosses the crash point is torn.
+    pub fn crash_after_bytes(&self, n: u64) {
+        let mut state = self.state();
+        state.crash_at = Some(state.bytes_written + n);
+    }
+
+    /// Makes the next `n` fsyncs fail.
+    pub fn fail_next_fsyncs(&self, n: usize) {
+        self.state().failing_fsyncs = n;
+    }
+
+    /// Simulates a crash right now.
+    ///
+    /// All following operations fail until [`SimFs::restart`] is called.
+    pub fn crash(&self) {
+        self.state().crashed = true;
+    }
+
+    /// Returns `true` if the simulated process has crashed.
+    #[must_use]
+    pub fn is_crashed(&self) -> bool {
+        self.state().crashed
+    }
+
+    /// Returns the amount of bytes written through the file system.
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.state().bytes_written
+    }
+
+    /// Simulates a power loss & restart.
+    ///
+    /// Unsynced data of every file is cut off at a random point, and all injected faults are reset.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    #[allow(clippy::significant_drop_tightening)]
+    pub fn restart(&self) -> std::io::Result<()> {
+        let mut state = self.state();
+
+        let mut paths = state.files.keys().cloned().collect::<Vec<_>>();
+
+        // NOTE: Sort to make truncation deterministic
+        paths.sort();
+
+        for path in paths {
+            let rnd = state.next_random();
+
+            let Some(file) = state.files.get_mut(&path) else {
+                continue;
+            };
+
+            if file.len > file.synced_len {
+                let unsynced = file.len - file.synced_len;
+                let keep = file.synced_len + rnd % (unsynced + 1);
+
+                log::debug!(
+                    "Simulated power loss: truncating {} from {} to {keep} bytes",
+                    path.display(),
+                    file.len,
+                );
+
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(&path)?
+                    .set_len(keep)?;
+
+                file.len = keep;
+                file.synced_len = keep;
+            }
+        }
+
+        state.crashed = false;
+        state.crash_at = None;
+        state.failing_fsyncs = 0;
+
+        Ok(())
+    }
+}
+
+struct SimFile {
+    path: PathBuf,
+    inner: File,
+    state: Arc<Mutex<SimState>>,
+}
+
+impl SimFile {
+    #[allow(clippy::expect_used)]
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().expect("lock is poisoned")
+    }
+}
+
+impl Read for SimFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.state().check_crashed()?;
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for SimFile {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.state().check_crashed()?;
+        self.inner.seek(pos)
+    }
+}
+
+impl Write for SimFile {
+    #[allow(clippy::significant_drop_tightening)]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let state = self.state.clone();
+
+        #[allow(clippy::expect_used)]
+        let mut state = state.lock().expect("lock is poisoned");
+        state.check_crashed()?;
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = usize::try_from(state.remaining_bytes())
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+
+        if n == 0 {
+            log::debug!("Simulated crash while writing {}", self.path.display());
+            state.crashed = true;
+            return Err(crash_error());
+        }
+
+        #[allow(clippy::indexing_slicing)]
+        let n = self.inner.write(&buf[..n])?;
+        state.bytes_written += n as u64;
+
+        let pos = self.inner.stream_position()?;
+        let file = state.files.entry(self.path.clone()).or_default();
+        file.len = file.len.max(pos);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.state().check_crashed()?;
+        self.inner.flush()
+    }
+}
+
+#[allow(clippy::significant_drop_tightening)]
+impl FsFile for SimFile {
+    fn sync_all(&self) -> std::io::Result<()> {
+        let mut state = self.state();
+        state.check_crashed()?;
+
+        if state.take_fsync_failure() {
+            return Err(fsync_error());
+        }
+
+        // NOTE: No need to actually fsync, durability is simulated
+        if let Some(file) = state.files.get_mut(&self.path) {
+            file.synced_len = file.len;
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::significant_drop_tightening)]
+impl Fs for SimFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        let mut state = self.state();
+        state.check_crashed()?;
+
+        let inner = File::create(path)?;
+        state.files.insert(path.into(), FileState::default());
+
+        Ok(Box::new(SimFile {
+            path: path.into(),
+            inner,
+            state: self.0.clone(),
+        }))
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        self.state().check_crashed()?;
+
+        Ok(Box::new(SimFile {
+            path: path.into(),
+            inner: File::open(path)?,
+            state: self.0.clone(),
+        }))
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        self.state().check_crashed()?;
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        let mut state = self.state();
+        state.check_crashed()?;
+
+        StdFs.remove_file(path)?;
+        state.files.remove(path);
+
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        self.state().check_crashed()?;
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        self.state().check_crashed()?;
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        self.state().check_crashed()?;
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        let mut state = self.state();
+        state.check_crashed()?;
+
+        // NOTE: The rewrite is atomic, so crashing in the middle of it leaves the old content
+        if (content.len() as u64) > state.remaining_bytes() {
+            log::debug!("Simulated crash while rewriting {}", path.display());
+            state.crashed = true;
+            return Err(crash_error());
+        }
+
+        if state.take_fsync_failure() {
+            return Err(fsync_error());
+        }
+
+        StdFs.rewrite_atomic(path, content)?;
+        state.bytes_written += content.len() as u64;
+        state.files.remove(path);
+
+        Ok(())
+    }
+
+    fn sync_directory(&self, _path: &Path) -> std::io::Result<()> {
+        let mut state = self.state();
+        state.check_crashed()?;
+
+        if state.take_fsync_failure() {
+            return Err(fsync_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Restarts the simulated file system and re-opens the value log.
+///
+/// The given config is used with the simulated file system.
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+pub fn reopen<C: Compressor + Clone, P: Into<PathBuf>>(
+    fs: &Arc<SimFs>,
+    path: P,
+    config: Config<C>,
+) -> crate::Result<ValueLog<C>> {
+    fs.restart()?;
+    ValueLog::open(path, config.fs(fs.clone()))
+}
+
+/// Checks that the value log is consistent with the given index:
+///
+/// - the index invariants hold (see [`MockIndex::check_invariants`])
+/// - every blob checksum matches
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+///
+/// # Panics
+///
+/// Panics if an invariant is violated.
+pub fn verify_invariants<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    index: &MockIndex,
+) -> crate::Result<()> {
+    index.check_invariants(value_log)?;
+
+    let corrupted = value_log.verify()?;
+    assert_eq!(
+        0, corrupted,
+        "value log contains {corrupted} corrupted blobs"
+    );
+
+    Ok(())
+}
+
+/// Checks the invariants that need to hold right after a value log was recovered:
+///
+/// - the invariants checked by [`verify_invariants`]
+/// - every segment file in the segments folder is registered, so unfinished segments were cleaned up
+/// - every registered segment has a segment file (unless a segment source is configured)
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+///
+/// # Panics
+///
+/// Panics if an invariant is violated.
+pub fn verify_recovery<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    index: &MockIndex,
+) -> crate::Result<()> {
+    verify_invariants(value_log, index)?;
+
+    let fs = &value_log.config.fs;
+    let segments_folder = value_log.path.join(SEGMENTS_FOLDER);
+    let registered = value_log.manifest.list_segment_ids();
+
+    for path in fs.list_files(&segments_folder)? {
+        let Some(file_name) = path.file_name().and_then(|x| x.to_str()) else {
+            continue;
+        };
+
+        // NOTE: Skip .DS_Store files when using MacOS
+        if file_name == ".DS_Store" {
+            continue;
+        }
+
+        let is_registered = file_name
+            .parse::<u64>()
+            .is_ok_and(|id| registered.contains(&id));
+
+        assert!(
+            is_registered,
+            "unregistered segment file {} was not cleaned up",
+            path.display()
+        );
+    }
+
+    if value_log.config.segment_source.is_none() {
+        for id in registered {
+            assert!(
+                fs.exists(&segments_folder.join(id.to_string()))?,
+                "segment file of registered segment #{id} is missing",
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Truncates a file to `len` bytes, simulating a torn write.
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs.
+pub fn truncate_file<P: AsRef<Path>>(path: P, len: u64) -> std::io::Result<()> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .set_len(len)
+}
+
+/// In-memory copy of all files of a folder (usually a value log folder)
+///
+/// Capturing a snapshot at some point and restoring it later reproduces the on-disk
+/// state of that point, as if the process had crashed there (with all written data being durable).
+/// Files can be truncated in the snapshot to simulate torn writes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DirSnapshot {
+    /// File contents, by path relative to the folder
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl DirSnapshot {
+    /// Copies all files of the folder, including subfolders.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn capture<P: AsRef<Path>>(folder: P) -> std::io::Result<Self> {
+        let folder = folder.as_ref();
+        let mut files = BTreeMap::new();
+        let mut folders = vec![folder.to_path_buf()];
+
+        while let Some(current) = folders.pop() {
+            for entry in std::fs::read_dir(&current)? {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    folders.push(path);
+                    continue;
+                }
+
+                let relative = path
+                    .strip_prefix(folder)
+                    .map_err(std::io::Error::other)?
+                    .to_path_buf();
+
+                files.insert(relative, std::fs::read(&path)?);
+            }
+        }
+
+        Ok(Self { files })
+    }
+
+    /// Replaces the contents of the folder with the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn restore<P: AsRef<Path>>(&self, folder: P) -> std::io::Result<()> {
+        let folder = folder.as_ref();
+
+        if folder.try_exists()? {
+            std::fs::remove_dir_all(folder)?;
+        }
+        std::fs::create_dir_all(folder)?;
+
+        for (relative, content) in &self.files {
+            let path = folder.join(relative);
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(path, content)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the paths (relative to the folder) and lengths of the captured files.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, u64)> + '_ {
+        self.files
+            .iter()
+            .map(|(path, content)| (path.as_path(), content.len() as u64))
+    }
+
+    /// Truncates a captured file to `len` bytes, simulating a torn write.
+    ///
+    /// Returns `false` if the file is not part of the snapshot.
+    pub fn truncate_file<P: AsRef<Path>>(&mut self, path: P, len: u64) -> bool {
+        let Some(content) = self.files.get_mut(path.as_ref()) else {
+            return false;
+        };
+
+        content.truncate(usize::try_from(len).unwrap_or(usize::MAX));
+        true
+    }
+}
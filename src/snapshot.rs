@@ -0,0 +1,79 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Serialized value log metadata, used by [`crate::ValueLog::metadata_snapshot`]:
+//!
+//! ```text
+//! [magic; 8]
+//! [segment count; u64]
+//! ([segment id; u64] [metadata] [stale items; u64] [stale bytes; u64])*
+//! ```
+
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    id::SegmentId,
+    segment::meta::Metadata,
+    Compressor, Segment,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+pub const SNAPSHOT_MAGIC: &[u8] = &[b'V', b'L', b'O', b'G', b'S', b'N', b'P', 1];
+
+/// Snapshotted state of a single segment
+pub struct SegmentSnapshot {
+    pub id: SegmentId,
+    pub meta: Metadata,
+    pub stale_items: u64,
+    pub stale_bytes: u64,
+}
+
+/// Encodes the manifest and per-segment stats of the given segments.
+pub fn encode_snapshot<C: Compressor + Clone, W: Write>(
+    writer: &mut W,
+    segments: &[&Segment<C>],
+) -> Result<(), EncodeError> {
+    writer.write_all(SNAPSHOT_MAGIC)?;
+
+    writer.write_u64::<BigEndian>(segments.len() as u64)?;
+
+    for segment in segments {
+        writer.write_u64::<BigEndian>(segment.id)?;
+        segment.meta.encode_into(writer)?;
+        writer.write_u64::<BigEndian>(segment.gc_stats.stale_items())?;
+        writer.write_u64::<BigEndian>(segment.gc_stats.stale_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Decodes a snapshot created by [`encode_snapshot`].
+pub fn decode_snapshot<R: Read>(reader: &mut R) -> Result<Vec<SegmentSnapshot>, DecodeError> {
+    let mut magic = [0u8; SNAPSHOT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic != SNAPSHOT_MAGIC {
+        return Err(DecodeError::InvalidHeader("MetadataSnapshot"));
+    }
+
+    let cnt = reader.read_u64::<BigEndian>()?;
+
+    let mut segments = vec![];
+
+    for _ in 0..cnt {
+        let id = reader.read_u64::<BigEndian>()?;
+        let meta = Metadata::decode_from(reader)?;
+        let stale_items = reader.read_u64::<BigEndian>()?;
+        let stale_bytes = reader.read_u64::<BigEndian>()?;
+
+        segments.push(SegmentSnapshot {
+            id,
+            meta,
+            stale_items,
+            stale_bytes,
+        });
+    }
+
+    Ok(segments)
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::id::SegmentId;
+use std::{
+    io::{Read, Seek, SeekFrom},
+    sync::Arc,
+};
+
+/// Size of the ranges that are requested from a [`SegmentSource`] at once
+const FETCH_SIZE: u64 = /* 64 KiB */ 64 * 1_024;
+
+/// Trait that allows fetching segment data from a remote location
+///
+/// When a segment is registered in the value log, but its file is missing
+/// locally, reads fall back to the configured segment source, e.g. to
+/// restore data from a backup (HTTP range requests, peer replicas, ...).
+pub trait SegmentSource: Send + Sync {
+    /// Reads up to `len` bytes of the given segment, starting at `offset`.
+    ///
+    /// Returning less than `len` bytes signals the end of the segment file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn read_range(&self, segment_id: SegmentId, offset: u64, len: u64) -> std::io::Result<Vec<u8>>;
+}
+
+/// Buffered reader over a [`SegmentSource`]
+pub struct RangeReader {
+    source: Arc<dyn SegmentSource>,
+    segment_id: SegmentId,
+
+    /// Current read position
+    pos: u64,
+
+    /// File offset of the buffered range
+    buf_offset: u64,
+    buf: Vec<u8>,
+}
+
+impl RangeReader {
+    pub fn new(source: Arc<dyn SegmentSource>, segment_id: SegmentId) -> Self {
+        Self {
+            source,
+            segment_id,
+            pos: 0,
+            buf_offset: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    fn buffered(&self) -> &[u8] {
+        let buf_end = self.buf_offset + self.buf.len() as u64;
+
+        if self.pos < self.buf_offset || self.pos >= buf_end {
+            return &[];
+        }
+
+        // NOTE: Truncation is fine, the buffer is at most FETCH_SIZE large
+        #[allow(clippy::cast_possible_truncation)]
+        let start = (self.pos - self.buf_offset) as usize;
+
+        self.buf.get(start..).unwrap_or_default()
+    }
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        if self.buffered().is_empty() {
+            self.buf = self
+                .source
+                .read_range(self.segment_id, self.pos, FETCH_SIZE)?;
+            self.buf_offset = self.pos;
+        }
+
+        let buffered = self.buffered();
+        let n = buffered.len().min(out.len());
+
+        #[allow(clippy::indexing_slicing)]
+        out[..n].copy_from_slice(&buffered[..n]);
+
+        self.pos += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl Seek for RangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.pos = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek")
+            })?,
+            SeekFrom::End(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "segment source does not support seeking from end",
+                ))
+            }
+        };
+
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{id::SegmentId, Compressor, Segment};
+
+/// Point-in-time statistics of a segment
+///
+/// The [`Display`](std::fmt::Display) output is a single line of `key=value` pairs,
+/// so it can be parsed by operational tooling.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct SegmentSummary {
+    /// Segment ID
+    pub id: SegmentId,
+
+    /// Number of blobs in the segment
+    pub item_count: u64,
+
+    /// Size of the blobs on disk
+    pub compressed_bytes: u64,
+
+    /// Uncompressed size of the blobs
+    pub total_bytes: u64,
+
+    /// Number of blobs that are not referenced anymore
+    pub stale_items: u64,
+
+    /// Uncompressed size of the blobs that are not referenced anymore
+    pub stale_bytes: u64,
+
+    /// Version of the key the segment is encrypted with, if it is encrypted
+    pub key_version: Option<u32>,
+
+    /// Bytes of the segment file that were deallocated by punching holes
+    pub punched_bytes: u64,
+}
+
+impl<C: Compressor + Clone> From<&Segment<C>> for SegmentSummary {
+    fn from(segment: &Segment<C>) -> Self {
+        Self {
+            id: segment.id,
+            item_count: segment.meta.item_count,
+            compressed_bytes: segment.meta.compressed_bytes,
+            total_bytes: segment.meta.total_uncompressed_bytes,
+            stale_items: segment.gc_stats.stale_items(),
+            stale_bytes: segment.gc_stats.stale_bytes(),
+            key_version: segment.meta.key_version,
+            punched_bytes: segment.gc_stats.punched_bytes(),
+        }
+    }
+}
+
+impl std::fmt::Display for SegmentSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "segment id={} items={} compressed_bytes={} total_bytes={} stale_items={} stale_bytes={}",
+            self.id,
+            self.item_count,
+            self.compressed_bytes,
+            self.total_bytes,
+            self.stale_items,
+            self.stale_bytes,
+        )?;
+
+        if let Some(key_version) = self.key_version {
+            write!(f, " key_version={key_version}")?;
+        }
+
+        if self.punched_bytes > 0 {
+            write!(f, " punched_bytes={}", self.punched_bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Point-in-time statistics of a value log's segments
+///
+/// The [`Display`](std::fmt::Display) output consists of a line of `key=value` pairs
+/// for the whole value log, followed by one line per segment (ordered by segment ID),
+/// so it can be parsed by operational tooling.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct ManifestSummary {
+    /// Number of blobs
+    pub item_count: u64,
+
+    /// Size of the blobs on disk
+    pub compressed_bytes: u64,
+
+    /// Uncompressed size of the blobs
+    pub total_bytes: u64,
+
+    /// Number of blobs that are not referenced anymore
+    pub stale_items: u64,
+
+    /// Uncompressed size of the blobs that are not referenced anymore
+    pub stale_bytes: u64,
+
+    /// Segments, ordered by segment ID
+    pub segments: Vec<SegmentSummary>,
+}
+
+impl ManifestSummary {
+    pub(crate) fn new(mut segments: Vec<SegmentSummary>) -> Self {
+        segments.sort_by_key(|x| x.id);
+
+        Self {
+            item_count: segments.iter().map(|x| x.item_count).sum(),
+            compressed_bytes: segments.iter().map(|x| x.compressed_bytes).sum(),
+            total_bytes: segments.iter().map(|x| x.total_bytes).sum(),
+            stale_items: segments.iter().map(|x| x.stale_items).sum(),
+            stale_bytes: segments.iter().map(|x| x.stale_bytes).sum(),
+            segments,
+        }
+    }
+
+    /// Returns the number of segments that only contain stale blobs.
+    #[must_use]
+    pub fn stale_segment_count(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|x| x.stale_items == x.item_count)
+            .count()
+    }
+}
+
+impl std::fmt::Display for ManifestSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "vlog segments={} stale_segments={} items={} compressed_bytes={} total_bytes={} stale_items={} stale_bytes={}",
+            self.segments.len(),
+            self.stale_segment_count(),
+            self.item_count,
+            self.compressed_bytes,
+            self.total_bytes,
+            self.stale_items,
+            self.stale_bytes,
+        )?;
+
+        for segment in &self.segments {
+            writeln!(f, "{segment}")?;
+        }
+
+        Ok(())
+    }
+}
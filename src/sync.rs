@@ -0,0 +1,47 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Synchronization primitives that guard the value log's shared state
+//!
+//! When compiled with `--cfg loom`, these are swapped for `loom`'s
+//! instrumented types, so races between registering, dropping
+//! and reading segments can be model-checked.
+
+#[cfg(not(loom))]
+pub use {
+    arc_swap::ArcSwap,
+    std::sync::{atomic::AtomicU64, Mutex, MutexGuard},
+};
+
+#[cfg(loom)]
+pub use {
+    self::loom_arc_swap::ArcSwap,
+    loom::sync::{atomic::AtomicU64, Mutex, MutexGuard},
+};
+
+#[cfg(loom)]
+mod loom_arc_swap {
+    use loom::sync::RwLock;
+    use std::sync::{Arc, PoisonError};
+
+    /// Stand-in for [`arc_swap::ArcSwap`] that `loom` can instrument
+    pub struct ArcSwap<T>(RwLock<Arc<T>>);
+
+    impl<T> ArcSwap<T> {
+        pub fn from_pointee(value: T) -> Self {
+            Self(RwLock::new(Arc::new(value)))
+        }
+
+        pub fn load_full(&self) -> Arc<T> {
+            self.0
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        }
+
+        pub fn store(&self, value: Arc<T>) {
+            *self.0.write().unwrap_or_else(PoisonError::into_inner) = value;
+        }
+    }
+}
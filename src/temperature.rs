@@ -0,0 +1,159 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::sync::AtomicU64;
+use std::{sync::atomic::Ordering, time::Duration};
+
+/// Classification of a segment by how often it is read
+/// (see [`ValueLog::temperature`](crate::ValueLog::temperature))
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Temperature {
+    /// The segment is (almost) never read
+    Cold,
+
+    /// The segment is read occasionally
+    Warm,
+
+    /// The segment is read frequently
+    Hot,
+}
+
+impl Temperature {
+    /// Classifies a read rate (reads per second).
+    pub(crate) fn from_read_rate(read_rate: f64, cold_below: f64, hot_above: f64) -> Self {
+        if read_rate > hot_above {
+            Self::Hot
+        } else if read_rate < cold_below {
+            Self::Cold
+        } else {
+            Self::Warm
+        }
+    }
+}
+
+impl std::fmt::Display for Temperature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Cold => "cold",
+                Self::Warm => "warm",
+                Self::Hot => "hot",
+            }
+        )
+    }
+}
+
+// NOTE: Precision is not important here
+#[allow(clippy::cast_precision_loss)]
+fn decay(score: f64, elapsed_millis: u64, half_life: Duration) -> f64 {
+    let half_life_millis = half_life.as_millis().max(1) as f64;
+    score * 0.5_f64.powf(elapsed_millis as f64 / half_life_millis)
+}
+
+/// Read counters of a segment (see [`ValueLog::read_counters`](crate::ValueLog::read_counters))
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ReadCounters {
+    /// Amount of reads of the segment that were not served by the blob cache
+    pub reads: u64,
+
+    /// Time of the last read, in milliseconds since the Unix epoch
+    pub last_read_at: Option<u64>,
+
+    /// Decayed read count, as of the last read
+    pub(crate) score: f64,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ReadCounters {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        // NOTE: A last read time of 0 is persisted as "never read"
+        let last_read_at: u64 = u.arbitrary()?;
+
+        Ok(Self {
+            reads: u.arbitrary()?,
+            last_read_at: (last_read_at > 0).then_some(last_read_at),
+            score: u.arbitrary()?,
+        })
+    }
+}
+
+/// Exponentially-decayed read counter
+///
+/// Every read adds 1 to the score, which halves every half-life.
+/// Updates are lock-free, and may lose some precision when racing.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ReadStats {
+    /// Decayed read count (as [`f64`] bits), as of `updated_at`
+    score: AtomicU64,
+
+    /// Time the score was last updated at, in milliseconds since the Unix epoch
+    updated_at: AtomicU64,
+
+    /// Total amount of reads
+    reads: AtomicU64,
+}
+
+impl std::fmt::Debug for ReadStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadStats")
+            .field("score", &f64::from_bits(self.score.load(Ordering::Acquire)))
+            .field("updated_at", &self.updated_at.load(Ordering::Acquire))
+            .field("reads", &self.reads.load(Ordering::Acquire))
+            .finish()
+    }
+}
+
+impl From<ReadCounters> for ReadStats {
+    fn from(counters: ReadCounters) -> Self {
+        Self {
+            score: AtomicU64::new(counters.score.to_bits()),
+            updated_at: AtomicU64::new(counters.last_read_at.unwrap_or_default()),
+            reads: AtomicU64::new(counters.reads),
+        }
+    }
+}
+
+impl ReadStats {
+    /// Records a read that happened at `now` (in milliseconds since the Unix epoch).
+    pub fn record(&self, half_life: Duration, now: u64) {
+        self.reads.fetch_add(1, Ordering::AcqRel);
+
+        let elapsed = now.saturating_sub(self.updated_at.swap(now, Ordering::AcqRel));
+
+        // NOTE: The closure always returns Some, so this cannot fail
+        let _ = self
+            .score
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+                Some((decay(f64::from_bits(bits), elapsed, half_life) + 1.0).to_bits())
+            });
+    }
+
+    /// Returns the current counters.
+    pub fn counters(&self) -> ReadCounters {
+        let updated_at = self.updated_at.load(Ordering::Acquire);
+
+        ReadCounters {
+            reads: self.reads.load(Ordering::Acquire),
+            last_read_at: (updated_at > 0).then_some(updated_at),
+            score: f64::from_bits(self.score.load(Ordering::Acquire)),
+        }
+    }
+
+    /// Returns the decayed read rate at `now` (in milliseconds since the Unix epoch),
+    /// in reads per second.
+    pub fn read_rate(&self, half_life: Duration, now: u64) -> f64 {
+        let score = f64::from_bits(self.score.load(Ordering::Acquire));
+        let updated_at = self.updated_at.load(Ordering::Acquire);
+        let elapsed = now.saturating_sub(updated_at);
+
+        // NOTE: A steady rate r converges to a score of r * half_life / ln(2)
+        decay(score, elapsed, half_life) * std::f64::consts::LN_2
+            / half_life.as_secs_f64().max(0.001)
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Returns the current time in milliseconds since the Unix epoch.
+pub fn unix_timestamp_millis() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    // NOTE: Milliseconds since the epoch fit into a u64 for a very long time
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        elapsed.as_millis() as u64
+    }
+}
+
+/// Returns `true` if the given timestamp (in milliseconds since the Unix epoch)
+/// is at least `max_age` older than `now_millis`.
+pub fn is_older_than(timestamp_millis: u64, max_age: Duration, now_millis: u64) -> bool {
+    let age = now_millis.saturating_sub(timestamp_millis);
+    u128::from(age) >= max_age.as_millis()
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{fs::Fs, id::SegmentId};
+use std::path::{Path, PathBuf};
+
+/// Folder (inside the value log folder) dropped segments are kept in,
+/// see [`Config::trash_window`](crate::Config::trash_window)
+pub const TRASH_FOLDER: &str = "trash";
+
+/// Segment file that was moved to the trash folder
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrashedSegment {
+    /// ID of the dropped segment
+    pub segment_id: SegmentId,
+
+    /// Time the segment was dropped at, in milliseconds since the Unix epoch
+    pub dropped_at: u64,
+
+    /// Path of the segment file in the trash folder
+    pub path: PathBuf,
+}
+
+/// Moves a segment file to the trash folder.
+///
+/// The file name records the segment ID and the time it was dropped at,
+/// so a segment ID can be in the trash multiple times.
+pub fn move_to_trash(
+    fs: &dyn Fs,
+    folder: &Path,
+    segment_id: SegmentId,
+    segment_path: &Path,
+    dropped_at: u64,
+) -> crate::Result<()> {
+    fs.create_dir_all(folder)?;
+
+    let path = folder.join(format!("{segment_id}.{dropped_at}"));
+
+    // NOTE: The trash file may exist if the segment was dropped before, but the drop crashed
+    if fs.exists(&path)? {
+        fs.remove_file(&path)?;
+    }
+
+    fs.hard_link(segment_path, &path)?;
+    fs.sync_directory(folder)?;
+    fs.remove_file(segment_path)?;
+
+    log::trace!("Moved segment #{segment_id} to the trash");
+
+    Ok(())
+}
+
+/// Lists the segment files in the trash folder, oldest drop first.
+pub fn list_trash(fs: &dyn Fs, folder: &Path) -> crate::Result<Vec<TrashedSegment>> {
+    if !fs.exists(folder)? {
+        return Ok(vec![]);
+    }
+
+    let mut entries = fs
+        .list_files(folder)?
+        .into_iter()
+        .filter_map(|path| {
+            let (segment_id, dropped_at) = path.file_name()?.to_str()?.split_once('.')?;
+
+            Some(TrashedSegment {
+                segment_id: segment_id.parse().ok()?,
+                dropped_at: dropped_at.parse().ok()?,
+                path,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort_by_key(|x| (x.dropped_at, x.segment_id));
+
+    Ok(entries)
+}
@@ -3,39 +3,215 @@
 // (found in the LICENSE-* files in the repository)
 
 use crate::{
+    archive::{read_archive, write_archive},
+    backup::{copy_file, BackupReport},
     blob_cache::BlobCache,
-    gc::report::GcReport,
+    context::RequestContext,
+    corruption::{CorruptionReport, CorruptionSource},
+    failpoints,
+    fs::{advise, Fs, FsFile, FsLock, IoClass},
+    gc::{
+        history::{GcHistory, GcHistoryEntry, GcOperation, GC_HISTORY_FILE},
+        report::{DropReport, GcReport, MaintenanceReport},
+    },
+    generation::{Generation, PROMOTED_TAG},
     id::{IdGenerator, SegmentId},
-    index::Writer as IndexWriter,
-    manifest::{SegmentManifest, SEGMENTS_FOLDER, VLOG_MARKER},
+    index::{LiveHandles, Writer as IndexWriter},
+    iter::{as_slice_bound, BlobIter},
+    manifest::{
+        ManifestEdit, SegmentManifest, SegmentTags, LOCK_FILE, MANIFEST_FILE, SEGMENTS_FOLDER,
+        VLOG_MARKER,
+    },
+    memory::{MemoryTracker, MemoryUsage},
+    metrics::{LatencyOp, Metrics, Timer},
+    parity::{remove_orphaned_parity_files, write_parity_file, Parity, PARITY_FOLDER},
     path::absolute_path,
-    scanner::{Scanner, SizeMap},
-    segment::merge::MergeReader,
-    value::UserValue,
+    pipeline::Pipeline,
+    progress::{Operation, ProgressTracker},
+    remap::{Remaps, REMAP_FOLDER},
+    scanner::{Scanner, SegmentCounter, SizeMap},
+    segment::{
+        file_slot::FileSlot,
+        gc_stats::GcStats,
+        merge::MergeReader,
+        meta::Metadata,
+        meta_reader::MetaReader,
+        reader::{ReadSeek, ValueBuffer},
+        trailer::SegmentFileTrailer,
+        writer::{BlobLayout, Writer as SegmentFileWriter, BLOB_HEADER_MAGIC},
+    },
+    snapshot::{decode_snapshot, encode_snapshot},
+    source::RangeReader,
+    sync::{AtomicU64, Mutex, MutexGuard},
+    temperature::{ReadCounters, ReadStats, Temperature},
+    trash::{list_trash, move_to_trash, TRASH_FOLDER},
+    value::{UserKey, UserValue},
     version::Version,
-    Compressor, Config, GcStrategy, IndexReader, SegmentReader, SegmentWriter, ValueHandle,
+    BlobCacheStats, Compressor, Config, ConfigDump, DebugDump, DiskSpaceBreakdown, ErrorCategory,
+    GcDecision, GcPolicy, GcReason, GcStrategy, InFlightOperations, IndexReader, IoContext,
+    MaintenancePause, ManifestSummary, OpenOptions, Relocation, RelocationMeta, Segment,
+    SegmentDebugInfo, SegmentReader, SegmentSummary, SegmentWriter, SpaceAmpStrategy,
+    TrashedSegment, ValueHandle,
 };
 use std::{
-    fs::File,
-    io::{BufReader, Seek},
-    path::PathBuf,
-    sync::{atomic::AtomicU64, Arc, Mutex},
+    collections::BTreeMap,
+    io::{BufReader, Read, Seek, Write},
+    marker::PhantomData,
+    ops::{Bound, Range, RangeBounds},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        mpsc::{Receiver, Sender, SyncSender},
+        Arc, OnceLock, PoisonError, Weak,
+    },
+    time::{Duration, Instant},
 };
 
+/// Writes relocated blobs, and passes their new locations to the index writer in batches
+struct Relocator<'a, C: Compressor + Clone, W: IndexWriter> {
+    writer: SegmentWriter<C>,
+    index_writer: W,
+    batch: Vec<Relocation>,
+    batch_size: usize,
+    metrics: &'a Metrics,
+
+    /// Relocations that are recorded in remap tables instead of being passed to the index,
+    /// see [`Config::remap_relocations`]
+    remapped: Option<Vec<Relocation>>,
+}
+
+/// Live blob that is relocated once all live blobs of a rollover are collected
+struct CollectedBlob {
+    partition: u64,
+    key: UserKey,
+    value: UserValue,
+    vhandle: ValueHandle,
+
+    /// Value handle the index stores, which may be remapped to `vhandle`
+    indexed: ValueHandle,
+
+    checksum: u64,
+}
+
+impl<C: Compressor + Clone, W: IndexWriter> Relocator<'_, C, W> {
+    fn relocate(
+        &mut self,
+        key: UserKey,
+        value: &[u8],
+        old_vhandle: ValueHandle,
+        indexed: ValueHandle,
+        checksum: u64,
+    ) -> crate::Result<()> {
+        failpoints::eval(failpoints::ROLLOVER_RELOCATE)?;
+
+        let vhandle = self.writer.get_next_value_handle();
+
+        // IMPORTANT: Write first, so we know the size of the stored value
+        let stored_size = self.writer.write(&key, value)?;
+
+        // IMPORTANT: The key may be overwritten by a user write while we relocate,
+        // so the index must only apply the relocation if it still points to the old blob
+        //
+        // NOTE: Remap tables are keyed by the blob's actual old location
+        let expected = if self.remapped.is_some() {
+            old_vhandle
+        } else {
+            indexed
+        };
+
+        // NOTE: Truncation is OK because we know values are u32 max
+        #[allow(clippy::cast_possible_truncation)]
+        self.batch.push(Relocation {
+            size: value.len() as u32,
+            key,
+            expected,
+            vhandle,
+            meta: RelocationMeta {
+                checksum,
+                stored_size,
+            },
+        });
+
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        if let Some(remapped) = &mut self.remapped {
+            remapped.append(&mut self.batch);
+            return Ok(());
+        }
+
+        flush_relocations(&mut self.index_writer, &mut self.batch, self.metrics)
+    }
+}
+
+/// Passes buffered relocations to the index writer, sorted by key.
+fn flush_relocations<W: IndexWriter>(
+    index_writer: &mut W,
+    batch: &mut Vec<Relocation>,
+    metrics: &Metrics,
+) -> crate::Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let timer = Timer::start();
+
+    batch.sort_by(|a, b| a.key.cmp(&b.key));
+    index_writer.relocate_batch(batch)?;
+    batch.clear();
+
+    metrics.record(LatencyOp::RolloverIndex, timer);
+
+    Ok(())
+}
+
+/// Tag of segments that were rewritten with the cold compressor
+const RECOMPRESSED_TAG: (&str, &str) = ("vlog.compression", "cold");
+
+/// Item produced by a (parallel) scan
+type ScanItem = crate::Result<(UserKey, ValueHandle, UserValue)>;
+
+/// Job of the background flush thread
+enum FlushJob<C: Compressor + Clone> {
+    /// Finish & register a writer, within the context it was submitted in
+    Register(Box<SegmentWriter<C>>, Option<RequestContext>),
+
+    /// Report back once all previously submitted writers are registered
+    Barrier(SyncSender<crate::Result<()>>),
+}
+
 /// Unique value log ID
 #[allow(clippy::module_name_repetitions)]
 pub type ValueLogId = u64;
 
 /// Hands out a unique (monotonically increasing) value log ID.
 pub fn get_next_vlog_id() -> ValueLogId {
-    static VLOG_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+    static VLOG_ID_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
     VLOG_ID_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
 /// A disk-resident value log
-#[derive(Clone)]
+///
+/// Cloning a value log is cheap, because it is reference counted internally;
+/// all clones refer to the same value log.
+///
+/// A value log is `Send + Sync` if its compressor is, so it can be
+/// shared between threads without wrapping it in an `Arc` or lock.
 pub struct ValueLog<C: Compressor + Clone>(Arc<ValueLogInner<C>>);
 
+impl<C: Compressor + Clone> Clone for ValueLog<C> {
+    fn clone(&self) -> Self {
+        self.handles
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Self(self.0.clone())
+    }
+}
+
 impl<C: Compressor + Clone> std::ops::Deref for ValueLog<C> {
     type Target = ValueLogInner<C>;
 
@@ -44,6 +220,32 @@ impl<C: Compressor + Clone> std::ops::Deref for ValueLog<C> {
     }
 }
 
+impl<C: Compressor + Clone> Drop for ValueLog<C> {
+    fn drop(&mut self) {
+        // NOTE: Best effort, if this is the last handle, wait for writers
+        // that are still queued up in the background flush thread
+        //
+        // The flush thread temporarily holds references to the value log as well,
+        // so the handles are counted separately.
+        let is_last = self
+            .handles
+            .fetch_sub(1, std::sync::atomic::Ordering::AcqRel)
+            == 1;
+
+        if is_last {
+            if !self.is_closed() {
+                if let Err(e) = self.flush_barrier() {
+                    log::warn!("Failed to flush vLog on drop: {e:?}");
+                }
+            }
+
+            // NOTE: Background threads may still hold on to the value log for a moment,
+            // but the value log can already be reopened
+            self.release_directory_lock();
+        }
+    }
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct ValueLogInner<C: Compressor + Clone> {
     /// Unique value log ID
@@ -53,11 +255,14 @@ pub struct ValueLogInner<C: Compressor + Clone> {
     pub path: PathBuf,
 
     /// Value log configuration
-    config: Config<C>,
+    pub(crate) config: Config<C>,
 
     /// In-memory blob cache
     blob_cache: Arc<BlobCache>,
 
+    /// In-memory cache of decompressed values, if enabled
+    value_cache: Option<Arc<BlobCache>>,
+
     /// Segment manifest
     #[doc(hidden)]
     pub manifest: SegmentManifest<C>,
@@ -65,278 +270,244 @@ pub struct ValueLogInner<C: Compressor + Clone> {
     /// Generator to get next segment ID
     id_generator: IdGenerator,
 
+    /// Generation of the segment list, which is increased whenever the segment
+    /// list is replaced as a whole, invalidating all outstanding writers
+    generation: AtomicU64,
+
     /// Guards the rollover (compaction) process to only
     /// allow one to happen at a time
     #[doc(hidden)]
     pub rollover_guard: Mutex<()>,
-}
-
-impl<C: Compressor + Clone> ValueLog<C> {
-    /// Creates or recovers a value log in the given directory.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn open<P: Into<PathBuf>>(
-        path: P, // TODO: move path into config?
-        config: Config<C>,
-    ) -> crate::Result<Self> {
-        let path = path.into();
 
-        if path.join(VLOG_MARKER).try_exists()? {
-            Self::recover(path, config)
-        } else {
-            Self::create_new(path, config)
-        }
-    }
+    /// Queue of the background flush thread, if started
+    flusher: OnceLock<Sender<FlushJob<C>>>,
 
-    /* /// Prints fragmentation histogram.
-    pub fn print_fragmentation_histogram(&self) {
-        let lock = self.manifest.segments.read().expect("lock is poisoned");
+    /// Set once the background maintenance thread was started
+    maintainer_started: AtomicBool,
 
-        for (id, segment) in &*lock {
-            let stale_ratio = segment.stale_ratio();
+    /// Amount of active maintenance pauses, maintenance only runs if there are none
+    maintenance_pauses: AtomicUsize,
 
-            let progress = (stale_ratio * 10.0) as usize;
-            let void = 10 - progress;
+    /// Memory used by reader & writer buffers
+    memory: MemoryTracker,
 
-            let progress = "=".repeat(progress);
-            let void = " ".repeat(void);
+    /// Past GC operations
+    gc_history: GcHistory,
 
-            println!(
-                "{id:0>4} [{progress}{void}] {}%",
-                (stale_ratio * 100.0) as usize
-            );
-        }
-    } */
+    /// New locations of relocated blobs the index was not updated for yet
+    remaps: Remaps,
 
-    #[doc(hidden)]
-    pub fn verify(&self) -> crate::Result<usize> {
-        let _lock = self.rollover_guard.lock().expect("lock is poisoned");
+    /// Latency histograms
+    metrics: Arc<Metrics>,
 
-        let mut sum = 0;
+    /// Set once the value log is closed
+    closed: AtomicBool,
 
-        for item in self.get_reader()? {
-            let (k, v, _, expected_checksum) = item?;
+    /// Amount of `ValueLog` handles referring to the value log
+    handles: AtomicUsize,
 
-            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
-            hasher.update(&k);
-            hasher.update(&v);
+    /// Bytes written by writers that are not registered yet,
+    /// which count against the disk usage quota
+    pending_bytes: AtomicU64,
 
-            if hasher.digest() != expected_checksum {
-                sum += 1;
-            }
-        }
+    /// Exclusive lock on the value log folder, so it is not opened twice,
+    /// which is released once the value log is closed
+    dir_lock: Mutex<Option<Box<dyn FsLock>>>,
+}
 
-        Ok(sum)
+impl<C: Compressor + Clone> ValueLogInner<C> {
+    /// Locks the rollover guard.
+    ///
+    /// The guard does not protect any data, so if another thread
+    /// panicked while holding it, the lock is simply recovered.
+    fn lock_rollover_raw(&self) -> MutexGuard<'_, ()> {
+        self.rollover_guard
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
     }
 
-    /// Creates a new empty value log in a directory.
-    pub(crate) fn create_new<P: Into<PathBuf>>(path: P, config: Config<C>) -> crate::Result<Self> {
-        let path = absolute_path(path.into());
-        log::trace!("Creating value-log at {}", path.display());
-
-        std::fs::create_dir_all(&path)?;
-
-        let marker_path = path.join(VLOG_MARKER);
-        assert!(!marker_path.try_exists()?);
-
-        std::fs::create_dir_all(path.join(SEGMENTS_FOLDER))?;
+    /// Locks the rollover guard, failing if the value log is closed.
+    fn lock_rollover(&self) -> crate::Result<MutexGuard<'_, ()>> {
+        let lock = self.lock_rollover_raw();
+        self.check_open()?;
+        Ok(lock)
+    }
 
-        // NOTE: Lastly, fsync .vlog marker, which contains the version
-        // -> the V-log is fully initialized
+    /// Releases the lock on the value log folder, so it can be opened again.
+    fn release_directory_lock(&self) {
+        self.dir_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
 
-        let mut file = std::fs::File::create(marker_path)?;
-        Version::V1.write_file_header(&mut file)?;
-        file.sync_all()?;
+    /// Returns [`Error::Closed`](crate::Error::Closed) if the value log is closed.
+    fn check_open(&self) -> crate::Result<()> {
+        if self.closed.load(std::sync::atomic::Ordering::Acquire) {
+            return Err(crate::Error::Closed);
+        }
+        Ok(())
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            // fsync folders on Unix
+    /// Returns `true` if the value handle points to a segment that is older than
+    /// the configured retention (see [`Config::retention`]).
+    ///
+    /// Returns [`Error::Expired`](crate::Error::Expired) instead, if configured.
+    ///
+    /// Reads do not change the segment's GC state, garbage collection
+    /// picks expired segments on its own (see [`ValueLog::expired_segments`]).
+    fn check_expired(&self, vhandle: &ValueHandle) -> crate::Result<bool> {
+        let Some(max_age) = self.config.retention else {
+            return Ok(false);
+        };
 
-            let folder = std::fs::File::open(path.join(SEGMENTS_FOLDER))?;
-            folder.sync_all()?;
+        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
+            return Ok(false);
+        };
 
-            let folder = std::fs::File::open(&path)?;
-            folder.sync_all()?;
+        if !segment.is_expired(max_age) {
+            return Ok(false);
         }
 
-        let blob_cache = config.blob_cache.clone();
-        let manifest = SegmentManifest::create_new(&path)?;
+        if self.config.expired_reads_error {
+            return Err(crate::Error::Expired(vhandle.segment_id));
+        }
 
-        Ok(Self(Arc::new(ValueLogInner {
-            id: get_next_vlog_id(),
-            config,
-            path,
-            blob_cache,
-            manifest,
-            id_generator: IdGenerator::default(),
-            rollover_guard: Mutex::new(()),
-        })))
+        Ok(true)
     }
 
-    pub(crate) fn recover<P: Into<PathBuf>>(path: P, config: Config<C>) -> crate::Result<Self> {
-        let path = path.into();
-        log::info!("Recovering vLog at {}", path.display());
-
-        {
-            let bytes = std::fs::read(path.join(VLOG_MARKER))?;
+    /// Fails if there are remapped blobs, because the index that is backed up
+    /// together with the value log may point into segments that were dropped.
+    fn check_no_remaps(&self) -> crate::Result<()> {
+        let count = self.remaps.len();
 
-            if let Some(version) = Version::parse_file_header(&bytes) {
-                if version != Version::V1 {
-                    return Err(crate::Error::InvalidVersion(Some(version)));
-                }
-            } else {
-                return Err(crate::Error::InvalidVersion(None));
-            }
+        if count > 0 {
+            log::error!("Cannot copy vLog metadata, {count} remapped blobs need to be applied to the index first");
+            return Err(crate::Error::PendingRemaps);
         }
 
-        let blob_cache = config.blob_cache.clone();
-        let manifest = SegmentManifest::recover(&path)?;
+        Ok(())
+    }
 
-        let highest_id = manifest
-            .segments
-            .read()
-            .expect("lock is poisoned")
-            .values()
-            .map(|x| x.id)
-            .max()
-            .unwrap_or_default();
+    /// Returns the current location of a blob, following remaps.
+    fn resolve_handle(&self, vhandle: &ValueHandle) -> ValueHandle {
+        self.remaps
+            .resolve(vhandle)
+            .unwrap_or_else(|| vhandle.clone())
+    }
 
-        Ok(Self(Arc::new(ValueLogInner {
-            id: get_next_vlog_id(),
-            config,
-            path,
-            blob_cache,
-            manifest,
-            id_generator: IdGenerator::new(highest_id + 1),
-            rollover_guard: Mutex::new(()),
-        })))
+    /// Returns the value handle the index stores for the given key, if it (possibly
+    /// through remaps) points to the given blob, meaning the blob is live.
+    fn indexed_handle<R: IndexReader>(
+        &self,
+        index_reader: &R,
+        key: &[u8],
+        vhandle: &ValueHandle,
+    ) -> crate::Result<Option<ValueHandle>> {
+        Ok(index_reader
+            .get(key)?
+            .filter(|indexed| self.resolve_handle(indexed) == *vhandle))
     }
 
-    /// Registers a [`SegmentWriter`].
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn register_writer(&self, writer: SegmentWriter<C>) -> crate::Result<()> {
-        let _lock = self.rollover_guard.lock().expect("lock is poisoned");
-        self.manifest.register(writer)?;
-        Ok(())
+    fn segment_pipeline(&self, segment: &Segment<C>) -> crate::Result<Pipeline<C>> {
+        Pipeline::resolve(&self.config, segment.id, &segment.meta.pipeline)
     }
 
-    /// Returns the amount of segments in the value log.
-    #[must_use]
-    pub fn segment_count(&self) -> usize {
-        self.manifest.len()
+    /// Opens a reader that reverts the pipeline of the segment's blobs.
+    fn decoding_reader(
+        &self,
+        segment: &Segment<C>,
+        class: IoClass,
+    ) -> crate::Result<SegmentReader<C>> {
+        Ok(segment
+            .scan_with_advice(self.config.page_cache_hints.get(class))?
+            .use_pipeline(self.segment_pipeline(segment)?)
+            .with_memory_reservation(self.memory.reserve_read_buffer()))
     }
 
-    /// Resolves a value handle.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn get(&self, vhandle: &ValueHandle) -> crate::Result<Option<UserValue>> {
-        self.get_with_prefetch(vhandle, 0)
+    /// Folder parity files are stored in.
+    fn parity_folder(&self) -> PathBuf {
+        self.config
+            .parity_folder
+            .clone()
+            .unwrap_or_else(|| self.path.join(PARITY_FOLDER))
     }
 
-    /// Resolves a value handle, and prefetches some values after it.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn get_with_prefetch(
-        &self,
-        vhandle: &ValueHandle,
-        prefetch_size: usize,
-    ) -> crate::Result<Option<UserValue>> {
-        if let Some(value) = self.blob_cache.get(self.id, vhandle) {
-            return Ok(Some(value));
+    /// Reads back the sampled blobs of finished segments, if paranoid checks are enabled
+    /// (see [`Config::paranoid_checks`]).
+    fn verify_written(&self, writers: &[SegmentFileWriter<C>]) -> crate::Result<()> {
+        for writer in writers {
+            writer.verify_samples(&*self.config.fs)?;
         }
 
-        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
-            return Ok(None);
-        };
-
-        let mut reader = BufReader::new(File::open(&segment.path)?);
-        reader.seek(std::io::SeekFrom::Start(vhandle.offset))?;
-        let mut reader = SegmentReader::with_reader(vhandle.segment_id, reader)
-            .use_compression(self.config.compression.clone());
+        Ok(())
+    }
 
-        let Some(item) = reader.next() else {
-            return Ok(None);
+    /// Writes the parity files of finished segments, if parity is enabled.
+    fn write_parity(&self, writers: &[SegmentFileWriter<C>]) -> crate::Result<()> {
+        let Some(data_shards) = self.config.parity_shards else {
+            return Ok(());
         };
-        let (_key, val, _checksum) = item?;
 
-        self.blob_cache
-            .insert((self.id, vhandle.clone()).into(), val.clone());
+        let folder = self.parity_folder();
 
-        // TODO: maybe we can look at the value size and prefetch some more values
-        // without causing another I/O...
-        // TODO: benchmark range reads for rather small non-inlined blobs (maybe ~512-1000B)
-        // and see how different BufReader capacities and prefetch changes range read performance
-        for _ in 0..prefetch_size {
-            let offset = reader.get_offset()?;
-
-            let Some(item) = reader.next() else {
-                break;
-            };
-            let (_key, val, _checksum) = item?;
-
-            let value_handle = ValueHandle {
-                segment_id: vhandle.segment_id,
-                offset,
-            };
-
-            self.blob_cache.insert((self.id, value_handle).into(), val);
+        for writer in writers.iter().filter(|x| x.item_count > 0) {
+            write_parity_file(
+                &*self.config.fs,
+                &folder,
+                writer.segment_id,
+                &writer.path,
+                data_shards,
+            )?;
         }
 
-        Ok(Some(val))
+        Ok(())
     }
 
-    fn get_writer_raw(&self) -> crate::Result<SegmentWriter<C>> {
-        SegmentWriter::new(
-            self.id_generator.clone(),
-            self.config.segment_size_bytes,
-            self.path.join(SEGMENTS_FOLDER),
-        )
-        .map_err(Into::into)
+    /// Deletes the parity file of a dropped segment, if it exists.
+    fn remove_parity(&self, segment_id: SegmentId) {
+        let path = self.parity_folder().join(segment_id.to_string());
+
+        match self.config.fs.exists(&path) {
+            Ok(false) => {}
+            Ok(true) => {
+                if let Err(e) = self.config.fs.remove_file(&path) {
+                    log::warn!("Could not delete parity file {}: {e:?}", path.display());
+                }
+            }
+            Err(e) => {
+                log::warn!("Could not access parity file {}: {e:?}", path.display());
+            }
+        }
     }
 
-    /// Initializes a new segment writer.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn get_writer(&self) -> crate::Result<SegmentWriter<C>> {
-        self.get_writer_raw()
-            .map(|x| x.use_compression(self.config.compression.clone()))
+    fn generation(&self) -> u64 {
+        self.generation.load(std::sync::atomic::Ordering::SeqCst)
     }
 
-    /// Drops stale segments.
-    ///
-    /// Returns the amount of disk space (compressed data) freed.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn drop_stale_segments(&self) -> crate::Result<u64> {
+    /// Drops stale segments, see [`ValueLog::drop_stale_segments_with_report`].
+    fn drop_stale(&self) -> crate::Result<DropReport> {
         // IMPORTANT: Only allow 1 rollover or GC at any given time
-        let _guard = self.rollover_guard.lock().expect("lock is poisoned");
+        let _guard = self.lock_rollover()?;
+
+        let start = Instant::now();
+        let started_at = self.config.clock.now_millis();
 
+        let attributes = self.manifest.attributes();
+
+        // NOTE: Pinned segments are kept, even if they are stale
         let segments = self
             .manifest
-            .segments
-            .read()
-            .expect("lock is poisoned")
+            .read_segments()
             .values()
-            .filter(|x| x.is_stale())
+            .filter(|x| x.is_stale() && !attributes.pinned.contains(&x.id))
             .cloned()
             .collect::<Vec<_>>();
 
         let bytes_freed = segments.iter().map(|x| x.meta.compressed_bytes).sum();
 
         let ids = segments.iter().map(|x| x.id).collect::<Vec<_>>();
+        let mut disk_bytes_freed = 0;
 
         if ids.is_empty() {
             log::trace!("No blob files to drop");
@@ -344,176 +515,4027 @@ impl<C: Compressor + Clone> ValueLog<C> {
             log::info!("Dropping stale blob files: {ids:?}");
             self.manifest.drop_segments(&ids)?;
 
-            for segment in segments {
-                std::fs::remove_file(&segment.path)?;
+            if let Some(replicator) = &self.config.replicator {
+                replicator.segments_dropped(&ids);
             }
-        }
 
-        Ok(bytes_freed)
-    }
+            for segment in segments {
+                match self.config.fs.allocated_size(&segment.path) {
+                    Ok(size) => disk_bytes_freed += size,
+                    Err(e) => {
+                        log::warn!(
+                            "Could not get size of blob file {}: {e:?}",
+                            segment.path.display(),
+                        );
+                    }
+                }
 
-    /// Marks some segments as stale.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    fn mark_as_stale(&self, ids: &[SegmentId]) {
-        // NOTE: Read-locking is fine because we are dealing with an atomic bool
-        #[allow(clippy::significant_drop_tightening)]
-        let segments = self.manifest.segments.read().expect("lock is poisoned");
+                segment.file_slot.close();
 
-        for id in ids {
-            let Some(segment) = segments.get(id) else {
-                continue;
-            };
+                if self.config.trash_window.is_some() {
+                    move_to_trash(
+                        &*self.config.fs,
+                        &self.path.join(TRASH_FOLDER),
+                        segment.id,
+                        &segment.path,
+                        started_at,
+                    )?;
+                } else {
+                    self.delete_segment_file(&segment.path)?;
+                }
 
-            segment.mark_as_stale();
+                self.remove_parity(segment.id);
+            }
+
+            self.record_gc(GcHistoryEntry {
+                operation: GcOperation::Drop,
+                started_at,
+                duration: start.elapsed(),
+                input_segment_ids: ids.clone(),
+                output_segment_ids: vec![],
+                bytes_reclaimed: bytes_freed,
+            });
+        }
+
+        self.purge_trash_older_than(self.config.trash_window.unwrap_or_default())?;
+
+        Ok(DropReport {
+            segment_ids: ids,
+            bytes_freed,
+            disk_bytes_freed,
+        })
+    }
+
+    /// Deletes a segment file that is not part of the value log anymore.
+    fn delete_segment_file(&self, path: &Path) -> crate::Result<()> {
+        if self.config.discard_on_drop {
+            if let Err(e) = self.config.fs.discard(path) {
+                log::warn!("Could not discard blob file {}: {e:?}", path.display());
+            }
+        }
+
+        self.config.fs.remove_file(path)?;
+
+        Ok(())
+    }
+
+    /// Deletes segments that were moved to the trash folder at least `window` ago.
+    ///
+    /// Returns the amount of disk space freed.
+    fn purge_trash_older_than(&self, window: Duration) -> crate::Result<u64> {
+        let folder = self.path.join(TRASH_FOLDER);
+        let now = self.config.clock.now_millis();
+
+        let mut disk_bytes_freed = 0;
+
+        for entry in list_trash(&*self.config.fs, &folder)? {
+            if !crate::time::is_older_than(entry.dropped_at, window, now) {
+                continue;
+            }
+
+            log::debug!("Purging segment #{} from the trash", entry.segment_id);
+
+            disk_bytes_freed += self
+                .config
+                .fs
+                .allocated_size(&entry.path)
+                .unwrap_or_default();
+            self.delete_segment_file(&entry.path)?;
+        }
+
+        Ok(disk_bytes_freed)
+    }
+
+    /// Appends an operation to the GC history, persisting it if configured.
+    fn record_gc(&self, entry: GcHistoryEntry) {
+        self.gc_history.push(entry);
+
+        if !self.config.persist_gc_history {
+            return;
+        }
+
+        // NOTE: The GC operation itself succeeded, so failing to persist its record is not fatal
+        if let Err(e) = self.gc_history.encode().and_then(|bytes| {
+            self.config
+                .fs
+                .rewrite_atomic(&self.path.join(GC_HISTORY_FILE), &bytes)
+        }) {
+            log::warn!("Could not persist GC history: {e:?}");
+        }
+    }
+
+    /// Returns the amount of free disk space available to the value log.
+    pub(crate) fn available_space(&self) -> crate::Result<u64> {
+        Ok(self.config.fs.available_space(&self.path)?)
+    }
+
+    /// Returns `true` if maintenance is paused, see [`ValueLog::pause_maintenance`].
+    fn is_maintenance_paused(&self) -> bool {
+        self.maintenance_pauses
+            .load(std::sync::atomic::Ordering::Acquire)
+            > 0
+    }
+
+    /// Releases a maintenance pause, see [`MaintenancePause`].
+    pub(crate) fn release_maintenance_pause(&self) {
+        // NOTE: Pauses may have been cleared by `resume_maintenance` already
+        let _ = self.maintenance_pauses.fetch_update(
+            std::sync::atomic::Ordering::AcqRel,
+            std::sync::atomic::Ordering::Acquire,
+            |pauses| pauses.checked_sub(1),
+        );
+    }
+
+    /// Reserves disk space for a write, see [`Config::max_disk_usage`].
+    ///
+    /// If the quota would be exceeded, stale segments are dropped first (if enabled).
+    pub(crate) fn reserve_disk_space(&self, bytes: u64) -> crate::Result<()> {
+        let Some(max_bytes) = self.config.max_disk_usage else {
+            return Ok(());
+        };
+
+        if self.try_reserve_disk_space(bytes, max_bytes) {
+            return Ok(());
+        }
+
+        if self.config.emergency_gc {
+            log::warn!("Disk usage quota of {max_bytes} bytes exceeded, dropping stale segments");
+
+            self.drop_stale()?;
+
+            if self.try_reserve_disk_space(bytes, max_bytes) {
+                return Ok(());
+            }
+        }
+
+        Err(crate::Error::QuotaExceeded)
+    }
+
+    fn try_reserve_disk_space(&self, bytes: u64, max_bytes: u64) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let pending_bytes = self.pending_bytes.fetch_add(bytes, Ordering::AcqRel) + bytes;
+
+        if self.manifest.disk_space_used() + pending_bytes <= max_bytes {
+            return true;
+        }
+
+        self.pending_bytes.fetch_sub(bytes, Ordering::AcqRel);
+        false
+    }
+
+    /// Releases disk space reserved using [`ValueLogInner::reserve_disk_space`].
+    pub(crate) fn release_disk_space(&self, bytes: u64) {
+        if self.config.max_disk_usage.is_some() {
+            self.pending_bytes
+                .fetch_sub(bytes, std::sync::atomic::Ordering::AcqRel);
+        }
+    }
+
+    /// Registers multiple writers using a single manifest update.
+    ///
+    /// Stale writers are skipped, and reported as [`Error::StaleWriter`](crate::Error::StaleWriter)
+    /// after the other writers are registered.
+    fn register_writers(&self, writers: Vec<SegmentWriter<C>>) -> crate::Result<()> {
+        let writers = writers
+            .into_iter()
+            .map(|writer| {
+                let lease = writer.lease;
+                let writers = writer.finish()?;
+                self.verify_written(&writers)?;
+                self.write_parity(&writers)?;
+                Ok((lease, writers))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let lock = self.lock_rollover()?;
+
+        let mut is_stale = false;
+        let mut finished = vec![];
+
+        for (lease, writers) in writers {
+            if lease == Some((self.id, self.generation())) {
+                finished.extend(writers);
+            } else {
+                log::warn!("Rejecting stale segment writer with lease {lease:?}");
+                is_stale = true;
+            }
+        }
+
+        let staged = (!finished.is_empty()).then(|| {
+            self.manifest.stage_apply(ManifestEdit {
+                add: finished,
+                remove: vec![],
+            })
+        });
+
+        // NOTE: The manifest is written outside of the rollover lock, so the segments of writers
+        // that finish at the same time are persisted using a single manifest write
+        drop(lock);
+
+        if let Some((segment_ids, edit)) = staged {
+            self.manifest.commit(edit)?;
+            self.notify_registered(&segment_ids);
+        }
+
+        if is_stale {
+            return Err(crate::Error::StaleWriter);
+        }
+
+        Ok(())
+    }
+
+    /// Notifies the replicator (if any) about newly registered segments.
+    fn notify_registered(&self, segment_ids: &[SegmentId]) {
+        let Some(replicator) = &self.config.replicator else {
+            return;
+        };
+
+        for &segment_id in segment_ids {
+            if let Some(segment) = self.manifest.get_segment(segment_id) {
+                replicator.segment_registered(segment_id, &segment.path, &segment.meta);
+            }
+        }
+    }
+}
+
+impl<C: Compressor + Clone> ValueLog<C> {
+    /// Creates or recovers a value log in the given directory.
+    ///
+    /// Use [`OpenOptions`] to only create or only recover a value log.
+    ///
+    /// The directory is locked while the value log is open, so it cannot be opened twice.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::InvalidVersion`](crate::Error::InvalidVersion) if the value log
+    /// was written in an unsupported data format version, and
+    /// [`Error::CorruptManifest`](crate::Error::CorruptManifest) if its segment list
+    /// cannot be read.
+    ///
+    /// Will return [`Error::Locked`](crate::Error::Locked) if the value log is already
+    /// opened, possibly by another process.
+    pub fn open<P: Into<PathBuf>>(
+        path: P, // TODO: move path into config?
+        config: Config<C>,
+    ) -> crate::Result<Self> {
+        OpenOptions::new().open(path, config)
+    }
+
+    /// Returns a handle to the value log that does not keep it open.
+    pub(crate) fn downgrade(&self) -> Weak<ValueLogInner<C>> {
+        Arc::downgrade(&self.0)
+    }
+
+    /// Upgrades a handle returned by [`ValueLog::downgrade`], if the value log is still open.
+    ///
+    /// The new handle is counted like a clone.
+    pub(crate) fn upgrade(value_log: &Weak<ValueLogInner<C>>) -> Option<Self> {
+        let inner = value_log.upgrade()?;
+
+        inner
+            .handles
+            .fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+
+        Some(Self(inner))
+    }
+
+    /* /// Prints fragmentation histogram.
+    pub fn print_fragmentation_histogram(&self) {
+        let lock = self.manifest.read_segments();
+
+        for (id, segment) in &*lock {
+            let stale_ratio = segment.stale_ratio();
+
+            let progress = (stale_ratio * 10.0) as usize;
+            let void = 10 - progress;
+
+            let progress = "=".repeat(progress);
+            let void = " ".repeat(void);
+
+            println!(
+                "{id:0>4} [{progress}{void}] {}%",
+                (stale_ratio * 100.0) as usize
+            );
+        }
+    } */
+
+    #[doc(hidden)]
+    pub fn verify(&self) -> crate::Result<usize> {
+        let _lock = self.lock_rollover()?;
+
+        let mut sum = 0;
+        let mut progress = ProgressTracker::new(self.config.progress.as_ref(), Operation::Verify);
+
+        for segment in self.manifest.read_segments().values() {
+            let mut reader = segment.scan()?;
+
+            while let Some(item) = reader.next() {
+                let (k, v, expected_checksum) = item.map_err(|e| {
+                    self.check_corruption(
+                        e,
+                        CorruptionSource::Verify,
+                        segment.id,
+                        reader.last_offset(),
+                    )
+                })?;
+                progress.advance(segment.id, v.len() as u64);
+
+                let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+                hasher.update(&k);
+                hasher.update(&v);
+
+                if hasher.digest() != expected_checksum {
+                    sum += 1;
+
+                    self.report_corruption(&CorruptionReport {
+                        source: CorruptionSource::Verify,
+                        segment_id: segment.id,
+                        offset: Some(reader.last_offset()),
+                        key: Some(k),
+                    });
+                }
+            }
+        }
+
+        progress.finish();
+
+        Ok(sum)
+    }
+
+    /// Takes the exclusive lock on a value log folder.
+    ///
+    /// Returns [`Error::Locked`](crate::Error::Locked) if the value log is already opened.
+    pub(crate) fn lock_directory(fs: &dyn Fs, path: &Path) -> crate::Result<Box<dyn FsLock>> {
+        fs.try_lock(&path.join(LOCK_FILE))?.ok_or_else(|| {
+            log::error!("vLog at {} is already opened", path.display());
+            crate::Error::Locked
+        })
+    }
+
+    /// Creates a new empty value log in a directory, whose lock is already taken.
+    pub(crate) fn create_new<P: Into<PathBuf>>(
+        path: P,
+        config: Config<C>,
+        dir_lock: Box<dyn FsLock>,
+    ) -> crate::Result<Self> {
+        let path = absolute_path(path.into());
+        log::trace!("Creating value-log at {}", path.display());
+
+        let fs = config.fs.clone();
+
+        fs.create_dir_all(&path)?;
+
+        let marker_path = path.join(VLOG_MARKER);
+        assert!(!fs.exists(&marker_path)?);
+
+        fs.create_dir_all(&path.join(SEGMENTS_FOLDER))?;
+
+        // NOTE: Lastly, fsync .vlog marker, which contains the version
+        // -> the V-log is fully initialized
+        Self::write_marker(&*fs, &path)?;
+
+        let blob_cache = config.blob_cache.clone();
+        let value_cache = config.value_cache.clone();
+        let metrics = Arc::new(Metrics::default());
+        let manifest =
+            SegmentManifest::create_new(&path, fs, config.clock.clone(), metrics.clone())?;
+        let gc_history = GcHistory::new(config.gc_history_capacity);
+        let remaps = Remaps::recover(&*config.fs, &path.join(REMAP_FOLDER))?;
+
+        Ok(Self(Arc::new(ValueLogInner {
+            id: get_next_vlog_id(),
+            config,
+            path,
+            blob_cache,
+            value_cache,
+            manifest,
+            id_generator: IdGenerator::default(),
+            generation: AtomicU64::default(),
+            rollover_guard: Mutex::new(()),
+            flusher: OnceLock::new(),
+            maintainer_started: AtomicBool::new(false),
+            maintenance_pauses: AtomicUsize::new(0),
+            memory: MemoryTracker::default(),
+            gc_history,
+            remaps,
+            metrics,
+            closed: AtomicBool::new(false),
+            handles: AtomicUsize::new(1),
+            pending_bytes: AtomicU64::default(),
+            dir_lock: Mutex::new(Some(dir_lock)),
+        })))
+    }
+
+    /// Writes the .vlog marker and fsyncs the value log folder.
+    fn write_marker(fs: &dyn Fs, path: &Path) -> crate::Result<()> {
+        let mut file = fs.create(&path.join(VLOG_MARKER))?;
+        Version::V1.write_file_header(&mut file)?;
+        file.sync_all()?;
+
+        fs.sync_directory(&path.join(SEGMENTS_FOLDER))?;
+        fs.sync_directory(path)?;
+
+        Ok(())
+    }
+
+    /// Creates a consistent point-in-time snapshot of the value log in the given directory.
+    ///
+    /// Segment files are immutable, so they are hard linked instead of copied, making
+    /// checkpoints cheap. The destination needs to be on the same file system and
+    /// must not exist yet.
+    ///
+    /// The checkpoint is a complete value log that can be opened using [`ValueLog::open`].
+    /// Note that the value handles of the external index need to be snapshotted at the
+    /// same point in time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the destination already exists.
+    pub fn checkpoint<P: AsRef<Path>>(&self, dest: P) -> crate::Result<()> {
+        let dest = absolute_path(dest.as_ref());
+        let fs = &*self.config.fs;
+
+        // IMPORTANT: Prevent segments from being registered or dropped
+        // so the checkpoint is consistent
+        let _lock = self.lock_rollover()?;
+
+        log::info!("Creating vLog checkpoint at {}", dest.display());
+
+        if fs.exists(&dest)? {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "checkpoint destination already exists",
+            )));
+        }
+
+        let segments_folder = dest.join(SEGMENTS_FOLDER);
+        fs.create_dir_all(&segments_folder)?;
+
+        let segments = self.manifest.list_segments();
+
+        for segment in &segments {
+            fs.hard_link(&segment.path, &segments_folder.join(segment.id.to_string()))?;
+        }
+
+        // NOTE: The index snapshot may still point to the old locations of remapped blobs
+        self.remaps.write_to(fs, &dest.join(REMAP_FOLDER))?;
+
+        let ids = segments.iter().map(|x| x.id).collect::<Vec<_>>();
+        SegmentManifest::<C>::write_with_attributes(
+            fs,
+            dest.join(MANIFEST_FILE),
+            &ids,
+            &self.manifest.attributes(),
+        )?;
+
+        // NOTE: Lastly, write the marker, so a half-finished checkpoint cannot be opened
+        Self::write_marker(fs, &dest)?;
+
+        log::debug!("Created vLog checkpoint with segments {ids:?}");
+
+        Ok(())
+    }
+
+    /// Creates an incremental backup of the value log.
+    ///
+    /// Only segments that are not contained in `previous` (the `segment_ids` of
+    /// the previous backup's [`BackupReport`]) are returned.
+    ///
+    /// If a destination is given, the new segments and the current manifest are copied into it.
+    /// Every backup can be written into its own folder, or into the folder of the previous backup.
+    /// Otherwise, the caller is responsible for copying the returned segment files; note that they may be
+    /// deleted by garbage collection at any point.
+    ///
+    /// Use [`ValueLog::restore_backup`] to restore a value log from a chain of backups.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::PendingRemaps`](crate::Error::PendingRemaps) if there are
+    /// remapped blobs (see [`ValueLog::apply_remaps`]).
+    pub fn incremental_backup<P: AsRef<Path>>(
+        &self,
+        previous: &[SegmentId],
+        dest: Option<P>,
+    ) -> crate::Result<BackupReport> {
+        let fs = &*self.config.fs;
+
+        // IMPORTANT: Prevent segments from being registered or dropped while copying
+        let _lock = self.lock_rollover()?;
+
+        self.check_no_remaps()?;
+
+        let segments = self.manifest.list_segments();
+
+        let mut segment_ids = segments.iter().map(|x| x.id).collect::<Vec<_>>();
+        segment_ids.sort_unstable();
+
+        let new_segments = segments
+            .iter()
+            .filter(|x| !previous.contains(&x.id))
+            .map(|x| (x.id, x.path.clone()))
+            .collect::<Vec<_>>();
+
+        let mut copied_bytes = 0;
+
+        if let Some(dest) = dest {
+            let dest = dest.as_ref();
+
+            log::info!(
+                "Backing up {} new vLog segments to {}",
+                new_segments.len(),
+                dest.display(),
+            );
+
+            let segments_folder = dest.join(SEGMENTS_FOLDER);
+            fs.create_dir_all(&segments_folder)?;
+
+            for (id, path) in &new_segments {
+                copied_bytes += copy_file(fs, path, &segments_folder.join(id.to_string()))?;
+            }
+
+            fs.sync_directory(&segments_folder)?;
+
+            SegmentManifest::<C>::write_to_disk(fs, dest.join(MANIFEST_FILE), &segment_ids)?;
+        }
+
+        Ok(BackupReport {
+            segment_ids,
+            new_segments,
+            copied_bytes,
+        })
+    }
+
+    /// Restores a value log from a chain of backups (oldest first) into
+    /// the given directory, and opens it.
+    ///
+    /// The manifest of the newest backup determines which segments are restored,
+    /// each segment is copied from the newest backup that contains it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, a segment is missing from
+    /// the backups, or a value log already exists in the given directory.
+    pub fn restore_backup<P: AsRef<Path>, Q: Into<PathBuf>>(
+        backups: &[P],
+        path: Q,
+        config: Config<C>,
+    ) -> crate::Result<Self> {
+        let path = absolute_path(path.into());
+        let fs = config.fs.clone();
+
+        let Some(latest) = backups.last() else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no backups given",
+            )));
+        };
+
+        fs.create_dir_all(&path)?;
+        let dir_lock = Self::lock_directory(&*fs, &path)?;
+
+        if fs.exists(&path.join(VLOG_MARKER))? {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "value log already exists",
+            )));
+        }
+
+        let ids =
+            SegmentManifest::<C>::load_ids_from_disk(&*fs, latest.as_ref().join(MANIFEST_FILE))?;
+
+        log::info!(
+            "Restoring {} vLog segments from {} backups into {}",
+            ids.len(),
+            backups.len(),
+            path.display(),
+        );
+
+        let segments_folder = path.join(SEGMENTS_FOLDER);
+        fs.create_dir_all(&segments_folder)?;
+
+        for id in &ids {
+            let mut src = None;
+
+            for backup in backups.iter().rev() {
+                let candidate = backup.as_ref().join(SEGMENTS_FOLDER).join(id.to_string());
+
+                if fs.exists(&candidate)? {
+                    src = Some(candidate);
+                    break;
+                }
+            }
+
+            let Some(src) = src else {
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("segment #{id} is missing from backups"),
+                )));
+            };
+
+            copy_file(&*fs, &src, &segments_folder.join(id.to_string()))?;
+        }
+
+        SegmentManifest::<C>::write_to_disk(&*fs, path.join(MANIFEST_FILE), &ids)?;
+
+        // NOTE: Lastly, write the marker, so a half-finished restore cannot be opened
+        Self::write_marker(&*fs, &path)?;
+
+        Self::recover(path, config, dir_lock)
+    }
+
+    /// Ingests a segment file that was built outside of the value log,
+    /// e.g. using a [`SegmentBuilder`](crate::SegmentBuilder).
+    ///
+    /// The file is validated (trailer and blob checksums), copied into the value log,
+    /// assigned a new segment ID and registered using a single manifest write.
+    /// The source file is left untouched.
+    ///
+    /// Returns the assigned segment ID. Blob offsets are unchanged, so value handles
+    /// into the ingested segment consist of the returned ID and the original offsets.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment file is invalid.
+    ///
+    /// Will return [`Error::QuotaExceeded`](crate::Error::QuotaExceeded) if the segment
+    /// would exceed the disk usage quota (see [`Config::max_disk_usage`]).
+    pub fn ingest_segment<P: AsRef<Path>>(&self, path: P) -> crate::Result<SegmentId> {
+        let path = path.as_ref();
+
+        let meta = Self::validate_segment_file(&*self.config.fs, path)?;
+        let bytes = meta.compressed_bytes;
+
+        // NOTE: Reserve before locking, because emergency GC needs the rollover lock
+        self.reserve_disk_space(bytes)?;
+        let result = self.ingest_segment_file(path, meta);
+        self.release_disk_space(bytes);
+
+        result
+    }
+
+    fn ingest_segment_file(&self, path: &Path, meta: Metadata) -> crate::Result<SegmentId> {
+        let fs = &*self.config.fs;
+
+        // IMPORTANT: Serialize with rollover & GC, so the manifest write is not lost
+        let _lock = self.lock_rollover()?;
+
+        let segment_id = self.id_generator.next();
+        let segments_folder = self.path.join(SEGMENTS_FOLDER);
+        let segment_path = segments_folder.join(segment_id.to_string());
+
+        log::debug!(
+            "Ingesting segment file {} as segment #{segment_id}",
+            path.display(),
+        );
+
+        copy_file(fs, path, &segment_path)?;
+        fs.sync_directory(&segments_folder)?;
+
+        if let Some(data_shards) = self.config.parity_shards {
+            write_parity_file(
+                fs,
+                &self.parity_folder(),
+                segment_id,
+                &segment_path,
+                data_shards,
+            )?;
+        }
+
+        self.manifest
+            .register_file(segment_id, segment_path, meta)?;
+
+        self.notify_registered(&[segment_id]);
+
+        Ok(segment_id)
+    }
+
+    /// Checks that a segment file is well-formed and all its blobs are intact.
+    fn validate_segment_file(fs: &dyn Fs, path: &Path) -> crate::Result<Metadata> {
+        let trailer = SegmentFileTrailer::from_file(fs, path)?;
+
+        if trailer.metadata.item_count == 0 {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "segment file is empty",
+            )));
+        }
+
+        // NOTE: Checksums are calculated over the stored (possibly compressed) value,
+        // so the blobs do not need to be decompressed
+        let file = fs.open(path)?;
+        let reader = SegmentReader::<C>::from_source(0, Box::new(BufReader::new(file)));
+
+        let mut item_count = 0;
+
+        for item in reader {
+            let (k, v, expected_checksum) = item?;
+
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            hasher.update(&k);
+            hasher.update(&v);
+
+            if hasher.digest() != expected_checksum {
+                return Err(crate::Error::ChecksumMismatch);
+            }
+
+            item_count += 1;
+        }
+
+        if item_count != trailer.metadata.item_count {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "segment item count does not match metadata",
+            )));
+        }
+
+        Ok(trailer.metadata)
+    }
+
+    /// Ingests all segments of another value log (e.g. when consolidating shards).
+    ///
+    /// The other value log's segment files are validated, copied into this value log,
+    /// assigned new segment IDs and registered using a single manifest write.
+    /// The other value log is left untouched, and must not be written to while it is absorbed.
+    /// Its pinned segments and segment tags are not carried over.
+    ///
+    /// Every blob of the absorbed segments is then passed to the index writer as a
+    /// relocation (see [`IndexWriter::relocate_indirect_with_meta`](crate::IndexWriter::relocate_indirect_with_meta)),
+    /// from its handle in the other value log (`expected`) to its handle in this one.
+    /// The index should only apply a relocation if the other value log's index points
+    /// to `expected`, so stale blobs are skipped, and are dropped by the next garbage collection.
+    ///
+    /// Because both value logs assign segment IDs independently, `expected` may equal the
+    /// handle of a blob of this value log. This is only ambiguous if both blobs have the same key,
+    /// in which case nothing is absorbed, and an error is returned.
+    ///
+    /// Returns the new segment IDs, by the IDs they had in the other value log.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, a segment file is invalid, or a blob
+    /// has the same key and value handle as a blob of this value log.
+    ///
+    /// Will return [`Error::NotFound`](crate::Error::NotFound) if there is no value log
+    /// at the given path.
+    ///
+    /// Will return [`Error::PendingRemaps`](crate::Error::PendingRemaps) if either value log has
+    /// remapped blobs (see [`ValueLog::apply_remaps`]), because the index may point into
+    /// segments that were already rewritten.
+    ///
+    /// Will return [`Error::QuotaExceeded`](crate::Error::QuotaExceeded) if the segments
+    /// would exceed the disk usage quota (see [`Config::max_disk_usage`]).
+    pub fn absorb<P: AsRef<Path>, W: IndexWriter>(
+        &self,
+        path: P,
+        index_writer: W,
+    ) -> crate::Result<BTreeMap<SegmentId, SegmentId>> {
+        let path = path.as_ref();
+        let fs = &*self.config.fs;
+
+        if !fs.exists(&path.join(VLOG_MARKER))? {
+            return Err(crate::Error::NotFound);
+        }
+
+        let remap_count = Remaps::recover(fs, &path.join(REMAP_FOLDER))?.len();
+
+        if remap_count > 0 {
+            log::error!("Cannot absorb vLog, {remap_count} remapped blobs need to be applied to its index first");
+            return Err(crate::Error::PendingRemaps);
+        }
+
+        let mut segment_ids =
+            SegmentManifest::<C>::load_ids_from_disk(fs, path.join(MANIFEST_FILE))?;
+        segment_ids.sort_unstable();
+
+        let files = segment_ids
+            .into_iter()
+            .map(|id| {
+                let segment_path = path.join(SEGMENTS_FOLDER).join(id.to_string());
+                let meta = Self::validate_segment_file(fs, &segment_path)?;
+                Ok((id, segment_path, meta))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let bytes = files.iter().map(|(_, _, meta)| meta.compressed_bytes).sum();
+
+        // NOTE: Reserve before locking, because emergency GC needs the rollover lock
+        self.reserve_disk_space(bytes)?;
+        let result = self.absorb_segment_files(files, index_writer);
+        self.release_disk_space(bytes);
+
+        result
+    }
+
+    fn absorb_segment_files<W: IndexWriter>(
+        &self,
+        files: Vec<(SegmentId, PathBuf, Metadata)>,
+        mut index_writer: W,
+    ) -> crate::Result<BTreeMap<SegmentId, SegmentId>> {
+        let fs = &*self.config.fs;
+
+        // IMPORTANT: Serialize with rollover & GC, so the manifest write is not lost
+        let _lock = self.lock_rollover()?;
+
+        self.check_no_remaps()?;
+
+        for (old_id, src, _) in &files {
+            self.check_absorbed_handles(*old_id, src)?;
+        }
+
+        let segments_folder = self.path.join(SEGMENTS_FOLDER);
+
+        let mut id_map = BTreeMap::new();
+        let mut registered = Vec::with_capacity(files.len());
+
+        for (old_id, src, meta) in files {
+            let segment_id = self.id_generator.next();
+            let segment_path = segments_folder.join(segment_id.to_string());
+
+            log::debug!(
+                "Absorbing segment file {} as segment #{segment_id}",
+                src.display(),
+            );
+
+            copy_file(fs, &src, &segment_path)?;
+
+            if let Some(data_shards) = self.config.parity_shards {
+                write_parity_file(
+                    fs,
+                    &self.parity_folder(),
+                    segment_id,
+                    &segment_path,
+                    data_shards,
+                )?;
+            }
+
+            id_map.insert(old_id, segment_id);
+            registered.push((segment_id, segment_path, meta));
+        }
+
+        fs.sync_directory(&segments_folder)?;
+
+        // IMPORTANT: New segments need to be persisted before adding to index
+        // to avoid dangling pointers
+        self.manifest.register_files(registered)?;
+
+        let new_ids = id_map.values().copied().collect::<Vec<_>>();
+        self.notify_registered(&new_ids);
+
+        let batch_size = self.config.relocation_batch_size;
+        let mut batch = Vec::with_capacity(batch_size);
+
+        for (&old_id, &segment_id) in &id_map {
+            let Some(segment) = self.manifest.get_segment(segment_id) else {
+                continue;
+            };
+
+            // NOTE: The index needs the value sizes, so values are decoded,
+            // while the stored sizes are read from the blob headers
+            let blobs = self
+                .decoding_reader(&segment, IoClass::Scan)?
+                .zip(segment.scan_meta()?);
+
+            for (item, meta) in blobs {
+                let (key, value, _checksum) = item?;
+                let meta = meta?;
+
+                // NOTE: Truncation is OK because we know values are u32 max
+                #[allow(clippy::cast_possible_truncation)]
+                batch.push(Relocation {
+                    size: value.len() as u32,
+                    key,
+                    expected: ValueHandle {
+                        segment_id: old_id,
+                        offset: meta.offset,
+                    },
+                    vhandle: ValueHandle {
+                        segment_id,
+                        offset: meta.offset,
+                    },
+                    meta: RelocationMeta {
+                        checksum: meta.checksum,
+                        stored_size: meta.value_size,
+                    },
+                });
+
+                if batch.len() >= batch_size {
+                    flush_relocations(&mut index_writer, &mut batch, &self.metrics)?;
+                }
+            }
+        }
+
+        flush_relocations(&mut index_writer, &mut batch, &self.metrics)?;
+
+        // NOTE: If we crash here, it's fine, the segments are registered
+        // but never referenced, so they can just be dropped after recovery
+        index_writer.finish()?;
+
+        Ok(id_map)
+    }
+
+    /// Fails if a blob of a segment file that is absorbed has the same key and value handle
+    /// as a blob of this value log, so the index could not tell them apart when relocating.
+    fn check_absorbed_handles(&self, old_id: SegmentId, src: &Path) -> crate::Result<()> {
+        let Some(segment) = self.manifest.get_segment(old_id) else {
+            return Ok(());
+        };
+
+        let local = segment
+            .scan_meta()?
+            .map(|x| x.map(|x| (x.offset, x.key)))
+            .collect::<crate::Result<BTreeMap<_, _>>>()?;
+
+        let file = self.config.fs.open(src)?;
+
+        for meta in MetaReader::new(BufReader::new(file)) {
+            let meta = meta?;
+
+            if local.get(&meta.offset) == Some(&meta.key) {
+                log::error!(
+                    "Cannot absorb segment file {}, blob at {old_id}:{} is ambiguous",
+                    src.display(),
+                    meta.offset,
+                );
+
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "absorbed blob has the same key and value handle as an existing blob",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the segment manifest and per-segment statistics.
+    ///
+    /// This allows replicated (e.g. Raft-based) storage engines to include the
+    /// value log metadata in their snapshots. Segment files are not included,
+    /// and need to be shipped separately (see [`crate::Replicator`]).
+    ///
+    /// Use [`ValueLog::apply_metadata_snapshot`] to restore the metadata.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::PendingRemaps`](crate::Error::PendingRemaps) if there are
+    /// remapped blobs (see [`ValueLog::apply_remaps`]).
+    pub fn metadata_snapshot(&self) -> crate::Result<Vec<u8>> {
+        let _lock = self.lock_rollover()?;
+
+        self.check_no_remaps()?;
+
+        let mut segments = self.manifest.list_segments();
+        segments.sort_by_key(|x| x.id);
+
+        let segments = segments.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+        let mut bytes = vec![];
+        encode_snapshot(&mut bytes, &segments)?;
+
+        Ok(bytes)
+    }
+
+    /// Replaces the segment manifest and per-segment statistics with a
+    /// snapshot created by [`ValueLog::metadata_snapshot`].
+    ///
+    /// The segment files referenced by the snapshot need to exist in the value log's
+    /// segments folder, unless a [`crate::SegmentSource`] is configured.
+    /// Segments that are not part of the snapshot are dropped, and their files deleted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, the snapshot is invalid,
+    /// or a segment file is missing.
+    ///
+    /// Will return [`Error::PendingRemaps`](crate::Error::PendingRemaps) if there are
+    /// remapped blobs (see [`ValueLog::apply_remaps`]), because they may point into
+    /// segments that are not part of the snapshot.
+    pub fn apply_metadata_snapshot(&self, mut bytes: &[u8]) -> crate::Result<()> {
+        let snapshot = decode_snapshot(&mut bytes)?;
+
+        let fs = &self.config.fs;
+        let segments_folder = self.path.join(SEGMENTS_FOLDER);
+
+        if self.config.segment_source.is_none() {
+            for segment in &snapshot {
+                if !fs.exists(&segments_folder.join(segment.id.to_string()))? {
+                    return Err(crate::Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("segment file #{} is missing", segment.id),
+                    )));
+                }
+            }
+        }
+
+        // IMPORTANT: Serialize with rollover & GC
+        let _lock = self.lock_rollover()?;
+
+        self.check_no_remaps()?;
+
+        let ids = snapshot.iter().map(|x| x.id).collect::<Vec<_>>();
+        let mut dropped = vec![];
+
+        self.manifest.atomic_swap(|recipe| {
+            dropped = recipe
+                .values()
+                .filter(|x| !ids.contains(&x.id))
+                .cloned()
+                .collect();
+
+            recipe.clear();
+
+            for segment in snapshot {
+                let gc_stats = GcStats::default();
+                gc_stats.set_stale_items(segment.stale_items);
+                gc_stats.set_stale_bytes(segment.stale_bytes);
+
+                recipe.insert(
+                    segment.id,
+                    Arc::new(Segment {
+                        id: segment.id,
+                        path: segments_folder.join(segment.id.to_string()),
+                        meta: segment.meta,
+                        gc_stats,
+                        read_stats: ReadStats::default(),
+                        file_slot: FileSlot::default(),
+                        fs: fs.clone(),
+                        clock: self.config.clock.clone(),
+                        _phantom: PhantomData,
+                    }),
+                );
+            }
+        })?;
+
+        // NOTE: New segments need to get IDs that are not part of the snapshot
+        if let Some(highest_id) = ids.iter().max() {
+            self.id_generator
+                .fetch_max(highest_id + 1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        // IMPORTANT: Outstanding writers may have reserved IDs that are now part of the snapshot
+        self.generation
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+        log::info!("Applied vLog metadata snapshot with segments {ids:?}");
+
+        if !dropped.is_empty() {
+            let dropped_ids = dropped.iter().map(|x| x.id).collect::<Vec<_>>();
+
+            if let Some(replicator) = &self.config.replicator {
+                replicator.segments_dropped(&dropped_ids);
+            }
+
+            for segment in dropped {
+                segment.file_slot.close();
+                fs.remove_file(&segment.path)?;
+                self.remove_parity(segment.id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Streams the entire value log into a writer, using a simple framed archive format.
+    ///
+    /// Use [`ValueLog::import`] to create a value log from the archive.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::PendingRemaps`](crate::Error::PendingRemaps) if there are
+    /// remapped blobs (see [`ValueLog::apply_remaps`]).
+    pub fn export<W: Write>(&self, mut writer: W) -> crate::Result<()> {
+        // IMPORTANT: Prevent segments from being registered or dropped while exporting
+        let _lock = self.lock_rollover()?;
+
+        self.check_no_remaps()?;
+
+        let mut segments = self.manifest.list_segments();
+        segments.sort_by_key(|x| x.id);
+
+        log::info!("Exporting {} vLog segments", segments.len());
+
+        let segments = segments
+            .iter()
+            .map(|x| (x.id, x.path.as_path()))
+            .collect::<Vec<_>>();
+
+        write_archive(&*self.config.fs, &mut writer, &segments)
+    }
+
+    /// Creates a value log in the given directory from an archive
+    /// created by [`ValueLog::export`], and opens it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, the archive is corrupted,
+    /// or a value log already exists in the given directory.
+    pub fn import<R: Read, P: Into<PathBuf>>(
+        mut reader: R,
+        path: P,
+        config: Config<C>,
+    ) -> crate::Result<Self> {
+        let path = absolute_path(path.into());
+        let fs = config.fs.clone();
+
+        fs.create_dir_all(&path)?;
+        let dir_lock = Self::lock_directory(&*fs, &path)?;
+
+        if fs.exists(&path.join(VLOG_MARKER))? {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "value log already exists",
+            )));
+        }
+
+        log::info!("Importing vLog into {}", path.display());
+
+        let segments_folder = path.join(SEGMENTS_FOLDER);
+        fs.create_dir_all(&segments_folder)?;
+
+        let ids = read_archive(&*fs, &mut reader, &segments_folder)?;
+
+        SegmentManifest::<C>::write_to_disk(&*fs, path.join(MANIFEST_FILE), &ids)?;
+
+        // NOTE: Lastly, write the marker, so a half-finished import cannot be opened
+        Self::write_marker(&*fs, &path)?;
+
+        Self::recover(path, config, dir_lock)
+    }
+
+    /// Loads the persisted GC history, if enabled.
+    ///
+    /// The history is for debugging only, so if it cannot be loaded, it starts empty.
+    fn recover_gc_history(path: &Path, config: &Config<C>) -> GcHistory {
+        let capacity = config.gc_history_capacity;
+        let file_path = path.join(GC_HISTORY_FILE);
+
+        if !config.persist_gc_history || !config.fs.exists(&file_path).unwrap_or(false) {
+            return GcHistory::new(capacity);
+        }
+
+        match config
+            .fs
+            .read(&file_path)
+            .and_then(|bytes| GcHistory::decode(&bytes, capacity))
+        {
+            Ok(history) => history,
+            Err(e) => {
+                log::warn!("Could not load GC history: {e:?}");
+                GcHistory::new(capacity)
+            }
+        }
+    }
+
+    /// Recovers a value log from a directory, whose lock is already taken.
+    pub(crate) fn recover<P: Into<PathBuf>>(
+        path: P,
+        config: Config<C>,
+        dir_lock: Box<dyn FsLock>,
+    ) -> crate::Result<Self> {
+        let path = path.into();
+        log::info!("Recovering vLog at {}", path.display());
+
+        {
+            let bytes = config.fs.read(&path.join(VLOG_MARKER))?;
+
+            if let Some(version) = Version::parse_file_header(&bytes) {
+                if version != Version::V1 {
+                    return Err(crate::Error::InvalidVersion(Some(version)));
+                }
+            } else {
+                return Err(crate::Error::InvalidVersion(None));
+            }
+        }
+
+        let blob_cache = config.blob_cache.clone();
+        let value_cache = config.value_cache.clone();
+        let metrics = Arc::new(Metrics::default());
+        let manifest = SegmentManifest::recover(
+            &path,
+            config.fs.clone(),
+            config.clock.clone(),
+            metrics.clone(),
+        )?;
+        let gc_history = Self::recover_gc_history(&path, &config);
+        let remaps = Remaps::recover(&*config.fs, &path.join(REMAP_FOLDER))?;
+
+        // NOTE: Trashed segments keep their IDs, so they can be restored
+        let trashed_ids = list_trash(&*config.fs, &path.join(TRASH_FOLDER))?
+            .into_iter()
+            .map(|x| x.segment_id);
+
+        let highest_id = manifest
+            .read_segments()
+            .values()
+            .map(|x| x.id)
+            .chain(trashed_ids)
+            .max()
+            .unwrap_or_default();
+
+        let value_log = Self(Arc::new(ValueLogInner {
+            id: get_next_vlog_id(),
+            config,
+            path,
+            blob_cache,
+            value_cache,
+            manifest,
+            id_generator: IdGenerator::new(highest_id + 1),
+            generation: AtomicU64::default(),
+            rollover_guard: Mutex::new(()),
+            flusher: OnceLock::new(),
+            maintainer_started: AtomicBool::new(false),
+            maintenance_pauses: AtomicUsize::new(0),
+            memory: MemoryTracker::default(),
+            gc_history,
+            remaps,
+            metrics,
+            closed: AtomicBool::new(false),
+            handles: AtomicUsize::new(1),
+            pending_bytes: AtomicU64::default(),
+            dir_lock: Mutex::new(Some(dir_lock)),
+        }));
+
+        // NOTE: Segments that were never registered (or dropped before a crash)
+        // may have left their parity behind
+        remove_orphaned_parity_files(
+            &*value_log.config.fs,
+            &value_log.parity_folder(),
+            &value_log.manifest.list_segment_ids(),
+        )?;
+
+        Ok(value_log)
+    }
+
+    /// Registers a [`SegmentWriter`].
+    ///
+    /// This can be called from multiple threads at the same time.
+    /// The writer's segments are flushed to disk before the segment list is locked,
+    /// so only the (short) manifest update is serialized.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// Will return [`Error::StaleWriter`](crate::Error::StaleWriter) if the writer was not
+    /// created by this value log, or the segment list was replaced (see
+    /// [`ValueLog::apply_metadata_snapshot`]) after the writer was created.
+    /// The writer's segment files are then left unreferenced, and are cleaned up on recovery.
+    pub fn register_writer(&self, writer: SegmentWriter<C>) -> crate::Result<()> {
+        self.register_writers(vec![writer])
+    }
+
+    /// Starts a background thread that finishes (fsyncs) and registers the writers
+    /// passed to [`ValueLog::submit_writer`].
+    ///
+    /// Writers that are queued up are registered in a batch, using a single manifest update.
+    ///
+    /// Calling this again after the thread was started has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the thread could not be spawned.
+    pub fn start_background_flush(&self) -> crate::Result<()>
+    where
+        C: Send + Sync + 'static,
+    {
+        if self.flusher.get().is_some() {
+            return Ok(());
+        }
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let value_log = Arc::downgrade(&self.0);
+
+        std::thread::Builder::new()
+            .name("vlog-flush".into())
+            .spawn(move || Self::run_flusher(&value_log, &rx))?;
+
+        // NOTE: If another thread won the race, our flush thread exits
+        // immediately, because its queue is disconnected
+        let _ = self.flusher.set(tx);
+
+        Ok(())
+    }
+
+    fn run_flusher(value_log: &Weak<ValueLogInner<C>>, rx: &Receiver<FlushJob<C>>) {
+        // NOTE: Errors are kept until the next barrier reports them
+        let mut error = None;
+
+        while let Ok(job) = rx.recv() {
+            let mut writers = vec![];
+            let mut contexts = vec![];
+            let mut barriers = vec![];
+
+            for job in std::iter::once(job).chain(rx.try_iter()) {
+                match job {
+                    FlushJob::Register(writer, ctx) => {
+                        writers.push(*writer);
+                        contexts.push(ctx);
+                    }
+                    FlushJob::Barrier(tx) => barriers.push(tx),
+                }
+            }
+
+            // NOTE: A batch is registered within the context of its writers,
+            // unless they were submitted in different contexts
+            let ctx = contexts.split_first().and_then(|(first, rest)| {
+                let first = first.as_ref()?;
+
+                rest.iter()
+                    .all(|x| x.as_ref().is_some_and(|x| x.ptr_eq(first)))
+                    .then(|| first.clone())
+            });
+
+            if !writers.is_empty() {
+                let Some(inner) = value_log.upgrade() else {
+                    log::warn!(
+                        "Value log was dropped, discarding {} writers",
+                        writers.len()
+                    );
+                    return;
+                };
+
+                log::trace!("Flushing {} segment writers in background", writers.len());
+
+                if let Err(e) = RequestContext::scope_opt(ctx, || inner.register_writers(writers)) {
+                    log::error!("Background flush failed: {e:?}");
+                    error.get_or_insert(e);
+                }
+            }
+
+            for tx in barriers {
+                let _ = tx.send(error.take().map_or(Ok(()), Err));
+            }
+        }
+    }
+
+    /// Starts a background thread that runs [`ValueLog::run_maintenance`] every `interval`,
+    /// until the value log is closed or dropped.
+    ///
+    /// Failed runs are logged, and retried after `interval`.
+    /// Dropping the segments that were rewritten is still up to the caller
+    /// (see [`ValueLog::drop_stale_segments`]).
+    ///
+    /// Calling this again after the thread was started has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the thread could not be spawned.
+    pub fn start_background_maintenance<R, W, F>(
+        &self,
+        interval: Duration,
+        index_reader: R,
+        make_index_writer: F,
+    ) -> crate::Result<()>
+    where
+        C: Send + Sync + 'static,
+        R: IndexReader + Send + 'static,
+        W: IndexWriter,
+        F: FnMut() -> W + Send + 'static,
+    {
+        if self
+            .maintainer_started
+            .swap(true, std::sync::atomic::Ordering::AcqRel)
+        {
+            return Ok(());
+        }
+
+        let value_log = Arc::downgrade(&self.0);
+        let ctx = RequestContext::current();
+
+        let spawned = std::thread::Builder::new()
+            .name("vlog-maintenance".into())
+            .spawn(move || {
+                RequestContext::scope_opt(ctx, || {
+                    Self::run_maintainer(&value_log, interval, &index_reader, make_index_writer);
+                });
+            });
+
+        if let Err(e) = spawned {
+            self.maintainer_started
+                .store(false, std::sync::atomic::Ordering::Release);
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    fn run_maintainer<R: IndexReader, W: IndexWriter, F: FnMut() -> W>(
+        value_log: &Weak<ValueLogInner<C>>,
+        interval: Duration,
+        index_reader: &R,
+        mut make_index_writer: F,
+    ) {
+        loop {
+            // NOTE: This does not go through the configured clock, because a
+            // manual clock does not block, which would make this a busy loop
+            std::thread::sleep(interval);
+
+            let Some(value_log) = Self::upgrade(value_log) else {
+                return;
+            };
+
+            if value_log.is_closed() {
+                return;
+            }
+
+            match value_log.run_maintenance(index_reader, &mut make_index_writer) {
+                Ok(report) => log::trace!("Background maintenance finished: {report:?}"),
+                Err(e) => log::error!("Background maintenance failed: {e:?}"),
+            }
+        }
+    }
+
+    /// Registers a [`SegmentWriter`] in the background, if the background flush thread
+    /// was started (see [`ValueLog::start_background_flush`]).
+    ///
+    /// The writer's data is not durable, and its value handles must not be made visible
+    /// in a durable index, until a subsequent [`ValueLog::flush_barrier`] returns.
+    ///
+    /// If the background flush thread is not running, the writer is registered immediately.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs while registering immediately.
+    pub fn submit_writer(&self, writer: SegmentWriter<C>) -> crate::Result<()> {
+        self.check_open()?;
+
+        let Some(tx) = self.flusher.get() else {
+            return self.register_writer(writer);
+        };
+
+        // NOTE: If the flush thread is gone, fall back to registering immediately
+        if let Err(std::sync::mpsc::SendError(FlushJob::Register(writer, _))) = tx.send(
+            FlushJob::Register(Box::new(writer), RequestContext::current()),
+        ) {
+            return self.register_writer(*writer);
+        }
+
+        Ok(())
+    }
+
+    /// Blocks until all writers submitted before this call are durable and registered.
+    ///
+    /// Returns immediately if the background flush thread is not running.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the background flush of any writer since the last
+    /// barrier failed.
+    pub fn flush_barrier(&self) -> crate::Result<()> {
+        let Some(tx) = self.flusher.get() else {
+            return Ok(());
+        };
+
+        let (barrier_tx, barrier_rx) = std::sync::mpsc::sync_channel(1);
+
+        let flush_thread_gone = || {
+            crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "background flush thread is not running",
+            ))
+        };
+
+        tx.send(FlushJob::Barrier(barrier_tx))
+            .map_err(|_| flush_thread_gone())?;
+
+        barrier_rx.recv().map_err(|_| flush_thread_gone())?
+    }
+
+    /// Makes all data written to the value log durable.
+    ///
+    /// Segments are fsynced when their writer is registered, so this only needs to wait
+    /// for writers that were submitted to the background flush thread (see [`ValueLog::flush_barrier`]).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log is closed.
+    pub fn flush(&self) -> crate::Result<()> {
+        self.check_open()?;
+        self.flush_barrier()
+    }
+
+    /// Flushes the value log (see [`ValueLog::flush`]) and closes it.
+    ///
+    /// Afterwards, all operations on the value log (and its clones)
+    /// fail with [`Error::Closed`](crate::Error::Closed), and the directory lock
+    /// is released, so the value log can be opened again.
+    /// Closing an already closed value log has no effect.
+    ///
+    /// Dropping the last handle of a value log releases the directory lock as well.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn close(&self) -> crate::Result<()> {
+        if self.is_closed() {
+            return Ok(());
+        }
+
+        self.flush_barrier()?;
+
+        // NOTE: Wait for running rollovers and GC to finish
+        let _lock = self.lock_rollover_raw();
+        self.persist_read_counters()?;
+
+        self.closed
+            .store(true, std::sync::atomic::Ordering::Release);
+
+        self.release_directory_lock();
+
+        log::debug!("Closed vLog at {}", self.path.display());
+
+        Ok(())
+    }
+
+    /// Returns `true` if the value log is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Returns point-in-time statistics of the value log's segments.
+    #[must_use]
+    pub fn summary(&self) -> ManifestSummary {
+        self.manifest.summary()
+    }
+
+    /// Returns a dump of the value log's internal state, for debugging purposes.
+    ///
+    /// With the `serde` feature enabled, the dump can be serialized (e.g. as JSON),
+    /// so it can be attached to bug reports.
+    #[must_use]
+    pub fn debug_dump(&self) -> DebugDump {
+        let attributes = self.manifest.attributes();
+
+        let mut segments = self
+            .manifest
+            .read_segments()
+            .values()
+            .map(|segment| {
+                let counters = segment.read_stats.counters();
+
+                SegmentDebugInfo {
+                    summary: SegmentSummary::from(&**segment),
+                    path: segment.path.clone(),
+                    created_at: segment.meta.created_at,
+                    pipeline: segment.meta.pipeline.clone(),
+                    is_stale: segment.is_stale(),
+                    is_pinned: attributes.pinned.contains(&segment.id),
+                    tags: attributes
+                        .tags
+                        .get(&segment.id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    reads: counters.reads,
+                    last_read_at: counters.last_read_at,
+                    is_file_open: segment.file_slot.is_open(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        segments.sort_by_key(|x| x.summary.id);
+
+        let config = &self.config;
+
+        DebugDump {
+            path: self.path.clone(),
+            manifest: self.summary(),
+            segments,
+            config: ConfigDump {
+                segment_size_bytes: config.segment_size_bytes,
+                encryption: config.encryption.is_some(),
+                transforms: config.transforms.iter().map(|x| x.id()).collect(),
+                parity_shards: config.parity_shards,
+                blob_chunk_size: config.blob_chunk_size,
+                key_restart_interval: config.key_restart_interval,
+                max_disk_usage: config.max_disk_usage,
+                emergency_gc: config.emergency_gc,
+                retention: config.retention,
+                cold_compression_age: config.cold_compression.as_ref().map(|(_, age)| *age),
+                old_generation_segment_size: config.old_generation.as_ref().map(|(_, size)| *size),
+                gc_policy: config.gc_policy.is_some(),
+                relocation_batch_size: config.relocation_batch_size,
+                idle_file_timeout: config.idle_file_timeout,
+                memory_budget: config.memory_budget,
+                discard_on_drop: config.discard_on_drop,
+            },
+            blob_cache: self.cache_stats(),
+            value_cache: self.value_cache_stats(),
+            memory: self.memory_usage(),
+            in_flight: InFlightOperations {
+                rollover: self.rollover_guard.try_lock().is_err(),
+                maintenance_paused: self.is_maintenance_paused(),
+                background_maintenance: self
+                    .maintainer_started
+                    .load(std::sync::atomic::Ordering::Acquire),
+                background_flush: self.flusher.get().is_some(),
+                pending_writer_bytes: self
+                    .pending_bytes
+                    .load(std::sync::atomic::Ordering::Acquire),
+                open_files: self.open_file_count(),
+                closed: self.closed.load(std::sync::atomic::Ordering::Acquire),
+            },
+            gc_history: self.gc_history(),
+        }
+    }
+
+    /// Returns the latency distributions of reads, writes, fsyncs and rollover steps.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn statistics(&self) -> crate::Statistics {
+        self.metrics.statistics()
+    }
+
+    /// Returns the amount of segments in the value log.
+    #[must_use]
+    pub fn segment_count(&self) -> usize {
+        self.manifest.len()
+    }
+
+    /// Resolves a value handle.
+    ///
+    /// Blobs of expired segments (see [`Config::retention`]) resolve to `None`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get(&self, vhandle: &ValueHandle) -> crate::Result<Option<UserValue>> {
+        let timer = Timer::start();
+        let value = self.get_with_prefetch(vhandle, 0);
+        self.metrics.record(LatencyOp::Get, timer);
+        value
+    }
+
+    /// Returns `true` if the value handle points to a blob of the value log.
+    ///
+    /// This checks that the segment exists and that a blob header is located at
+    /// the value handle's offset, without reading (or decompressing) the value.
+    ///
+    /// Like [`ValueLog::get`], blobs of expired segments (see [`Config::retention`])
+    /// are not contained.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the blob is expired and
+    /// [`Config::expired_reads_error`] is enabled.
+    pub fn contains(&self, vhandle: &ValueHandle) -> crate::Result<bool> {
+        self.check_open()?;
+
+        let remapped = self.remaps.resolve(vhandle);
+        let vhandle = remapped.as_ref().unwrap_or(vhandle);
+
+        if self.check_expired(vhandle)? {
+            return Ok(false);
+        }
+
+        if self.is_cached(vhandle) {
+            return Ok(true);
+        }
+
+        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
+            return Ok(false);
+        };
+
+        let ctx = || {
+            IoContext::new("read blob header")
+                .segment_id(vhandle.segment_id)
+                .offset(vhandle.offset)
+                .path(&segment.path)
+        };
+
+        let mut reader = self
+            .open_blob_reader(&segment, vhandle, 0)
+            .map_err(|e| e.with_context(ctx()))?;
+
+        let mut buf = [0; BLOB_HEADER_MAGIC.len()];
+        match reader.read_exact(&mut buf) {
+            Ok(()) => Ok(BlobLayout::from_header(&buf).is_some()),
+
+            // NOTE: Offset is out of bounds
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+
+            Err(e) => Err(crate::Error::from(e).with_context(ctx())),
+        }
+    }
+
+    /// Resolves a value handle, and prefetches some values after it.
+    ///
+    /// If a value after it cannot be read, prefetching stops there,
+    /// and the damage is reported to the corruption callback (if any).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_with_prefetch(
+        &self,
+        vhandle: &ValueHandle,
+        prefetch_size: usize,
+    ) -> crate::Result<Option<UserValue>> {
+        self.check_open()?;
+
+        let remapped = self.remaps.resolve(vhandle);
+        let vhandle = remapped.as_ref().unwrap_or(vhandle);
+
+        if self.check_expired(vhandle)? {
+            return Ok(None);
+        }
+
+        if let Some(value) = self.get_cached(vhandle)? {
+            return Ok(Some(value));
+        }
+
+        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
+            return Ok(None);
+        };
+
+        let ctx = || {
+            IoContext::new("read blob")
+                .segment_id(vhandle.segment_id)
+                .offset(vhandle.offset)
+                .path(&segment.path)
+        };
+
+        // NOTE: Blob sizes are unknown, so the prefetched range is estimated
+        // using the segment's average blob size
+        let prefetch_bytes = if prefetch_size > 0 {
+            let avg_blob_size = segment.meta.compressed_bytes / segment.meta.item_count.max(1);
+            avg_blob_size.saturating_mul(prefetch_size as u64 + 1)
+        } else {
+            0
+        };
+
+        let pipeline = self.segment_pipeline(&segment)?;
+
+        // NOTE: If there is a value cache, the blob cache holds blobs as stored
+        let keep_stored = self.value_cache.is_some() && !pipeline.is_empty();
+
+        let reader = self
+            .open_blob_reader(&segment, vhandle, prefetch_bytes)
+            .map_err(|e| e.with_context(ctx()))?;
+        let mut reader = SegmentReader::from_source(vhandle.segment_id, reader)
+            .at_offset(vhandle.offset)
+            .without_key_resolution()
+            .use_pipeline(pipeline)
+            .keep_stored_values(keep_stored)
+            .with_memory_reservation(self.memory.reserve_read_buffer());
+
+        let Some(item) = reader.next() else {
+            return Ok(None);
+        };
+        let (_key, val, _checksum) = item.map_err(|e| {
+            self.check_corruption(
+                e.with_context(ctx()),
+                CorruptionSource::Read,
+                vhandle.segment_id,
+                vhandle.offset,
+            )
+        })?;
+
+        segment.read_stats.record(
+            self.config.read_rate_half_life,
+            self.config.clock.now_millis(),
+        );
+
+        self.cache_read_blob(&mut reader, vhandle.clone(), val.clone());
+
+        // TODO: maybe we can look at the value size and prefetch some more values
+        // without causing another I/O...
+        // TODO: benchmark range reads for rather small non-inlined blobs (maybe ~512-1000B)
+        // and see how different BufReader capacities and prefetch changes range read performance
+        for _ in 0..prefetch_size {
+            let Some(item) = reader.next() else {
+                break;
+            };
+
+            // NOTE: The requested blob was read successfully, so a damaged blob after it
+            // only stops prefetching
+            let (_key, val, _checksum) = match item {
+                Ok(item) => item,
+                Err(e) => {
+                    let e = self.check_corruption(
+                        e,
+                        CorruptionSource::Read,
+                        vhandle.segment_id,
+                        reader.last_offset(),
+                    );
+                    log::warn!("Stopped prefetching after blob {vhandle:?}: {e}");
+                    break;
+                }
+            };
+
+            let value_handle = ValueHandle {
+                segment_id: vhandle.segment_id,
+                offset: reader.last_offset(),
+            };
+
+            self.cache_read_blob(&mut reader, value_handle, val);
+        }
+
+        Ok(Some(val))
+    }
+
+    /// Resolves a value handle, reading the value into the given buffer
+    /// (which is cleared first), so hot paths can reuse buffers across reads.
+    ///
+    /// Values that are stored as is are read directly into the buffer, which is resized
+    /// to the exact value length. Unlike [`ValueLog::get`], the value is not
+    /// inserted into the blob cache.
+    ///
+    /// Returns `false` if the value does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn read_into(&self, vhandle: &ValueHandle, buf: &mut Vec<u8>) -> crate::Result<bool> {
+        self.read_value_into(vhandle, ValueBuffer::Vec(buf))
+            .map(|len| len.is_some())
+    }
+
+    /// Resolves a value handle, writing the value to the start of the given buffer,
+    /// which needs to be large enough (e.g. if the value length is known from the index).
+    ///
+    /// Like [`ValueLog::read_into`], the value is not inserted into the blob cache.
+    ///
+    /// Returns the length of the value, or `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the buffer is too small.
+    pub fn read_into_slice(
+        &self,
+        vhandle: &ValueHandle,
+        buf: &mut [u8],
+    ) -> crate::Result<Option<usize>> {
+        self.read_value_into(vhandle, ValueBuffer::Slice(buf))
+    }
+
+    fn read_value_into(
+        &self,
+        vhandle: &ValueHandle,
+        mut buf: ValueBuffer<'_>,
+    ) -> crate::Result<Option<usize>> {
+        self.check_open()?;
+
+        let remapped = self.remaps.resolve(vhandle);
+        let vhandle = remapped.as_ref().unwrap_or(vhandle);
+
+        if self.check_expired(vhandle)? {
+            return Ok(None);
+        }
+
+        if let Some(value) = self.get_cached(vhandle)? {
+            return buf.copy_from(&value).map(Some);
+        }
+
+        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
+            return Ok(None);
+        };
+
+        let ctx = || {
+            IoContext::new("read blob")
+                .segment_id(vhandle.segment_id)
+                .offset(vhandle.offset)
+                .path(&segment.path)
+        };
+
+        let reader = self
+            .open_blob_reader(&segment, vhandle, 0)
+            .map_err(|e| e.with_context(ctx()))?;
+        let mut reader = SegmentReader::from_source(vhandle.segment_id, reader)
+            .at_offset(vhandle.offset)
+            .without_key_resolution()
+            .use_pipeline(self.segment_pipeline(&segment)?)
+            .with_memory_reservation(self.memory.reserve_read_buffer());
+
+        let Some(item) = reader.next_into(buf) else {
+            return Ok(None);
+        };
+        let (_key, len, _checksum) = item.map_err(|e| {
+            self.check_corruption(
+                e.with_context(ctx()),
+                CorruptionSource::Read,
+                vhandle.segment_id,
+                vhandle.offset,
+            )
+        })?;
+
+        segment.read_stats.record(
+            self.config.read_rate_half_life,
+            self.config.clock.now_millis(),
+        );
+
+        Ok(Some(len))
+    }
+
+    /// Opens a reader positioned at the given value handle, falling back to
+    /// the configured segment source if the segment file is missing locally.
+    ///
+    /// If `prefetch_bytes` is not 0, the range after the value handle is going to be read.
+    fn open_blob_reader(
+        &self,
+        segment: &Segment<C>,
+        vhandle: &ValueHandle,
+        prefetch_bytes: u64,
+    ) -> crate::Result<Box<dyn ReadSeek>> {
+        let advise_prefetch = |file: &dyn FsFile| {
+            if prefetch_bytes > 0 {
+                advise(
+                    file,
+                    vhandle.offset,
+                    prefetch_bytes,
+                    self.config.page_cache_hints.get(IoClass::Prefetch),
+                );
+            }
+        };
+
+        let file = if self.config.idle_file_timeout.is_zero() {
+            self.config.fs.open(&segment.path).map(|file| {
+                advise_prefetch(&*file);
+                Box::new(file) as Box<dyn ReadSeek>
+            })
+        } else {
+            segment
+                .file_slot
+                .checkout(
+                    &*self.config.fs,
+                    &segment.path,
+                    self.config.idle_file_timeout,
+                )
+                .map(|file| {
+                    file.with_file(advise_prefetch);
+                    Box::new(file) as Box<dyn ReadSeek>
+                })
+        };
+
+        match file {
+            Ok(file) => {
+                let mut reader = BufReader::new(file);
+                reader.seek(std::io::SeekFrom::Start(vhandle.offset))?;
+                Ok(Box::new(reader))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let Some(source) = &self.config.segment_source else {
+                    return Err(e.into());
+                };
+
+                log::debug!(
+                    "Blob file #{} is missing locally, reading from segment source",
+                    vhandle.segment_id,
+                );
+
+                let mut reader = RangeReader::new(source.clone(), vhandle.segment_id);
+                reader.seek(std::io::SeekFrom::Start(vhandle.offset))?;
+                Ok(Box::new(reader))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Initializes a new segment writer that is not subject to the disk usage quota.
+    fn get_writer_raw(&self) -> crate::Result<SegmentWriter<C>> {
+        self.check_open()?;
+
+        SegmentWriter::with_fs(
+            self.id_generator.clone(),
+            self.config.segment_size_bytes,
+            self.path.join(SEGMENTS_FOLDER),
+            self.config.fs.clone(),
+        )
+        .map(|writer| {
+            let writer = writer
+                .with_lease(self.id, self.generation())
+                .use_pipeline(Pipeline::for_writing(&self.config, None))
+                .use_chunking(self.config.blob_chunk_size)
+                .use_key_restart_interval(self.config.key_restart_interval)
+                .use_sampling(self.config.paranoid_sample_interval)
+                .with_memory_reservation(self.memory.reserve_write_buffer())
+                .with_metrics(self.metrics.clone());
+
+            if self.config.records_creation_time() {
+                writer.with_clock(self.config.clock.clone())
+            } else {
+                writer
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    /// Passes a detected corruption to the corruption callback (if any).
+    fn report_corruption(&self, report: &CorruptionReport) {
+        log::error!(
+            "Detected corruption in segment #{} at offset {:?}",
+            report.segment_id,
+            report.offset,
+        );
+
+        if let Some(callback) = &self.config.corruption_callback {
+            callback(report);
+        }
+    }
+
+    /// Reports the error to the corruption callback if it is caused by corruption
+    /// of the blob at the given offset.
+    fn check_corruption(
+        &self,
+        e: crate::Error,
+        source: CorruptionSource,
+        segment_id: SegmentId,
+        offset: u64,
+    ) -> crate::Error {
+        if e.category() == ErrorCategory::Corruption {
+            self.report_corruption(&CorruptionReport {
+                source,
+                segment_id,
+                offset: Some(offset),
+                key: None,
+            });
+        }
+
+        e
+    }
+
+    /// Returns `true` if the value handle is in the blob or value cache.
+    fn is_cached(&self, vhandle: &ValueHandle) -> bool {
+        self.value_cache
+            .as_ref()
+            .is_some_and(|cache| cache.get(self.id, vhandle).is_some())
+            || self.blob_cache.get(self.id, vhandle).is_some()
+    }
+
+    /// Looks up a value in the value & blob caches.
+    ///
+    /// If there is a value cache, the blob cache holds blobs as stored in their segment,
+    /// which are decoded and inserted into the value cache.
+    fn get_cached(&self, vhandle: &ValueHandle) -> crate::Result<Option<UserValue>> {
+        let Some(value_cache) = &self.value_cache else {
+            return Ok(self.blob_cache.get(self.id, vhandle));
+        };
+
+        if let Some(value) = value_cache.get(self.id, vhandle) {
+            return Ok(Some(value));
+        }
+
+        let Some(stored) = self.blob_cache.get(self.id, vhandle) else {
+            return Ok(None);
+        };
+
+        let Some(segment) = self.manifest.get_segment(vhandle.segment_id) else {
+            return Ok(None);
+        };
+
+        let pipeline = self.segment_pipeline(&segment)?;
+        if pipeline.is_empty() {
+            return Ok(Some(stored));
+        }
+
+        let value = UserValue::from(pipeline.revert(vhandle.segment_id, stored.to_vec())?);
+        self.cache_insert(value_cache, vhandle.clone(), value.clone());
+
+        Ok(Some(value))
+    }
+
+    /// Caches a blob that was just read by the given reader.
+    ///
+    /// If the reader keeps stored values (because there is a value cache), the blob cache
+    /// gets the blob as stored in the segment, and the value cache gets the decoded value.
+    fn cache_read_blob(
+        &self,
+        reader: &mut SegmentReader<C>,
+        vhandle: ValueHandle,
+        value: UserValue,
+    ) {
+        let Some(value_cache) = self
+            .value_cache
+            .as_ref()
+            .filter(|_| reader.keeps_stored_values())
+        else {
+            self.cache_insert(&self.blob_cache, vhandle, value);
+            return;
+        };
+
+        // NOTE: Chunked blobs are decoded chunk by chunk, so they are only held by the value cache
+        if let Some(stored) = reader.take_stored_value() {
+            self.cache_insert(&self.blob_cache, vhandle.clone(), stored);
+        }
+
+        self.cache_insert(value_cache, vhandle, value);
+    }
+
+    /// Inserts a blob into the given cache, unless that would exceed the memory budget.
+    fn cache_insert(&self, cache: &BlobCache, vhandle: ValueHandle, value: UserValue) {
+        if let Some(budget) = self.config.memory_budget {
+            if self.memory_usage().total() + value.len() as u64 > budget {
+                log::trace!("Not caching blob {vhandle:?}, memory budget is exhausted");
+                return;
+            }
+        }
+
+        cache.insert((self.id, vhandle).into(), value);
+    }
+
+    /// Returns the size & access statistics of the blob cache.
+    ///
+    /// If the blob cache is shared between value logs, the statistics
+    /// include the accesses of all of them.
+    #[must_use]
+    pub fn cache_stats(&self) -> BlobCacheStats {
+        self.blob_cache.stats()
+    }
+
+    /// Returns the size & access statistics of the value cache,
+    /// if one is configured (see [`Config::value_cache`]).
+    ///
+    /// If the value cache is shared between value logs, the statistics
+    /// include the accesses of all of them.
+    #[must_use]
+    pub fn value_cache_stats(&self) -> Option<BlobCacheStats> {
+        self.value_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Returns the current memory usage of the value log, which should fit into
+    /// the memory budget (see [`Config::memory_budget`]).
+    #[must_use]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            blob_cache: self.blob_cache.size(),
+            value_cache: self.value_cache.as_ref().map_or(0, |cache| cache.size()),
+            read_buffers: self.memory.read_buffers(),
+            write_buffers: self.memory.write_buffers(),
+            budget: self.config.memory_budget,
+        }
+    }
+
+    /// Returns a breakdown of the disk space used by the value log.
+    ///
+    /// Unlike the manifest's disk space counter, which only counts blob bytes,
+    /// this accounts for the sizes of all segment files and the manifest.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn disk_space_breakdown(&self) -> crate::Result<DiskSpaceBreakdown> {
+        let fs = &*self.config.fs;
+
+        // NOTE: Files may be deleted concurrently (e.g. by dropping stale segments),
+        // those are not counted
+        let file_len = |path: &Path| match fs.open(path) {
+            Ok(mut file) => file.seek(std::io::SeekFrom::End(0)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e),
+        };
+
+        let mut breakdown = DiskSpaceBreakdown {
+            manifest_bytes: file_len(&self.path.join(MANIFEST_FILE))?,
+            ..Default::default()
+        };
+
+        let segments = self.manifest.read_segments();
+
+        for segment in segments.values() {
+            let report = segment.gc_report();
+            let blob_bytes = segment.meta.compressed_bytes;
+
+            breakdown.live_blob_bytes += blob_bytes - report.reclaimable_bytes;
+            breakdown.stale_blob_bytes += report
+                .reclaimable_bytes
+                .saturating_sub(segment.gc_stats.punched_bytes());
+            breakdown.format_overhead_bytes += file_len(&segment.path)?.saturating_sub(blob_bytes);
+        }
+
+        for path in fs.list_files(&self.path.join(SEGMENTS_FOLDER))? {
+            let is_registered = path
+                .file_name()
+                .and_then(|x| x.to_str())
+                .and_then(|x| x.parse::<SegmentId>().ok())
+                .is_some_and(|id| segments.contains_key(&id));
+
+            if !is_registered {
+                breakdown.quarantined_bytes += file_len(&path)?;
+            }
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Initializes a new segment writer.
+    ///
+    /// Multiple writers can be active at the same time, e.g. to flush multiple
+    /// memtables in parallel. Each writer reserves its own segment IDs.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_writer(&self) -> crate::Result<SegmentWriter<C>> {
+        let writer = self.get_writer_raw()?;
+
+        if self.config.max_disk_usage.is_some() {
+            return Ok(writer.with_quota(Arc::downgrade(&self.0)));
+        }
+
+        Ok(writer)
+    }
+
+    /// Drops stale segments.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn drop_stale_segments(&self) -> crate::Result<u64> {
+        self.drop_stale_segments_with_report()
+            .map(|report| report.bytes_freed)
+    }
+
+    /// Drops stale segments, like [`ValueLog::drop_stale_segments`].
+    ///
+    /// Returns a report that also contains the amount of disk space
+    /// the dropped segment files actually occupied.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn drop_stale_segments_with_report(&self) -> crate::Result<DropReport> {
+        self.drop_stale()
+    }
+
+    /// Returns the dropped segments that are kept in the trash folder
+    /// (see [`Config::trash_window`]), oldest drop first.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn trashed_segments(&self) -> crate::Result<Vec<TrashedSegment>> {
+        list_trash(&*self.config.fs, &self.path.join(TRASH_FOLDER))
+    }
+
+    /// Restores a dropped segment from the trash folder (see [`Config::trash_window`]),
+    /// registering it under its original ID, so value handles that point into it resolve again.
+    ///
+    /// If the segment was dropped multiple times, the latest drop is restored.
+    /// Its GC statistics start out empty, so it is only dropped again once it is found
+    /// to be stale (see [`ValueLog::scan_for_stats`]).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment is not in the trash.
+    ///
+    /// Will return [`Error::QuotaExceeded`](crate::Error::QuotaExceeded) if the segment
+    /// would exceed the disk usage quota (see [`Config::max_disk_usage`]).
+    pub fn undrop_segment(&self, segment_id: SegmentId) -> crate::Result<()> {
+        let fs = &*self.config.fs;
+
+        let Some(entry) = self
+            .trashed_segments()?
+            .into_iter()
+            .rev()
+            .find(|x| x.segment_id == segment_id)
+        else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} is not in the trash"),
+            )));
+        };
+
+        let meta = SegmentFileTrailer::from_file(fs, &entry.path)?.metadata;
+        let bytes = meta.compressed_bytes;
+
+        // NOTE: Reserve before locking, because emergency GC needs the rollover lock
+        self.reserve_disk_space(bytes)?;
+        let result = self.restore_trashed_segment(&entry, meta);
+        self.release_disk_space(bytes);
+
+        result
+    }
+
+    fn restore_trashed_segment(&self, entry: &TrashedSegment, meta: Metadata) -> crate::Result<()> {
+        let fs = &*self.config.fs;
+        let segment_id = entry.segment_id;
+
+        // IMPORTANT: Serialize with rollover & GC, so the manifest write is not lost
+        let _lock = self.lock_rollover()?;
+
+        if self.manifest.get_segment(segment_id).is_some() {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("segment #{segment_id} is part of the value log"),
+            )));
+        }
+
+        log::info!("Restoring segment #{segment_id} from the trash");
+
+        let segments_folder = self.path.join(SEGMENTS_FOLDER);
+        let segment_path = segments_folder.join(segment_id.to_string());
+
+        fs.hard_link(&entry.path, &segment_path)?;
+        fs.sync_directory(&segments_folder)?;
+
+        if let Some(data_shards) = self.config.parity_shards {
+            write_parity_file(
+                fs,
+                &self.parity_folder(),
+                segment_id,
+                &segment_path,
+                data_shards,
+            )?;
+        }
+
+        self.manifest
+            .register_file(segment_id, segment_path, meta)?;
+
+        self.notify_registered(&[segment_id]);
+
+        fs.remove_file(&entry.path)?;
+
+        Ok(())
+    }
+
+    /// Deletes all segments from the trash folder that were dropped longer than
+    /// the configured window ago (see [`Config::trash_window`]), or all of them
+    /// if the trash is disabled.
+    ///
+    /// This happens automatically whenever stale segments are dropped.
+    ///
+    /// Returns the amount of disk space freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn purge_trash(&self) -> crate::Result<u64> {
+        let _lock = self.lock_rollover()?;
+        self.purge_trash_older_than(self.config.trash_window.unwrap_or_default())
+    }
+
+    /// Returns the past GC operations (rollovers & dropping stale segments), oldest first.
+    ///
+    /// Only the newest operations are kept (see [`Config::gc_history_capacity`]).
+    #[must_use]
+    pub fn gc_history(&self) -> Vec<GcHistoryEntry> {
+        self.gc_history.entries()
+    }
+
+    /// Marks some segments as stale.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn mark_as_stale(&self, ids: &[SegmentId]) {
+        // NOTE: Reading a snapshot is fine because we are dealing with an atomic bool
+        let segments = self.manifest.read_segments();
+
+        for id in ids {
+            let Some(segment) = segments.get(id) else {
+                continue;
+            };
+
+            segment.mark_as_stale();
         }
     }
 
     // TODO: remove?
     /// Returns the approximate space amplification.
     ///
-    /// Returns 0.0 if there are no items.
+    /// Returns 0.0 if there are no items.
+    #[must_use]
+    pub fn space_amp(&self) -> f32 {
+        self.manifest.space_amp()
+    }
+
+    #[doc(hidden)]
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn consume_scan_result(&self, size_map: &SizeMap) -> GcReport {
+        let mut report = GcReport {
+            path: self.path.clone(),
+            segment_count: self.segment_count(),
+            stale_segment_count: 0,
+            stale_bytes: 0,
+            total_bytes: 0,
+            stale_blobs: 0,
+            total_blobs: 0,
+        };
+
+        for (&id, counter) in size_map {
+            let segment = self.manifest.get_segment(id).expect("segment should exist");
+
+            let total_bytes = segment.meta.total_uncompressed_bytes;
+            let total_items = segment.meta.item_count;
+
+            report.total_bytes += total_bytes;
+            report.total_blobs += total_items;
+
+            if counter.item_count > 0 {
+                let used_size = counter.size;
+                let alive_item_count = counter.item_count;
+
+                let segment = self.manifest.get_segment(id).expect("segment should exist");
+
+                let stale_bytes = total_bytes - used_size;
+                let stale_items = total_items - alive_item_count;
+
+                segment.gc_stats.set_stale_bytes(stale_bytes);
+                segment.gc_stats.set_stale_items(stale_items);
+
+                report.stale_bytes += stale_bytes;
+                report.stale_blobs += stale_items;
+            } else {
+                log::debug!(
+                "Blob file #{id} has no incoming references - can be dropped, freeing {} KiB on disk (userdata={} MiB)",
+                segment.meta.compressed_bytes / 1_024,
+                total_bytes / 1_024 / 1_024,
+            );
+                self.mark_as_stale(&[id]);
+
+                report.stale_segment_count += 1;
+                report.stale_bytes += total_bytes;
+                report.stale_blobs += total_items;
+            }
+        }
+
+        report
+    }
+
+    /// Scans the given index and collects GC statistics.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_for_stats(
+        &self,
+        iter: impl Iterator<Item = std::io::Result<(ValueHandle, u32)>>,
+    ) -> crate::Result<GcReport> {
+        let lock_guard = self.lock_rollover()?;
+
+        let mut progress =
+            ProgressTracker::new(self.config.progress.as_ref(), Operation::ScanForStats);
+
+        // NOTE: The index may still point to the old locations of remapped blobs
+        let iter = iter
+            .map(|item| item.map(|(vhandle, size)| (self.resolve_handle(&vhandle), size)))
+            .inspect(|item| {
+                if let Ok((vhandle, size)) = item {
+                    progress.advance(vhandle.segment_id, u64::from(*size));
+                }
+            });
+
+        let ids = self.manifest.list_segment_ids();
+        let mut scanner = Scanner::new(iter, lock_guard, &ids);
+        scanner.scan()?;
+        let size_map = scanner.finish();
+        progress.finish();
+
+        let report = self.consume_scan_result(&size_map);
+
+        Ok(report)
+    }
+
+    /// Scans all segments, looking up every blob's key in the given index,
+    /// and collects GC statistics.
+    ///
+    /// Unlike [`ValueLog::scan_for_stats`], this does not require the caller to iterate
+    /// over the index; instead the value sizes are taken from
+    /// [`IndexReader::get_with_size`](crate::IndexReader::get_with_size).
+    /// A blob is alive if the index still points to it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_index_for_stats<R: IndexReader>(&self, index: &R) -> crate::Result<GcReport> {
+        let _lock = self.lock_rollover()?;
+
+        let mut progress =
+            ProgressTracker::new(self.config.progress.as_ref(), Operation::ScanForStats);
+
+        let segments = self.manifest.list_segments();
+
+        let mut size_map = segments
+            .iter()
+            .map(|segment| (segment.id, SegmentCounter::default()))
+            .collect::<SizeMap>();
+
+        for segment in segments {
+            for item in segment.handles()? {
+                let (key, vhandle, _) = item?;
+
+                let Some(indexed) = index.get_with_size(&key)? else {
+                    continue;
+                };
+
+                if self.resolve_handle(&indexed.vhandle) != vhandle {
+                    continue;
+                }
+
+                let size = u64::from(indexed.size);
+                progress.advance(vhandle.segment_id, size);
+
+                let counter = size_map.entry(vhandle.segment_id).or_default();
+                counter.item_count += 1;
+                counter.size += size;
+            }
+        }
+
+        progress.finish();
+
+        Ok(self.consume_scan_result(&size_map))
+    }
+
+    /// Scans all segments using the given amount of threads, calling
+    /// the callback (on the calling thread) for every blob.
+    ///
+    /// Blobs are not returned in any particular order.
+    /// If a segment fails to be read, the scan stops and the error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn par_scan<F: FnMut(UserKey, ValueHandle, UserValue)>(
+        &self,
+        concurrency: usize,
+        mut callback: F,
+    ) -> crate::Result<()>
+    where
+        C: Send + Sync,
+    {
+        // NOTE: Scoped threads are not instrumented by loom, so this is a plain std mutex
+        let queue = std::sync::Mutex::new(self.manifest.list_segments());
+        let stop = AtomicBool::new(false);
+
+        // NOTE: Bounded, so fast workers cannot buffer unbounded amounts of values
+        let (tx, rx) = std::sync::mpsc::sync_channel::<ScanItem>(/* items */ 1_024);
+
+        let ctx = RequestContext::current();
+
+        std::thread::scope(|scope| {
+            for _ in 0..concurrency.max(1) {
+                let tx = tx.clone();
+                let queue = &queue;
+                let stop = &stop;
+                let ctx = ctx.clone();
+
+                scope.spawn(move || {
+                    RequestContext::scope_opt(ctx, || {
+                        while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                            let Some(segment) =
+                                queue.lock().unwrap_or_else(PoisonError::into_inner).pop()
+                            else {
+                                break;
+                            };
+
+                            if let Err(e) = self.scan_segment_into(&segment, &tx) {
+                                // NOTE: If the receiver is gone, the scan was aborted anyway
+                                let _ = tx.send(Err(e));
+                                break;
+                            }
+                        }
+                    });
+                });
+            }
+
+            // NOTE: Drop our sender, so the channel closes once all workers are done
+            drop(tx);
+
+            for item in rx {
+                match item {
+                    Ok((key, vhandle, value)) => callback(key, vhandle, value),
+                    Err(e) => {
+                        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+                        return Err(e);
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Sends all blobs of a segment into the channel.
+    fn scan_segment_into(
+        &self,
+        segment: &Segment<C>,
+        tx: &SyncSender<ScanItem>,
+    ) -> crate::Result<()> {
+        let mut reader = self.decoding_reader(segment, IoClass::Scan)?;
+
+        loop {
+            let Some(item) = reader.next() else {
+                return Ok(());
+            };
+            let (key, value, _) = item?;
+
+            let vhandle = ValueHandle {
+                segment_id: segment.id,
+                offset: reader.last_offset(),
+            };
+
+            if tx.send(Ok((key, vhandle, value))).is_err() {
+                // NOTE: Receiver is gone, scan was aborted
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns an iterator over the blobs of all segments, in key order.
+    ///
+    /// If a key exists in multiple segments, all versions are returned,
+    /// starting with the one in the newest segment.
+    ///
+    /// Because the value log has no index, stale blobs are returned as well.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_blobs(&self) -> crate::Result<BlobIter<C>> {
+        Ok(BlobIter::new(
+            self.get_decompressing_reader(|_| true)?.without_dedup(),
+        ))
+    }
+
+    /// Returns an iterator over the blobs of all segments, in key order.
+    ///
+    /// If a key exists in multiple segments, only the version in the newest segment is returned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_latest_blobs(&self) -> crate::Result<BlobIter<C>> {
+        Ok(BlobIter::new(self.get_decompressing_reader(|_| true)?))
+    }
+
+    /// Returns an iterator over the blobs whose keys are inside the given range, in key order.
+    ///
+    /// Segments whose key range does not overlap with the range are not scanned at all.
+    ///
+    /// Like [`ValueLog::scan_blobs`], all versions of a key are returned, including stale blobs.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn scan_range<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+    ) -> crate::Result<BlobIter<C>> {
+        let to_owned_bound = |bound: Bound<&K>| match bound {
+            Bound::Included(x) => Bound::Included(UserKey::from(x.as_ref())),
+            Bound::Excluded(x) => Bound::Excluded(UserKey::from(x.as_ref())),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        let start = to_owned_bound(range.start_bound());
+        let end = to_owned_bound(range.end_bound());
+
+        let bounds = (as_slice_bound(&start), as_slice_bound(&end));
+
+        let reader = self
+            .get_decompressing_reader(|x| x.meta.key_range.overlaps_with_bounds(&bounds))?
+            .without_dedup();
+
+        Ok(BlobIter::new(reader).with_range((start, end)))
+    }
+
+    fn get_decompressing_reader<F: Fn(&Segment<C>) -> bool>(
+        &self,
+        filter: F,
+    ) -> crate::Result<MergeReader<C>> {
+        self.check_open()?;
+
+        let segments = self.manifest.read_segments();
+
+        let readers = segments
+            .values()
+            .filter(|x| filter(x))
+            .map(|x| self.decoding_reader(x, IoClass::Scan))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(MergeReader::new(readers))
+    }
+
+    #[doc(hidden)]
+    pub fn get_reader(&self) -> crate::Result<MergeReader<C>> {
+        self.check_open()?;
+
+        let segments = self.manifest.read_segments();
+
+        let readers = segments
+            .values()
+            .map(|x| x.scan())
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(MergeReader::new(readers))
+    }
+
+    /// Rewrites the live blobs of all segments into as few new segments as possible
+    /// (see [`Config::segment_size_bytes`]), and drops all stale segments.
+    ///
+    /// This is meant for off-peak maintenance: afterwards, the value log has no space
+    /// amplification. Pinned segments (see [`ValueLog::pin_segment`]) are kept as is.
+    ///
+    /// Like [`ValueLog::finish_gc`], the old segments are dropped right after the
+    /// index writer is finished, so reads must not use value handles they looked up
+    /// before the compaction anymore.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn major_compact<R: IndexReader, W: IndexWriter>(
+        &self,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<u64> {
+        let size_before = self.manifest.disk_space_used();
+
+        let ids = self.manifest.list_segment_ids();
+        self.rollover(&ids, index_reader, index_writer)?;
+        self.drop_stale_segments()?;
+
+        Ok(size_before.saturating_sub(self.manifest.disk_space_used()))
+    }
+
+    /// Applies a GC strategy.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn apply_gc_strategy<R: IndexReader, W: IndexWriter>(
+        &self,
+        strategy: &impl GcStrategy<C>,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<u64> {
+        let segment_ids = self.pick_candidates(strategy);
+        self.rollover(&segment_ids, index_reader, index_writer)
+    }
+
+    /// Picks segments to garbage collect, without rewriting them.
+    ///
+    /// This is the first step of a GC run that is driven by the embedding storage engine:
+    /// 1. [`ValueLog::pick_candidates`] selects segments
+    /// 2. [`ValueLog::relocate_segment`] rewrites the live blobs of a segment (may be interleaved with other work)
+    /// 3. [`ValueLog::finish_gc`] drops the relocated segments, once the index changes are durable
+    ///    and no reads may access the old segments anymore
+    ///
+    /// Expired segments (see [`Config::retention`]) are always picked,
+    /// pinned segments (see [`ValueLog::pin_segment`]) never are.
+    #[must_use]
+    pub fn pick_candidates(&self, strategy: &impl GcStrategy<C>) -> Vec<SegmentId> {
+        let mut segment_ids = strategy.pick(self);
+
+        for segment_id in self.expired_segments() {
+            if !segment_ids.contains(&segment_id) {
+                segment_ids.push(segment_id);
+            }
+        }
+
+        let attributes = self.manifest.attributes();
+        segment_ids.retain(|id| !attributes.pinned.contains(id));
+
+        // NOTE: Cold segments are rewritten first, as rewriting hot segments
+        // competes with reads
+        segment_ids.sort_by_key(|id| self.temperature(*id));
+
+        segment_ids
+    }
+
+    /// Classifies a segment by its read rate (see [`Config::temperature_thresholds`]).
+    ///
+    /// The read rate is an exponentially-decayed average of the reads of the segment
+    /// that were not served by the blob cache (see [`Config::read_rate_half_life`]).
+    /// It survives restarts as far as the read counters were persisted
+    /// (see [`ValueLog::persist_read_counters`]).
+    ///
+    /// Returns `None` if the segment does not exist.
+    #[must_use]
+    pub fn temperature(&self, segment_id: SegmentId) -> Option<Temperature> {
+        self.manifest
+            .get_segment(segment_id)
+            .map(|segment| self.segment_temperature(&segment))
+    }
+
+    fn segment_temperature(&self, segment: &Segment<C>) -> Temperature {
+        let (cold_below, hot_above) = self.config.temperature_thresholds;
+        let read_rate = segment.read_stats.read_rate(
+            self.config.read_rate_half_life,
+            self.config.clock.now_millis(),
+        );
+
+        Temperature::from_read_rate(read_rate, cold_below, hot_above)
+    }
+
+    /// Returns the read counters of a segment.
+    ///
+    /// Returns `None` if the segment does not exist.
+    #[must_use]
+    pub fn read_counters(&self, segment_id: SegmentId) -> Option<ReadCounters> {
+        self.manifest
+            .get_segment(segment_id)
+            .map(|segment| segment.read_stats.counters())
+    }
+
+    /// Persists the read counters of all segments in the manifest, so they survive restarts,
+    /// e.g. to warm caches after a restart.
+    ///
+    /// Read counters are also persisted by [`ValueLog::run_maintenance`] and [`ValueLog::close`].
+    /// Does nothing if no segment was read since the counters were last persisted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn persist_read_counters(&self) -> crate::Result<()> {
+        let persisted = self.manifest.attributes();
+
+        let counters = self
+            .manifest
+            .read_segments()
+            .values()
+            .map(|segment| (segment.id, segment.read_stats.counters()))
+            .filter(|(_, counters)| counters.reads > 0)
+            .collect::<BTreeMap<_, _>>();
+
+        if counters == persisted.read_counters {
+            return Ok(());
+        }
+
+        log::trace!("Persisting read counters of {} segments", counters.len());
+
+        self.manifest.update_attributes(|attributes| {
+            attributes.read_counters = counters;
+        })
+    }
+
+    /// Returns the IDs of all segments with the given temperature (see [`ValueLog::temperature`]),
+    /// e.g. to move cold segments to cheaper storage.
     #[must_use]
-    pub fn space_amp(&self) -> f32 {
-        self.manifest.space_amp()
+    pub fn list_segments_with_temperature(&self, temperature: Temperature) -> Vec<SegmentId> {
+        let mut segment_ids = self
+            .manifest
+            .read_segments()
+            .values()
+            .filter(|x| self.segment_temperature(x) == temperature)
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+
+        segment_ids.sort_unstable();
+        segment_ids
     }
 
-    #[doc(hidden)]
-    #[allow(clippy::cast_precision_loss)]
+    /// Returns the generation of a segment (see [`Config::old_generation`]).
+    ///
+    /// Returns `None` if the segment does not exist.
     #[must_use]
-    pub fn consume_scan_result(&self, size_map: &SizeMap) -> GcReport {
-        let mut report = GcReport {
-            path: self.path.clone(),
-            segment_count: self.segment_count(),
-            stale_segment_count: 0,
-            stale_bytes: 0,
-            total_bytes: 0,
-            stale_blobs: 0,
-            total_blobs: 0,
+    pub fn segment_generation(&self, segment_id: SegmentId) -> Option<Generation> {
+        self.manifest.get_segment(segment_id)?;
+
+        Some(Generation::from_tags(
+            self.manifest.attributes().tags.get(&segment_id),
+        ))
+    }
+
+    /// Returns the IDs of all segments of the given generation (see [`Config::old_generation`]).
+    #[must_use]
+    pub fn list_segments_with_generation(&self, generation: Generation) -> Vec<SegmentId> {
+        let attributes = self.manifest.attributes();
+
+        let mut segment_ids = self
+            .manifest
+            .list_segment_ids()
+            .into_iter()
+            .filter(|id| Generation::from_tags(attributes.tags.get(id)) == generation)
+            .collect::<Vec<_>>();
+
+        segment_ids.sort_unstable();
+        segment_ids
+    }
+
+    /// Pins a segment, so it is never picked for garbage collection or dropped,
+    /// until it is unpinned again.
+    ///
+    /// This can be used to keep segments that are referenced by external snapshots.
+    /// Pins are persisted in the manifest.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment does not exist.
+    pub fn pin_segment(&self, segment_id: SegmentId) -> crate::Result<()> {
+        // IMPORTANT: Serialize with rollover & GC, which check the pins
+        let _guard = self.lock_rollover()?;
+
+        if self.manifest.get_segment(segment_id).is_none() {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} does not exist"),
+            )));
+        }
+
+        log::debug!("Pinning segment #{segment_id}");
+
+        self.manifest.update_attributes(|attributes| {
+            attributes.pinned.insert(segment_id);
+        })
+    }
+
+    /// Unpins a segment (see [`ValueLog::pin_segment`]).
+    ///
+    /// Unpinning a segment that is not pinned does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn unpin_segment(&self, segment_id: SegmentId) -> crate::Result<()> {
+        let _guard = self.lock_rollover()?;
+
+        if !self.manifest.is_pinned(segment_id) {
+            return Ok(());
+        }
+
+        log::debug!("Unpinning segment #{segment_id}");
+
+        self.manifest.update_attributes(|attributes| {
+            attributes.pinned.remove(&segment_id);
+        })
+    }
+
+    /// Returns the IDs of all segments that have the given tag
+    /// (see [`SegmentWriter::with_tag`]), in ascending order.
+    #[must_use]
+    pub fn list_segments_with_tag(&self, key: &str, value: &str) -> Vec<SegmentId> {
+        self.manifest
+            .attributes()
+            .tags
+            .iter()
+            .filter(|(_, tags)| tags.get(key).is_some_and(|x| x == value))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Returns the tags of a segment (see [`SegmentWriter::with_tag`]).
+    #[must_use]
+    pub fn segment_tags(&self, segment_id: SegmentId) -> SegmentTags {
+        self.manifest
+            .attributes()
+            .tags
+            .get(&segment_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the IDs of all pinned segments (see [`ValueLog::pin_segment`]).
+    #[must_use]
+    pub fn pinned_segments(&self) -> Vec<SegmentId> {
+        self.manifest.attributes().pinned.iter().copied().collect()
+    }
+
+    /// Returns the IDs of segments that are older than the configured retention
+    /// (see [`Config::retention`]).
+    #[must_use]
+    pub fn expired_segments(&self) -> Vec<SegmentId> {
+        let Some(max_age) = self.config.retention else {
+            return vec![];
+        };
+
+        self.manifest
+            .read_segments()
+            .values()
+            .filter(|x| x.is_expired(max_age))
+            .map(|x| x.id)
+            .collect()
+    }
+
+    /// Rewrites the live blobs of a segment into new segment(s), and points the index to them.
+    ///
+    /// The old segment is kept until [`ValueLog::finish_gc`] is called.
+    /// Pinned segments are not relocated.
+    ///
+    /// Returns the IDs of the new segments.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment does not exist.
+    pub fn relocate_segment<R: IndexReader, W: IndexWriter>(
+        &self,
+        segment_id: SegmentId,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<Vec<SegmentId>> {
+        // IMPORTANT: Only allow 1 rollover or GC at any given time
+        let _guard = self.lock_rollover()?;
+
+        let Some(segment) = self.manifest.get_segment(segment_id) else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} does not exist"),
+            )));
+        };
+
+        if self.manifest.is_pinned(segment_id) {
+            log::debug!("Not relocating segment #{segment_id} because it is pinned");
+            return Ok(vec![]);
+        }
+
+        log::debug!("Relocating segment #{segment_id}");
+
+        self.relocate(
+            &[segment],
+            Pipeline::for_writing(&self.config, None),
+            &SegmentTags::new(),
+            None,
+            index_reader,
+            index_writer,
+        )
+    }
+
+    /// Rewrites the live blobs of a segment into roughly `parts` new segments of equal size,
+    /// and points the index to them.
+    ///
+    /// This allows garbage collecting (huge) segments that were written with a larger
+    /// segment size (see [`Config::segment_size_bytes`]) piece by piece.
+    /// Because only live blobs are rewritten, there may be fewer new segments.
+    ///
+    /// Like [`ValueLog::relocate_segment`], the old segment is kept until [`ValueLog::finish_gc`]
+    /// is called, and pinned segments are not split.
+    ///
+    /// Returns the IDs of the new segments.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment does not exist.
+    pub fn split_segment<R: IndexReader, W: IndexWriter>(
+        &self,
+        segment_id: SegmentId,
+        parts: usize,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<Vec<SegmentId>> {
+        // IMPORTANT: Only allow 1 rollover or GC at any given time
+        let _guard = self.lock_rollover()?;
+
+        let Some(segment) = self.manifest.get_segment(segment_id) else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} does not exist"),
+            )));
+        };
+
+        if self.manifest.is_pinned(segment_id) {
+            log::debug!("Not splitting segment #{segment_id} because it is pinned");
+            return Ok(vec![]);
+        }
+
+        // NOTE: Records are larger than their stored values, so the written parts
+        // reach the target size slightly earlier, which is compensated by rounding up
+        let target_size = segment
+            .meta
+            .compressed_bytes
+            .div_ceil(parts.max(1) as u64)
+            .max(1);
+
+        log::debug!("Splitting segment #{segment_id} into {parts} parts of {target_size} bytes");
+
+        self.relocate(
+            &[segment],
+            Pipeline::for_writing(&self.config, None),
+            &SegmentTags::new(),
+            Some(target_size),
+            index_reader,
+            index_writer,
+        )
+    }
+
+    /// Punches holes into a segment file where its blobs are stale, freeing their
+    /// disk space without rewriting the segment (see [`Segment::punch_stale_ranges`]).
+    ///
+    /// A blob is stale if the index does not point to it anymore.
+    ///
+    /// Iterators that scan the segment at the same time may fail.
+    ///
+    /// Pinned segments (see [`ValueLog::pin_segment`]) are left untouched.
+    ///
+    /// Returns the amount of bytes that were punched.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, the segment does not exist,
+    /// or the file system does not support punching holes.
+    pub fn punch_stale_blobs<R: IndexReader>(
+        &self,
+        segment_id: SegmentId,
+        index_reader: &R,
+    ) -> crate::Result<u64> {
+        // IMPORTANT: Rollovers scan the segment, so they must not run at the same time
+        let _guard = self.lock_rollover()?;
+
+        let Some(segment) = self.manifest.get_segment(segment_id) else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} does not exist"),
+            )));
+        };
+
+        // NOTE: Stale blobs of pinned segments may still be read through external snapshots
+        if self.manifest.is_pinned(segment_id) {
+            log::debug!("Not punching segment #{segment_id} because it is pinned");
+            return Ok(0);
+        }
+
+        let mut ranges: Vec<Range<u64>> = vec![];
+
+        // NOTE: Adjacent stale blobs are merged into a single hole
+        let mut stale_run: Option<Range<u64>> = None;
+        let mut segment_end = 0;
+
+        for blob in segment.scan_meta()? {
+            let blob = blob?;
+
+            // NOTE: Delta-encoded keys need the previous blob's key, so a hole
+            // can only end before a blob that stores its key as a whole
+            if blob.shared_prefix_len == 0 {
+                if let Some(run) = &mut stale_run {
+                    run.end = blob.offset;
+                }
+            }
+
+            segment_end = blob.offset + blob.record_len();
+
+            let vhandle = ValueHandle {
+                segment_id,
+                offset: blob.offset,
+            };
+
+            if self
+                .indexed_handle(index_reader, &blob.key, &vhandle)?
+                .is_some()
+            {
+                ranges.extend(stale_run.take().filter(|x| !x.is_empty()));
+            } else if stale_run.is_none() {
+                stale_run = Some(blob.offset..blob.offset);
+            }
+        }
+
+        if let Some(mut run) = stale_run {
+            run.end = segment_end;
+            ranges.push(run);
+        }
+
+        log::debug!(
+            "Punching {} hole(s) into segment #{segment_id}",
+            ranges.len()
+        );
+
+        let punched = segment.punch_stale_ranges(&ranges)?;
+
+        // NOTE: Punched ranges read back as zeroes, so the parity needs to be updated
+        if punched > 0 {
+            if let Some(data_shards) = self.config.parity_shards {
+                write_parity_file(
+                    &*self.config.fs,
+                    &self.parity_folder(),
+                    segment_id,
+                    &segment.path,
+                    data_shards,
+                )?;
+            }
+        }
+
+        Ok(punched)
+    }
+
+    /// Repairs a damaged (or lost) segment file using its parity file
+    /// (see [`Config::parity_shards`]).
+    ///
+    /// If the parity file is damaged or missing, but the segment is intact,
+    /// the parity file is rewritten (if parity is enabled).
+    ///
+    /// Returns the amount of bytes repaired.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the segment does not exist.
+    ///
+    /// Will return [`Error::Unrepairable`](crate::Error::Unrepairable) if the segment
+    /// is damaged, and its parity is not sufficient to repair it.
+    pub fn repair_segment(&self, segment_id: SegmentId) -> crate::Result<u64> {
+        // IMPORTANT: Serialize with rollover & GC, which read & delete segment files
+        let _guard = self.lock_rollover()?;
+
+        let Some(segment) = self.manifest.get_segment(segment_id) else {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("segment #{segment_id} does not exist"),
+            )));
+        };
+
+        let fs = &*self.config.fs;
+        let parity_folder = self.parity_folder();
+        let parity_path = parity_folder.join(segment_id.to_string());
+
+        let parity = fs
+            .read(&parity_path)
+            .and_then(|bytes| Parity::decode(&bytes))
+            .map_err(|e| {
+                log::warn!(
+                    "Parity file {} is missing or damaged: {e:?}",
+                    parity_path.display(),
+                );
+            })
+            .ok();
+
+        let Some(parity) = parity else {
+            if let Err(e) = Self::validate_segment_file(fs, &segment.path) {
+                log::error!("Segment #{segment_id} is damaged, and has no parity: {e:?}");
+                return Err(crate::Error::Unrepairable(segment_id));
+            }
+
+            if let Some(data_shards) = self.config.parity_shards {
+                write_parity_file(fs, &parity_folder, segment_id, &segment.path, data_shards)?;
+            }
+
+            return Ok(0);
+        };
+
+        let mut data = match fs.read(&segment.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+
+        let (repaired, parity_damaged) = parity.repair(segment_id, &mut data)?;
+
+        if repaired > 0 {
+            log::info!("Repaired {repaired} bytes of segment #{segment_id}");
+            fs.rewrite_atomic(&segment.path, &data)?;
+
+            // IMPORTANT: The cached handle refers to the replaced file
+            segment.file_slot.close();
+        }
+
+        if parity_damaged {
+            log::info!("Rewriting damaged parity of segment #{segment_id}");
+            write_parity_file(
+                fs,
+                &parity_folder,
+                segment_id,
+                &segment.path,
+                parity.data_shards(),
+            )?;
+        }
+
+        Ok(repaired)
+    }
+
+    /// Marks relocated segments as stale and drops all stale segments.
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn finish_gc(&self, relocated_ids: &[SegmentId]) -> crate::Result<u64> {
+        self.mark_as_stale(relocated_ids);
+        self.drop_stale_segments()
+    }
+
+    /// Returns the new location of a blob that was relocated without updating the index,
+    /// or `None` if the value handle is not remapped (see [`Config::remap_relocations`]).
+    ///
+    /// Reads resolve remapped value handles transparently, so this is only needed
+    /// to update index entries lazily, e.g. when they are read anyway.
+    #[must_use]
+    pub fn remapped_handle(&self, vhandle: &ValueHandle) -> Option<ValueHandle> {
+        self.remaps.resolve(vhandle)
+    }
+
+    /// Returns the amount of relocated blobs whose new location has not been
+    /// passed to the index yet (see [`Config::remap_relocations`]).
+    #[must_use]
+    pub fn remap_count(&self) -> usize {
+        self.remaps.len()
+    }
+
+    /// Passes the new locations of all remapped blobs (see [`Config::remap_relocations`])
+    /// to the index, and deletes the remap tables.
+    ///
+    /// Like relocations of garbage collection, every relocation is only applied if the index
+    /// still points to the blob's old location.
+    ///
+    /// Returns the amount of relocations that were passed to the index.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn apply_remaps<W: IndexWriter>(&self, mut index_writer: W) -> crate::Result<usize> {
+        // IMPORTANT: Rollovers must not add remaps while they are applied
+        let _guard = self.lock_rollover()?;
+
+        let (segment_ids, relocations) = self.remaps.pending();
+        let count = relocations.len();
+
+        if segment_ids.is_empty() {
+            return Ok(0);
+        }
+
+        log::debug!("Applying {count} remapped relocations of segments {segment_ids:?}");
+
+        for chunk in relocations.chunks(self.config.relocation_batch_size.max(1)) {
+            flush_relocations(&mut index_writer, &mut chunk.to_vec(), &self.metrics)?;
+        }
+
+        index_writer.finish()?;
+
+        // NOTE: If we crash here, the remaps are applied again after recovery,
+        // which does nothing, because the index does not point to the old locations anymore
+        self.remaps.remove(
+            &*self.config.fs,
+            &self.path.join(REMAP_FOLDER),
+            &segment_ids,
+        )?;
+
+        Ok(count)
+    }
+
+    /// Hints the page cache that rewritten segments are not read anymore.
+    ///
+    /// Their data should not push out data that is still read from the page cache.
+    fn advise_rollover_finished(&self, segments: &[Arc<Segment<C>>]) {
+        if let Some(advice) = self.config.page_cache_hints.get(IoClass::RolloverFinished) {
+            for segment in segments {
+                if let Ok(file) = self.config.fs.open(&segment.path) {
+                    advise(&*file, 0, 0, Some(advice));
+                }
+            }
+        }
+    }
+
+    /// Rewrites the live blobs of the given segments into new segment(s),
+    /// returning the IDs of the new segments.
+    ///
+    /// The new segments are written with the given pipeline, and get the given tags.
+    ///
+    /// If a target size is given, it replaces the configured segment size.
+    ///
+    /// The rollover lock needs to be held by the caller.
+    fn relocate<R: IndexReader, W: IndexWriter>(
+        &self,
+        segments: &[Arc<Segment<C>>],
+        pipeline: Pipeline<C>,
+        tags: &SegmentTags,
+        target_size: Option<u64>,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<Vec<SegmentId>> {
+        let start = Instant::now();
+        let started_at = self.config.clock.now_millis();
+
+        let readers = segments
+            .iter()
+            .map(|x| self.decoding_reader(x, IoClass::Rollover))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        // TODO: 2.0.0: Store uncompressed size per blob
+        // so we can avoid recompression costs during GC
+        // but have stats be correct
+
+        // NOTE: Every version of a key needs to be looked at, because
+        // only the version the index points to is live
+        let mut reader = MergeReader::new(readers).without_dedup();
+
+        // NOTE: Blobs of expired segments are dropped instead of being relocated
+        let expired_ids = self.config.retention.map_or_else(Vec::new, |max_age| {
+            segments
+                .iter()
+                .filter(|x| x.is_expired(max_age))
+                .map(|x| x.id)
+                .collect::<Vec<_>>()
+        });
+
+        let writer = self.relocation_writer(segments, &expired_ids, pipeline, tags, target_size)?;
+
+        let mut progress = ProgressTracker::new(self.config.progress.as_ref(), Operation::Rollover);
+
+        let mut relocator = Relocator {
+            writer,
+            index_writer,
+            batch: Vec::with_capacity(self.config.relocation_batch_size),
+            batch_size: self.config.relocation_batch_size,
+            metrics: &self.metrics,
+            remapped: self.config.remap_relocations.then(Vec::new),
         };
 
-        for (&id, counter) in size_map {
-            let segment = self.manifest.get_segment(id).expect("segment should exist");
+        let relocate_timer = Timer::start();
+
+        // NOTE: Segments are only sorted by key if they were written in key order,
+        // so live blobs are collected and sorted before being written, if configured
+        let partitioner = self.config.rollover_partitioner.as_ref();
+        let mut collected =
+            (self.config.key_ordered_rollover || partitioner.is_some()).then(Vec::new);
+
+        while let Some(item) = reader.next_entry() {
+            let item = item?;
+            progress.advance(item.segment_id, item.value.len() as u64);
+
+            let old_vhandle = ValueHandle {
+                segment_id: item.segment_id,
+                offset: item.offset,
+            };
+
+            if expired_ids.contains(&item.segment_id) {
+                continue;
+            }
+
+            // If the index does not point to this blob, it is stale and can be discarded
+            let Some(indexed) = self.indexed_handle(index_reader, &item.key, &old_vhandle)? else {
+                continue;
+            };
+
+            if let Some(collected) = &mut collected {
+                collected.push(CollectedBlob {
+                    partition: partitioner.map_or(0, |f| f(&item.key)),
+                    key: item.key,
+                    value: item.value,
+                    vhandle: old_vhandle,
+                    indexed,
+                    checksum: item.checksum,
+                });
+                continue;
+            }
 
-            let total_bytes = segment.meta.total_uncompressed_bytes;
-            let total_items = segment.meta.item_count;
+            relocator.relocate(item.key, &item.value, old_vhandle, indexed, item.checksum)?;
+        }
 
-            report.total_bytes += total_bytes;
-            report.total_blobs += total_items;
+        if let Some(collected) = collected {
+            Self::relocate_collected(&mut relocator, collected)?;
+        }
 
-            if counter.item_count > 0 {
-                let used_size = counter.size;
-                let alive_item_count = counter.item_count;
+        relocator.flush()?;
 
-                let segment = self.manifest.get_segment(id).expect("segment should exist");
+        let Relocator {
+            writer,
+            mut index_writer,
+            remapped,
+            ..
+        } = relocator;
 
-                let stale_bytes = total_bytes - used_size;
-                let stale_items = total_items - alive_item_count;
+        self.metrics
+            .record(LatencyOp::RolloverRelocate, relocate_timer);
 
-                segment.gc_stats.set_stale_bytes(stale_bytes);
-                segment.gc_stats.set_stale_items(stale_items);
+        progress.finish();
 
-                report.stale_bytes += stale_bytes;
-                report.stale_blobs += stale_items;
-            } else {
-                log::debug!(
-                "Blob file #{id} has no incoming references - can be dropped, freeing {} KiB on disk (userdata={} MiB)",
-                segment.meta.compressed_bytes / 1_024,
-                total_bytes / 1_024 / 1_024,
-            );
-                self.mark_as_stale(&[id]);
+        let commit_timer = Timer::start();
 
-                report.stale_segment_count += 1;
-                report.stale_bytes += total_bytes;
-                report.stale_blobs += total_items;
-            }
+        // IMPORTANT: New segments need to be persisted before adding to index
+        // to avoid dangling pointers
+        let writers = writer.finish()?;
+        self.verify_written(&writers)?;
+        self.write_parity(&writers)?;
+
+        failpoints::eval(failpoints::ROLLOVER_REGISTER)?;
+        let segment_ids = self.manifest.register(writers)?;
+        self.notify_registered(&segment_ids);
+
+        if let Some(remapped) = remapped {
+            self.remaps
+                .insert(&*self.config.fs, &self.path.join(REMAP_FOLDER), remapped)?;
         }
 
-        report
+        self.metrics.record(LatencyOp::RolloverCommit, commit_timer);
+
+        // NOTE: If we crash here, it's fine, the segments are registered
+        // but never referenced, so they can just be dropped after recovery
+        let timer = Timer::start();
+        failpoints::eval(failpoints::ROLLOVER_INDEX_FINISH)?;
+        index_writer.finish()?;
+        self.metrics.record(LatencyOp::RolloverIndex, timer);
+
+        self.advise_rollover_finished(segments);
+        self.record_rollover(segments, &segment_ids, started_at, start);
+
+        Ok(segment_ids)
     }
 
-    /// Scans the given index and collects GC statistics.
+    /// Relocates live blobs that were collected during a rollover, grouped by their
+    /// partition (see [`Config::rollover_partitioner`]) and in key order.
     ///
-    /// # Errors
+    /// The blobs' values were already decoded by the merge pass, so they are not read again.
     ///
-    /// Will return `Err` if an IO error occurs.
-    pub fn scan_for_stats(
+    /// Every partition starts a new segment.
+    fn relocate_collected<W: IndexWriter>(
+        relocator: &mut Relocator<'_, C, W>,
+        mut collected: Vec<CollectedBlob>,
+    ) -> crate::Result<()> {
+        collected.sort_by(|a, b| (a.partition, &a.key).cmp(&(b.partition, &b.key)));
+
+        let mut prev_partition = None;
+
+        for blob in collected {
+            if prev_partition.is_some_and(|x| x != blob.partition) {
+                relocator.writer.start_new_segment()?;
+            }
+            prev_partition = Some(blob.partition);
+
+            relocator.relocate(
+                blob.key,
+                &blob.value,
+                blob.vhandle,
+                blob.indexed,
+                blob.checksum,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the writer that the live blobs of the given segments are relocated into.
+    fn relocation_writer(
         &self,
-        iter: impl Iterator<Item = std::io::Result<(ValueHandle, u32)>>,
-    ) -> crate::Result<GcReport> {
-        let lock_guard = self.rollover_guard.lock().expect("lock is poisoned");
+        segments: &[Arc<Segment<C>>],
+        expired_ids: &[SegmentId],
+        pipeline: Pipeline<C>,
+        tags: &SegmentTags,
+        target_size: Option<u64>,
+    ) -> crate::Result<SegmentWriter<C>> {
+        let mut writer = self.get_writer_raw()?.use_pipeline(pipeline);
 
-        let ids = self.manifest.list_segment_ids();
-        let mut scanner = Scanner::new(iter, lock_guard, &ids);
-        scanner.scan()?;
-        let size_map = scanner.finish();
-        let report = self.consume_scan_result(&size_map);
+        if let Some(target_size) = target_size {
+            writer = writer.use_target_size(target_size);
+        }
 
-        Ok(report)
+        for (key, value) in tags {
+            writer = writer.with_tag(key.as_str(), value.as_str());
+        }
+
+        // NOTE: Relocated blobs keep the age of their oldest source segment,
+        // so rewriting a segment does not extend its retention
+        let created_at = segments
+            .iter()
+            .filter(|x| !expired_ids.contains(&x.id))
+            .map(|x| x.meta.created_at)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|x| x.into_iter().min());
+
+        if let Some(created_at) = created_at {
+            writer = writer.with_created_at(created_at);
+        }
+
+        Ok(writer)
     }
 
-    #[doc(hidden)]
-    pub fn get_reader(&self) -> crate::Result<MergeReader<C>> {
-        let segments = self.manifest.segments.read().expect("lock is poisoned");
+    /// Appends a finished rollover to the GC history.
+    fn record_rollover(
+        &self,
+        segments: &[Arc<Segment<C>>],
+        segment_ids: &[SegmentId],
+        started_at: u64,
+        start: Instant,
+    ) {
+        let input_bytes = segments
+            .iter()
+            .map(|x| x.meta.compressed_bytes)
+            .sum::<u64>();
 
-        let readers = segments
-            .values()
-            .map(|x| x.scan())
-            .collect::<crate::Result<Vec<_>>>()?;
+        let output_bytes = segment_ids
+            .iter()
+            .filter_map(|id| self.manifest.get_segment(*id))
+            .map(|x| x.meta.compressed_bytes)
+            .sum::<u64>();
 
-        Ok(MergeReader::new(readers))
+        self.record_gc(GcHistoryEntry {
+            operation: GcOperation::Rollover,
+            started_at,
+            duration: start.elapsed(),
+            input_segment_ids: segments.iter().map(|x| x.id).collect(),
+            output_segment_ids: segment_ids.to_vec(),
+            bytes_reclaimed: input_bytes.saturating_sub(output_bytes),
+        });
     }
 
+    /// Rewrites some segments into new segment(s), blocking the caller
+    /// until the operation is completely done.
+    ///
     /// Returns the amount of disk space (compressed data) freed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
     #[doc(hidden)]
-    pub fn major_compact<R: IndexReader, W: IndexWriter>(
+    pub fn rollover<R: IndexReader, W: IndexWriter>(
         &self,
+        ids: &[u64],
         index_reader: &R,
         index_writer: W,
     ) -> crate::Result<u64> {
-        let ids = self.manifest.list_segment_ids();
-        self.rollover(&ids, index_reader, index_writer)
+        self.rollover_with_pipeline(
+            ids,
+            self.survivor_pipeline(None),
+            &SegmentTags::new(),
+            index_reader,
+            index_writer,
+        )
     }
 
-    /// Applies a GC strategy.
+    /// Rewrites some segments into new segment(s), like [`ValueLog::rollover`], but
+    /// determines which blobs are live using an iterator over the index's entries,
+    /// instead of looking up every blob in the index.
+    ///
+    /// This allows a single ordered pass over the index (e.g. a snapshot iterator),
+    /// instead of a random read per blob. Only the handles that point into the rewritten
+    /// segments are kept in memory.
+    ///
+    /// The iterator needs to yield the current value handle of every key in the index
+    /// (at least of the keys that point into the rewritten segments).
+    ///
+    /// Returns the amount of disk space (compressed data) freed.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    pub fn apply_gc_strategy<R: IndexReader, W: IndexWriter>(
+    pub fn rollover_with_live_handles<W: IndexWriter>(
         &self,
-        strategy: &impl GcStrategy<C>,
-        index_reader: &R,
+        ids: &[SegmentId],
+        live_handles: impl Iterator<Item = std::io::Result<(UserKey, ValueHandle)>>,
         index_writer: W,
     ) -> crate::Result<u64> {
-        let segment_ids = strategy.pick(self);
-        self.rollover(&segment_ids, index_reader, index_writer)
+        let index_reader =
+            LiveHandles::collect(live_handles, ids, |vhandle| self.resolve_handle(vhandle))?;
+        self.rollover(ids, &index_reader, index_writer)
     }
 
-    /// Rewrites some segments into new segment(s), blocking the caller
-    /// until the operation is completely done.
+    /// Returns the IDs of segments that are not encrypted with the given key version,
+    /// including unencrypted segments.
+    ///
+    /// These are the segments that need to be rewritten to complete a key rotation.
+    #[must_use]
+    pub fn outdated_key_segments(&self, key_version: u32) -> Vec<SegmentId> {
+        self.manifest
+            .read_segments()
+            .values()
+            .filter(|x| x.meta.key_version != Some(key_version))
+            .map(|x| x.id)
+            .collect()
+    }
+
+    /// Re-encrypts the live blobs of some segments under the given key version,
+    /// blocking the caller until the operation is completely done.
+    ///
+    /// Like a rollover, the segments are rewritten into new segment(s) and then marked
+    /// as stale, so they can be dropped once no reads may access them anymore.
+    ///
+    /// The configured [`Encryptor`] needs to be able to encrypt using the new key version,
+    /// and decrypt using the key versions of the rewritten segments.
     ///
     /// Returns the amount of disk space (compressed data) freed.
     ///
     /// # Errors
     ///
+    /// Will return `Err` if an IO error occurs, or no encryptor is configured.
+    pub fn rewrite_with_key<R: IndexReader, W: IndexWriter>(
+        &self,
+        ids: &[SegmentId],
+        key_version: u32,
+        index_reader: &R,
+        index_writer: W,
+    ) -> crate::Result<u64> {
+        if self.config.encryption.is_none() {
+            log::error!(
+                "Cannot rewrite segments with key version {key_version} without an encryptor"
+            );
+            return Err(crate::Error::Encrypt);
+        }
+
+        self.rollover_with_pipeline(
+            ids,
+            self.survivor_pipeline(Some(key_version)),
+            &SegmentTags::new(),
+            index_reader,
+            index_writer,
+        )
+    }
+
+    /// Returns the IDs of segments that are rewritten with the cold compressor
+    /// by the next maintenance run (see [`Config::cold_compression`]).
+    #[must_use]
+    pub fn recompression_candidates(&self) -> Vec<SegmentId> {
+        let Some((_, min_age)) = &self.config.cold_compression else {
+            return vec![];
+        };
+
+        let attributes = self.manifest.attributes();
+
+        let mut segment_ids = self
+            .manifest
+            .read_segments()
+            .values()
+            .filter(|x| !x.is_stale() && x.is_expired(*min_age))
+            .filter(|x| !attributes.pinned.contains(&x.id))
+            .filter(|x| {
+                attributes
+                    .tags
+                    .get(&x.id)
+                    .and_then(|tags| tags.get(RECOMPRESSED_TAG.0))
+                    .map_or(true, |value| value != RECOMPRESSED_TAG.1)
+            })
+            .filter(|x| self.segment_temperature(x) == Temperature::Cold)
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+
+        segment_ids.sort_unstable();
+        segment_ids
+    }
+
+    /// Closes segment file handles that were not used for the configured timeout
+    /// (see [`Config::idle_file_timeout`]).
+    ///
+    /// This is also done by [`ValueLog::run_maintenance`].
+    ///
+    /// Returns the amount of closed handles.
+    #[must_use]
+    pub fn close_idle_files(&self) -> usize {
+        let timeout = self.config.idle_file_timeout;
+
+        self.manifest
+            .read_segments()
+            .values()
+            .filter(|segment| segment.file_slot.close_if_idle(timeout))
+            .count()
+    }
+
+    /// Returns the amount of segment file handles that are currently open for reuse
+    /// (see [`Config::idle_file_timeout`]).
+    #[must_use]
+    pub fn open_file_count(&self) -> usize {
+        self.manifest
+            .read_segments()
+            .values()
+            .filter(|segment| segment.file_slot.is_open())
+            .count()
+    }
+
+    /// Pauses maintenance (see [`ValueLog::run_maintenance`]) until the returned guard is dropped,
+    /// e.g. during backups or latency-critical windows.
+    ///
+    /// While paused, maintenance runs (including those of the background maintenance thread)
+    /// do nothing, and a run that is in progress stops before its next job.
+    /// A job that is already running is finished.
+    ///
+    /// Pauses can be nested, maintenance resumes once all guards are dropped.
+    pub fn pause_maintenance(&self) -> MaintenancePause<C> {
+        self.maintenance_pauses
+            .fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+
+        log::debug!("Paused vLog maintenance");
+
+        MaintenancePause {
+            value_log: Arc::downgrade(&self.0),
+        }
+    }
+
+    /// Resumes maintenance, releasing all pauses (see [`ValueLog::pause_maintenance`]).
+    ///
+    /// Dropping the guards of released pauses has no effect.
+    pub fn resume_maintenance(&self) {
+        self.maintenance_pauses
+            .store(0, std::sync::atomic::Ordering::Release);
+
+        log::debug!("Resumed vLog maintenance");
+    }
+
+    /// Returns `true` if maintenance is paused (see [`ValueLog::pause_maintenance`]).
+    #[must_use]
+    pub fn is_maintenance_paused(&self) -> bool {
+        self.0.is_maintenance_paused()
+    }
+
+    /// Runs the configured maintenance tasks, blocking the caller until they are done:
+    ///
+    /// - garbage collection (see [`Config::gc_policy`])
+    /// - rewriting cold segments with a stronger codec (see [`Config::cold_compression`])
+    /// - persisting read counters (see [`ValueLog::persist_read_counters`])
+    /// - closing idle segment file handles (see [`ValueLog::close_idle_files`])
+    ///
+    /// Every job commits its own index write batch, created by `make_index_writer`.
+    ///
+    /// Rewritten segments are marked as stale, so they can be dropped
+    /// once no reads may access them anymore (see [`ValueLog::drop_stale_segments`]).
+    ///
+    /// # Errors
+    ///
     /// Will return `Err` if an IO error occurs.
-    #[doc(hidden)]
-    pub fn rollover<R: IndexReader, W: IndexWriter>(
+    pub fn run_maintenance<R: IndexReader, W: IndexWriter, F: FnMut() -> W>(
+        &self,
+        index_reader: &R,
+        mut make_index_writer: F,
+    ) -> crate::Result<MaintenanceReport> {
+        let mut report = MaintenanceReport::default();
+
+        if self.is_maintenance_paused() {
+            log::debug!("Maintenance is paused, skipping");
+            return Ok(report);
+        }
+
+        if let Some(policy) = &self.config.gc_policy {
+            for (idx, segment_id) in self.gc_jobs(policy).into_iter().enumerate() {
+                if idx > 0 && !policy.throttle_pause().is_zero() {
+                    self.config.clock.sleep(policy.throttle_pause());
+                }
+
+                if self.is_maintenance_paused() {
+                    log::debug!("Maintenance was paused, stopping garbage collection");
+                    return Ok(report);
+                }
+
+                log::debug!("Garbage collecting segment #{segment_id}");
+
+                self.rollover(&[segment_id], index_reader, make_index_writer())?;
+                report.gc_segment_ids.push(segment_id);
+            }
+        }
+
+        if let Some((compressor, _)) = &self.config.cold_compression {
+            let segment_ids = self.recompression_candidates();
+
+            if !segment_ids.is_empty() {
+                log::info!("Recompressing cold segments {segment_ids:?}");
+
+                let old_bytes = segment_ids
+                    .iter()
+                    .filter_map(|id| self.manifest.get_segment(*id))
+                    .map(|x| x.meta.compressed_bytes)
+                    .sum::<u64>();
+
+                let size_before = self.manifest.disk_space_used();
+
+                let pipeline = Pipeline::for_writing(&self.config, None)
+                    .with_compression(Some(compressor.clone()));
+
+                let tags =
+                    SegmentTags::from([(RECOMPRESSED_TAG.0.into(), RECOMPRESSED_TAG.1.into())]);
+
+                self.rollover_with_pipeline(
+                    &segment_ids,
+                    pipeline,
+                    &tags,
+                    index_reader,
+                    make_index_writer(),
+                )?;
+
+                // NOTE: The old segments are not dropped yet, so the growth is the size of the new segments
+                let new_bytes = self.manifest.disk_space_used().saturating_sub(size_before);
+
+                report.recompressed_segment_ids = segment_ids;
+                report.recompression_bytes_saved = old_bytes.saturating_sub(new_bytes);
+            }
+        }
+
+        self.persist_read_counters()?;
+
+        let closed_files = self.close_idle_files();
+        if closed_files > 0 {
+            log::debug!("Closed {closed_files} idle segment file handles");
+        }
+
+        Ok(report)
+    }
+
+    /// Returns the segments the GC policy rewrites in a maintenance run.
+    fn gc_jobs(&self, policy: &GcPolicy) -> Vec<SegmentId> {
+        let mut segment_ids = self.pick_candidates(policy);
+
+        // NOTE: Fully stale segments only need to be dropped, not rewritten
+        segment_ids.retain(|id| {
+            self.manifest
+                .get_segment(*id)
+                .is_some_and(|x| !x.is_stale())
+        });
+
+        // NOTE: Most blobs die young, so young segments are the most worthwhile to rewrite
+        if self.config.old_generation.is_some() {
+            let attributes = self.manifest.attributes();
+            segment_ids.sort_by_key(|id| Generation::from_tags(attributes.tags.get(id)));
+        }
+        segment_ids.truncate(policy.job_limit());
+
+        segment_ids
+    }
+
+    /// Explains for every segment why it would or would not be rewritten by garbage
+    /// collection in the next maintenance run (see [`Config::gc_policy`]),
+    /// ordered by segment ID.
+    #[must_use]
+    pub fn explain_gc_choice(&self) -> Vec<GcDecision> {
+        let policy = self.config.gc_policy.as_ref();
+
+        let jobs = policy.map(|x| self.gc_jobs(x)).unwrap_or_default();
+        let expired = self.expired_segments();
+        let attributes = self.manifest.attributes();
+
+        let threshold = policy.and_then(|x| x.effective_stale_ratio(self));
+        let space_amp_picks = policy
+            .and_then(GcPolicy::space_amp)
+            .map(|target| (target, SpaceAmpStrategy::new(target).pick(self)));
+
+        let mut decisions = self
+            .manifest
+            .read_segments()
+            .values()
+            .map(|segment| {
+                let report = segment.gc_report();
+                let selected = jobs.contains(&segment.id);
+
+                let reason = if attributes.pinned.contains(&segment.id) {
+                    GcReason::Pinned
+                } else if segment.is_stale() {
+                    GcReason::FullyStale
+                } else if policy.is_none() {
+                    GcReason::NoPolicy
+                } else if expired.contains(&segment.id) {
+                    GcReason::Expired
+                } else if let Some(min_age) = policy
+                    .and_then(GcPolicy::min_age_limit)
+                    .filter(|x| segment.meta.created_at.is_some() && !segment.is_expired(*x))
+                {
+                    GcReason::TooYoung { min_age }
+                } else if let Some(threshold) = threshold.filter(|x| segment.stale_ratio() > *x) {
+                    GcReason::StaleThresholdExceeded { threshold }
+                } else if let Some((target, _)) = space_amp_picks
+                    .as_ref()
+                    .filter(|(_, picks)| picks.contains(&segment.id))
+                {
+                    GcReason::SpaceAmpTarget { target: *target }
+                } else {
+                    GcReason::BelowThreshold { threshold }
+                };
+
+                // NOTE: Candidates that did not fit into the run were cut off by the job limit
+                let reason = match reason {
+                    GcReason::Expired
+                    | GcReason::StaleThresholdExceeded { .. }
+                    | GcReason::SpaceAmpTarget { .. }
+                        if !selected =>
+                    {
+                        GcReason::JobLimit
+                    }
+                    reason => reason,
+                };
+
+                GcDecision {
+                    segment_id: segment.id,
+                    selected,
+                    reason,
+                    stale_ratio: report.stale_ratio,
+                    age: report.age,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        decisions.sort_by_key(|x| x.segment_id);
+
+        decisions
+    }
+
+    /// Builds the pipeline that the survivors of garbage collection are written with,
+    /// which uses the old generation's compressor, if configured (see [`Config::old_generation`]).
+    fn survivor_pipeline(&self, key_version: Option<u32>) -> Pipeline<C> {
+        let pipeline = Pipeline::for_writing(&self.config, key_version);
+
+        match &self.config.old_generation {
+            Some((compressor, _)) => pipeline.with_compression(Some(compressor.clone())),
+            None => pipeline,
+        }
+    }
+
+    /// Rewrites the live blobs of some segments into new segment(s), and marks the old segments as stale.
+    ///
+    /// If generations are enabled (see [`Config::old_generation`]), the new segments
+    /// are promoted to the old generation.
+    fn rollover_with_pipeline<R: IndexReader, W: IndexWriter>(
         &self,
         ids: &[u64],
+        pipeline: Pipeline<C>,
+        tags: &SegmentTags,
         index_reader: &R,
-        mut index_writer: W,
+        index_writer: W,
     ) -> crate::Result<u64> {
+        // IMPORTANT: Only allow 1 rollover or GC at any given time
+        let _guard = self.lock_rollover()?;
+
+        let attributes = self.manifest.attributes();
+        let ids = ids
+            .iter()
+            .copied()
+            .filter(|id| !attributes.pinned.contains(id))
+            .collect::<Vec<_>>();
+
         if ids.is_empty() {
             return Ok(0);
         }
 
-        // IMPORTANT: Only allow 1 rollover or GC at any given time
-        let _guard = self.rollover_guard.lock().expect("lock is poisoned");
-
         let size_before = self.manifest.disk_space_used();
 
         log::info!("Rollover segments {ids:?}");
@@ -527,57 +4549,28 @@ impl<C: Compressor + Clone> ValueLog<C> {
             return Ok(0);
         };
 
-        let readers = segments
-            .into_iter()
-            .map(|x| x.scan())
-            .collect::<crate::Result<Vec<_>>>()?;
-
-        // TODO: 2.0.0: Store uncompressed size per blob
-        // so we can avoid recompression costs during GC
-        // but have stats be correct
-
-        let reader = MergeReader::new(
-            readers
-                .into_iter()
-                .map(|x| x.use_compression(self.config.compression.clone()))
-                .collect(),
-        );
-
-        let mut writer = self
-            .get_writer_raw()?
-            .use_compression(self.config.compression.clone());
-
-        for item in reader {
-            let (k, v, segment_id, _) = item?;
-
-            match index_reader.get(&k)? {
-                // If this value is in an older segment, we can discard it
-                Some(vhandle) if segment_id < vhandle.segment_id => continue,
-                None => continue,
-                _ => {}
+        let (tags, target_size) = match &self.config.old_generation {
+            Some((_, segment_size)) => {
+                let mut tags = tags.clone();
+                tags.insert(PROMOTED_TAG.0.into(), PROMOTED_TAG.1.into());
+                (tags, Some(*segment_size))
             }
+            None => (tags.clone(), None),
+        };
 
-            let vhandle = writer.get_next_value_handle();
-
-            // NOTE: Truncation is OK because we know values are u32 max
-            #[allow(clippy::cast_possible_truncation)]
-            index_writer.insert_indirect(&k, vhandle, v.len() as u32)?;
-
-            writer.write(&k, &v)?;
-        }
-
-        // IMPORTANT: New segments need to be persisted before adding to index
-        // to avoid dangling pointers
-        self.manifest.register(writer)?;
-
-        // NOTE: If we crash here, it's fine, the segments are registered
-        // but never referenced, so they can just be dropped after recovery
-        index_writer.finish()?;
+        self.relocate(
+            &segments,
+            pipeline,
+            &tags,
+            target_size,
+            index_reader,
+            index_writer,
+        )?;
 
         // IMPORTANT: We only mark the segments as definitely stale
         // The external index needs to decide when it is safe to drop
         // the old segments, as some reads may still be performed
-        self.mark_as_stale(ids);
+        self.mark_as_stale(&ids);
 
         let size_after = self.manifest.disk_space_used();
 
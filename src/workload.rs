@@ -0,0 +1,420 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Synthetic workloads to evaluate garbage collection policies.
+//!
+//! A [`Workload`] writes batches of generated values into a value log, indexed by
+//! a [`MockIndex`], and periodically runs maintenance (see [`ValueLog::run_maintenance`]),
+//! which applies the configured GC policy. After every maintenance run, it records
+//! the space & write amplification, so policies can be compared reproducibly:
+//! the same seed always generates the same workload.
+//!
+//! ```
+//! # use value_log::{workload::{ValueSize, Workload}, Config, GcPolicy, MockIndex, ValueLog};
+//! #
+//! # #[derive(Clone, Default)]
+//! # struct MyCompressor;
+//! #
+//! # impl value_log::Compressor for MyCompressor {
+//! #    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! #
+//! #    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+//! #        Ok(bytes.into())
+//! #    }
+//! # }
+//! # fn main() -> value_log::Result<()> {
+//! # let folder = tempfile::tempdir()?;
+//! # let path = folder.path();
+//! let config = Config::<MyCompressor>::default().gc_policy(GcPolicy::default().stale_threshold(0.5));
+//! let value_log = ValueLog::open(path, config)?;
+//!
+//! let report = Workload::new(/* seed */ 42)
+//!     .key_count(1_000)
+//!     .value_size(ValueSize::Uniform { min: 100, max: 1_000 })
+//!     .overwrite_ratio(0.5)
+//!     .run(&value_log, &MockIndex::default(), /* batches */ 20)?;
+//!
+//! let last = report.samples.last().unwrap();
+//! assert!(last.write_amp >= 1.0);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{id::SegmentId, Compressor, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+use std::collections::HashSet;
+
+/// Distribution of generated value sizes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ValueSize {
+    /// Every value has the same size
+    Fixed(u32),
+
+    /// Sizes are distributed uniformly between `min` and `max` (inclusive)
+    Uniform {
+        /// Smallest value size
+        min: u32,
+
+        /// Largest value size
+        max: u32,
+    },
+
+    /// Values are either small or large
+    Bimodal {
+        /// Size of small values
+        small: u32,
+
+        /// Size of large values
+        large: u32,
+
+        /// Ratio of values that are large
+        large_ratio: f32,
+    },
+}
+
+/// Generator of a synthetic write workload
+///
+/// See the [module documentation](self) for more.
+#[derive(Clone, Debug)]
+pub struct Workload {
+    seed: u64,
+    key_count: u64,
+    value_size: ValueSize,
+    overwrite_ratio: f32,
+    key_skew: f64,
+    batch_size: usize,
+    maintenance_interval: usize,
+}
+
+impl Workload {
+    /// Creates a workload, which is determined by the seed and its settings.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            key_count: 10_000,
+            value_size: ValueSize::Fixed(1_024),
+            overwrite_ratio: 0.5,
+            key_skew: 1.0,
+            batch_size: 100,
+            maintenance_interval: 1,
+        }
+    }
+
+    /// Sets the maximum amount of distinct keys.
+    ///
+    /// Once all keys are written, every write is an overwrite.
+    ///
+    /// Default = 10000
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key count is 0.
+    #[must_use]
+    pub fn key_count(mut self, keys: u64) -> Self {
+        assert!(keys > 0, "key count needs to be at least 1");
+        self.key_count = keys;
+        self
+    }
+
+    /// Sets the distribution of value sizes.
+    ///
+    /// Default = 1 KiB
+    #[must_use]
+    pub fn value_size(mut self, value_size: ValueSize) -> Self {
+        self.value_size = value_size;
+        self
+    }
+
+    /// Sets the ratio of writes that overwrite an existing key.
+    ///
+    /// Default = 0.5
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratio is invalid.
+    #[must_use]
+    pub fn overwrite_ratio(mut self, ratio: f32) -> Self {
+        assert!((0.0..=1.0).contains(&ratio), "invalid overwrite ratio");
+        self.overwrite_ratio = ratio;
+        self
+    }
+
+    /// Sets the skew of overwritten keys.
+    ///
+    /// The key is picked as `floor(n * r^skew)`, where `r` is uniformly distributed in `[0, 1)`
+    /// and `n` is the amount of keys written so far. A skew of 1 picks keys uniformly,
+    /// a larger skew concentrates overwrites on a small set of hot keys.
+    ///
+    /// Default = 1.0
+    ///
+    /// # Panics
+    ///
+    /// Panics if the skew is < 1.0.
+    #[must_use]
+    pub fn key_skew(mut self, skew: f64) -> Self {
+        assert!(skew >= 1.0, "invalid key skew");
+        self.key_skew = skew;
+        self
+    }
+
+    /// Sets the amount of values per write batch (and segment writer).
+    ///
+    /// Default = 100
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch size is 0.
+    #[must_use]
+    pub fn batch_size(mut self, values: usize) -> Self {
+        assert!(values > 0, "batch size needs to be at least 1");
+        self.batch_size = values;
+        self
+    }
+
+    /// Sets the amount of write batches between two maintenance runs.
+    ///
+    /// Default = 1
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is 0.
+    #[must_use]
+    pub fn maintenance_interval(mut self, batches: usize) -> Self {
+        assert!(batches > 0, "maintenance interval needs to be at least 1");
+        self.maintenance_interval = batches;
+        self
+    }
+
+    /// Writes the given amount of batches into the value log.
+    ///
+    /// After every `maintenance_interval` batches (and after the last one), the GC statistics
+    /// are refreshed from the index (see [`ValueLog::scan_for_stats`]), maintenance is run
+    /// and stale segments are dropped, then a sample is recorded.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn run<C: Compressor + Clone>(
+        &self,
+        value_log: &ValueLog<C>,
+        index: &MockIndex,
+        batches: usize,
+    ) -> crate::Result<WorkloadReport> {
+        let mut state = RunState {
+            rng: self.seed.max(1),
+            next_key: index.len() as u64,
+            seen_segments: value_log.manifest.list_segment_ids().into_iter().collect(),
+            user_bytes_written: 0,
+            bytes_written: 0,
+        };
+
+        let mut report = WorkloadReport::default();
+
+        for batch in 1..=batches {
+            self.write_batch(value_log, index, &mut state)?;
+
+            if batch % self.maintenance_interval == 0 || batch == batches {
+                value_log.scan_for_stats(
+                    index
+                        .range(..)
+                        .into_iter()
+                        .map(|(_, vhandle, size)| Ok((vhandle, size))),
+                )?;
+                value_log.run_maintenance(index, || MockIndexWriter(index.clone()))?;
+                value_log.drop_stale_segments()?;
+
+                state.track_new_segments(value_log);
+                report.samples.push(state.sample(batch, value_log, index));
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn write_batch<C: Compressor + Clone>(
+        &self,
+        value_log: &ValueLog<C>,
+        index: &MockIndex,
+        state: &mut RunState,
+    ) -> crate::Result<()> {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for _ in 0..self.batch_size {
+            let key = self.next_key(state);
+            let value_len = self.next_value_size(state);
+
+            // NOTE: The value is derived from the key, so it can be checked after GC
+            let value = key.iter().cycle().take(value_len as usize).copied();
+            let value = value.collect::<Vec<_>>();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(&key, vhandle, value_len)?;
+            writer.write(&key, value)?;
+
+            state.user_bytes_written += u64::from(value_len);
+        }
+
+        value_log.register_writer(writer)?;
+        index_writer.finish()?;
+
+        state.track_new_segments(value_log);
+
+        Ok(())
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn next_key(&self, state: &mut RunState) -> Vec<u8> {
+        let is_overwrite = state.next_key > 0
+            && (state.next_key >= self.key_count
+                || state.next_ratio() < f64::from(self.overwrite_ratio));
+
+        let idx = if is_overwrite {
+            let r = state.next_ratio().powf(self.key_skew);
+            ((state.next_key as f64 * r) as u64).min(state.next_key - 1)
+        } else {
+            state.next_key += 1;
+            state.next_key - 1
+        };
+
+        format!("key{idx:016}").into_bytes()
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn next_value_size(&self, state: &mut RunState) -> u32 {
+        match self.value_size {
+            ValueSize::Fixed(size) => size,
+            ValueSize::Uniform { min, max } => {
+                let range = u64::from(max.saturating_sub(min)) + 1;
+                min + (state.next_random() % range) as u32
+            }
+            ValueSize::Bimodal {
+                small,
+                large,
+                large_ratio,
+            } => {
+                if state.next_ratio() < f64::from(large_ratio) {
+                    large
+                } else {
+                    small
+                }
+            }
+        }
+    }
+}
+
+struct RunState {
+    rng: u64,
+
+    /// Amount of distinct keys written so far
+    next_key: u64,
+
+    /// Segments whose size was already added to the written bytes
+    seen_segments: HashSet<SegmentId>,
+
+    user_bytes_written: u64,
+    bytes_written: u64,
+}
+
+impl RunState {
+    /// xorshift64
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    /// Returns a random number in `[0, 1)`.
+    #[allow(clippy::cast_precision_loss)]
+    fn next_ratio(&mut self) -> f64 {
+        (self.next_random() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Adds the size of segments that were created since the last call to the written bytes.
+    ///
+    /// Segments that are created and dropped between two calls are not accounted,
+    /// so the write amplification is a lower bound.
+    fn track_new_segments<C: Compressor + Clone>(&mut self, value_log: &ValueLog<C>) {
+        for segment in value_log.manifest.list_segments() {
+            if self.seen_segments.insert(segment.id) {
+                self.bytes_written += segment.meta.total_uncompressed_bytes;
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn sample<C: Compressor + Clone>(
+        &self,
+        batch: usize,
+        value_log: &ValueLog<C>,
+        index: &MockIndex,
+    ) -> WorkloadSample {
+        let live_bytes = index
+            .range(..)
+            .into_iter()
+            .map(|(_, _, size)| u64::from(size))
+            .sum::<u64>();
+
+        let stored_bytes = value_log.manifest.total_bytes();
+
+        let ratio = |a: u64, b: u64| if b == 0 { 0.0 } else { a as f64 / b as f64 };
+
+        WorkloadSample {
+            batch,
+            user_bytes_written: self.user_bytes_written,
+            bytes_written: self.bytes_written,
+            live_bytes,
+            stored_bytes,
+            segment_count: value_log.segment_count(),
+            space_amp: ratio(stored_bytes, live_bytes),
+            write_amp: ratio(self.bytes_written, self.user_bytes_written),
+        }
+    }
+}
+
+/// State of the value log after a maintenance run, see [`WorkloadReport`]
+///
+/// Sizes are uncompressed value sizes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkloadSample {
+    /// Amount of write batches written so far
+    pub batch: usize,
+
+    /// Bytes written by the workload
+    pub user_bytes_written: u64,
+
+    /// Bytes written into segments, including rewrites by garbage collection
+    pub bytes_written: u64,
+
+    /// Bytes of the values that are referenced by the index
+    pub live_bytes: u64,
+
+    /// Bytes of all values in the value log's segments
+    pub stored_bytes: u64,
+
+    /// Amount of segments
+    pub segment_count: usize,
+
+    /// Stored bytes per live byte
+    pub space_amp: f64,
+
+    /// Bytes written into segments per byte written by the workload
+    pub write_amp: f64,
+}
+
+/// Result of a workload run, see [`Workload::run`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkloadReport {
+    /// Samples taken after every maintenance run, oldest first
+    pub samples: Vec<WorkloadSample>,
+}
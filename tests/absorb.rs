@@ -0,0 +1,169 @@
+use test_log::test;
+use value_log::{Compressor, Config, Error, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn absorb_shard() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    // NOTE: The index of the consolidated shards, which still points into the other shard
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &index, &["a", "b"])?;
+
+    {
+        let other = ValueLog::open(other_folder.path(), Config::<NoCompressor>::default())?;
+        write_items(&other, &index, &["x", "y"])?;
+        write_items(&other, &index, &["y", "z"])?;
+    }
+
+    let id_map = value_log.absorb(other_folder.path(), MockIndexWriter(index.clone()))?;
+    assert_eq!(2, id_map.len());
+    assert_eq!(3, value_log.segment_count());
+
+    for segment_id in id_map.values() {
+        assert!(value_log.manifest.get_segment(*segment_id).is_some());
+    }
+
+    assert_eq!(0, index.verify(&value_log)?);
+    for key in ["a", "b", "x", "y", "z"] {
+        let (vhandle, _) = index.read().unwrap().get(key.as_bytes()).cloned().unwrap();
+        assert_eq!(
+            key.repeat(1_000).as_bytes(),
+            &*value_log.get(&vhandle)?.unwrap()
+        );
+    }
+
+    // NOTE: The overwritten version of "y" was not relocated, so it is stale
+    let report = value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    assert_eq!(1, report.stale_blobs);
+
+    // NOTE: The other value log is left untouched
+    let other = ValueLog::open(other_folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(2, other.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn absorb_missing() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    assert!(matches!(
+        value_log.absorb(other_folder.path(), MockIndexWriter(index.clone())),
+        Err(Error::NotFound),
+    ));
+    assert_eq!(0, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn absorb_ambiguous_handle() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &MockIndex::default(), &["a", "b"])?;
+
+    // NOTE: "a" has the same segment ID & offset in both value logs,
+    // so the index could not tell which of them it points to
+    {
+        let other = ValueLog::open(other_folder.path(), Config::<NoCompressor>::default())?;
+        write_items(&other, &MockIndex::default(), &["a", "c"])?;
+    }
+
+    let index = MockIndex::default();
+    assert!(value_log
+        .absorb(other_folder.path(), MockIndexWriter(index.clone()))
+        .is_err());
+    assert_eq!(1, value_log.segment_count());
+    assert!(index.read().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn absorb_pending_remaps() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &MockIndex::default(), &["a"])?;
+
+    let other_index = MockIndex::default();
+    let other = ValueLog::open(
+        other_folder.path(),
+        Config::<NoCompressor>::default().remap_relocations(true),
+    )?;
+    write_items(&other, &other_index, &["x", "y"])?;
+    write_items(&other, &other_index, &["y"])?;
+    other.major_compact(&other_index, MockIndexWriter(other_index.clone()))?;
+
+    // NOTE: The other index still points into rewritten segments
+    assert!(matches!(
+        value_log.absorb(other_folder.path(), MockIndexWriter(index.clone())),
+        Err(Error::PendingRemaps),
+    ));
+    assert_eq!(1, value_log.segment_count());
+
+    other.apply_remaps(MockIndexWriter(other_index.clone()))?;
+    other.drop_stale_segments()?;
+
+    let id_map = value_log.absorb(other_folder.path(), MockIndexWriter(other_index.clone()))?;
+    assert_eq!(other.segment_count(), id_map.len());
+
+    for key in ["x", "y"] {
+        let (vhandle, _) = other_index
+            .read()
+            .unwrap()
+            .get(key.as_bytes())
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            key.repeat(1_000).as_bytes(),
+            &*value_log.get(&vhandle)?.unwrap()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn submit_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.submit_writer(writer)
+}
+
+#[test]
+fn background_flush_barrier() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    value_log.start_background_flush()?;
+    value_log.start_background_flush()?;
+
+    for batch in [["a", "b"], ["c", "d"], ["e", "f"]] {
+        submit_batch(&value_log, &index, &batch)?;
+    }
+
+    value_log.flush_barrier()?;
+    assert_eq!(3, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    // NOTE: Everything before the barrier is durable
+    drop(value_log);
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(3, value_log.segment_count());
+
+    for key in ["a", "b", "c", "d", "e", "f"] {
+        let vhandle = index.get(key.as_bytes())?.unwrap();
+        let item = value_log.get(&vhandle)?.unwrap();
+        assert_eq!(&*item, key.repeat(1_000).as_bytes());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn background_flush_not_started() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    // NOTE: Without the background thread, writers are registered immediately
+    submit_batch(&value_log, &index, &["a"])?;
+    assert_eq!(1, value_log.segment_count());
+
+    value_log.flush_barrier()?;
+
+    Ok(())
+}
+
+#[test]
+fn background_flush_reports_errors() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let a = ValueLog::open(folder.path().join("a"), Config::<NoCompressor>::default())?;
+    let b = ValueLog::open(folder.path().join("b"), Config::<NoCompressor>::default())?;
+    b.start_background_flush()?;
+
+    // NOTE: Writer of another value log is rejected by the flush thread
+    let mut writer = a.get_writer()?;
+    writer.write("a", "a")?;
+    b.submit_writer(writer)?;
+
+    assert!(matches!(b.flush_barrier(), Err(Error::StaleWriter)));
+
+    // NOTE: The error is only reported once
+    b.flush_barrier()?;
+    assert_eq!(0, b.segment_count());
+
+    Ok(())
+}
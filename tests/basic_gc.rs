@@ -107,3 +107,52 @@ fn basic_gc() -> value_log::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn basic_major_compact() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().segment_size_bytes(25_000),
+    )?;
+
+    let keys = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+
+    // NOTE: Write every key twice, into many small segments
+    for _ in 0..2 {
+        for key in keys {
+            let mut index_writer = MockIndexWriter(index.clone());
+            let mut writer = value_log.get_writer()?;
+
+            let value = key.repeat(10_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+            writer.write(key, value)?;
+
+            value_log.register_writer(writer)?;
+        }
+    }
+    assert_eq!(20, value_log.segment_count());
+
+    let freed = value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    assert!(freed >= 100_000);
+
+    // NOTE: 100 KB of live data fit into 4-5 segments of 25 KB
+    assert!(value_log.segment_count() <= 5);
+    assert_eq!(
+        10,
+        value_log
+            .manifest
+            .list_segments()
+            .iter()
+            .map(|x| x.len())
+            .sum::<u64>()
+    );
+    assert_eq!(0, value_log.manifest.stale_bytes());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use test_log::test;
+use value_log::{BlobCache, Compressor, Config, ValueHandle, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Halves values, and counts decompressions
+#[derive(Clone, Default)]
+struct CountingCompressor(Arc<AtomicUsize>);
+
+impl Compressor for CountingCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.iter().step_by(2).copied().collect())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(bytes.iter().flat_map(|&b| [b, b]).collect())
+    }
+}
+
+fn write_items(value_log: &ValueLog<NoCompressor>) -> value_log::Result<Vec<ValueHandle>> {
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = vec![];
+
+    for key in ["a", "b", "c"] {
+        vhandles.push(writer.get_next_value_handle());
+        writer.write(key, key.repeat(1_000))?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(vhandles)
+}
+
+#[test]
+fn cache_stats() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let vhandles = write_items(&value_log)?;
+
+    let stats = value_log.cache_stats();
+    assert_eq!(0, stats.hits);
+    assert_eq!(0, stats.misses);
+    assert_eq!(0, stats.len);
+
+    for vhandle in &vhandles {
+        value_log.get(vhandle)?.unwrap();
+        value_log.get(vhandle)?.unwrap();
+    }
+
+    let stats = value_log.cache_stats();
+    assert_eq!(3, stats.hits);
+    assert_eq!(3, stats.misses);
+    assert_eq!(3, stats.insertions);
+    assert_eq!(3_000, stats.inserted_bytes);
+    assert_eq!(0, stats.evictions);
+    assert_eq!(0, stats.evicted_bytes);
+    assert_eq!(3, stats.len);
+    assert_eq!(3_000, stats.size);
+
+    Ok(())
+}
+
+#[test]
+fn cache_stats_evictions() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().blob_cache(Arc::new(BlobCache::with_capacity_bytes(0))),
+    )?;
+    let vhandles = write_items(&value_log)?;
+
+    for vhandle in &vhandles {
+        value_log.get(vhandle)?.unwrap();
+        value_log.get(vhandle)?.unwrap();
+    }
+
+    // NOTE: No blob fits into the cache
+    let stats = value_log.cache_stats();
+    assert_eq!(0, stats.hits);
+    assert_eq!(6, stats.misses);
+    assert_eq!(6, stats.insertions);
+    assert_eq!(6, stats.evictions);
+    assert_eq!(6_000, stats.evicted_bytes);
+    assert_eq!(0, stats.len);
+
+    Ok(())
+}
+
+#[test]
+fn cache_stores_decompressed_values() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let compressor = CountingCompressor::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<CountingCompressor>::default().compression(compressor.clone()),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(1_000))?;
+    value_log.register_writer(writer)?;
+
+    for _ in 0..3 {
+        assert_eq!(
+            "a".repeat(1_000).as_bytes(),
+            &*value_log.get(&vhandle)?.unwrap()
+        );
+    }
+
+    // NOTE: Hot values are only decompressed once, and weighed by their decompressed size
+    assert_eq!(1, compressor.0.load(Ordering::Relaxed));
+    assert_eq!(1_000, value_log.cache_stats().size);
+
+    Ok(())
+}
+
+#[test]
+fn value_cache_holds_decompressed_values() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let compressor = CountingCompressor::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<CountingCompressor>::default()
+            .compression(compressor.clone())
+            .value_cache(Arc::new(BlobCache::with_capacity_bytes(1_000_000))),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(1_000))?;
+    value_log.register_writer(writer)?;
+
+    for _ in 0..3 {
+        assert_eq!(
+            "a".repeat(1_000).as_bytes(),
+            &*value_log.get(&vhandle)?.unwrap()
+        );
+    }
+    assert_eq!(1, compressor.0.load(Ordering::Relaxed));
+
+    // NOTE: The blob cache holds the compressed blob, the value cache the decompressed value
+    assert_eq!(500, value_log.cache_stats().size);
+
+    let stats = value_log.value_cache_stats().unwrap();
+    assert_eq!(1_000, stats.size);
+    assert_eq!(2, stats.hits);
+
+    let memory = value_log.memory_usage();
+    assert_eq!(500, memory.blob_cache);
+    assert_eq!(1_000, memory.value_cache);
+
+    Ok(())
+}
+
+#[test]
+fn value_cache_falls_back_to_blob_cache() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let compressor = CountingCompressor::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<CountingCompressor>::default()
+            .compression(compressor.clone())
+            .value_cache(Arc::new(BlobCache::with_capacity_bytes(0))),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(1_000))?;
+    value_log.register_writer(writer)?;
+
+    for _ in 0..3 {
+        assert_eq!(
+            "a".repeat(1_000).as_bytes(),
+            &*value_log.get(&vhandle)?.unwrap()
+        );
+    }
+
+    // NOTE: No value fits into the value cache, so the cached blob is decompressed on every read
+    assert_eq!(3, compressor.0.load(Ordering::Relaxed));
+    assert_eq!(2, value_log.cache_stats().hits);
+    assert_eq!(0, value_log.value_cache_stats().unwrap().len);
+
+    Ok(())
+}
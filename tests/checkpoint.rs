@@ -0,0 +1,70 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn checkpoint_point_in_time() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path().join("vlog");
+    let checkpoint_path = folder.path().join("checkpoint");
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(&vl_path, Config::<NoCompressor>::default())?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c", "d", "e"] {
+            let value = key.repeat(10_000);
+            let value = value.as_bytes();
+
+            let key = key.as_bytes();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let snapshot = index.read().unwrap().clone();
+    value_log.checkpoint(&checkpoint_path)?;
+
+    assert!(value_log.checkpoint(&checkpoint_path).is_err());
+
+    // NOTE: Make everything stale and drop it in the source value log
+    for key in ["a", "b", "c", "d", "e"] {
+        index.remove(key.as_bytes());
+    }
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(0, value_log.segment_count());
+
+    let checkpoint = ValueLog::open(&checkpoint_path, Config::<NoCompressor>::default())?;
+    assert_eq!(1, checkpoint.segment_count());
+
+    for (key, (vhandle, _)) in &snapshot {
+        let item = checkpoint.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(10_000));
+    }
+
+    assert_eq!(0, checkpoint.verify()?);
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ScanFilter, ValueLog,
+};
+
+#[derive(Clone, Debug, Default)]
+struct Lz4Compressor;
+impl Compressor for Lz4Compressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        lz4_flex::decompress_size_prepended(bytes).map_err(|_| value_log::Error::Decompress)
+    }
+}
+
+fn value(key: &str) -> Vec<u8> {
+    match key {
+        // NOTE: Not a multiple of the chunk size
+        "big" => (0..10_500u32).map(|x| (x % 251) as u8).collect(),
+        _ => key.repeat(100).into_bytes(),
+    }
+}
+
+#[test]
+fn chunked_values() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<Lz4Compressor>::default().blob_chunk_size(1_000),
+    )?;
+
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in ["a", "big", "c"] {
+        let value = value(key);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, &value)?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    assert_eq!(3, segment.meta.item_count);
+
+    let metas = segment
+        .scan_meta()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    let chunk_counts = metas.iter().map(|x| x.chunk_count).collect::<Vec<_>>();
+    assert_eq!(vec![0, 11, 0], chunk_counts);
+
+    // NOTE: Records are contiguous
+    for pair in metas.windows(2) {
+        assert_eq!(pair[0].offset + pair[0].record_len(), pair[1].offset);
+    }
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        assert!(value_log.contains(vhandle)?);
+
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, value(std::str::from_utf8(key).unwrap()));
+    }
+
+    assert_eq!(0, value_log.verify()?);
+
+    let items = value_log
+        .scan_blobs()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(3, items.len());
+    for (key, _, item) in items {
+        assert_eq!(&*item, value(std::str::from_utf8(&key).unwrap()));
+    }
+
+    let keys = segment
+        .scan_rev()?
+        .map(|x| x.map(|(k, _, _)| k))
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(keys, [&b"c"[..], b"big", b"a"]);
+
+    // NOTE: Filtered scans return the stored (compressed) chunks
+    let filtered = segment
+        .scan_filtered(|key| {
+            if key == b"c" {
+                ScanFilter::Yield
+            } else {
+                ScanFilter::Skip
+            }
+        })?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(1, filtered.len());
+
+    // NOTE: Relocated values stay chunked
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    assert!(segment
+        .scan_meta()?
+        .any(|x| x.is_ok_and(|x| x.chunk_count == 11)));
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, value(std::str::from_utf8(key).unwrap()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn chunked_values_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<Lz4Compressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("big", value("big"))?;
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    for meta in segment.scan_meta()? {
+        assert_eq!(0, meta?.chunk_count);
+    }
+
+    assert_eq!(&*value_log.get(&vhandle)?.unwrap(), value("big"));
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use test_log::test;
+use value_log::{
+    BlobCache, Compressor, Config, GcOperation, GcPolicy, IndexWriter, ManualClock, MockIndex,
+    MockIndexWriter, StaleThresholdStrategy, Temperature, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const START: u64 = 1_700_000_000_000;
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn clock_segment_created_at() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(START));
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .clock(clock.clone())
+            .retention(Duration::from_secs(86_400)),
+    )?;
+
+    write_items(&value_log, &index, &["a"])?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    assert_eq!(Some(START), segment.meta.created_at);
+
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(Some(Duration::from_secs(60)), segment.gc_report().age);
+
+    Ok(())
+}
+
+#[test]
+fn clock_retention() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(START));
+
+    let config = Config::<NoCompressor>::default()
+        .clock(clock.clone())
+        .retention(Duration::from_secs(3_600));
+
+    {
+        let value_log = ValueLog::open(folder.path(), config.clone())?;
+
+        write_items(&value_log, &index, &["a", "b"])?;
+        assert!(value_log.expired_segments().is_empty());
+    }
+
+    clock.advance(Duration::from_secs(3_600));
+
+    // NOTE: The creation time is persisted, so the segment expires across restarts
+    let value_log = ValueLog::open(folder.path(), config)?;
+    let old_id = value_log.manifest.list_segment_ids()[0];
+
+    write_items(&value_log, &index, &["c"])?;
+    assert_eq!(vec![old_id], value_log.expired_segments());
+
+    let strategy = StaleThresholdStrategy::new(0.5);
+    value_log.apply_gc_strategy(&strategy, &index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(1, value_log.segment_count());
+    assert!(value_log.expired_segments().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn clock_gc_policy_min_age() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(START));
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .clock(clock.clone())
+            .gc_policy(
+                GcPolicy::default()
+                    .stale_threshold(0.4)
+                    .min_age(Duration::from_secs(3_600)),
+            ),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b"])?;
+    write_items(&value_log, &index, &["a"])?;
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.gc_segment_ids.is_empty());
+
+    clock.advance(Duration::from_secs(3_600));
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report.gc_segment_ids.len());
+
+    Ok(())
+}
+
+#[test]
+fn clock_gc_throttle() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(START));
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .clock(clock.clone())
+            .gc_policy(
+                GcPolicy::default()
+                    .stale_threshold(0.4)
+                    .throttle(Duration::from_secs(3_600)),
+            ),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b"])?;
+    write_items(&value_log, &index, &["c", "d"])?;
+    write_items(&value_log, &index, &["a", "c"])?;
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    // NOTE: The manual clock does not block, so the pause between the 2 jobs is skipped
+    let start = Instant::now();
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(2, report.gc_segment_ids.len());
+    assert!(start.elapsed() < Duration::from_secs(60));
+
+    let started_at = value_log
+        .gc_history()
+        .into_iter()
+        .filter(|x| x.operation == GcOperation::Rollover)
+        .map(|x| x.started_at)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![START, START + 3_600_000], started_at);
+
+    Ok(())
+}
+
+#[test]
+fn clock_read_rate_decay() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(START));
+
+    // NOTE: Disable the blob cache, so every read hits the segment
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .clock(clock.clone())
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .read_rate_half_life(Duration::from_secs(1))
+            .temperature_thresholds(0.5, 20.0),
+    )?;
+
+    write_items(&value_log, &index, &["a"])?;
+
+    let (vhandle, _) = index.read().unwrap().get("a".as_bytes()).cloned().unwrap();
+
+    for _ in 0..100 {
+        value_log.get(&vhandle)?;
+    }
+    assert_eq!(
+        Some(Temperature::Hot),
+        value_log.temperature(vhandle.segment_id)
+    );
+    assert_eq!(
+        Some(START),
+        value_log
+            .read_counters(vhandle.segment_id)
+            .unwrap()
+            .last_read_at
+    );
+
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(
+        Some(Temperature::Warm),
+        value_log.temperature(vhandle.segment_id)
+    );
+
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(
+        Some(Temperature::Cold),
+        value_log.temperature(vhandle.segment_id)
+    );
+
+    Ok(())
+}
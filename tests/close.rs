@@ -0,0 +1,168 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn submit_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.submit_writer(writer)
+}
+
+#[test]
+fn vlog_close() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    value_log.start_background_flush()?;
+
+    submit_batch(&value_log, &index, &["a", "b"])?;
+    let writer = value_log.get_writer()?;
+    let other_writer = value_log.get_writer()?;
+
+    let clone = value_log.clone();
+    value_log.close()?;
+    assert!(clone.is_closed());
+
+    // NOTE: Closing again has no effect
+    value_log.close()?;
+
+    // NOTE: Submitted writers are durable after closing
+    assert_eq!(1, value_log.segment_count());
+
+    let vhandle = index.get(b"a")?.unwrap();
+    assert!(matches!(clone.get(&vhandle), Err(Error::Closed)));
+    assert!(matches!(clone.get_writer(), Err(Error::Closed)));
+    assert!(matches!(clone.register_writer(writer), Err(Error::Closed)));
+    assert!(matches!(
+        clone.submit_writer(other_writer),
+        Err(Error::Closed)
+    ));
+    assert!(matches!(clone.flush(), Err(Error::Closed)));
+    assert!(matches!(clone.drop_stale_segments(), Err(Error::Closed)));
+    assert!(matches!(
+        clone.major_compact(&index, MockIndexWriter(index.clone())),
+        Err(Error::Closed)
+    ));
+
+    drop(value_log);
+    drop(clone);
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert!(!value_log.is_closed());
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn vlog_flush_on_drop() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        value_log.start_background_flush()?;
+
+        submit_batch(&value_log, &index, &["a", "b"])?;
+        submit_batch(&value_log, &index, &["c"])?;
+        value_log.flush()?;
+
+        submit_batch(&value_log, &index, &["d"])?;
+    }
+
+    // NOTE: Dropping the last handle waits for queued writers
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(3, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn vlog_flush_on_drop_after_upgrade() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        value_log.start_background_flush()?;
+
+        // NOTE: Every finished batch of a checked index upgrades (and drops) a handle
+        let checked = MockIndex::checked(&value_log);
+        for _ in 0..3 {
+            MockIndexWriter(checked.clone()).finish()?;
+        }
+
+        submit_batch(&value_log, &index, &["a", "b"])?;
+        submit_batch(&value_log, &index, &["c"])?;
+    }
+
+    // NOTE: Dropping the last handle still waits for queued writers
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(2, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn vlog_close_releases_lock() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    value_log.start_background_flush()?;
+    submit_batch(&value_log, &MockIndex::default(), &["a"])?;
+
+    assert!(matches!(
+        ValueLog::open(folder.path(), Config::<NoCompressor>::default()),
+        Err(Error::Locked)
+    ));
+
+    // NOTE: The closed handle is still alive, but does not hold the lock anymore
+    value_log.close()?;
+
+    let reopened = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(1, reopened.segment_count());
+
+    // NOTE: Dropping the last handle releases the lock as well
+    drop(reopened);
+    let reopened = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(1, reopened.segment_count());
+
+    drop(value_log);
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::{sync::Arc, time::Duration};
+use test_log::test;
+use value_log::{BlobCache, Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+/// Stores values as is, or LZ4-compressed, prefixed with a marker,
+/// so either output can be decompressed by any instance
+#[derive(Clone, Default)]
+struct TieredCompressor {
+    strong: bool,
+}
+
+impl Compressor for TieredCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        if self.strong {
+            let mut out = vec![1];
+            out.extend(lz4_flex::compress_prepend_size(bytes));
+            Ok(out)
+        } else {
+            let mut out = vec![0];
+            out.extend_from_slice(bytes);
+            Ok(out)
+        }
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        match bytes.split_first() {
+            Some((0, rest)) => Ok(rest.into()),
+            Some((1, rest)) => {
+                lz4_flex::decompress_size_prepended(rest).map_err(|_| value_log::Error::Decompress)
+            }
+            _ => Err(value_log::Error::Decompress),
+        }
+    }
+}
+
+#[test]
+fn cold_recompression() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<TieredCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .temperature_thresholds(0.05, 20.0)
+            .cold_compression(TieredCompressor { strong: true }, Duration::ZERO),
+    )?;
+
+    let mut handles = vec![];
+
+    for key in ["a", "b", "c"] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let value = key.repeat(10_000);
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), value.len() as u32)?;
+        writer.write(key, value)?;
+
+        value_log.register_writer(writer)?;
+        handles.push(vhandle);
+    }
+
+    // NOTE: The first segment is hot, so it is not recompressed
+    for _ in 0..100 {
+        value_log.get(&handles[0])?;
+    }
+
+    let mut cold_ids = vec![handles[1].segment_id, handles[2].segment_id];
+    cold_ids.sort_unstable();
+    assert_eq!(cold_ids, value_log.recompression_candidates());
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(cold_ids, report.recompressed_segment_ids);
+    assert!(report.recompression_bytes_saved > 10_000);
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(2, value_log.segment_count());
+
+    // NOTE: Recompressed segments are not recompressed again
+    assert!(value_log.recompression_candidates().is_empty());
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.recompressed_segment_ids.is_empty());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, &*key.repeat(10_000));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn cold_recompression_min_age() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<TieredCompressor>::default().cold_compression(
+            TieredCompressor { strong: true },
+            Duration::from_secs(3_600),
+        ),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    writer.write("a", "hello")?;
+    value_log.register_writer(writer)?;
+
+    assert!(value_log.recompression_candidates().is_empty());
+
+    Ok(())
+}
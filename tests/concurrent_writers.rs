@@ -0,0 +1,113 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn concurrent_writers_reserve_ids() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut a = value_log.get_writer()?;
+    let mut b = value_log.get_writer()?;
+    assert_ne!(
+        a.get_next_value_handle().segment_id,
+        b.get_next_value_handle().segment_id,
+    );
+
+    a.write("a", "a")?;
+    b.write("b", "b")?;
+
+    // NOTE: Register in reverse order of creation
+    value_log.register_writer(b)?;
+    value_log.register_writer(a)?;
+
+    assert_eq!(2, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writers_parallel_register() -> value_log::Result<()> {
+    const THREADS: usize = 8;
+    const ITEMS: usize = 1_000;
+
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().segment_size_bytes(100_000),
+    )?;
+
+    std::thread::scope(|s| {
+        let handles = (0..THREADS)
+            .map(|thread_no| {
+                let value_log = value_log.clone();
+                let index = index.clone();
+
+                s.spawn(move || -> value_log::Result<()> {
+                    let mut index_writer = MockIndexWriter(index);
+                    let mut writer = value_log.get_writer()?;
+
+                    for idx in 0..ITEMS {
+                        let key = format!("{thread_no}-{idx:0>5}");
+                        let value = key.repeat(10);
+
+                        let vhandle = writer.get_next_value_handle();
+                        index_writer.insert_indirect(
+                            key.as_bytes(),
+                            vhandle,
+                            value.len() as u32,
+                        )?;
+
+                        writer.write(key, value)?;
+                    }
+
+                    value_log.register_writer(writer)?;
+                    index_writer.finish()?;
+
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+
+        Ok::<_, value_log::Error>(())
+    })?;
+
+    assert_eq!(THREADS * ITEMS, index.len());
+    assert_eq!(
+        (THREADS * ITEMS) as u64,
+        value_log
+            .manifest
+            .list_segments()
+            .iter()
+            .map(|x| x.len())
+            .sum::<u64>(),
+    );
+    assert_eq!(0, index.verify(&value_log)?);
+
+    // NOTE: Everything survives recovery
+    drop(value_log);
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::{sync::Arc, time::Duration};
+use test_log::test;
+use value_log::{Compressor, Config, Error, ManualClock, ValueHandle, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn vlog_contains() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut handles = vec![];
+
+    for key in ["a", "b", "c"] {
+        handles.push(writer.get_next_value_handle());
+        writer.write(key, key.repeat(1_000))?;
+    }
+
+    // NOTE: Not registered yet
+    assert!(!value_log.contains(&handles[0])?);
+
+    value_log.register_writer(writer)?;
+
+    let segment_id = handles[0].segment_id;
+
+    for vhandle in &handles {
+        assert!(value_log.contains(vhandle)?);
+    }
+
+    // NOTE: Points into the middle of a value
+    assert!(!value_log.contains(&ValueHandle {
+        segment_id,
+        offset: handles[0].offset + 100,
+    })?);
+
+    // NOTE: Points past the end of the segment file
+    assert!(!value_log.contains(&ValueHandle {
+        segment_id,
+        offset: 1_000_000,
+    })?);
+
+    // NOTE: Segment does not exist
+    assert!(!value_log.contains(&ValueHandle {
+        segment_id: segment_id + 1,
+        offset: 0,
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn vlog_contains_expired() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let clock = Arc::new(ManualClock::new(1_000_000));
+
+    let config = Config::<NoCompressor>::default()
+        .clock(clock.clone())
+        .retention(Duration::from_secs(60));
+
+    let value_log = ValueLog::open(folder.path(), config.clone())?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(1_000))?;
+    value_log.register_writer(writer)?;
+
+    assert!(value_log.contains(&vhandle)?);
+
+    clock.advance(Duration::from_secs(60));
+
+    // NOTE: The blob is still on disk, but cannot be read anymore
+    assert!(value_log.get(&vhandle)?.is_none());
+    assert!(!value_log.contains(&vhandle)?);
+    drop(value_log);
+
+    let value_log = ValueLog::open(folder.path(), config.expired_reads_error(true))?;
+    assert!(matches!(
+        value_log.contains(&vhandle),
+        Err(Error::Expired(id)) if id == vhandle.segment_id,
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use test_log::test;
+use value_log::{Compressor, Config, ContentAddressedValueLog, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn content_addressed() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let (a, b, empty) = {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        let store = ContentAddressedValueLog::open(value_log)?;
+
+        let mut writer = store.get_writer()?;
+        let a = writer.write("a".repeat(100_000))?;
+        let a2 = writer.write("a".repeat(100_000))?;
+        let b = writer.write("b")?;
+        let empty = writer.write("")?;
+        store.register_writer(writer)?;
+
+        assert_eq!(a, a2);
+        assert_ne!(a, b);
+        assert_eq!(3, store.len());
+        assert_eq!(2, store.refcount(a));
+        assert_eq!(
+            3,
+            store.value_log().manifest.list_segments()[0]
+                .meta
+                .item_count
+        );
+
+        // NOTE: Identical values written by another writer are not stored again
+        let mut writer = store.get_writer()?;
+        assert_eq!(a, writer.write("a".repeat(100_000))?);
+        store.register_writer(writer)?;
+
+        assert_eq!(3, store.refcount(a));
+        assert_eq!(
+            3,
+            store
+                .value_log()
+                .manifest
+                .list_segments()
+                .iter()
+                .map(|x| x.meta.item_count)
+                .sum::<u64>(),
+        );
+
+        (a, b, empty)
+    };
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let store = ContentAddressedValueLog::open(value_log)?;
+
+    assert_eq!(&*store.get(a)?.unwrap(), "a".repeat(100_000).as_bytes());
+    assert_eq!(&*store.get(b)?.unwrap(), b"b");
+    assert!(store.get(empty)?.unwrap().is_empty());
+
+    store.release(&[a, a, a])?;
+    assert!(!store.contains(a));
+    assert!(store.get(a)?.is_none());
+
+    store.major_compact()?;
+    store.value_log().drop_stale_segments()?;
+
+    assert_eq!(2, store.len());
+    assert_eq!(0, store.scan_for_stats()?.stale_blobs);
+    assert_eq!(&*store.get(b)?.unwrap(), b"b");
+
+    Ok(())
+}
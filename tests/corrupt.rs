@@ -0,0 +1,135 @@
+#![cfg(feature = "test_utils")]
+
+use std::sync::Arc;
+use test_log::test;
+use value_log::{
+    test_utils::{self, MockIndex, MockIndexWriter},
+    BlobCache, Compressor, Config, ErrorCategory, IndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+// NOTE: Disable the blob cache, so every read hits the segment
+fn config() -> Config<NoCompressor> {
+    Config::<NoCompressor>::default().blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<Vec<ValueHandle>> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = vec![];
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), value.len() as u32)?;
+        vhandles.push(vhandle);
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    Ok(vhandles)
+}
+
+#[test]
+fn corrupt_flip_blob_byte() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+    let vhandles = write_items(&value_log, &index, &["a", "b", "c"])?;
+    assert_eq!(0, value_log.verify()?);
+
+    test_utils::flip_blob_byte(&value_log, &vhandles[1], 500)?;
+    assert_eq!(1, value_log.verify()?);
+
+    let value = value_log.get(&vhandles[1])?.unwrap();
+    assert_eq!(!b'b', value[500]);
+    assert_eq!(
+        &*value_log.get(&vhandles[2])?.unwrap(),
+        "c".repeat(1_000).as_bytes()
+    );
+
+    assert!(test_utils::flip_blob_byte(&value_log, &vhandles[1], 1_000).is_err());
+
+    // NOTE: Flipping the byte back restores the blob
+    test_utils::flip_blob_byte(&value_log, &vhandles[1], 500)?;
+    assert_eq!(0, value_log.verify()?);
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_zero_blob_checksum() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+    let vhandles = write_items(&value_log, &index, &["a", "b"])?;
+
+    test_utils::zero_blob_checksum(&value_log, &vhandles[0])?;
+    assert_eq!(1, value_log.verify()?);
+
+    // NOTE: Only the checksum is damaged
+    assert_eq!(
+        &*value_log.get(&vhandles[0])?.unwrap(),
+        "a".repeat(1_000).as_bytes()
+    );
+
+    let invalid = ValueHandle {
+        segment_id: vhandles[0].segment_id,
+        offset: vhandles[0].offset + 1,
+    };
+    assert!(test_utils::zero_blob_checksum(&value_log, &invalid).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_truncate_segment_mid_blob() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), config().parity_shards(1))?;
+    let vhandles = write_items(&value_log, &index, &["a", "b", "c"])?;
+
+    let len = test_utils::truncate_segment_mid_blob(&value_log, &vhandles[1])?;
+    assert!(len > vhandles[1].offset && len < vhandles[2].offset);
+
+    assert!(value_log.get(&vhandles[0])?.is_some());
+
+    let err = value_log.get(&vhandles[1]).unwrap_err();
+    assert_eq!(ErrorCategory::Corruption, err.category());
+
+    assert!(value_log.repair_segment(vhandles[1].segment_id)? > 0);
+    assert_eq!(0, value_log.verify()?);
+    assert_eq!(
+        &*value_log.get(&vhandles[1])?.unwrap(),
+        "b".repeat(1_000).as_bytes()
+    );
+
+    drop(value_log);
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
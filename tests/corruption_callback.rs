@@ -0,0 +1,148 @@
+use std::{
+    io::{Seek, SeekFrom, Write},
+    sync::{Arc, Mutex},
+};
+use test_log::test;
+use value_log::{
+    BlobCache, Compressor, Config, CorruptionReport, CorruptionSource, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const VALUE_SIZE: usize = 1_000;
+
+fn damage(path: &std::path::Path, offset: u64, len: usize) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&vec![0xFF; len])?;
+    file.sync_all()
+}
+
+#[test]
+fn corruption_callback() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let reports = Arc::new(Mutex::new(Vec::<CorruptionReport>::new()));
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .corruption_callback({
+                let reports = reports.clone();
+                Arc::new(move |report| reports.lock().unwrap().push(report.clone()))
+            }),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = Vec::<ValueHandle>::new();
+
+    for key in [b'a', b'b', b'c'] {
+        vhandles.push(writer.get_next_value_handle());
+        writer.write([key], vec![key; VALUE_SIZE])?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    assert_eq!(0, value_log.verify()?);
+    assert!(reports.lock().unwrap().is_empty());
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    let vhandle_b = vhandles.get(1).unwrap();
+    let vhandle_c = vhandles.get(2).unwrap();
+
+    // NOTE: Damage the end of b's value, so only its checksum does not match
+    damage(&segment.path, vhandle_c.offset - 10, 10)?;
+
+    assert_eq!(1, value_log.verify()?);
+    assert_eq!(
+        vec![CorruptionReport {
+            source: CorruptionSource::Verify,
+            segment_id: segment.id,
+            offset: Some(vhandle_b.offset),
+            key: Some((*b"b").into()),
+        }],
+        std::mem::take(&mut *reports.lock().unwrap()),
+    );
+
+    // NOTE: Damage c's blob header, so it cannot be read
+    damage(&segment.path, vhandle_c.offset, 4)?;
+
+    assert!(value_log.get(vhandle_c).is_err());
+    assert_eq!(
+        vec![CorruptionReport {
+            source: CorruptionSource::Read,
+            segment_id: segment.id,
+            offset: Some(vhandle_c.offset),
+            key: None,
+        }],
+        std::mem::take(&mut *reports.lock().unwrap()),
+    );
+
+    // NOTE: Intact blobs are not reported
+    assert!(value_log.get(vhandles.first().unwrap())?.is_some());
+    assert!(reports.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn corruption_callback_prefetch() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let reports = Arc::new(Mutex::new(Vec::<CorruptionReport>::new()));
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .corruption_callback({
+                let reports = reports.clone();
+                Arc::new(move |report| reports.lock().unwrap().push(report.clone()))
+            }),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = Vec::<ValueHandle>::new();
+
+    for key in [b'a', b'b', b'c'] {
+        vhandles.push(writer.get_next_value_handle());
+        writer.write([key], vec![key; VALUE_SIZE])?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    let vhandle_c = vhandles.get(2).unwrap();
+
+    // NOTE: Damage c's blob header, so it cannot be prefetched
+    damage(&segment.path, vhandle_c.offset, 4)?;
+
+    // NOTE: The requested blob is intact, so it is returned anyway
+    assert_eq!(
+        vec![b'a'; VALUE_SIZE],
+        &*value_log
+            .get_with_prefetch(vhandles.first().unwrap(), 2)?
+            .unwrap(),
+    );
+    assert_eq!(
+        vec![CorruptionReport {
+            source: CorruptionSource::Read,
+            segment_id: segment.id,
+            offset: Some(vhandle_c.offset),
+            key: None,
+        }],
+        std::mem::take(&mut *reports.lock().unwrap()),
+    );
+
+    Ok(())
+}
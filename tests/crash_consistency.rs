@@ -0,0 +1,138 @@
+#![cfg(feature = "simulation")]
+
+use test_log::test;
+use value_log::{
+    sim::{self, DirSnapshot},
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn crash_snapshot_restore() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let restored = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b"])?;
+
+    let snapshot = DirSnapshot::capture(folder.path())?;
+    assert!(snapshot
+        .files()
+        .any(|(path, len)| path.starts_with("segments") && len > 2_000));
+
+    snapshot.restore(restored.path())?;
+    assert_eq!(snapshot, DirSnapshot::capture(restored.path())?);
+
+    let value_log = ValueLog::open(restored.path(), Config::<NoCompressor>::default())?;
+    sim::verify_recovery(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn crash_torn_segment_write() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b"])?;
+
+    // NOTE: Crash while a writer is writing, before it is registered
+    let mut writer = value_log.get_writer()?;
+    writer.write("c", "c".repeat(1_000))?;
+    writer.write("d", "d".repeat(1_000))?;
+    let snapshot = DirSnapshot::capture(folder.path())?;
+    drop(writer);
+    drop(value_log);
+
+    let (unfinished, len) = snapshot
+        .files()
+        .find(|(path, _)| path.starts_with("segments") && !path.ends_with("0"))
+        .map(|(path, len)| (path.to_path_buf(), len))
+        .unwrap();
+
+    for torn_len in (0..=len).step_by(97) {
+        let restored = tempfile::tempdir()?;
+
+        let mut snapshot = snapshot.clone();
+        assert!(snapshot.truncate_file(&unfinished, torn_len));
+        snapshot.restore(restored.path())?;
+
+        let value_log = ValueLog::open(restored.path(), Config::<NoCompressor>::default())?;
+        sim::verify_recovery(&value_log, &index)?;
+        assert_eq!(1, value_log.segment_count());
+
+        // NOTE: The value log needs to be writable again after recovery
+        write_batch(&value_log, &index, &["e"])?;
+        sim::verify_recovery(&value_log, &index)?;
+    }
+
+    assert!(!snapshot.clone().truncate_file("missing", 0));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic = "was not cleaned up"]
+fn crash_verify_recovery_orphaned_file() {
+    let folder = tempfile::tempdir().unwrap();
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+    write_batch(&value_log, &index, &["a"]).unwrap();
+
+    std::fs::write(folder.path().join("segments").join("1000"), b"orphan").unwrap();
+    sim::verify_recovery(&value_log, &index).unwrap();
+}
+
+#[test]
+fn crash_torn_registered_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b"])?;
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    drop(value_log);
+
+    // NOTE: Registered segments are durable, so tearing one is detected as corruption
+    let segment = folder.path().join("segments").join(segment_id.to_string());
+    sim::truncate_file(&segment, 1_500)?;
+
+    assert!(ValueLog::open(folder.path(), Config::<NoCompressor>::default()).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn debug_dump() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().relocation_batch_size(10),
+    )?;
+
+    let mut writer = value_log.get_writer()?.with_tag("origin", "test");
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle.clone(), 3)?;
+    writer.write("a", "abc")?;
+    value_log.register_writer(writer)?;
+
+    let segment_id = vhandle.segment_id;
+    value_log.pin_segment(segment_id)?;
+    assert!(value_log.get(&vhandle)?.is_some());
+
+    let dump = value_log.debug_dump();
+
+    assert_eq!(value_log.summary(), dump.manifest);
+    assert_eq!(10, dump.config.relocation_batch_size);
+    assert!(!dump.config.encryption);
+
+    assert_eq!(1, dump.segments.len());
+    let segment = dump.segments.first().unwrap();
+    assert_eq!(segment_id, segment.summary.id);
+    assert_eq!(1, segment.summary.item_count);
+    assert!(segment.is_pinned);
+    assert!(!segment.is_stale);
+    assert_eq!(Some(&"test".to_string()), segment.tags.get("origin"));
+    assert_eq!(1, segment.reads);
+    assert!(segment.is_file_open);
+
+    assert_eq!(1, dump.blob_cache.len);
+    assert!(dump.blob_cache.size > 0);
+
+    assert!(!dump.in_flight.rollover);
+    assert!(!dump.in_flight.maintenance_paused);
+    assert!(!dump.in_flight.closed);
+    assert_eq!(1, dump.in_flight.open_files);
+
+    assert!(dump.gc_history.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn debug_dump_in_flight() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let pause = value_log.pause_maintenance();
+    let guard = value_log.rollover_guard.lock().unwrap();
+
+    let dump = value_log.debug_dump();
+    assert!(dump.in_flight.rollover);
+    assert!(dump.in_flight.maintenance_paused);
+
+    drop(guard);
+    drop(pause);
+
+    let dump = value_log.debug_dump();
+    assert!(!dump.in_flight.rollover);
+    assert!(!dump.in_flight.maintenance_paused);
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn debug_dump_serializable() {
+    fn assert_serde<T: serde::Serialize + for<'de> serde::Deserialize<'de>>() {}
+
+    assert_serde::<value_log::DebugDump>();
+}
@@ -0,0 +1,175 @@
+use test_log::test;
+use value_log::{Chunker, Compressor, Config, DedupValueLog, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.max(1);
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state.to_le_bytes()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn chunker_split() {
+    let chunker = Chunker::new(256, 1_024, 4_096);
+
+    let value = random_bytes(1, 100_000);
+    let chunks = chunker.split(&value);
+
+    assert!(chunks.len() > 1);
+    assert_eq!(value, chunks.concat());
+    assert!(chunks.iter().all(|x| x.len() <= 4_096));
+    assert!(chunks.iter().rev().skip(1).all(|x| x.len() >= 256));
+
+    // NOTE: Inserting bytes at the start only changes the first chunks
+    let mut shifted = b"hello".to_vec();
+    shifted.extend_from_slice(&value);
+    let shifted_chunks = chunker.split(&shifted);
+
+    let shared = shifted_chunks.iter().filter(|x| chunks.contains(x)).count();
+    assert!(shared >= chunks.len() - 2);
+
+    assert_eq!(vec![b"abc"], chunker.split(b"abc"));
+    assert!(chunker.split(b"").is_empty());
+}
+
+#[test]
+fn dedup_write_read() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let shared = random_bytes(2, 50_000);
+
+    let a = [&shared[..], &random_bytes(3, 1_000)].concat();
+    let b = [&random_bytes(4, 1_000), &shared[..]].concat();
+
+    let (handle_a, handle_b, chunk_count) = {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        let dedup = DedupValueLog::open(value_log, Chunker::new(256, 1_024, 4_096))?;
+
+        let mut writer = dedup.get_writer()?;
+        let handle_a = writer.write(&a)?;
+        let handle_b = writer.write(&b)?;
+        dedup.register_writer(writer)?;
+
+        assert_eq!(a.len() as u64, handle_a.size);
+        assert_eq!(&*dedup.get(&handle_a)?.unwrap(), a);
+        assert_eq!(&*dedup.get(&handle_b)?.unwrap(), b);
+
+        // NOTE: Most chunks are shared
+        let total_chunks = handle_a.chunks.len() + handle_b.chunks.len();
+        assert!(dedup.chunk_count() < total_chunks * 2 / 3);
+        assert!(dedup.value_log().manifest.disk_space_used() < (a.len() + b.len()) as u64 * 2 / 3);
+
+        (handle_a, handle_b, dedup.chunk_count())
+    };
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let dedup = DedupValueLog::open(value_log, Chunker::new(256, 1_024, 4_096))?;
+    assert_eq!(chunk_count, dedup.chunk_count());
+
+    let handle_a = value_log::CompositeHandle::from_bytes(&handle_a.to_bytes())?;
+    assert_eq!(&*dedup.get(&handle_a)?.unwrap(), a);
+    assert_eq!(&*dedup.get(&handle_b)?.unwrap(), b);
+
+    // NOTE: Writing an existing value does not store any chunk
+    let mut writer = dedup.get_writer()?;
+    let handle_c = writer.write(&a)?;
+    dedup.register_writer(writer)?;
+    assert_eq!(handle_a, handle_c);
+    assert_eq!(chunk_count, dedup.chunk_count());
+    assert_eq!(
+        2,
+        dedup.refcount(handle_a.chunks[handle_a.chunks.len() - 1])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dedup_gc_respects_refcounts() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let dedup = DedupValueLog::open(value_log, Chunker::new(256, 1_024, 4_096))?;
+
+    let shared = random_bytes(5, 50_000);
+
+    let a = [&shared[..], &random_bytes(6, 20_000)].concat();
+    let b = [&random_bytes(7, 20_000), &shared[..]].concat();
+
+    let mut writer = dedup.get_writer()?;
+    let handle_a = writer.write(&a)?;
+    let handle_b = writer.write(&b)?;
+    dedup.register_writer(writer)?;
+
+    let report = dedup.scan_for_stats()?;
+    assert_eq!(0, report.stale_blobs);
+
+    dedup.release(&[handle_a.clone()])?;
+
+    let report = dedup.scan_for_stats()?;
+    assert!(report.stale_blobs > 0);
+    assert!(report.stale_blobs < report.total_blobs);
+
+    let disk_space_before = dedup.value_log().manifest.disk_space_used();
+    assert!(dedup.major_compact()? > 0);
+    assert!(dedup.value_log().manifest.disk_space_used() < disk_space_before);
+
+    let report = dedup.scan_for_stats()?;
+    assert_eq!(0, report.stale_blobs);
+
+    // NOTE: The shared chunks are still referenced by B
+    assert_eq!(&*dedup.get(&handle_b)?.unwrap(), b);
+    assert!(dedup.get(&handle_a)?.is_none());
+
+    dedup.release(&[handle_b])?;
+    assert_eq!(0, dedup.chunk_count());
+
+    Ok(())
+}
+
+#[test]
+fn dedup_unregistered_writer() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let dedup = DedupValueLog::open(value_log, Chunker::new(256, 1_024, 4_096))?;
+
+    let value = random_bytes(8, 10_000);
+
+    let mut writer = dedup.get_writer()?;
+    let handle = writer.write(&value)?;
+    dedup.register_writer(writer)?;
+
+    let hash = handle.chunks[0];
+    assert_eq!(1, dedup.refcount(hash));
+
+    {
+        let mut writer = dedup.get_writer()?;
+        writer.write(&value)?;
+        assert_eq!(2, dedup.refcount(hash));
+    }
+
+    // NOTE: References of dropped writers are released again
+    assert_eq!(1, dedup.refcount(hash));
+
+    Ok(())
+}
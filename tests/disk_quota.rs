@@ -0,0 +1,134 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexWriter, MockIndex, MockIndexWriter, SegmentBuilder, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const VALUE_SIZE: usize = 10_000;
+
+/// Fits 2 values, but not 3
+const MAX_DISK_USAGE: u64 = 3 * VALUE_SIZE as u64;
+
+#[test]
+fn disk_quota() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().max_disk_usage(MAX_DISK_USAGE),
+    )?;
+
+    {
+        let mut writer = value_log.get_writer()?;
+        writer.write("a", vec![0; VALUE_SIZE])?;
+        writer.write("b", vec![0; VALUE_SIZE])?;
+        assert!(matches!(
+            writer.write("c", vec![0; VALUE_SIZE]),
+            Err(Error::QuotaExceeded)
+        ));
+    }
+
+    // NOTE: Dropping the writer releases its reserved space
+    let mut writer = value_log.get_writer()?;
+    writer.write("a", vec![0; VALUE_SIZE])?;
+    writer.write("b", vec![0; VALUE_SIZE])?;
+    value_log.register_writer(writer)?;
+
+    let mut writer = value_log.get_writer()?;
+    assert!(matches!(
+        writer.write("c", vec![0; VALUE_SIZE]),
+        Err(Error::QuotaExceeded)
+    ));
+    writer.write("c", "small")?;
+    value_log.register_writer(writer)?;
+    assert_eq!(2, value_log.segment_count());
+
+    let mut builder = SegmentBuilder::<NoCompressor>::new(folder.path().join("prepared"))?;
+    builder.write("d", vec![0; VALUE_SIZE])?;
+    let path = builder.finish()?;
+
+    assert!(matches!(
+        value_log.ingest_segment(path),
+        Err(Error::QuotaExceeded)
+    ));
+    assert_eq!(2, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn disk_quota_emergency_gc() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .max_disk_usage(MAX_DISK_USAGE)
+            .emergency_gc(true),
+    )?;
+
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["a", "b"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, VALUE_SIZE as u32)?;
+        writer.write(key, vec![0; VALUE_SIZE])?;
+    }
+    value_log.register_writer(writer)?;
+
+    // NOTE: Nothing is stale yet, so GC cannot free any space
+    let mut writer = value_log.get_writer()?;
+    assert!(matches!(
+        writer.write("c", vec![0; VALUE_SIZE]),
+        Err(Error::QuotaExceeded)
+    ));
+    drop(writer);
+    assert_eq!(1, value_log.segment_count());
+
+    // NOTE: Delete all keys, so the segment becomes stale
+    index.write().unwrap().clear();
+    value_log.scan_for_stats(std::iter::empty())?;
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["c", "d"] {
+        writer.write(key, vec![0; VALUE_SIZE])?;
+    }
+    assert_eq!(0, value_log.segment_count());
+
+    value_log.register_writer(writer)?;
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn disk_quota_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().max_disk_usage(0),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["a", "b", "c", "d"] {
+        writer.write(key, vec![0; VALUE_SIZE])?;
+    }
+    value_log.register_writer(writer)?;
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in keys {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+#[test]
+fn disk_space_breakdown() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let breakdown = value_log.disk_space_breakdown()?;
+    assert_eq!(0, breakdown.live_blob_bytes);
+    assert_eq!(0, breakdown.stale_blob_bytes);
+    assert_eq!(0, breakdown.format_overhead_bytes);
+    assert_eq!(0, breakdown.quarantined_bytes);
+    assert!(breakdown.manifest_bytes > 0);
+
+    write_items(&value_log, &index, &["a", "b", "c", "d"])?;
+    write_items(&value_log, &index, &["a", "b"])?;
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let breakdown = value_log.disk_space_breakdown()?;
+    assert_eq!(4_000, breakdown.live_blob_bytes);
+    assert_eq!(2_000, breakdown.stale_blob_bytes);
+    assert!(breakdown.format_overhead_bytes > 0);
+    assert_eq!(0, breakdown.quarantined_bytes);
+    assert_eq!(
+        value_log.manifest.disk_space_used(),
+        breakdown.live_blob_bytes + breakdown.stale_blob_bytes,
+    );
+
+    let segment_file_bytes = value_log
+        .manifest
+        .list_segments()
+        .iter()
+        .map(|x| std::fs::metadata(&x.path).map(|x| x.len()))
+        .sum::<std::io::Result<u64>>()?;
+    assert_eq!(
+        segment_file_bytes,
+        breakdown.live_blob_bytes + breakdown.stale_blob_bytes + breakdown.format_overhead_bytes,
+    );
+
+    Ok(())
+}
+
+#[test]
+fn disk_space_breakdown_quarantined() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+
+    // NOTE: Enough to exceed the writer's buffer
+    for key in 0..20_u32 {
+        writer.write(key.to_be_bytes(), "a".repeat(1_000))?;
+    }
+
+    let breakdown = value_log.disk_space_breakdown()?;
+    assert_eq!(0, breakdown.live_blob_bytes);
+    assert!(breakdown.quarantined_bytes > 0);
+
+    value_log.register_writer(writer)?;
+
+    let breakdown = value_log.disk_space_breakdown()?;
+    assert_eq!(20_000, breakdown.live_blob_bytes);
+    assert_eq!(0, breakdown.quarantined_bytes);
+
+    Ok(())
+}
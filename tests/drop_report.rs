@@ -0,0 +1,69 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn vlog_drop_report() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().discard_on_drop(true),
+    )?;
+
+    let index = MockIndex::default();
+
+    for _ in 0..2 {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 10_000)?;
+            writer.write(key, key.repeat(10_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let report = value_log.drop_stale_segments_with_report()?;
+    assert!(report.segment_ids.is_empty());
+    assert_eq!(0, report.bytes_freed);
+    assert_eq!(0, report.disk_bytes_freed);
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+
+    let first_segment = value_log.manifest.get_segment(ids[0]).unwrap();
+    let file_size = std::fs::metadata(&first_segment.path)?.len();
+
+    // NOTE: The first segment is completely overwritten by the second one
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let report = value_log.drop_stale_segments_with_report()?;
+    assert_eq!(vec![ids[0]], report.segment_ids);
+    assert_eq!(first_segment.meta.compressed_bytes, report.bytes_freed);
+    assert!(report.disk_bytes_freed > 0);
+
+    #[cfg(unix)]
+    assert!(report.disk_bytes_freed >= file_size);
+    #[cfg(not(unix))]
+    assert_eq!(file_size, report.disk_bytes_freed);
+
+    assert!(!first_segment.path.try_exists()?);
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
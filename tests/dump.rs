@@ -0,0 +1,114 @@
+use test_log::test;
+use value_log::{dump::SegmentDump, Compressor, Config, PipelineStage, ValueLog};
+
+#[derive(Clone, Default)]
+struct Lz4Compressor;
+
+impl Compressor for Lz4Compressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        lz4_flex::decompress_size_prepended(bytes).map_err(|_| value_log::Error::Decompress)
+    }
+}
+
+const ITEMS: [(&str, usize); 4] = [("a", 10), ("ab", 2_500), ("abc", 100), ("b", 10)];
+
+fn config() -> Config<Lz4Compressor> {
+    Config::<Lz4Compressor>::default()
+        .blob_chunk_size(1_000)
+        .key_restart_interval(8)
+}
+
+#[test]
+fn dump_segment_file() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = vec![];
+
+    for (key, value_len) in ITEMS {
+        vhandles.push(writer.get_next_value_handle());
+        writer.write(key, key.repeat(value_len))?;
+    }
+    value_log.register_writer(writer)?;
+
+    let path = value_log
+        .manifest
+        .list_segments()
+        .pop()
+        .unwrap()
+        .path
+        .clone();
+    drop(value_log);
+
+    let dump = SegmentDump::open(&path)?;
+    assert_eq!(vhandles[0].segment_id, dump.segment_id());
+    assert_eq!(4, dump.metadata().item_count);
+    assert_eq!(vec![PipelineStage::Compression], dump.metadata().pipeline);
+
+    let records = dump.collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(4, records.len());
+
+    for ((record, vhandle), (key, _)) in records.iter().zip(&vhandles).zip(ITEMS) {
+        assert_eq!(vhandle.offset, record.offset);
+        assert_eq!(key.as_bytes(), &*record.key);
+        assert!(record.is_checksum_valid);
+        assert!(record.value.is_none());
+    }
+
+    assert_eq!(
+        vec![(false, false), (true, true), (false, true), (false, false)],
+        records
+            .iter()
+            .map(|x| (x.layout.chunked, x.layout.prefixed))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(5, records[1].chunk_count);
+    assert!(records[1].stored_len < 5_000);
+
+    let dump = SegmentDump::open(&path)?.decode_with(&config())?;
+
+    for (record, (key, value_len)) in dump.zip(ITEMS) {
+        assert_eq!(
+            Some(key.repeat(value_len).as_bytes()),
+            record?.value.as_deref()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dump_segment_checksum_mismatch() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(100))?;
+    writer.write("b", "b".repeat(100))?;
+    value_log.register_writer(writer)?;
+
+    let mut bytes = std::fs::read(&value_log.manifest.list_segments().pop().unwrap().path)?;
+
+    // NOTE: Damage the last byte of the first blob's stored value
+    let record = SegmentDump::from_bytes(vhandle.segment_id, bytes.clone())?
+        .next()
+        .unwrap()?;
+    bytes[(record.offset + record.len - 1) as usize] ^= 0xFF;
+
+    let records = SegmentDump::from_bytes(vhandle.segment_id, bytes.clone())?
+        .map(|x| x.map(|x| x.is_checksum_valid))
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(vec![false, true], records);
+
+    assert!(SegmentDump::from_bytes(vhandle.segment_id, bytes[..100].to_vec()).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use test_log::test;
+use value_log::{
+    Compressor, Config, Encryptor, Error, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Toy cipher that XORs every byte with a per-segment key stream
+struct XorEncryptor(u32);
+
+impl XorEncryptor {
+    #[allow(clippy::cast_possible_truncation)]
+    fn apply(segment_id: u64, key_version: u32, bytes: &[u8]) -> Vec<u8> {
+        let seed = (segment_id as u8) ^ (key_version as u8) ^ 0xA5;
+
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(idx, b)| b ^ seed.wrapping_add(idx as u8))
+            .collect()
+    }
+}
+
+impl Encryptor for XorEncryptor {
+    fn key_version(&self) -> u32 {
+        self.0
+    }
+
+    fn encrypt(
+        &self,
+        segment_id: u64,
+        key_version: u32,
+        bytes: &[u8],
+    ) -> value_log::Result<Vec<u8>> {
+        Ok(Self::apply(segment_id, key_version, bytes))
+    }
+
+    fn decrypt(
+        &self,
+        segment_id: u64,
+        key_version: u32,
+        bytes: &[u8],
+    ) -> value_log::Result<Vec<u8>> {
+        if key_version > self.0 {
+            return Err(Error::Decrypt);
+        }
+        Ok(Self::apply(segment_id, key_version, bytes))
+    }
+}
+
+fn config(key_version: u32) -> Config<NoCompressor> {
+    Config::<NoCompressor>::default().encryption(Arc::new(XorEncryptor(key_version)))
+}
+
+#[test]
+fn vlog_encryption() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(folder.path(), config(3))?;
+
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let value = key.repeat(1_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+
+        for segment in value_log.manifest.list_segments() {
+            assert_eq!(Some(3), segment.meta.key_version);
+
+            let bytes = std::fs::read(&segment.path)?;
+            assert!(!bytes.windows(100).any(|x| x == "a".repeat(100).as_bytes()));
+        }
+
+        assert_eq!(0, value_log.verify()?);
+
+        for (key, (vhandle, _)) in index.read().unwrap().iter() {
+            let item = value_log.get(vhandle)?.unwrap();
+            assert_eq!(&*item, key.repeat(1_000));
+        }
+
+        let items = value_log
+            .scan_blobs()?
+            .collect::<value_log::Result<Vec<_>>>()?;
+        assert_eq!(3, items.len());
+        for (key, _, value) in items {
+            assert_eq!(&*value, key.repeat(1_000));
+        }
+    }
+
+    {
+        // NOTE: Old segments keep using their key version,
+        // rewritten segments use the new one
+        let value_log = ValueLog::open(folder.path(), config(4))?;
+
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        value_log.drop_stale_segments()?;
+
+        for segment in value_log.manifest.list_segments() {
+            assert_eq!(Some(4), segment.meta.key_version);
+        }
+
+        for (key, (vhandle, _)) in index.read().unwrap().iter() {
+            let item = value_log.get(vhandle)?.unwrap();
+            assert_eq!(&*item, key.repeat(1_000));
+        }
+    }
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+        for (_, (vhandle, _)) in index.read().unwrap().iter() {
+            assert!(matches!(value_log.get(vhandle), Err(Error::Decrypt)));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn vlog_encryption_unencrypted_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write("a", "a".repeat(1_000))?;
+    value_log.register_writer(writer)?;
+
+    for segment in value_log.manifest.list_segments() {
+        assert_eq!(None, segment.meta.key_version);
+    }
+    drop(value_log);
+
+    // NOTE: Unencrypted segments stay readable after enabling encryption
+    let value_log = ValueLog::open(folder.path(), config(1))?;
+    assert_eq!(
+        &*value_log.get(&vhandle)?.unwrap(),
+        "a".repeat(1_000).as_bytes()
+    );
+
+    Ok(())
+}
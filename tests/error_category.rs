@@ -0,0 +1,73 @@
+use std::error::Error as _;
+use test_log::test;
+use value_log::{Compressor, Config, Error, ErrorCategory, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn error_category() {
+    assert_eq!(
+        ErrorCategory::Corruption,
+        Error::ChecksumMismatch.category()
+    );
+    assert_eq!(ErrorCategory::Corruption, Error::Decompress.category());
+    assert_eq!(ErrorCategory::Corruption, Error::Unrepairable(1).category());
+    assert_eq!(
+        ErrorCategory::Config,
+        Error::InvalidVersion(None).category()
+    );
+    assert_eq!(ErrorCategory::Config, Error::MissingTransform(1).category());
+    assert_eq!(ErrorCategory::Concurrency, Error::StaleWriter.category());
+    assert_eq!(ErrorCategory::Concurrency, Error::Locked.category());
+    assert_eq!(ErrorCategory::Resource, Error::Closed.category());
+    assert_eq!(ErrorCategory::Resource, Error::QuotaExceeded.category());
+
+    assert_eq!(
+        ErrorCategory::Io,
+        Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied)).category(),
+    );
+    assert_eq!(
+        ErrorCategory::Corruption,
+        Error::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)).category(),
+    );
+}
+
+#[test]
+fn error_source_chain() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    writer.write(b"a", b"abc")?;
+    value_log.register_writer(writer)?;
+
+    // NOTE: Truncated snapshot
+    let snapshot = value_log.metadata_snapshot()?;
+    let err = value_log
+        .apply_metadata_snapshot(&snapshot[..snapshot.len() - 4])
+        .unwrap_err();
+    assert!(matches!(err, Error::Decode(_)), "{err:?}");
+    assert_eq!(ErrorCategory::Corruption, err.category());
+
+    let source = err.source().unwrap();
+    let io_error = source
+        .source()
+        .unwrap()
+        .downcast_ref::<std::io::Error>()
+        .unwrap();
+    assert_eq!(std::io::ErrorKind::UnexpectedEof, io_error.kind());
+
+    Ok(())
+}
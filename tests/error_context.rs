@@ -0,0 +1,85 @@
+use test_log::test;
+use value_log::{Compressor, Config, Error, IoContext, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn error_context_read_blob() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    writer.write(b"a", b"abc")?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write(b"b", b"def")?;
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.get_segment(vhandle.segment_id).unwrap();
+    std::fs::remove_file(&segment.path)?;
+
+    let err = value_log.get(&vhandle).unwrap_err();
+
+    let Error::IoWithContext { source, ctx } = &err else {
+        panic!("expected error with context, got {err:?}");
+    };
+    assert_eq!(std::io::ErrorKind::NotFound, source.kind());
+    assert_eq!(
+        &IoContext::new("read blob")
+            .segment_id(vhandle.segment_id)
+            .offset(vhandle.offset)
+            .path(&segment.path),
+        ctx,
+    );
+
+    assert!(err.to_string().contains(&format!(
+        "read blob segment #{} at offset {}",
+        vhandle.segment_id, vhandle.offset,
+    )));
+    assert!(std::error::Error::source(&err).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn error_context_recover() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let segment_id = {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+        let mut writer = value_log.get_writer()?;
+        let vhandle = writer.get_next_value_handle();
+        writer.write(b"a", b"abc")?;
+        value_log.register_writer(writer)?;
+
+        let segment = value_log.manifest.get_segment(vhandle.segment_id).unwrap();
+        std::fs::File::create(&segment.path)?;
+
+        vhandle.segment_id
+    };
+
+    let err = ValueLog::open(folder.path(), Config::<NoCompressor>::default())
+        .err()
+        .unwrap();
+
+    let Error::IoWithContext { ctx, .. } = &err else {
+        panic!("expected error with context, got {err:?}");
+    };
+    assert_eq!("recover", ctx.operation);
+    assert_eq!(Some(segment_id), ctx.segment_id);
+    assert!(err.io_error().is_some());
+
+    Ok(())
+}
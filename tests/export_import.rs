@@ -0,0 +1,77 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn export_import_roundtrip() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path().join("vlog");
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        &vl_path,
+        Config::<NoCompressor>::default().segment_size_bytes(25_000),
+    )?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c", "d", "e"] {
+            let value = key.repeat(10_000);
+            let value = value.as_bytes();
+
+            let key = key.as_bytes();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+    assert_eq!(2, value_log.segment_count());
+
+    let mut archive = vec![];
+    value_log.export(&mut archive)?;
+
+    let imported = ValueLog::import(
+        &*archive,
+        folder.path().join("imported"),
+        Config::<NoCompressor>::default(),
+    )?;
+    assert_eq!(2, imported.segment_count());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = imported.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(10_000));
+    }
+
+    // NOTE: Corrupt a byte inside the first segment's data
+    *archive.get_mut(200).unwrap() ^= 0xFF;
+
+    assert!(matches!(
+        ValueLog::import(
+            &*archive,
+            folder.path().join("corrupt"),
+            Config::<NoCompressor>::default(),
+        ),
+        Err(value_log::Error::ChecksumMismatch)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "failpoints")]
+
+use test_log::test;
+use value_log::{
+    failpoints::{
+        FailPoint, FailScenario, MANIFEST_WRITE, ROLLOVER_INDEX_FINISH, ROLLOVER_RELOCATE,
+        SEGMENT_FSYNC,
+    },
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const ITEMS: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn write_items(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in ITEMS {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+fn check_values(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, key.repeat(1_000));
+    }
+    Ok(())
+}
+
+#[test]
+fn failpoint_rollover_relocate() -> value_log::Result<()> {
+    let scenario = FailScenario::setup();
+
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &index)?;
+
+    let ids = value_log.manifest.list_segment_ids();
+
+    // NOTE: Fail in the middle of the rollover
+    scenario.configure(ROLLOVER_RELOCATE, FailPoint::error().skip(2).times(1));
+
+    assert!(value_log
+        .rollover(&ids, &index, MockIndexWriter(index.clone()))
+        .is_err());
+    assert_eq!(3, scenario.hits(ROLLOVER_RELOCATE));
+    assert_eq!(ids, value_log.manifest.list_segment_ids());
+    check_values(&value_log, &index)?;
+
+    // NOTE: The failure point only triggered once
+    value_log.rollover(&ids, &index, MockIndexWriter(index.clone()))?;
+    assert_eq!(8, scenario.hits(ROLLOVER_RELOCATE));
+    check_values(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn failpoint_rollover_index_finish() -> value_log::Result<()> {
+    let scenario = FailScenario::setup();
+
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &index)?;
+
+    scenario.configure(ROLLOVER_INDEX_FINISH, FailPoint::error());
+
+    assert!(value_log
+        .rollover(&[0], &index, MockIndexWriter(index.clone()))
+        .is_err());
+
+    // NOTE: The new segment was registered before the index failed
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+    assert_eq!([0, 1], *ids);
+    check_values(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn failpoint_register_writer() -> value_log::Result<()> {
+    let scenario = FailScenario::setup();
+
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    scenario.configure(SEGMENT_FSYNC, FailPoint::error().times(1));
+    assert!(write_items(&value_log, &index).is_err());
+    assert_eq!(0, value_log.segment_count());
+
+    scenario.configure(MANIFEST_WRITE, FailPoint::error().times(1));
+    assert!(write_items(&value_log, &index).is_err());
+    assert_eq!(0, value_log.segment_count());
+
+    write_items(&value_log, &index)?;
+    assert_eq!(1, value_log.segment_count());
+    check_values(&value_log, &index)?;
+
+    Ok(())
+}
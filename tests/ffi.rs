@@ -0,0 +1,194 @@
+#![cfg(feature = "ffi")]
+
+use std::{
+    collections::BTreeMap,
+    ffi::{c_int, c_void, CString},
+};
+use test_log::test;
+use value_log::ffi::*;
+
+type Index = BTreeMap<Vec<u8>, VlogValueHandle>;
+
+unsafe extern "C" fn index_get(
+    ctx: *mut c_void,
+    key: *const u8,
+    key_len: usize,
+    out: *mut VlogValueHandle,
+) -> c_int {
+    let index = &*ctx.cast::<Index>();
+    let key = std::slice::from_raw_parts(key, key_len);
+
+    match index.get(key) {
+        Some(vhandle) => {
+            *out = *vhandle;
+            VLOG_OK
+        }
+        None => VLOG_NOT_FOUND,
+    }
+}
+
+unsafe extern "C" fn index_insert(
+    ctx: *mut c_void,
+    key: *const u8,
+    key_len: usize,
+    vhandle: VlogValueHandle,
+    _size: u32,
+) -> c_int {
+    let index = &mut *ctx.cast::<Index>();
+    let key = std::slice::from_raw_parts(key, key_len);
+    index.insert(key.to_vec(), vhandle);
+    VLOG_OK
+}
+
+unsafe extern "C" fn index_finish(_ctx: *mut c_void) -> c_int {
+    VLOG_OK
+}
+
+fn get(vlog: *const VlogHandle, vhandle: VlogValueHandle) -> Option<Vec<u8>> {
+    let mut ptr = std::ptr::null_mut();
+    let mut len = 0;
+
+    unsafe {
+        match vlog_get(vlog, vhandle, &mut ptr, &mut len) {
+            VLOG_OK => {
+                let value = std::slice::from_raw_parts(ptr, len).to_vec();
+                vlog_value_free(ptr, len);
+                Some(value)
+            }
+            VLOG_NOT_FOUND => None,
+            _ => panic!("vlog_get failed"),
+        }
+    }
+}
+
+#[test]
+fn ffi_write_read_rollover() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = CString::new(folder.path().to_str().unwrap()).unwrap();
+
+    let mut index = Index::default();
+
+    unsafe {
+        let vlog = vlog_open(path.as_ptr());
+        assert!(!vlog.is_null());
+
+        let writer = vlog_writer_create(vlog);
+        assert!(!writer.is_null());
+
+        for key in ["a", "b", "c"] {
+            let value = key.repeat(1_000);
+            let mut vhandle = VlogValueHandle::default();
+
+            assert_eq!(
+                VLOG_OK,
+                vlog_writer_write(
+                    writer,
+                    key.as_ptr(),
+                    key.len(),
+                    value.as_ptr(),
+                    value.len(),
+                    &mut vhandle,
+                )
+            );
+            index.insert(key.as_bytes().to_vec(), vhandle);
+        }
+
+        // NOTE: Empty keys are rejected instead of panicking
+        assert_eq!(
+            VLOG_ERROR,
+            vlog_writer_write(
+                writer,
+                b"".as_ptr(),
+                0,
+                b"".as_ptr(),
+                0,
+                std::ptr::null_mut()
+            )
+        );
+
+        assert_eq!(VLOG_OK, vlog_register_writer(vlog, writer));
+
+        for (key, vhandle) in &index {
+            assert_eq!(get(vlog, *vhandle).unwrap(), key.repeat(1_000));
+        }
+
+        let callbacks = VlogIndex {
+            ctx: std::ptr::addr_of_mut!(index).cast::<c_void>(),
+            get: index_get,
+            insert_indirect: index_insert,
+            finish: index_finish,
+        };
+
+        let ids = [0];
+        assert!(vlog_rollover(vlog, ids.as_ptr(), ids.len(), &callbacks) >= 0);
+
+        for (key, vhandle) in &index {
+            assert_eq!(1, vhandle.segment_id);
+            assert_eq!(get(vlog, *vhandle).unwrap(), key.repeat(1_000));
+        }
+
+        assert!(get(
+            vlog,
+            VlogValueHandle {
+                segment_id: 99,
+                offset: 0
+            }
+        )
+        .is_none());
+
+        vlog_close(vlog);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "failpoints")]
+#[test]
+fn ffi_rollover_panic() -> value_log::Result<()> {
+    use value_log::failpoints::{FailPoint, FailScenario, ROLLOVER_RELOCATE};
+
+    let folder = tempfile::tempdir()?;
+    let path = CString::new(folder.path().to_str().unwrap()).unwrap();
+
+    let mut index = Index::default();
+
+    unsafe {
+        let vlog = vlog_open(path.as_ptr());
+        assert!(!vlog.is_null());
+
+        let writer = vlog_writer_create(vlog);
+        assert!(!writer.is_null());
+
+        let mut vhandle = VlogValueHandle::default();
+        assert_eq!(
+            VLOG_OK,
+            vlog_writer_write(writer, b"a".as_ptr(), 1, b"a".as_ptr(), 1, &mut vhandle)
+        );
+        index.insert(b"a".to_vec(), vhandle);
+        assert_eq!(VLOG_OK, vlog_register_writer(vlog, writer));
+
+        let callbacks = VlogIndex {
+            ctx: std::ptr::addr_of_mut!(index).cast::<c_void>(),
+            get: index_get,
+            insert_indirect: index_insert,
+            finish: index_finish,
+        };
+
+        // NOTE: The panic does not unwind into the caller, but is reported as an error
+        let scenario = FailScenario::setup();
+        scenario.configure(ROLLOVER_RELOCATE, FailPoint::panic());
+
+        let ids = [0];
+        assert_eq!(
+            i64::from(VLOG_ERROR),
+            vlog_rollover(vlog, ids.as_ptr(), ids.len(), &callbacks)
+        );
+        drop(scenario);
+
+        assert_eq!(b"a".to_vec(), get(vlog, vhandle).unwrap());
+
+        vlog_close(vlog);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use std::{collections::BTreeMap, time::Duration};
+use test_log::test;
+use value_log::{
+    format::{self, BlobHeader, BlobLayout, ManifestContents, ManifestSectionKind},
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn format_blob_header_roundtrip() -> value_log::Result<()> {
+    for (chunked, prefixed) in [(false, false), (true, false), (false, true), (true, true)] {
+        let header = BlobHeader {
+            layout: BlobLayout { chunked, prefixed },
+            checksum: 0xdead_beef,
+            shared_prefix_len: if prefixed { 3 } else { 0 },
+            key: b"key".to_vec(),
+            len: 1_000,
+        };
+
+        let mut bytes = header.encode()?;
+        let header_len = bytes.len();
+        bytes.extend_from_slice(b"value");
+
+        assert_eq!(
+            (header.clone(), header_len),
+            format::parse_blob_header(&bytes)?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn format_blob_header_invalid() -> value_log::Result<()> {
+    let header = BlobHeader {
+        layout: BlobLayout {
+            chunked: false,
+            prefixed: false,
+        },
+        checksum: 0,
+        shared_prefix_len: 0,
+        key: b"key".to_vec(),
+        len: 0,
+    };
+    let bytes = header.encode()?;
+
+    for len in 0..bytes.len() {
+        assert!(format::parse_blob_header(&bytes[..len]).is_err());
+    }
+
+    let mut bytes = bytes;
+    bytes[0] = b'X';
+    assert!(format::parse_blob_header(&bytes).is_err());
+
+    let header = BlobHeader {
+        key: vec![0; 70_000],
+        ..header
+    };
+    assert!(header.encode().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn format_parse_files() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?.with_tag("origin", "test");
+
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle.clone(), 5)?;
+    writer.write("a", "hello")?;
+    value_log.register_writer(writer)?;
+    value_log.pin_segment(vhandle.segment_id)?;
+
+    let manifest = format::parse_manifest(&std::fs::read(folder.path().join("vlog_manifest"))?)?;
+    assert_eq!(vec![vhandle.segment_id], manifest.segment_ids);
+    assert!(manifest.pinned.contains(&vhandle.segment_id));
+    assert_eq!(
+        Some(&BTreeMap::from([("origin".into(), "test".into())])),
+        manifest.tags.get(&vhandle.segment_id)
+    );
+    assert_eq!(manifest, format::parse_manifest(&manifest.encode()?)?);
+
+    let segment = std::fs::read(
+        folder
+            .path()
+            .join("segments")
+            .join(vhandle.segment_id.to_string()),
+    )?;
+    let (header, header_len) = format::parse_blob_header(&segment[vhandle.offset as usize..])?;
+    assert_eq!(b"a", &*header.key);
+    assert_eq!(5, header.len);
+    assert_eq!(b"hello", &segment[header_len..header_len + 5]);
+
+    assert!(format::parse_segment_metadata(&segment).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn format_manifest_invalid() -> value_log::Result<()> {
+    let mut contents = ManifestContents {
+        segment_ids: vec![1, 2, 3],
+        ..Default::default()
+    };
+    contents.pinned.insert(2);
+
+    let bytes = contents.encode()?;
+
+    // NOTE: Truncating the attributes is allowed, because older manifests do not have them
+    for len in 0..bytes.len() {
+        if let Ok(parsed) = format::parse_manifest(&bytes[..len]) {
+            assert_eq!(contents.segment_ids, parsed.segment_ids);
+        }
+    }
+
+    assert!(format::parse_manifest(&[]).is_err());
+    assert!(format::parse_manifest(&u64::MAX.to_be_bytes()).is_err());
+
+    contents
+        .tags
+        .insert(1, BTreeMap::from([("a".repeat(70_000), String::new())]));
+    assert!(contents.encode().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn format_describe_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_chunk_size(1_000)
+            .key_restart_interval(4),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut vhandles = vec![];
+
+    for (key, value_len) in [("a", 10), ("ab", 2_500), ("abc", 10)] {
+        vhandles.push(writer.get_next_value_handle());
+        writer.write(key, "x".repeat(value_len))?;
+    }
+    value_log.register_writer(writer)?;
+
+    let segment_id = vhandles[0].segment_id;
+    let mut bytes = std::fs::read(folder.path().join("segments").join(segment_id.to_string()))?;
+
+    let description = format::describe_segment(&bytes)?;
+    assert_eq!(3, description.metadata.item_count);
+    assert_eq!(1, description.metadata_version);
+    assert_eq!(1, description.trailer_version);
+    assert_eq!(bytes.len() as u64 - 256, description.trailer_offset);
+    assert!(description.holes.is_empty());
+
+    let offsets = description.records.iter().map(|x| x.offset);
+    assert!(offsets.eq(vhandles.iter().map(|x| x.offset)));
+
+    let record = &description.records[0];
+    assert_eq!((1, b"a".as_slice()), (record.version, &*record.header.key));
+    assert_eq!(description.records[1].offset, record.offset + record.len);
+
+    let record = &description.records[1];
+    assert_eq!(4, record.version);
+    assert_eq!(b"b", &*record.header.key);
+    assert_eq!(1, record.header.shared_prefix_len);
+    assert_eq!(vec![1_000, 1_000, 500], record.chunk_lens);
+
+    let record = &description.records[2];
+    assert_eq!(3, record.version);
+    assert_eq!(
+        description.metadata_offset,
+        record.offset + record.len,
+        "records should end where the metadata starts",
+    );
+
+    // NOTE: Punched holes are zeroed out
+    let hole = 0..description.records[1].offset;
+    bytes[hole.start as usize..hole.end as usize].fill(0);
+
+    let description = format::describe_segment(&bytes)?;
+    assert_eq!(vec![hole], description.holes);
+    assert_eq!(2, description.records.len());
+
+    for len in [0, 100, bytes.len() - 1] {
+        assert!(format::describe_segment(&bytes[..len]).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn format_describe_segment_created_at() -> value_log::Result<()> {
+    for (retention, metadata_version) in [(None, 1), (Some(Duration::from_secs(60)), 4)] {
+        let folder = tempfile::tempdir()?;
+
+        let mut config = Config::<NoCompressor>::default();
+        if let Some(max_age) = retention {
+            config = config.retention(max_age);
+        }
+
+        let value_log = ValueLog::open(folder.path(), config)?;
+
+        let mut writer = value_log.get_writer()?;
+        let vhandle = writer.get_next_value_handle();
+        writer.write("a", "a")?;
+        value_log.register_writer(writer)?;
+
+        // NOTE: Segments only use the newer metadata format if they need to record their creation time
+        let bytes = std::fs::read(
+            folder
+                .path()
+                .join("segments")
+                .join(vhandle.segment_id.to_string()),
+        )?;
+        let description = format::describe_segment(&bytes)?;
+        assert_eq!(metadata_version, description.metadata_version);
+        assert_eq!(
+            retention.is_some(),
+            description.metadata.created_at.is_some()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn format_describe_manifest() -> value_log::Result<()> {
+    let mut contents = ManifestContents {
+        segment_ids: vec![1, 2, 3],
+        ..Default::default()
+    };
+    contents.pinned.insert(2);
+    contents
+        .tags
+        .insert(3, BTreeMap::from([("a".into(), "b".into())]));
+
+    let bytes = contents.encode()?;
+
+    let sections = format::describe_manifest(&bytes)?;
+    assert_eq!(
+        vec![
+            (ManifestSectionKind::Segments, 0..32, 3),
+            (ManifestSectionKind::Pinned, 32..48, 1),
+            (ManifestSectionKind::Tags, 48..72, 1),
+            (ManifestSectionKind::ReadCounters, 72..80, 0),
+        ],
+        sections
+            .into_iter()
+            .map(|x| (x.kind, x.range, x.entry_count))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(80, bytes.len());
+
+    // NOTE: Manifests written by older versions only contain the segment list
+    let sections = format::describe_manifest(&bytes[..32])?;
+    assert_eq!(1, sections.len());
+    assert_eq!(ManifestSectionKind::Segments, sections[0].kind);
+
+    assert!(format::describe_manifest(&bytes[..20]).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::sync::Mutex;
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+/// Index reader that runs a hook right after a key was looked up,
+/// simulating a user write that races with the relocation of that key
+struct RacingIndex {
+    index: MockIndex,
+    key: &'static [u8],
+    hook: Mutex<Option<Hook>>,
+}
+
+impl IndexReader for RacingIndex {
+    fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+        let vhandle = self.index.get(key)?;
+
+        if key == self.key {
+            if let Some(hook) = self.hook.lock().unwrap().take() {
+                hook();
+            }
+        }
+
+        Ok(vhandle)
+    }
+}
+
+#[test]
+fn gc_concurrent_write_not_clobbered() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let value = "old".repeat(1_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    // NOTE: The newer value of "b" is already in the value log,
+    // but the index is only updated while the old value is being relocated
+    let new_vhandle = {
+        let mut writer = value_log.get_writer()?;
+        let vhandle = writer.get_next_value_handle();
+        writer.write("b", "new".repeat(1_000))?;
+        value_log.register_writer(writer)?;
+        vhandle
+    };
+
+    let reader = RacingIndex {
+        index: index.clone(),
+        key: b"b",
+        hook: Mutex::new(Some(Box::new({
+            let index = index.clone();
+            let new_vhandle = new_vhandle.clone();
+
+            move || {
+                let mut index_writer = MockIndexWriter(index);
+                index_writer
+                    .insert_indirect(b"b", new_vhandle, 3_000)
+                    .unwrap();
+            }
+        }))),
+    };
+
+    value_log.rollover(&[0], &reader, MockIndexWriter(index.clone()))?;
+
+    // NOTE: The relocation did not resurrect the old value
+    assert_eq!(Some(new_vhandle), index.get(b"b")?);
+
+    let item = value_log.get(&index.get(b"b")?.unwrap())?.unwrap();
+    assert_eq!(&*item, "new".repeat(1_000).as_bytes());
+
+    // NOTE: The other keys were relocated
+    for key in [b"a", b"c"] {
+        let vhandle = index.get(key)?.unwrap();
+        assert_eq!(2, vhandle.segment_id);
+
+        let item = value_log.get(&vhandle)?.unwrap();
+        assert_eq!(&*item, "old".repeat(1_000).as_bytes());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn gc_only_relocates_referenced_version() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    // NOTE: Only the first version of "a" is indexed,
+    // e.g. because the index write of the second one was lost
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle, 1)?;
+    writer.write("a", "1")?;
+    value_log.register_writer(writer)?;
+
+    let mut writer = value_log.get_writer()?;
+    writer.write("a", "2")?;
+    value_log.register_writer(writer)?;
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    let item = value_log.get(&index.get(b"a")?.unwrap())?.unwrap();
+    assert_eq!(b"1", &*item);
+
+    Ok(())
+}
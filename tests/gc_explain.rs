@@ -0,0 +1,171 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, GcPolicy, GcReason, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in keys {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+/// Writes segments with different stale ratios, pinning the fourth one
+fn setup(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    write_items(value_log, index, &["a", "b"])?;
+    write_items(value_log, index, &["c", "d", "e", "f"])?;
+    write_items(value_log, index, &["g", "h", "i", "j"])?;
+    write_items(value_log, index, &["k", "l"])?;
+    write_items(value_log, index, &["a", "b", "c", "d", "e", "g", "k"])?;
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+
+    let pinned_id = *ids.get(3).unwrap();
+    value_log.pin_segment(pinned_id)?;
+
+    Ok(())
+}
+
+#[test]
+fn gc_explain() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_policy(GcPolicy::default().stale_threshold(0.4)),
+    )?;
+    setup(&value_log, &index)?;
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+
+    let decisions = value_log.explain_gc_choice();
+    assert_eq!(
+        ids,
+        decisions.iter().map(|x| x.segment_id).collect::<Vec<_>>(),
+    );
+    assert_eq!(
+        vec![
+            (false, GcReason::FullyStale),
+            (true, GcReason::StaleThresholdExceeded { threshold: 0.4 }),
+            (
+                false,
+                GcReason::BelowThreshold {
+                    threshold: Some(0.4)
+                }
+            ),
+            (false, GcReason::Pinned),
+            (
+                false,
+                GcReason::BelowThreshold {
+                    threshold: Some(0.4)
+                }
+            ),
+        ],
+        decisions
+            .iter()
+            .map(|x| (x.selected, x.reason))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(0.75, decisions.get(1).unwrap().stale_ratio);
+
+    let selected = decisions
+        .iter()
+        .filter(|x| x.selected)
+        .map(|x| x.segment_id)
+        .collect::<Vec<_>>();
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(selected, report.gc_segment_ids);
+
+    Ok(())
+}
+
+#[test]
+fn gc_explain_job_limit() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .gc_policy(GcPolicy::default().stale_threshold(0.2).max_jobs(1)),
+    )?;
+    setup(&value_log, &index)?;
+
+    let decisions = value_log.explain_gc_choice();
+
+    let selected = decisions
+        .iter()
+        .filter(|x| x.selected)
+        .map(|x| x.segment_id)
+        .collect::<Vec<_>>();
+    assert_eq!(1, selected.len());
+
+    assert_eq!(
+        1,
+        decisions
+            .iter()
+            .filter(|x| x.reason == GcReason::JobLimit)
+            .count()
+    );
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(selected, report.gc_segment_ids);
+
+    Ok(())
+}
+
+#[test]
+fn gc_explain_no_policy() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    setup(&value_log, &index)?;
+
+    let decisions = value_log.explain_gc_choice();
+    assert!(decisions.iter().all(|x| !x.selected));
+    assert_eq!(
+        vec![
+            GcReason::FullyStale,
+            GcReason::NoPolicy,
+            GcReason::NoPolicy,
+            GcReason::Pinned,
+            GcReason::NoPolicy,
+        ],
+        decisions.iter().map(|x| x.reason).collect::<Vec<_>>(),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, GcOperation, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in ["a", "b", "c", "d", "e"] {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+#[test]
+fn gc_history() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert!(value_log.gc_history().is_empty());
+
+    write_items(&value_log, &index)?;
+    write_items(&value_log, &index)?;
+
+    let input_ids = value_log.manifest.list_segment_ids();
+    assert_eq!(2, input_ids.len());
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    let history = value_log.gc_history();
+    assert_eq!(2, history.len());
+
+    let rollover = history.first().unwrap();
+    assert_eq!(GcOperation::Rollover, rollover.operation);
+    let mut rollover_inputs = rollover.input_segment_ids.clone();
+    rollover_inputs.sort_unstable();
+    assert_eq!(input_ids, rollover_inputs);
+    assert_eq!(
+        value_log.manifest.list_segment_ids(),
+        rollover.output_segment_ids
+    );
+
+    // NOTE: Half of the rewritten blobs were stale
+    assert_eq!(5_000, rollover.bytes_reclaimed);
+
+    let drop = history.get(1).unwrap();
+    assert_eq!(GcOperation::Drop, drop.operation);
+    let mut drop_inputs = drop.input_segment_ids.clone();
+    drop_inputs.sort_unstable();
+    assert_eq!(input_ids, drop_inputs);
+    assert!(drop.output_segment_ids.is_empty());
+    assert_eq!(10_000, drop.bytes_reclaimed);
+    assert!(drop.started_at >= rollover.started_at);
+
+    Ok(())
+}
+
+#[test]
+fn gc_history_capacity() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_history_capacity(1),
+    )?;
+
+    write_items(&value_log, &index)?;
+    write_items(&value_log, &index)?;
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    let history = value_log.gc_history();
+    assert_eq!(1, history.len());
+    assert_eq!(GcOperation::Drop, history.first().unwrap().operation);
+
+    Ok(())
+}
+
+#[test]
+fn gc_history_persisted() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let history = {
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().persist_gc_history(true),
+        )?;
+
+        write_items(&value_log, &index)?;
+        write_items(&value_log, &index)?;
+
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        value_log.drop_stale_segments()?;
+
+        value_log.gc_history()
+    };
+    assert_eq!(2, history.len());
+
+    {
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().persist_gc_history(true),
+        )?;
+
+        let mut recovered = value_log.gc_history();
+
+        // NOTE: Durations are persisted with microsecond precision
+        for (entry, original) in recovered.iter_mut().zip(&history) {
+            assert_eq!(entry.duration.as_micros(), original.duration.as_micros());
+            entry.duration = original.duration;
+        }
+
+        assert_eq!(history, recovered);
+    }
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        assert!(value_log.gc_history().is_empty());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter,
+    StaleThresholdStrategy, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+        let value = value.as_bytes();
+
+        let key = key.as_bytes();
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn gc_hooks_step_by_step() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b", "c", "d"])?;
+    write_batch(&value_log, &index, &["e", "f"])?;
+
+    index.remove(b"a");
+    index.remove(b"b");
+    index.remove(b"c");
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let candidates = value_log.pick_candidates(&StaleThresholdStrategy::new(0.5));
+    assert_eq!(candidates, [0]);
+
+    let new_ids = value_log.relocate_segment(0, &index, MockIndexWriter(index.clone()))?;
+    assert_eq!(new_ids, [2]);
+
+    // NOTE: The old segment is still readable until GC is finished
+    assert_eq!(3, value_log.segment_count());
+    assert_eq!(2, index.get(b"d")?.unwrap().segment_id);
+
+    let freed = value_log.finish_gc(&candidates)?;
+    assert!(freed > 0);
+    assert_eq!(2, value_log.segment_count());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(1_000));
+    }
+
+    assert!(value_log
+        .relocate_segment(0, &index, MockIndexWriter(index.clone()))
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+use test_log::test;
+use value_log::{Compressor, Config, GcPolicy, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Writes 2 half-stale segments, and returns their IDs
+fn write_half_stale_segments(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+) -> value_log::Result<Vec<u64>> {
+    for keys in [["a", "b"], ["c", "d"], ["a", "c"]] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in keys {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 1_000)?;
+            writer.write(key, key.repeat(1_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+    ids.truncate(2);
+    Ok(ids)
+}
+
+#[test]
+fn gc_policy_max_jobs() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .gc_policy(GcPolicy::default().stale_threshold(0.4).max_jobs(1)),
+    )?;
+
+    let ids = write_half_stale_segments(&value_log, &index)?;
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report.gc_segment_ids.len());
+
+    // NOTE: The rewritten segment is fully stale now, so it is not rewritten again
+    let report2 = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report2.gc_segment_ids.len());
+
+    let mut gc_ids = [report.gc_segment_ids, report2.gc_segment_ids].concat();
+    gc_ids.sort_unstable();
+    assert_eq!(ids, gc_ids);
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.gc_segment_ids.is_empty());
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(3, value_log.segment_count());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, &*key.repeat(1_000));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn gc_policy_min_age() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_policy(
+            GcPolicy::default()
+                .stale_threshold(0.4)
+                .min_age(Duration::from_secs(3_600)),
+        ),
+    )?;
+
+    write_half_stale_segments(&value_log, &index)?;
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.gc_segment_ids.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn gc_policy_background_maintenance() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_policy(
+            GcPolicy::default()
+                .space_amp_target(1.0)
+                .throttle(Duration::from_millis(1)),
+        ),
+    )?;
+
+    let ids = write_half_stale_segments(&value_log, &index)?;
+
+    let writer_index = index.clone();
+    value_log.start_background_maintenance(
+        Duration::from_millis(10),
+        index.clone(),
+        move || MockIndexWriter(writer_index.clone()),
+    )?;
+
+    let start = Instant::now();
+
+    while !ids
+        .iter()
+        .all(|id| value_log.manifest.get_segment(*id).unwrap().is_stale())
+    {
+        assert!(start.elapsed() < Duration::from_secs(10), "GC did not run");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::sync::{Arc, Mutex};
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, RelocationMeta,
+    ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Index writer that records the relocation metadata it receives
+struct RecordingIndexWriter {
+    inner: MockIndexWriter,
+    relocations: Arc<Mutex<Vec<(Vec<u8>, RelocationMeta)>>>,
+}
+
+impl IndexWriter for RecordingIndexWriter {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        self.inner.insert_indirect(key, vhandle, size)
+    }
+
+    fn relocate_indirect_with_meta(
+        &mut self,
+        key: &[u8],
+        expected: &ValueHandle,
+        vhandle: ValueHandle,
+        size: u32,
+        meta: &RelocationMeta,
+    ) -> std::io::Result<()> {
+        self.relocations
+            .lock()
+            .unwrap()
+            .push((key.to_vec(), meta.clone()));
+
+        self.inner.relocate_indirect(key, expected, vhandle, size)
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.inner.finish()
+    }
+}
+
+#[test]
+fn gc_relocation_meta() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut checksums = vec![];
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b"] {
+            let value = key.repeat(1_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, &value)?;
+
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            hasher.update(key.as_bytes());
+            hasher.update(value.as_bytes());
+            checksums.push(hasher.digest());
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let old_segment_id = value_log.manifest.list_segment_ids()[0];
+
+    let relocations = Arc::new(Mutex::new(vec![]));
+
+    value_log.major_compact(
+        &index,
+        RecordingIndexWriter {
+            inner: MockIndexWriter(index.clone()),
+            relocations: relocations.clone(),
+        },
+    )?;
+
+    let relocations = relocations.lock().unwrap();
+    assert_eq!(2, relocations.len());
+
+    for ((key, meta), checksum) in relocations.iter().zip(&checksums) {
+        assert_eq!(*checksum, meta.checksum);
+        assert_eq!(1_000, meta.stored_size);
+
+        let vhandle = index.get(key)?.unwrap();
+        assert_ne!(old_segment_id, vhandle.segment_id);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, Fs, FsFile, GcPolicy, IndexWriter, MockIndex, MockIndexWriter, StdFs,
+    ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// File system that reports a fixed amount of free space
+#[derive(Default)]
+struct FixedSpaceFs(AtomicU64);
+
+impl Fs for FixedSpaceFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.create(path)
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.open(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        StdFs.rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.sync_directory(path)
+    }
+
+    fn available_space(&self, _path: &Path) -> std::io::Result<u64> {
+        Ok(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[test]
+fn gc_watermarks() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let fs = Arc::new(FixedSpaceFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(fs.clone()).gc_policy(
+            GcPolicy::default()
+                .stale_threshold(0.5)
+                .low_watermark(1_000, 0.2)
+                .high_watermark(1_000_000, 0.9),
+        ),
+    )?;
+
+    for keys in [
+        ["a", "b", "c", "d"],
+        ["e", "f", "g", "h"],
+        ["a", "b", "c", "e"],
+    ] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in keys {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 1_000)?;
+            writer.write(key, key.repeat(1_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    // NOTE: The first segment is 3/4 stale, the second one 1/4
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    // NOTE: Plenty of space, so no segment is stale enough
+    fs.0.store(10_000_000, Ordering::Relaxed);
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.gc_segment_ids.is_empty());
+
+    // NOTE: Between the watermarks, the regular threshold applies
+    fs.0.store(100_000, Ordering::Relaxed);
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report.gc_segment_ids.len());
+
+    // NOTE: Low on space, so the remaining segment is picked as well
+    fs.0.store(100, Ordering::Relaxed);
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report.gc_segment_ids.len());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, &*key.repeat(1_000));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, GcPolicy, Generation, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+/// Stores values as is, counting how often the "strong" instance compresses
+#[derive(Clone, Default)]
+struct CountingCompressor(Option<Arc<AtomicUsize>>);
+
+impl Compressor for CountingCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        if let Some(counter) = &self.0 {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<CountingCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<u64> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+    let segment_id = writer.get_next_value_handle().segment_id;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    Ok(segment_id)
+}
+
+#[test]
+fn generations_promote_survivors() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let strong_compressions = Arc::new(AtomicUsize::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::default().old_generation(
+            CountingCompressor(Some(strong_compressions.clone())),
+            64 * 1_024 * 1_024,
+        ),
+    )?;
+
+    let young_id = write_items(&value_log, &index, &["a", "b", "c"])?;
+    write_items(&value_log, &index, &["b"])?;
+    assert_eq!(
+        Some(Generation::Young),
+        value_log.segment_generation(young_id)
+    );
+    assert_eq!(0, strong_compressions.load(Ordering::Relaxed));
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    // NOTE: The 3 live blobs were promoted, and written with the old generation's compressor
+    let old_ids = value_log.list_segments_with_generation(Generation::Old);
+    assert_eq!(1, old_ids.len());
+    assert_eq!(3, strong_compressions.load(Ordering::Relaxed));
+    assert!(value_log
+        .list_segments_with_generation(Generation::Young)
+        .is_empty());
+
+    let young_id = write_items(&value_log, &index, &["d"])?;
+    assert_eq!(
+        Some(Generation::Young),
+        value_log.segment_generation(young_id)
+    );
+    assert_eq!(
+        vec![young_id],
+        value_log.list_segments_with_generation(Generation::Young)
+    );
+    assert_eq!(None, value_log.segment_generation(young_id + 1));
+
+    Ok(())
+}
+
+#[test]
+fn generations_gc_young_first() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::default()
+            .old_generation(CountingCompressor::default(), 64 * 1_024 * 1_024)
+            .gc_policy(GcPolicy::default().stale_threshold(0.3).max_jobs(1)),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b", "c"])?;
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    // NOTE: The old segment is 2/3 stale, the young segment 1/3
+    let young_id = write_items(&value_log, &index, &["a", "b", "d"])?;
+    write_items(&value_log, &index, &["d"])?;
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(vec![young_id], report.gc_segment_ids);
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
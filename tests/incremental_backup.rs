@@ -0,0 +1,103 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(10_000);
+        let value = value.as_bytes();
+
+        let key = key.as_bytes();
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn incremental_backup_restore() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path().join("vlog");
+    let backup_1 = folder.path().join("backup_1");
+    let backup_2 = folder.path().join("backup_2");
+    let restore_path = folder.path().join("restore");
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(&vl_path, Config::<NoCompressor>::default())?;
+
+    write_batch(&value_log, &index, &["a", "b", "c"])?;
+
+    let report = value_log.incremental_backup(&[], Some(&backup_1))?;
+    assert_eq!(report.segment_ids, [0]);
+    assert_eq!(1, report.new_segments.len());
+    assert!(report.copied_bytes > 0);
+
+    write_batch(&value_log, &index, &["d", "e"])?;
+    write_batch(&value_log, &index, &["f"])?;
+
+    let report = value_log.incremental_backup(&report.segment_ids, Some(&backup_2))?;
+    assert_eq!(report.segment_ids, [0, 1, 2]);
+    assert_eq!(
+        {
+            let mut ids = report.new_segments.iter().map(|x| x.0).collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        },
+        [1, 2]
+    );
+
+    // NOTE: Without destination, nothing is copied
+    let report = value_log.incremental_backup::<&str>(&report.segment_ids, None)?;
+    assert!(report.new_segments.is_empty());
+    assert_eq!(0, report.copied_bytes);
+
+    // NOTE: The second backup alone is not enough
+    assert!(ValueLog::restore_backup(
+        &[&backup_2],
+        folder.path().join("broken"),
+        Config::<NoCompressor>::default()
+    )
+    .is_err());
+
+    let restored = ValueLog::restore_backup(
+        &[&backup_1, &backup_2],
+        &restore_path,
+        Config::<NoCompressor>::default(),
+    )?;
+    assert_eq!(3, restored.segment_count());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = restored.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(10_000));
+    }
+
+    // NOTE: New segments of the restored value log should not collide
+    write_batch(&restored, &index, &["g"])?;
+    assert_eq!(4, restored.segment_count());
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+        let value = value.as_bytes();
+
+        let key = key.as_bytes();
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn ingest_segment_file() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let staging_index = MockIndex::default();
+    let staging = ValueLog::open(
+        folder.path().join("staging"),
+        Config::<NoCompressor>::default(),
+    )?;
+    write_batch(&staging, &staging_index, &["x", "y", "z"])?;
+
+    let segment_path = staging
+        .manifest
+        .list_segments()
+        .first()
+        .unwrap()
+        .path
+        .clone();
+
+    let index = MockIndex::default();
+    let value_log = ValueLog::open(
+        folder.path().join("vlog"),
+        Config::<NoCompressor>::default(),
+    )?;
+    write_batch(&value_log, &index, &["a", "b", "c"])?;
+    assert_eq!(1, value_log.segment_count());
+
+    let segment_id = value_log.ingest_segment(&segment_path)?;
+    assert_eq!(1, segment_id);
+    assert_eq!(2, value_log.segment_count());
+
+    for (key, (vhandle, _)) in staging_index.read().unwrap().iter() {
+        let vhandle = ValueHandle {
+            segment_id,
+            offset: vhandle.offset,
+        };
+        let item = value_log.get(&vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(1_000));
+    }
+
+    // NOTE: Ingested segments survive recovery
+    drop(value_log);
+    let value_log = ValueLog::open(
+        folder.path().join("vlog"),
+        Config::<NoCompressor>::default(),
+    )?;
+    assert_eq!(2, value_log.segment_count());
+    assert_eq!(0, value_log.verify()?);
+
+    Ok(())
+}
+
+#[test]
+fn ingest_segment_corrupted() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let staging = ValueLog::open(
+        folder.path().join("staging"),
+        Config::<NoCompressor>::default(),
+    )?;
+    write_batch(&staging, &MockIndex::default(), &["x", "y", "z"])?;
+
+    let segment_path = staging
+        .manifest
+        .list_segments()
+        .first()
+        .unwrap()
+        .path
+        .clone();
+
+    // NOTE: Flip a byte inside the first value
+    let mut bytes = std::fs::read(&segment_path)?;
+    *bytes.get_mut(100).unwrap() ^= 0xFF;
+    let corrupt_path = folder.path().join("corrupt");
+    std::fs::write(&corrupt_path, bytes)?;
+
+    let value_log = ValueLog::open(
+        folder.path().join("vlog"),
+        Config::<NoCompressor>::default(),
+    )?;
+
+    assert!(matches!(
+        value_log.ingest_segment(&corrupt_path),
+        Err(value_log::Error::ChecksumMismatch)
+    ));
+    assert!(value_log
+        .ingest_segment(folder.path().join("missing"))
+        .is_err());
+    assert_eq!(0, value_log.segment_count());
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[(&str, &str)],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for (key, value) in items {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn iter_all_blobs() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &[("a", "a1"), ("b", "b1"), ("d", "d1")])?;
+    write_batch(&value_log, &index, &[("b", "b2"), ("c", "c2")])?;
+
+    let items = value_log
+        .scan_blobs()?
+        .map(|x| x.map(|(k, _, v)| (k, v)))
+        .collect::<value_log::Result<Vec<_>>>()?;
+
+    assert_eq!(
+        items.iter().map(|(k, v)| (&**k, &**v)).collect::<Vec<_>>(),
+        [
+            (&b"a"[..], &b"a1"[..]),
+            (b"b", b"b2"),
+            (b"b", b"b1"),
+            (b"c", b"c2"),
+            (b"d", b"d1"),
+        ],
+    );
+
+    // NOTE: Newest version wins, and its value handle matches the index
+    let mut count = 0;
+
+    for item in value_log.scan_latest_blobs()? {
+        let (key, vhandle, value) = item?;
+
+        let (expected_vhandle, _) = index.read().unwrap().get(&key).cloned().unwrap();
+        assert_eq!(expected_vhandle, vhandle);
+        assert_eq!(value_log.get(&vhandle)?.unwrap(), value);
+
+        count += 1;
+    }
+    assert_eq!(4, count);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, Temperature, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+/// Returns the keys of the only segment, in the order they are stored in
+fn stored_keys(value_log: &ValueLog<NoCompressor>) -> value_log::Result<Vec<String>> {
+    assert_eq!(1, value_log.segment_count());
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+
+    segment
+        .scan_meta()?
+        .map(|meta| Ok(String::from_utf8(meta?.key.to_vec()).unwrap()))
+        .collect()
+}
+
+#[test]
+fn key_ordered_rollover() -> value_log::Result<()> {
+    for enabled in [false, true] {
+        let folder = tempfile::tempdir()?;
+        let index = MockIndex::default();
+
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().key_ordered_rollover(enabled),
+        )?;
+
+        // NOTE: Keys are not written in key order
+        write_items(&value_log, &index, &["e", "c", "a", "d", "b"])?;
+        write_items(&value_log, &index, &["c"])?;
+
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        assert_eq!(0, index.verify(&value_log)?);
+
+        let sorted = ["a", "b", "c", "d", "e"].to_vec();
+        assert_eq!(enabled, sorted == stored_keys(&value_log)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn key_ordered_rollover_keeps_temperature() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .key_ordered_rollover(true)
+            .read_rate_half_life(Duration::from_secs(1))
+            .temperature_thresholds(0.5, 20.0),
+    )?;
+
+    write_items(&value_log, &index, &["e", "c", "a", "d", "b"])?;
+    write_items(&value_log, &index, &["c"])?;
+
+    let ids = value_log.manifest.list_segment_ids();
+    for id in &ids {
+        assert_eq!(Some(Temperature::Cold), value_log.temperature(*id));
+    }
+
+    value_log.rollover(&ids, &index, MockIndexWriter(index.clone()))?;
+    assert_eq!(ids.len() + 1, value_log.segment_count());
+
+    // NOTE: Relocating blobs is not a read, so it does not heat up the rewritten segments
+    for id in &ids {
+        assert_eq!(Some(Temperature::Cold), value_log.temperature(*id));
+        assert_eq!(0, value_log.read_counters(*id).unwrap().reads);
+    }
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
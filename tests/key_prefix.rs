@@ -0,0 +1,264 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ScanFilter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Fails to compress the first value that starts with `fail`
+#[derive(Clone, Default)]
+struct FailOnceCompressor(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl Compressor for FailOnceCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        if bytes.starts_with(b"fail") && !self.0.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return Err(value_log::Error::Compress);
+        }
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const ITEM_COUNT: usize = 20;
+
+fn key(idx: usize) -> String {
+    format!("user/1234567890/session/{idx:0>5}")
+}
+
+fn value(key: &[u8]) -> Vec<u8> {
+    key.repeat(10)
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: impl Iterator<Item = usize>,
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for idx in keys {
+        let key = key(idx);
+        let value = value(key.as_bytes());
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(&key, &value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+#[test]
+fn key_prefix_compression() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().key_restart_interval(4),
+    )?;
+    let index = MockIndex::default();
+
+    write_items(&value_log, &index, 0..ITEM_COUNT)?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+
+    let metas = segment
+        .scan_meta()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT, metas.len());
+
+    for (idx, meta) in metas.iter().enumerate() {
+        assert_eq!(&*meta.key, key(idx).as_bytes());
+
+        if idx % 4 == 0 {
+            assert_eq!(0, meta.shared_prefix_len);
+        } else {
+            assert!(meta.shared_prefix_len > 0);
+        }
+    }
+
+    // NOTE: Records are contiguous
+    for pair in metas.windows(2) {
+        assert_eq!(pair[0].offset + pair[0].record_len(), pair[1].offset);
+    }
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        assert!(value_log.contains(vhandle)?);
+
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, value(key));
+    }
+
+    assert_eq!(0, value_log.verify()?);
+
+    let items = value_log
+        .scan_blobs()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT, items.len());
+    for (idx, (k, _, v)) in items.iter().enumerate() {
+        assert_eq!(&**k, key(idx).as_bytes());
+        assert_eq!(&**v, value(k));
+    }
+
+    let keys = segment
+        .scan_rev()?
+        .map(|x| x.map(|(k, _, _)| k))
+        .collect::<value_log::Result<Vec<_>>>()?;
+    let expected = (0..ITEM_COUNT).rev().map(key).collect::<Vec<_>>();
+    assert_eq!(keys, expected);
+
+    let filtered = segment
+        .scan_filtered(|k| {
+            if k == key(7).as_bytes() || k == key(13).as_bytes() {
+                ScanFilter::Yield
+            } else {
+                ScanFilter::Skip
+            }
+        })?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(2, filtered.len());
+
+    // NOTE: Relocated segments are delta-encoded as well
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    assert!(segment
+        .scan_meta()?
+        .any(|x| x.is_ok_and(|x| x.shared_prefix_len > 0)));
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, value(key));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn key_prefix_compression_shrinks_segment() -> value_log::Result<()> {
+    let mut sizes = vec![];
+
+    for interval in [0, 16] {
+        let folder = tempfile::tempdir()?;
+
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().key_restart_interval(interval),
+        )?;
+
+        write_items(&value_log, &MockIndex::default(), 0..ITEM_COUNT)?;
+
+        let segment = value_log.manifest.list_segments().pop().unwrap();
+        sizes.push(std::fs::metadata(&segment.path)?.len());
+    }
+
+    assert!(sizes[1] < sizes[0]);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn key_prefix_compression_punch_hole() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().key_restart_interval(4),
+    )?;
+    let index = MockIndex::default();
+
+    write_items(&value_log, &index, 0..ITEM_COUNT)?;
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+
+    let metas = segment
+        .scan_meta()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+
+    // NOTE: Items 0..=5 become stale, but item 5 cannot be punched,
+    // because item 6 needs its key
+    write_items(&value_log, &index, 0..6)?;
+
+    let punched = value_log.punch_stale_blobs(segment_id, &index)?;
+    assert_eq!(metas[4].offset, punched);
+
+    // NOTE: Delta-encoded blobs must not be punched alone
+    assert!(segment
+        .punch_stale_ranges(&[metas[5].offset..metas[6].offset])
+        .is_err());
+
+    assert_eq!(ITEM_COUNT - 4, segment.scan()?.count());
+    assert_eq!(0, value_log.verify()?);
+    assert_eq!(0, index.verify(&value_log)?);
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, value(key));
+    }
+
+    let keys = segment
+        .scan_rev()?
+        .map(|x| x.map(|(k, _, _)| k))
+        .collect::<value_log::Result<Vec<_>>>()?;
+    let expected = (4..ITEM_COUNT).rev().map(key).collect::<Vec<_>>();
+    assert_eq!(keys, expected);
+
+    Ok(())
+}
+
+#[test]
+fn key_prefix_compression_failed_write() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<FailOnceCompressor>::default().key_restart_interval(4),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+
+    let a = writer.get_next_value_handle();
+    writer.write("user/1234567890/a", "a")?;
+
+    // NOTE: The failed blob is not written, so its key must not be used for delta-encoding
+    assert!(writer.write("user/1234567890/zzzzzzzzzz", "fail").is_err());
+
+    let b = writer.get_next_value_handle();
+    writer.write("user/1234567890/zzzzzzzzzz/b", "b")?;
+
+    value_log.register_writer(writer)?;
+
+    assert_eq!(&*value_log.get(&a)?.unwrap(), b"a");
+    assert_eq!(&*value_log.get(&b)?.unwrap(), b"b");
+
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    let keys = segment
+        .scan_meta()?
+        .map(|x| x.map(|x| x.key))
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(2, keys.len());
+    assert_eq!(&*keys[0], b"user/1234567890/a");
+    assert_eq!(&*keys[1], b"user/1234567890/zzzzzzzzzz/b");
+
+    assert_eq!(0, value_log.verify()?);
+
+    Ok(())
+}
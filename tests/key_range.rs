@@ -0,0 +1,49 @@
+use value_log::KeyRange;
+
+fn range(min: &str, max: &str) -> KeyRange {
+    KeyRange::new((min.as_bytes().into(), max.as_bytes().into()))
+}
+
+#[test]
+fn key_range_contains_key() {
+    let kr = range("b", "d");
+
+    assert!(!kr.contains_key(b"a"));
+    assert!(kr.contains_key(b"b"));
+    assert!(kr.contains_key(b"c"));
+    assert!(kr.contains_key(b"d"));
+    assert!(!kr.contains_key(b"da"));
+}
+
+#[test]
+fn key_range_overlaps() {
+    let kr = range("b", "d");
+
+    assert!(kr.overlaps(&range("a", "b")));
+    assert!(kr.overlaps(&range("c", "c")));
+    assert!(kr.overlaps(&range("d", "z")));
+    assert!(kr.overlaps(&range("a", "z")));
+    assert!(!kr.overlaps(&range("a", "aa")));
+    assert!(!kr.overlaps(&range("da", "z")));
+}
+
+#[test]
+fn key_range_intersection() {
+    let kr = range("b", "d");
+
+    assert_eq!(Some(range("c", "d")), kr.intersection(&range("c", "z")));
+    assert_eq!(Some(range("b", "c")), kr.intersection(&range("a", "c")));
+    assert_eq!(Some(range("d", "d")), kr.intersection(&range("d", "e")));
+    assert_eq!(Some(kr.clone()), kr.intersection(&range("a", "z")));
+    assert_eq!(None, kr.intersection(&range("e", "z")));
+}
+
+#[test]
+fn key_range_merge() {
+    let kr = range("b", "d");
+
+    assert_eq!(range("a", "d"), kr.merge(&range("a", "c")));
+    assert_eq!(range("b", "z"), kr.merge(&range("c", "z")));
+    assert_eq!(range("b", "f"), kr.merge(&range("e", "f")));
+    assert_eq!(kr, kr.merge(&range("c", "c")));
+}
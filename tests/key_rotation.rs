@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use test_log::test;
+use value_log::{
+    Compressor, Config, Encryptor, Error, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Toy cipher that XORs every byte with the key version
+struct XorEncryptor;
+
+impl Encryptor for XorEncryptor {
+    fn key_version(&self) -> u32 {
+        1
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn encrypt(&self, _: u64, key_version: u32, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.iter().map(|b| b ^ key_version as u8).collect())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn decrypt(&self, _: u64, key_version: u32, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.iter().map(|b| b ^ key_version as u8).collect())
+    }
+}
+
+#[test]
+fn vlog_rewrite_with_key() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().encryption(Arc::new(XorEncryptor)),
+    )?;
+
+    let index = MockIndex::default();
+
+    for key in ["a", "b", "c"] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, 1_000)?;
+        writer.write(key, key.repeat(1_000))?;
+
+        value_log.register_writer(writer)?;
+    }
+
+    let ids = value_log.manifest.list_segment_ids();
+    assert_eq!(ids, value_log.outdated_key_segments(2));
+    assert!(value_log.outdated_key_segments(1).is_empty());
+
+    // NOTE: Rotate two of the segments
+    value_log.rewrite_with_key(&ids[..2], 2, &index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    assert_eq!(vec![ids[2]], value_log.outdated_key_segments(2));
+    assert_eq!(1, value_log.outdated_key_segments(1).len());
+
+    let summary = value_log.summary();
+    assert!(summary
+        .segments
+        .iter()
+        .all(|x| x.key_version == Some(if x.id == ids[2] { 1 } else { 2 })));
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, key.repeat(1_000));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn vlog_rewrite_with_key_no_encryptor() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let index = MockIndex::default();
+
+    assert!(matches!(
+        value_log.rewrite_with_key(&[], 2, &index, MockIndexWriter(index.clone())),
+        Err(Error::Encrypt),
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::{sync::Arc, time::Duration};
+use test_log::test;
+use value_log::{BlobCache, Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_segments(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+) -> value_log::Result<Vec<value_log::ValueHandle>> {
+    let mut handles = vec![];
+
+    for key in ["a", "b"] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 100)?;
+        writer.write(key, key.repeat(100))?;
+
+        value_log.register_writer(writer)?;
+        handles.push(vhandle);
+    }
+
+    Ok(handles)
+}
+
+#[test]
+fn lazy_file_handles() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    // NOTE: Disable the blob cache, so every read hits the segment
+    let config = || {
+        Config::<NoCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .idle_file_timeout(Duration::from_millis(50))
+    };
+
+    let handles = {
+        let value_log = ValueLog::open(folder.path(), config())?;
+        let handles = write_segments(&value_log, &index)?;
+        assert_eq!(0, value_log.open_file_count());
+        handles
+    };
+
+    let value_log = ValueLog::open(folder.path(), config())?;
+    assert_eq!(0, value_log.open_file_count());
+
+    for _ in 0..10 {
+        assert_eq!(&*value_log.get(&handles[0])?.unwrap(), b"a".repeat(100));
+    }
+    assert_eq!(1, value_log.open_file_count());
+
+    value_log.get(&handles[1])?;
+    assert_eq!(2, value_log.open_file_count());
+
+    // NOTE: Handles are not idle yet
+    assert_eq!(0, value_log.close_idle_files());
+
+    std::thread::sleep(Duration::from_millis(100));
+    value_log.get(&handles[1])?;
+
+    assert_eq!(1, value_log.close_idle_files());
+    assert_eq!(1, value_log.open_file_count());
+
+    // NOTE: Dropped segments close their handle
+    index.write().unwrap().clear();
+    value_log.scan_for_stats(std::iter::empty())?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(0, value_log.open_file_count());
+
+    Ok(())
+}
+
+#[test]
+fn lazy_file_handles_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .idle_file_timeout(Duration::ZERO),
+    )?;
+
+    let handles = write_segments(&value_log, &index)?;
+
+    value_log.get(&handles[0])?;
+    assert_eq!(0, value_log.open_file_count());
+
+    Ok(())
+}
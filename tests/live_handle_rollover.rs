@@ -0,0 +1,70 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn rollover_with_live_handles() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for keys in [["a", "b", "c"], ["a", "d", "e"]] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in keys {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 1_000)?;
+            writer.write(key, key.repeat(1_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+    let first_id = ids[0];
+
+    let live_handles = index
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(key, (vhandle, _))| Ok((key.clone(), vhandle.clone())))
+        .collect::<Vec<_>>();
+
+    value_log.rollover_with_live_handles(
+        &[first_id],
+        live_handles.into_iter(),
+        MockIndexWriter(index.clone()),
+    )?;
+
+    assert!(value_log.manifest.get_segment(first_id).unwrap().is_stale());
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(2, value_log.segment_count());
+
+    let new_id = *value_log.manifest.list_segment_ids().iter().max().unwrap();
+
+    // NOTE: Only "b" and "c" were live in the first segment
+    assert_eq!(2, value_log.manifest.get_segment(new_id).unwrap().len());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, &*key.repeat(1_000));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Model-checks the manifest's concurrency using `loom`
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`
+#![cfg(loom)]
+
+use value_log::{Compressor, Config, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(2);
+    builder.check(f);
+}
+
+fn write(value_log: &ValueLog<NoCompressor>, key: &str) -> value_log::Result<()> {
+    let mut writer = value_log.get_writer()?;
+    writer.write(key, key)?;
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn loom_register_register() {
+    model(|| {
+        let folder = tempfile::tempdir().unwrap();
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+
+        let handle = {
+            let value_log = value_log.clone();
+            loom::thread::spawn(move || write(&value_log, "a").unwrap())
+        };
+
+        write(&value_log, "b").unwrap();
+        handle.join().unwrap();
+
+        // NOTE: Both writers reserved their own segment ID, and no registration got lost
+        let mut ids = value_log.manifest.list_segment_ids();
+        ids.sort_unstable();
+        assert_eq!(ids, [0, 1]);
+    });
+}
+
+#[test]
+fn loom_register_read() {
+    model(|| {
+        let folder = tempfile::tempdir().unwrap();
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+
+        let handle = {
+            let value_log = value_log.clone();
+            loom::thread::spawn(move || write(&value_log, "a").unwrap())
+        };
+
+        // NOTE: A reader either sees no segment, or the fully registered segment
+        let segments = value_log.manifest.list_segments();
+        assert!(segments.len() <= 1);
+        for segment in segments {
+            assert_eq!(0, segment.id);
+            assert_eq!(1, segment.len());
+        }
+
+        handle.join().unwrap();
+
+        assert_eq!(1, value_log.segment_count());
+    });
+}
+
+#[test]
+fn loom_register_drop() {
+    model(|| {
+        let folder = tempfile::tempdir().unwrap();
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+
+        write(&value_log, "a").unwrap();
+
+        // NOTE: Make segment 0 stale
+        value_log
+            .manifest
+            .get_segment(0)
+            .unwrap()
+            .gc_stats
+            .set_stale_items(1);
+
+        let handle = {
+            let value_log = value_log.clone();
+            loom::thread::spawn(move || write(&value_log, "b").unwrap())
+        };
+
+        value_log.drop_stale_segments().unwrap();
+        handle.join().unwrap();
+
+        // NOTE: Dropping must not lose the concurrently registered segment
+        assert_eq!(value_log.manifest.list_segment_ids(), [1]);
+    });
+}
@@ -0,0 +1,90 @@
+use test_log::test;
+use value_log::{Compressor, Config, GcPolicy, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_half_stale_segments(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+) -> value_log::Result<()> {
+    for keys in [["a", "b"], ["a", "c"]] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in keys {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 1_000)?;
+            writer.write(key, key.repeat(1_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    Ok(())
+}
+
+#[test]
+fn maintenance_pause_guard() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_policy(GcPolicy::default().stale_threshold(0.4)),
+    )?;
+
+    write_half_stale_segments(&value_log, &index)?;
+
+    let pause = value_log.pause_maintenance();
+    let nested_pause = value_log.pause_maintenance();
+    assert!(value_log.is_maintenance_paused());
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert!(report.gc_segment_ids.is_empty());
+
+    drop(pause);
+    assert!(value_log.is_maintenance_paused());
+
+    drop(nested_pause);
+    assert!(!value_log.is_maintenance_paused());
+
+    let report = value_log.run_maintenance(&index, || MockIndexWriter(index.clone()))?;
+    assert_eq!(1, report.gc_segment_ids.len());
+
+    Ok(())
+}
+
+#[test]
+fn maintenance_resume() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let pause = value_log.pause_maintenance();
+    let _nested_pause = value_log.pause_maintenance();
+    assert!(value_log.is_maintenance_paused());
+
+    value_log.resume_maintenance();
+    assert!(!value_log.is_maintenance_paused());
+
+    // NOTE: Dropping a released pause does nothing
+    drop(pause);
+    assert!(!value_log.is_maintenance_paused());
+
+    let _pause = value_log.pause_maintenance();
+    assert!(value_log.is_maintenance_paused());
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn manifest_concurrent_reads() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    // NOTE: No blob cache, so every read needs to look up the segment
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(value_log::BlobCache::with_capacity_bytes(0).into()),
+    )?;
+
+    let write = |key: &str| -> value_log::Result<()> {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, key.len() as u32)?;
+        writer.write(key, key)?;
+
+        value_log.register_writer(writer)?;
+        index_writer.finish()?;
+
+        Ok(())
+    };
+
+    write("hot")?;
+    let vhandle = index.get(b"hot")?.unwrap();
+
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|s| {
+        let readers = (0..4)
+            .map(|_| {
+                s.spawn(|| -> value_log::Result<usize> {
+                    let mut reads = 0;
+
+                    while !done.load(Ordering::Relaxed) {
+                        let item = value_log.get(&vhandle)?.unwrap();
+                        assert_eq!(b"hot", &*item);
+                        reads += 1;
+                    }
+
+                    Ok(reads)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for idx in 0..100 {
+            write(&format!("key-{idx}"))?;
+        }
+        done.store(true, Ordering::Relaxed);
+
+        for reader in readers {
+            assert!(reader.join().unwrap()? > 0);
+        }
+
+        Ok::<_, value_log::Error>(())
+    })?;
+
+    assert_eq!(101, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Barrier,
+    },
+    time::Duration,
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, Fs, FsFile, IndexWriter, MockIndex, MockIndexWriter, StdFs, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// File system with slow manifest writes, which are counted
+#[derive(Default)]
+struct SlowManifestFs {
+    manifest_writes: AtomicUsize,
+}
+
+impl Fs for SlowManifestFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.create(path)
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.open(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        if path.ends_with("vlog_manifest") {
+            self.manifest_writes.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        StdFs.rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.sync_directory(path)
+    }
+}
+
+#[test]
+fn manifest_group_commit() -> value_log::Result<()> {
+    const THREADS: usize = 8;
+
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let fs = Arc::new(SlowManifestFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(fs.clone()),
+    )?;
+
+    let writes_before = fs.manifest_writes.load(Ordering::Relaxed);
+    let barrier = Barrier::new(THREADS);
+
+    std::thread::scope(|s| {
+        let handles = (0..THREADS)
+            .map(|idx| {
+                let value_log = &value_log;
+                let barrier = &barrier;
+                let mut index_writer = MockIndexWriter(index.clone());
+
+                s.spawn(move || -> value_log::Result<()> {
+                    let key = format!("{idx}");
+
+                    let mut writer = value_log.get_writer()?;
+                    let vhandle = writer.get_next_value_handle();
+                    index_writer.insert_indirect(key.as_bytes(), vhandle, 3)?;
+                    writer.write(&key, "abc")?;
+
+                    barrier.wait();
+                    value_log.register_writer(writer)?;
+
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().expect("should join")?;
+        }
+
+        Ok::<_, value_log::Error>(())
+    })?;
+
+    let writes = fs.manifest_writes.load(Ordering::Relaxed) - writes_before;
+    assert!(
+        writes < THREADS,
+        "expected grouped manifest writes, got {writes}"
+    );
+    assert_eq!(THREADS, value_log.segment_count());
+
+    drop(value_log);
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(THREADS, value_log.segment_count());
+
+    for (vhandle, _) in index.read().expect("lock is poisoned").values() {
+        assert!(value_log.get(vhandle)?.is_some());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn memory_usage_write_buffers() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(0, value_log.memory_usage().write_buffers);
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle, 3)?;
+    writer.write("a", "abc")?;
+
+    assert!(value_log.memory_usage().write_buffers > 0);
+
+    value_log.register_writer(writer)?;
+    assert_eq!(0, value_log.memory_usage().write_buffers);
+    assert_eq!(0, value_log.memory_usage().read_buffers);
+
+    Ok(())
+}
+
+#[test]
+fn memory_usage_read_buffers() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["a", "b", "c"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, 1)?;
+        writer.write(key, key)?;
+    }
+    value_log.register_writer(writer)?;
+
+    let mut iter = value_log.scan_blobs()?;
+    assert!(iter.next().is_some());
+    assert!(value_log.memory_usage().read_buffers > 0);
+
+    drop(iter);
+    assert_eq!(0, value_log.memory_usage().read_buffers);
+
+    Ok(())
+}
+
+#[test]
+fn memory_budget_blob_cache_yields() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().memory_budget(20_000),
+    )?;
+
+    let mut writer = value_log.get_writer()?;
+    let mut handles = vec![];
+    for key in ["a", "b", "c"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 4_000)?;
+        writer.write(key, key.repeat(4_000))?;
+        handles.push(vhandle);
+    }
+    value_log.register_writer(writer)?;
+
+    for vhandle in &handles {
+        assert!(value_log.get(vhandle)?.is_some());
+    }
+
+    let usage = value_log.memory_usage();
+    assert_eq!(Some(20_000), usage.budget);
+    assert!(usage.blob_cache > 0);
+    assert!(usage.blob_cache < 12_000, "third blob should not be cached");
+    assert!(!usage.is_over_budget());
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+        let value = value.as_bytes();
+
+        let key = key.as_bytes();
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn metadata_snapshot_apply() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path().join("vlog");
+    let replica_path = folder.path().join("replica");
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(&vl_path, Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b"])?;
+    write_batch(&value_log, &index, &["c"])?;
+
+    // NOTE: Make "a" stale
+    index.remove(b"a");
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    let stale_bytes = value_log.manifest.stale_bytes();
+    assert!(stale_bytes > 0);
+
+    let snapshot = value_log.metadata_snapshot()?;
+
+    // NOTE: The replica lacks the segment files
+    let replica = ValueLog::open(&replica_path, Config::<NoCompressor>::default())?;
+    assert!(replica.apply_metadata_snapshot(&snapshot).is_err());
+    assert_eq!(0, replica.segment_count());
+
+    // NOTE: Ship segment files, as a replicator would
+    for segment in value_log.manifest.list_segments() {
+        std::fs::copy(
+            &segment.path,
+            replica_path.join("segments").join(segment.id.to_string()),
+        )?;
+    }
+
+    replica.apply_metadata_snapshot(&snapshot)?;
+    assert_eq!(2, replica.segment_count());
+    assert_eq!(stale_bytes, replica.manifest.stale_bytes());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = replica.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(1_000));
+    }
+
+    // NOTE: New segments do not collide with snapshotted ones
+    write_batch(&replica, &MockIndex::default(), &["d"])?;
+    assert_eq!(3, replica.segment_count());
+    assert!(replica_path.join("segments").join("2").try_exists()?);
+
+    // NOTE: Segments that are not part of the snapshot are dropped
+    replica.apply_metadata_snapshot(&snapshot)?;
+    assert_eq!(2, replica.segment_count());
+    assert!(!replica_path.join("segments").join("2").try_exists()?);
+
+    assert!(replica.apply_metadata_snapshot(b"garbage").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "metrics")]
+
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn metrics_latency_histograms() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let mut index_writer = MockIndexWriter(index.clone());
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(0, value_log.statistics().get.count);
+
+    let mut writer = value_log.get_writer()?;
+    let mut handles = vec![];
+
+    for key in ["a", "b", "c", "d"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 100)?;
+        writer.write(key, key.repeat(100))?;
+        handles.push(vhandle);
+    }
+
+    value_log.register_writer(writer)?;
+
+    for vhandle in &handles {
+        assert!(value_log.get(vhandle)?.is_some());
+    }
+
+    let stats = value_log.statistics();
+    assert_eq!(4, stats.get.count);
+    assert_eq!(4, stats.segment_write.count);
+    assert!(
+        stats.fsync.count >= 2,
+        "segment & manifest should be synced"
+    );
+    assert!(stats.get.p50 <= stats.get.p99);
+    assert!(stats.get.p99 <= stats.get.p999);
+    assert!(stats.get.p999 <= stats.get.max);
+    assert_eq!(0, stats.rollover_relocate.count);
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    let stats = value_log.statistics();
+    assert_eq!(1, stats.rollover_relocate.count);
+    assert_eq!(1, stats.rollover_commit.count);
+    assert!(stats.rollover_index.count >= 2);
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+#![cfg(feature = "test_utils")]
+
+use std::ops::Bound;
+use test_log::test;
+use value_log::{
+    test_utils::{MockIndex, MockIndexWriter},
+    Compressor, Config, IndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn mock_index_harness() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+    assert!(index.is_empty());
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for _ in 0..2 {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c", "d"] {
+            let value = key.repeat(1_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    assert_eq!(4, index.len());
+    assert_eq!(4, index.overwrite_count());
+
+    let items =
+        index.range::<(Bound<&[u8]>, Bound<&[u8]>)>((Bound::Included(b"b"), Bound::Excluded(b"d")));
+    assert_eq!(
+        items.iter().map(|(k, _, _)| &**k).collect::<Vec<_>>(),
+        [b"b", b"c"]
+    );
+    assert!(items.iter().all(|(_, vhandle, _)| vhandle.segment_id == 1));
+
+    index.remove(b"a");
+    assert_eq!(3, index.len());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    // NOTE: Checking against a value log that lacks the segments breaks the invariant
+    let other = ValueLog::open(
+        folder.path().join("other"),
+        Config::<NoCompressor>::default(),
+    )?;
+    assert_eq!(3, index.verify(&other)?);
+
+    Ok(())
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    index_writer.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn mock_index_checked() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let index = MockIndex::checked(&value_log);
+
+    write_batch(&value_log, &index, &["a", "b", "c"])?;
+    write_batch(&value_log, &index, &["a", "d"])?;
+
+    // NOTE: Relocations are checked when garbage collection finishes its write batch
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(1, value_log.segment_count());
+
+    index.check_invariants(&value_log)?;
+
+    // NOTE: Checks stop once the value log is dropped
+    drop(value_log);
+    MockIndexWriter(index.clone()).finish()?;
+
+    Ok(())
+}
+
+#[test]
+#[should_panic = "not part of the value log"]
+fn mock_index_checked_unregistered() {
+    let folder = tempfile::tempdir().unwrap();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+    let index = MockIndex::checked(&value_log);
+
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer().unwrap();
+
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle, 1).unwrap();
+    writer.write("a", "a").unwrap();
+
+    // NOTE: The write batch is finished before the writer is registered
+    index_writer.finish().unwrap();
+}
+
+#[test]
+#[should_panic = "not part of the value log"]
+fn mock_index_dropped_segment() {
+    let folder = tempfile::tempdir().unwrap();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+    let index = MockIndex::default();
+    write_batch(&value_log, &index, &["a"]).unwrap();
+
+    let ids = value_log.manifest.list_segment_ids();
+    value_log.manifest.drop_segments(&ids).unwrap();
+
+    index.check_invariants(&value_log).unwrap();
+}
+
+#[test]
+#[should_panic = "does not match index"]
+fn mock_index_size_mismatch() {
+    let folder = tempfile::tempdir().unwrap();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default()).unwrap();
+    let index = MockIndex::checked(&value_log);
+
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer().unwrap();
+
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle, 1_000).unwrap();
+    writer.write("a", "a").unwrap();
+
+    value_log.register_writer(writer).unwrap();
+    index_writer.finish().unwrap();
+}
@@ -0,0 +1,159 @@
+use test_log::test;
+use value_log::{Compressor, Config, Error, OpenOptions, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn open_options_create() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = folder.path().join("vlog");
+
+    assert!(matches!(
+        OpenOptions::new()
+            .create(false)
+            .open(&path, Config::<NoCompressor>::default()),
+        Err(Error::NotFound)
+    ));
+
+    let value_log = OpenOptions::new()
+        .create_new(true)
+        .open(&path, Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    writer.write(b"a", b"abc")?;
+    value_log.register_writer(writer)?;
+    drop(value_log);
+
+    assert!(matches!(
+        OpenOptions::new()
+            .create_new(true)
+            .open(&path, Config::<NoCompressor>::default()),
+        Err(Error::AlreadyExists)
+    ));
+
+    let value_log = OpenOptions::new()
+        .create(false)
+        .open(&path, Config::<NoCompressor>::default())?;
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn open_invalid_version() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    drop(ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default(),
+    )?);
+
+    // NOTE: Unknown (newer) format version
+    let marker_path = folder.path().join(".vlog");
+    let mut marker = std::fs::read(&marker_path)?;
+    marker[3] = 255;
+    std::fs::write(&marker_path, marker)?;
+
+    assert!(matches!(
+        ValueLog::open(folder.path(), Config::<NoCompressor>::default()),
+        Err(Error::InvalidVersion(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn open_corrupt_manifest() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+        let mut writer = value_log.get_writer()?;
+        writer.write(b"a", b"abc")?;
+        value_log.register_writer(writer)?;
+    }
+
+    let manifest_path = folder.path().join("vlog_manifest");
+    let bytes = std::fs::read(&manifest_path)?;
+    std::fs::write(&manifest_path, &bytes[..bytes.len() - 2])?;
+
+    assert!(matches!(
+        ValueLog::open(folder.path(), Config::<NoCompressor>::default()),
+        Err(Error::CorruptManifest)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn open_locked() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = folder.path().join("vlog");
+
+    let value_log = ValueLog::open(&path, Config::<NoCompressor>::default())?;
+
+    for options in [
+        OpenOptions::new(),
+        OpenOptions::new().create(false),
+        OpenOptions::new().create_new(true),
+    ] {
+        assert!(matches!(
+            options.open(&path, Config::<NoCompressor>::default()),
+            Err(Error::Locked)
+        ));
+    }
+
+    drop(value_log);
+
+    let value_log = OpenOptions::new()
+        .create(false)
+        .open(&path, Config::<NoCompressor>::default())?;
+    assert_eq!(0, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn open_create_new_concurrently() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = folder.path().join("vlog");
+
+    // NOTE: Exactly one of the racing opens creates the value log,
+    // the others see it either being created, or already existing
+    let results = std::thread::scope(|scope| {
+        let threads = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    OpenOptions::new()
+                        .create_new(true)
+                        .open(&path, Config::<NoCompressor>::default())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        threads
+            .into_iter()
+            .map(|x| x.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    assert_eq!(1, results.iter().filter(|x| x.is_ok()).count());
+    assert!(results
+        .iter()
+        .filter_map(|x| x.as_ref().err())
+        .all(|e| matches!(e, Error::Locked | Error::AlreadyExists)));
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, Fs, FsFile, IndexWriter, IoClass, MockIndex, MockIndexWriter,
+    PageCacheAdvice, StdFs, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+type AdviceLog = Arc<Mutex<Vec<(u64, u64, PageCacheAdvice)>>>;
+
+/// File that records the page cache advice it is given
+struct RecordingFile(File, AdviceLog);
+
+impl Read for RecordingFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for RecordingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Seek for RecordingFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl FsFile for RecordingFile {
+    fn sync_all(&self) -> std::io::Result<()> {
+        self.0.sync_all()
+    }
+
+    fn advise(&self, offset: u64, len: u64, advice: PageCacheAdvice) -> std::io::Result<()> {
+        self.1.lock().unwrap().push((offset, len, advice));
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingFs(AdviceLog);
+
+impl RecordingFs {
+    fn take(&self) -> Vec<(u64, u64, PageCacheAdvice)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Fs for RecordingFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.create(path)
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        Ok(Box::new(RecordingFile(File::open(path)?, self.0.clone())))
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        StdFs.rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.sync_directory(path)
+    }
+}
+
+fn write_segment(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+) -> value_log::Result<Vec<value_log::ValueHandle>> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+    let mut handles = vec![];
+
+    for key in ["a", "b", "c", "d"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 100)?;
+        writer.write(key, key.repeat(100))?;
+        handles.push(vhandle);
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(handles)
+}
+
+#[test]
+fn page_cache_advice_defaults() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let fs = Arc::new(RecordingFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(fs.clone()),
+    )?;
+
+    let handles = write_segment(&value_log, &index)?;
+    fs.take();
+
+    assert_eq!(4, value_log.scan_blobs()?.count());
+    assert_eq!(vec![(0, 0, PageCacheAdvice::Sequential)], fs.take());
+
+    value_log.get_with_prefetch(&handles[0], 2)?;
+    let advice = fs.take();
+    assert_eq!(1, advice.len());
+    assert_eq!(handles[0].offset, advice[0].0);
+    assert!(advice[0].1 > 0);
+    assert_eq!(PageCacheAdvice::WillNeed, advice[0].2);
+
+    // NOTE: Point reads without prefetching give no advice
+    value_log.get(&handles[1])?;
+    assert!(fs.take().is_empty());
+
+    let ids = value_log.manifest.list_segment_ids();
+    value_log.rollover(&ids, &index, MockIndexWriter(index.clone()))?;
+    assert_eq!(
+        vec![
+            (0, 0, PageCacheAdvice::Sequential),
+            (0, 0, PageCacheAdvice::DontNeed),
+        ],
+        fs.take()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn page_cache_advice_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let fs = Arc::new(RecordingFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .fs(fs.clone())
+            .page_cache_advice(IoClass::Scan, None)
+            .page_cache_advice(IoClass::Rollover, Some(PageCacheAdvice::Normal))
+            .page_cache_advice(IoClass::RolloverFinished, None)
+            .page_cache_advice(IoClass::Prefetch, None),
+    )?;
+
+    let handles = write_segment(&value_log, &index)?;
+    fs.take();
+
+    assert_eq!(4, value_log.scan_blobs()?.count());
+    value_log.get_with_prefetch(&handles[0], 2)?;
+    assert!(fs.take().is_empty());
+
+    let ids = value_log.manifest.list_segment_ids();
+    value_log.rollover(&ids, &index, MockIndexWriter(index.clone()))?;
+    assert_eq!(vec![(0, 0, PageCacheAdvice::Normal)], fs.take());
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn par_scan_all_segments() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for batch in 0..8u32 {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for item in 0..100u32 {
+            let key = format!("{batch}-{item:0>3}");
+            let value = key.repeat(100);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+    assert_eq!(8, value_log.segment_count());
+
+    let mut scanned = BTreeMap::new();
+
+    value_log.par_scan(4, |key, vhandle, value| {
+        assert_eq!(&*value, &*key.repeat(100));
+        scanned.insert(key, vhandle);
+    })?;
+
+    assert_eq!(800, scanned.len());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        assert_eq!(Some(vhandle), scanned.get(key));
+    }
+
+    // NOTE: Unreadable segments abort the scan
+    let segment = value_log.manifest.get_segment(3).unwrap();
+    std::fs::write(&segment.path, b"garbage")?;
+    assert!(value_log.par_scan(4, |_, _, _| {}).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, ErrorCategory, Fs, FsFile, IndexWriter, MockIndex, MockIndexWriter, StdFs,
+    ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const POISON: &[u8] = b"POISON";
+
+/// File that silently damages every written occurrence of [`POISON`]
+struct CorruptingFile(Box<dyn FsFile>);
+
+impl Read for CorruptingFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Seek for CorruptingFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl Write for CorruptingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut buf = buf.to_vec();
+
+        for idx in 0..buf.len().saturating_sub(POISON.len() - 1) {
+            if buf[idx..].starts_with(POISON) {
+                buf[idx] = b'X';
+            }
+        }
+
+        self.0.write_all(&buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl FsFile for CorruptingFile {
+    fn sync_all(&self) -> std::io::Result<()> {
+        self.0.sync_all()
+    }
+}
+
+/// File system whose segment files are written with bit rot
+struct CorruptingFs;
+
+impl Fs for CorruptingFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        Ok(Box::new(CorruptingFile(StdFs.create(path)?)))
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.open(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        StdFs.rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.sync_directory(path)
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[(&str, &[u8])],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for (key, value) in items {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    index_writer.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn paranoid_checks_pass() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_chunk_size(100)
+            .key_restart_interval(4)
+            .paranoid_checks(1),
+    )?;
+
+    let large = b"b".repeat(1_000);
+    write_items(
+        &value_log,
+        &index,
+        &[("a", b"a"), ("ab", &large), ("abc", b"c"), ("abcd", b"d")],
+    )?;
+    write_items(&value_log, &index, &[("a", b"new")])?;
+
+    // NOTE: Segments written by garbage collection are checked as well
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn paranoid_checks_detect_mismatch() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .fs(Arc::new(CorruptingFs))
+            .paranoid_checks(1),
+    )?;
+
+    write_items(&value_log, &index, &[("a", b"healthy")])?;
+
+    let err = write_items(&value_log, &index, &[("b", b"b"), ("c", POISON)]).unwrap_err();
+    assert_eq!(ErrorCategory::Corruption, err.category());
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn paranoid_checks_sampled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .fs(Arc::new(CorruptingFs))
+            .paranoid_checks(2),
+    )?;
+
+    // NOTE: Only the first & third blob are read back
+    write_items(
+        &value_log,
+        &index,
+        &[("a", b"a"), ("b", POISON), ("c", b"c")],
+    )?;
+    assert_eq!(1, value_log.verify()?);
+
+    let err = write_items(
+        &value_log,
+        &index,
+        &[("a", b"a"), ("b", b"b"), ("c", POISON)],
+    )
+    .unwrap_err();
+    assert_eq!(ErrorCategory::Corruption, err.category());
+
+    Ok(())
+}
+
+#[test]
+fn paranoid_checks_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(Arc::new(CorruptingFs)),
+    )?;
+
+    write_items(&value_log, &index, &[("a", POISON)])?;
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(1, value_log.verify()?);
+
+    Ok(())
+}
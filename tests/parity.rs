@@ -0,0 +1,214 @@
+use std::io::{Seek, SeekFrom, Write};
+use test_log::test;
+use value_log::{Compressor, Config, Error, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const VALUE_SIZE: usize = 16_000;
+
+fn write_keys(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: std::ops::Range<u8>,
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in keys {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(&[key], vhandle, VALUE_SIZE as u32)?;
+        writer.write([key], vec![key; VALUE_SIZE])?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+fn check_values(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, vec![key[0]; VALUE_SIZE]);
+    }
+    Ok(())
+}
+
+fn damage(path: &std::path::Path, offset: u64, len: usize) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&vec![0xFF; len])?;
+    file.sync_all()
+}
+
+#[test]
+fn parity_repair() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().parity_shards(4),
+    )?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..20)?;
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+
+    let parity_path = folder.path().join("parity").join(segment_id.to_string());
+    assert!(parity_path.try_exists()?);
+
+    // NOTE: Nothing to repair
+    assert_eq!(0, value_log.repair_segment(segment_id)?);
+
+    damage(&segment.path, 1_000, 20_000)?;
+    assert!(value_log.verify().map_or(true, |x| x > 0));
+
+    // NOTE: Damaged blocks are repaired as a whole
+    assert!(value_log.repair_segment(segment_id)? >= 20_000);
+
+    assert_eq!(0, value_log.verify()?);
+    check_values(&value_log, &index)?;
+
+    // NOTE: Damaged parity is rewritten
+    damage(&parity_path, 100, 10)?;
+    assert_eq!(0, value_log.repair_segment(segment_id)?);
+
+    damage(&segment.path, 0, 10)?;
+    assert!(value_log.repair_segment(segment_id)? > 0);
+    check_values(&value_log, &index)?;
+
+    // NOTE: Missing parity is rewritten, if the segment is intact
+    std::fs::remove_file(&parity_path)?;
+    assert_eq!(0, value_log.repair_segment(segment_id)?);
+    assert!(parity_path.try_exists()?);
+
+    Ok(())
+}
+
+#[test]
+fn parity_unrepairable() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().parity_shards(2),
+    )?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..20)?;
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+
+    // NOTE: Damage the same position in both shards
+    let file_len = std::fs::metadata(&segment.path)?.len();
+    let shard_len = file_len.div_ceil(4_096).div_ceil(2) * 4_096;
+
+    damage(&segment.path, 100, 10)?;
+    damage(&segment.path, shard_len + 100, 10)?;
+
+    assert!(matches!(
+        value_log.repair_segment(segment_id),
+        Err(Error::Unrepairable(id)) if id == segment_id,
+    ));
+
+    // NOTE: Without parity, damaged segments cannot be repaired
+    std::fs::remove_file(folder.path().join("parity").join(segment_id.to_string()))?;
+
+    assert!(matches!(
+        value_log.repair_segment(segment_id),
+        Err(Error::Unrepairable(_)),
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn parity_restore_lost_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let parity_folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .parity_shards(1)
+            .parity_folder(parity_folder.path()),
+    )?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..20)?;
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+    let file_len = std::fs::metadata(&segment.path)?.len();
+
+    assert!(parity_folder
+        .path()
+        .join(segment_id.to_string())
+        .try_exists()?);
+
+    std::fs::remove_file(&segment.path)?;
+
+    assert!(value_log.repair_segment(segment_id)? > 0);
+    assert_eq!(file_len, std::fs::metadata(&segment.path)?.len());
+    assert_eq!(0, value_log.verify()?);
+    check_values(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn parity_dropped_with_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().parity_shards(4),
+    )?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..20)?;
+    let old_segment_id = value_log.manifest.list_segment_ids()[0];
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    let parity_folder = folder.path().join("parity");
+
+    let new_segment_id = value_log.manifest.list_segment_ids()[0];
+    assert_ne!(old_segment_id, new_segment_id);
+
+    assert!(!parity_folder
+        .join(old_segment_id.to_string())
+        .try_exists()?);
+    assert!(parity_folder
+        .join(new_segment_id.to_string())
+        .try_exists()?);
+
+    // NOTE: Orphaned parity files are cleaned up on recovery
+    std::fs::write(parity_folder.join("999"), b"orphan")?;
+    drop(value_log);
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().parity_shards(4),
+    )?;
+    assert!(!parity_folder.join("999").try_exists()?);
+    assert!(parity_folder
+        .join(new_segment_id.to_string())
+        .try_exists()?);
+    check_values(&value_log, &index)?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, StaleThresholdStrategy, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn pinned_segment_is_not_dropped() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for _ in 0..2 {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, 10_000)?;
+            writer.write(key, key.repeat(10_000))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let mut ids = value_log.manifest.list_segment_ids();
+    ids.sort_unstable();
+    let old_id = ids[0];
+
+    value_log.pin_segment(old_id)?;
+    assert_eq!(vec![old_id], value_log.pinned_segments());
+    assert!(value_log.pin_segment(1_000).is_err());
+
+    // NOTE: The first segment is completely overwritten by the second one
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    assert!(value_log.manifest.get_segment(old_id).unwrap().is_stale());
+
+    let strategy = StaleThresholdStrategy::new(0.5);
+    assert!(value_log.pick_candidates(&strategy).is_empty());
+    assert!(value_log
+        .relocate_segment(old_id, &index, MockIndexWriter(index.clone()))?
+        .is_empty());
+    assert_eq!(0, value_log.punch_stale_blobs(old_id, &index)?);
+
+    assert!(value_log
+        .drop_stale_segments_with_report()?
+        .segment_ids
+        .is_empty());
+    assert_eq!(2, value_log.segment_count());
+
+    value_log.unpin_segment(old_id)?;
+    value_log.unpin_segment(old_id)?;
+    assert!(value_log.pinned_segments().is_empty());
+
+    assert_eq!(
+        vec![old_id],
+        value_log.drop_stale_segments_with_report()?.segment_ids
+    );
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn pins_are_persisted() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let segment_id = {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+        let mut writer = value_log.get_writer()?;
+        writer.write("a", "hello")?;
+        value_log.register_writer(writer)?;
+
+        let segment_id = value_log.manifest.list_segment_ids()[0];
+        value_log.pin_segment(segment_id)?;
+
+        // NOTE: Registering another segment rewrites the manifest, which needs to keep the pin
+        let mut writer = value_log.get_writer()?;
+        writer.write("b", "world")?;
+        value_log.register_writer(writer)?;
+
+        value_log.checkpoint(folder.path().join("checkpoint"))?;
+
+        segment_id
+    };
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert_eq!(vec![segment_id], value_log.pinned_segments());
+
+    let checkpoint = ValueLog::open(
+        folder.path().join("checkpoint"),
+        Config::<NoCompressor>::default(),
+    )?;
+    assert_eq!(vec![segment_id], checkpoint.pinned_segments());
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexWriter, MockIndex, MockIndexWriter, PipelineStage,
+    SegmentBuilder, Transform, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Debug, Default)]
+struct Lz4Compressor;
+
+impl Compressor for Lz4Compressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        lz4_flex::decompress_size_prepended(bytes).map_err(|_| Error::Decompress)
+    }
+}
+
+/// Toy transform that reverses the stored bytes
+struct Reverse;
+
+impl Transform for Reverse {
+    fn id(&self) -> u8 {
+        7
+    }
+
+    fn apply(&self, _: u64, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.iter().rev().copied().collect())
+    }
+
+    fn revert(&self, _: u64, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.iter().rev().copied().collect())
+    }
+}
+
+fn write_items(value_log: &ValueLog<Lz4Compressor>, index: &MockIndex) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in ["a", "b", "c"] {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+fn check_items(value_log: &ValueLog<Lz4Compressor>, index: &MockIndex) -> value_log::Result<()> {
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, key.repeat(1_000).as_slice());
+    }
+    Ok(())
+}
+
+#[test]
+fn pipeline_default_stages() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<Lz4Compressor>::default())?;
+    let index = MockIndex::default();
+    write_items(&value_log, &index)?;
+
+    for segment in value_log.manifest.list_segments() {
+        assert_eq!(vec![PipelineStage::Compression], segment.meta.pipeline);
+    }
+
+    check_items(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_transform() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<Lz4Compressor>::default().transform(Arc::new(Reverse)),
+        )?;
+        write_items(&value_log, &index)?;
+
+        for segment in value_log.manifest.list_segments() {
+            assert_eq!(
+                vec![PipelineStage::Compression, PipelineStage::Transform(7)],
+                segment.meta.pipeline,
+            );
+        }
+
+        check_items(&value_log, &index)?;
+        assert_eq!(0, value_log.verify()?);
+
+        let items = value_log
+            .scan_blobs()?
+            .collect::<value_log::Result<Vec<_>>>()?;
+        assert_eq!(3, items.len());
+        for (key, _, value) in items {
+            assert_eq!(&*value, key.repeat(1_000).as_slice());
+        }
+    }
+
+    {
+        // NOTE: The stages are recorded, so they survive a restart
+        let value_log = ValueLog::open(folder.path(), Config::<Lz4Compressor>::default())?;
+
+        for segment in value_log.manifest.list_segments() {
+            assert!(segment.meta.pipeline.contains(&PipelineStage::Transform(7)));
+        }
+
+        for (_, (vhandle, _)) in index.read().unwrap().iter() {
+            assert!(matches!(
+                value_log.get(vhandle),
+                Err(Error::MissingTransform(7))
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_transform_added_later() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<Lz4Compressor>::default())?;
+        write_items(&value_log, &index)?;
+    }
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<Lz4Compressor>::default().transform(Arc::new(Reverse)),
+    )?;
+
+    // NOTE: Old segments are read with the pipeline they were written with
+    check_items(&value_log, &index)?;
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    for segment in value_log.manifest.list_segments() {
+        assert_eq!(
+            vec![PipelineStage::Compression, PipelineStage::Transform(7)],
+            segment.meta.pipeline,
+        );
+    }
+
+    check_items(&value_log, &index)?;
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_uncompressed_segment_builder() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let mut builder = SegmentBuilder::<Lz4Compressor>::new(folder.path().join("prepared"))?;
+    let offset = builder.offset();
+    builder.write("a", "a".repeat(1_000))?;
+    let path = builder.finish()?;
+
+    let value_log = ValueLog::open(
+        folder.path().join("vlog"),
+        Config::<Lz4Compressor>::default(),
+    )?;
+    let segment_id = value_log.ingest_segment(path)?;
+
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+    assert!(segment.meta.pipeline.is_empty());
+
+    // NOTE: The value log compresses, but the segment's values are not decompressed
+    let item = value_log.get(&ValueHandle { segment_id, offset })?.unwrap();
+    assert_eq!(&*item, "a".repeat(1_000).as_bytes());
+
+    Ok(())
+}
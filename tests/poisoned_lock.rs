@@ -0,0 +1,64 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn poisoned_lock_recovery() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let write = |key: &str| -> value_log::Result<()> {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, key.len() as u32)?;
+        writer.write(key, key)?;
+
+        value_log.register_writer(writer)?;
+        index_writer.finish()?;
+
+        Ok(())
+    };
+
+    write("a")?;
+
+    // Poison the rollover guard
+    {
+        let value_log = value_log.clone();
+
+        std::thread::spawn(move || {
+            let _guard = value_log.rollover_guard.lock().unwrap();
+            panic!("oops");
+        })
+        .join()
+        .unwrap_err();
+    }
+
+    assert!(value_log.rollover_guard.is_poisoned());
+
+    // Operations keep working
+    write("b")?;
+    assert_eq!(2, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    assert_eq!(1.0, value_log.space_amp());
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::sync::{Arc, Mutex};
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, Operation, Progress, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn progress_callback() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().progress_callback({
+            let reports = reports.clone();
+            Arc::new(move |progress| reports.lock().unwrap().push(progress.clone()))
+        }),
+    )?;
+
+    for _ in 0..2 {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in 0..1_500u32 {
+            let key = key.to_be_bytes();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(&key, vhandle, 10)?;
+
+            writer.write(key, [0; 10])?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+    assert!(reports.lock().unwrap().is_empty());
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    {
+        let reports = std::mem::take(&mut *reports.lock().unwrap());
+        assert!(reports.len() >= 2);
+
+        let last = reports.last().unwrap();
+        assert_eq!(Operation::ScanForStats, last.operation);
+        assert!(last.done);
+        assert_eq!(1_500, last.items_processed);
+        assert_eq!(15_000, last.bytes_processed);
+        assert!(reports.iter().rev().skip(1).all(|x| !x.done));
+    }
+
+    assert_eq!(0, value_log.verify()?);
+    {
+        let reports = std::mem::take(&mut *reports.lock().unwrap());
+        let last = reports.last().unwrap();
+        assert_eq!(Operation::Verify, last.operation);
+        assert!(last.done);
+    }
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    {
+        let reports = std::mem::take(&mut *reports.lock().unwrap());
+        let last = reports.last().unwrap();
+        assert_eq!(Operation::Rollover, last.operation);
+        assert!(last.done);
+
+        // NOTE: Every version is scanned, even if only the latest one is relocated
+        assert_eq!(3_000, last.items_processed);
+    }
+
+    Ok(())
+}
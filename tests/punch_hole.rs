@@ -0,0 +1,115 @@
+use std::os::unix::fs::MetadataExt;
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+const VALUE_SIZE: usize = 16_000;
+
+fn write_keys(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: std::ops::Range<u8>,
+    fill: u8,
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in keys {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(&[key], vhandle, VALUE_SIZE as u32)?;
+        writer.write([key], vec![fill; VALUE_SIZE])?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn punch_stale_blobs() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..20, 1)?;
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    let segment = value_log.manifest.get_segment(segment_id).unwrap();
+
+    // NOTE: Overwrite the first half of the keys, so their blobs become stale
+    write_keys(&value_log, &index, 0..10, 2)?;
+
+    let blocks_before = std::fs::metadata(&segment.path)?.blocks();
+
+    let punched = value_log.punch_stale_blobs(segment_id, &index)?;
+
+    let record_len = (8 + 8 + 2 + 1 + 4 + VALUE_SIZE) as u64;
+    assert_eq!(10 * record_len, punched);
+    assert_eq!(punched, segment.gc_stats.punched_bytes());
+    assert_eq!(
+        punched,
+        value_log
+            .summary()
+            .segments
+            .iter()
+            .find(|x| x.id == segment_id)
+            .unwrap()
+            .punched_bytes,
+    );
+
+    assert!(std::fs::metadata(&segment.path)?.blocks() < blocks_before);
+
+    // NOTE: Punched blobs are skipped when scanning
+    assert_eq!(10, segment.scan()?.count());
+    assert_eq!(10, segment.scan_meta()?.count());
+    assert_eq!(0, value_log.verify()?);
+    assert_eq!(0, index.verify(&value_log)?);
+
+    for item in value_log.scan_blobs()? {
+        let (key, vhandle, value) = item?;
+        assert_eq!(if key[0] < 10 { 2 } else { 1 }, value[0]);
+        assert_eq!(value, value_log.get(&vhandle)?.unwrap());
+    }
+
+    // NOTE: Nothing is left to punch
+    assert_eq!(0, value_log.punch_stale_blobs(segment_id, &index)?);
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(0, index.verify(&value_log)?);
+    assert_eq!(20, value_log.scan_blobs()?.count());
+
+    Ok(())
+}
+
+#[test]
+fn punch_stale_ranges_unaligned() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let index = MockIndex::default();
+
+    write_keys(&value_log, &index, 0..2, 1)?;
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+
+    let err = segment.punch_stale_ranges(&[1..100]).unwrap_err();
+    assert!(matches!(
+        err,
+        value_log::Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput
+    ));
+    assert_eq!(0, segment.gc_stats.punched_bytes());
+
+    Ok(())
+}
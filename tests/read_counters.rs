@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use test_log::test;
+use value_log::{BlobCache, Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn read_counters_persisted() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    // NOTE: Disable the blob cache, so every read hits the segment
+    let config = || {
+        Config::<NoCompressor>::default()
+            .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+            .temperature_thresholds(0.001, 1_000.0)
+    };
+
+    let mut handles = vec![];
+
+    {
+        let value_log = ValueLog::open(folder.path(), config())?;
+
+        for key in ["a", "b"] {
+            let mut index_writer = MockIndexWriter(index.clone());
+            let mut writer = value_log.get_writer()?;
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 5)?;
+            writer.write(key, "hello")?;
+
+            value_log.register_writer(writer)?;
+            handles.push(vhandle);
+        }
+
+        let counters = value_log.read_counters(handles[0].segment_id).unwrap();
+        assert_eq!(0, counters.reads);
+        assert_eq!(None, counters.last_read_at);
+        assert_eq!(None, value_log.read_counters(1_000));
+
+        for _ in 0..10 {
+            value_log.get(&handles[0])?;
+        }
+
+        value_log.persist_read_counters()?;
+
+        // NOTE: Not persisted yet
+        value_log.get(&handles[0])?;
+    }
+
+    {
+        let value_log = ValueLog::open(folder.path(), config())?;
+
+        let counters = value_log.read_counters(handles[0].segment_id).unwrap();
+        assert_eq!(10, counters.reads);
+        assert!(counters.last_read_at.is_some());
+        assert_eq!(
+            Some(value_log::Temperature::Warm),
+            value_log.temperature(handles[0].segment_id)
+        );
+
+        assert_eq!(
+            0,
+            value_log
+                .read_counters(handles[1].segment_id)
+                .unwrap()
+                .reads
+        );
+
+        value_log.get(&handles[0])?;
+        value_log.close()?;
+    }
+
+    {
+        let value_log = ValueLog::open(folder.path(), config())?;
+        assert_eq!(
+            11,
+            value_log
+                .read_counters(handles[0].segment_id)
+                .unwrap()
+                .reads
+        );
+    }
+
+    Ok(())
+}
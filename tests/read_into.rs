@@ -0,0 +1,113 @@
+use test_log::test;
+use value_log::{
+    BlobCache, Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[derive(Clone, Default)]
+struct Lz4Compressor;
+
+impl Compressor for Lz4Compressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        lz4_flex::decompress_size_prepended(bytes).map_err(|_| value_log::Error::Decompress)
+    }
+}
+
+fn write_values<C: Compressor + Clone>(
+    value_log: &ValueLog<C>,
+    index: &MockIndex,
+) -> value_log::Result<Vec<ValueHandle>> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+    let mut handles = vec![];
+
+    for key in ["a", "b", "c"] {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 1_000)?;
+        writer.write(key, key.repeat(1_000))?;
+        handles.push(vhandle);
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(handles)
+}
+
+#[test]
+fn read_into_vec() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    // NOTE: Disable the blob cache, so every read hits the segment
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(std::sync::Arc::new(BlobCache::with_capacity_bytes(0))),
+    )?;
+
+    let handles = write_values(&value_log, &index)?;
+
+    let mut buf = b"leftover".repeat(1_000);
+
+    for (key, vhandle) in ["a", "b", "c"].iter().zip(&handles) {
+        assert!(value_log.read_into(vhandle, &mut buf)?);
+        assert_eq!(key.repeat(1_000).as_bytes(), &*buf);
+    }
+
+    let missing = ValueHandle {
+        segment_id: 1_000,
+        offset: 0,
+    };
+    assert!(!value_log.read_into(&missing, &mut buf)?);
+
+    Ok(())
+}
+
+#[test]
+fn read_into_slice() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<Lz4Compressor>::default())?;
+
+    let handles = write_values(&value_log, &index)?;
+
+    let mut buf = [0; 2_000];
+    assert_eq!(
+        Some(1_000),
+        value_log.read_into_slice(&handles[1], &mut buf)?
+    );
+    assert_eq!(b"b".repeat(1_000), &buf[..1_000]);
+
+    // NOTE: Cached values are copied as well
+    value_log.get(&handles[2])?;
+    assert_eq!(
+        Some(1_000),
+        value_log.read_into_slice(&handles[2], &mut buf)?
+    );
+    assert_eq!(b"c".repeat(1_000), &buf[..1_000]);
+
+    let mut small = [0; 10];
+    assert!(value_log.read_into_slice(&handles[0], &mut small).is_err());
+
+    let mut vec = vec![];
+    assert!(value_log.read_into(&handles[0], &mut vec)?);
+    assert_eq!(b"a".repeat(1_000), vec);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, Relocation, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Index writer that records the relocation batches it receives
+struct BatchRecorder {
+    inner: MockIndexWriter,
+    batches: Vec<Vec<Vec<u8>>>,
+}
+
+impl IndexWriter for &mut BatchRecorder {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        self.inner.insert_indirect(key, vhandle, size)
+    }
+
+    fn relocate_batch(&mut self, batch: &[Relocation]) -> std::io::Result<()> {
+        self.batches
+            .push(batch.iter().map(|x| x.key.to_vec()).collect());
+
+        for item in batch {
+            self.inner.relocate_indirect_with_meta(
+                &item.key,
+                &item.expected,
+                item.vhandle.clone(),
+                item.size,
+                &item.meta,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.inner.finish()
+    }
+}
+
+#[test]
+fn rollover_relocation_batches() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().relocation_batch_size(4),
+    )?;
+
+    // NOTE: Written in reverse order, so batches need to be sorted
+    let keys = (0..10u8).rev().map(|x| vec![b'a' + x]).collect::<Vec<_>>();
+
+    for chunk in keys.chunks(5) {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in chunk {
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key, vhandle, 100)?;
+            writer.write(key, key.repeat(100))?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let mut recorder = BatchRecorder {
+        inner: MockIndexWriter(index.clone()),
+        batches: vec![],
+    };
+
+    let ids = value_log.manifest.list_segment_ids();
+    value_log.rollover(&ids, &index, &mut recorder)?;
+
+    assert_eq!(
+        vec![4, 4, 2],
+        recorder.batches.iter().map(Vec::len).collect::<Vec<_>>()
+    );
+
+    for batch in &recorder.batches {
+        assert!(batch.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    assert_eq!(10, recorder.batches.concat().len());
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(1, value_log.segment_count());
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let value = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*value, &*key.repeat(100));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+fn indexed_handles(index: &MockIndex) -> Vec<ValueHandle> {
+    index.range(..).into_iter().map(|(_, x, _)| x).collect()
+}
+
+#[test]
+fn remap_relocations() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().remap_relocations(true),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b", "c"])?;
+    write_items(&value_log, &index, &["b", "d"])?;
+
+    let handles_before = indexed_handles(&index);
+    let overwrites_before = index.overwrite_count();
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(4, value_log.remap_count());
+
+    // NOTE: The index was not touched, but still resolves through the remap tables
+    assert_eq!(handles_before, indexed_handles(&index));
+    assert_eq!(overwrites_before, index.overwrite_count());
+    assert_eq!(0, index.verify(&value_log)?);
+    index.check_invariants(&value_log)?;
+
+    for vhandle in &handles_before {
+        let remapped = value_log.remapped_handle(vhandle).unwrap();
+        assert_eq!(value_log.get(vhandle)?, value_log.get(&remapped)?);
+    }
+
+    // NOTE: The new segment is referenced through the remaps, so it is not stale
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    assert_eq!(4, value_log.apply_remaps(MockIndexWriter(index.clone()))?);
+    assert_eq!(0, value_log.remap_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    assert!(indexed_handles(&index)
+        .iter()
+        .all(|x| x.segment_id == segment_id));
+
+    Ok(())
+}
+
+#[test]
+fn remap_relocations_chained() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().remap_relocations(true),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b", "c"])?;
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    write_items(&value_log, &index, &["c", "d"])?;
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    // NOTE: Applying the remaps points the index to the blobs' final locations
+    value_log.apply_remaps(MockIndexWriter(index.clone()))?;
+    assert_eq!(0, value_log.remap_count());
+    assert_eq!(0, index.verify(&value_log)?);
+    index.check_invariants(&value_log)?;
+
+    Ok(())
+}
+
+#[test]
+fn remap_relocations_recover() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    {
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().remap_relocations(true),
+        )?;
+
+        write_items(&value_log, &index, &["a", "b", "c"])?;
+        write_items(&value_log, &index, &["b"])?;
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        assert_eq!(3, value_log.remap_count());
+    }
+
+    {
+        let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+        assert_eq!(3, value_log.remap_count());
+        assert_eq!(0, index.verify(&value_log)?);
+
+        // NOTE: Without remapping, relocations are passed to the index
+        // for the value handles it actually stores
+        write_items(&value_log, &index, &["d"])?;
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        assert_eq!(0, index.verify(&value_log)?);
+        index.check_invariants(&value_log)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn remap_relocations_block_backups() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().remap_relocations(true),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b", "c"])?;
+    write_items(&value_log, &index, &["b"])?;
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+
+    // NOTE: The index points into dropped segments, so it cannot be restored alongside a copy
+    let backup = tempfile::tempdir()?;
+    assert!(matches!(
+        value_log.incremental_backup(&[], Some(backup.path())),
+        Err(Error::PendingRemaps),
+    ));
+    assert!(matches!(
+        value_log.export(Vec::new()),
+        Err(Error::PendingRemaps)
+    ));
+    assert!(matches!(
+        value_log.metadata_snapshot(),
+        Err(Error::PendingRemaps)
+    ));
+
+    value_log.apply_remaps(MockIndexWriter(index.clone()))?;
+
+    value_log.incremental_backup(&[], Some(backup.path()))?;
+    value_log.export(Vec::new())?;
+    value_log.metadata_snapshot()?;
+
+    Ok(())
+}
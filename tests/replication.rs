@@ -0,0 +1,100 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, Replicator, SegmentMetadata,
+    ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Ships segment files into a replica folder
+struct FolderReplicator {
+    folder: PathBuf,
+    registered: Mutex<Vec<(u64, u64)>>,
+    dropped: Mutex<Vec<u64>>,
+}
+
+impl Replicator for FolderReplicator {
+    fn segment_registered(&self, segment_id: u64, path: &Path, metadata: &SegmentMetadata) {
+        std::fs::copy(path, self.folder.join(segment_id.to_string())).unwrap();
+
+        self.registered
+            .lock()
+            .unwrap()
+            .push((segment_id, metadata.item_count));
+    }
+
+    fn segments_dropped(&self, segment_ids: &[u64]) {
+        for id in segment_ids {
+            std::fs::remove_file(self.folder.join(id.to_string())).unwrap();
+        }
+
+        self.dropped.lock().unwrap().extend_from_slice(segment_ids);
+    }
+}
+
+#[test]
+fn replication_hooks() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let replica_folder = tempfile::tempdir()?;
+
+    let replicator = Arc::new(FolderReplicator {
+        folder: replica_folder.path().into(),
+        registered: Mutex::default(),
+        dropped: Mutex::default(),
+    });
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().replicator(replicator.clone()),
+    )?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let value = key.repeat(1_000);
+            let value = value.as_bytes();
+
+            let key = key.as_bytes();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    assert_eq!(*replicator.registered.lock().unwrap(), [(0, 3)]);
+    assert!(replica_folder.path().join("0").try_exists()?);
+
+    // NOTE: Rewriting the segment registers a new one, and dropping the old one is replicated
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    assert_eq!(*replicator.registered.lock().unwrap(), [(0, 3), (1, 3)]);
+    assert_eq!(*replicator.dropped.lock().unwrap(), [0]);
+    assert!(!replica_folder.path().join("0").try_exists()?);
+    assert!(replica_folder.path().join("1").try_exists()?);
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread::ThreadId,
+};
+use test_log::test;
+use value_log::{Compressor, Config, Fs, FsFile, RequestContext, StdFs, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Request ID, as stored in the request context
+#[derive(Debug, Eq, PartialEq)]
+struct RequestId(u64);
+
+fn current_request_id() -> Option<u64> {
+    RequestContext::current().and_then(|ctx| ctx.downcast_ref::<RequestId>().map(|x| x.0))
+}
+
+/// File system that records the request of every file that is opened,
+/// and every manifest write
+#[derive(Default)]
+struct RecordingFs {
+    opens: Mutex<Vec<(ThreadId, Option<u64>)>>,
+    manifest_writes: Mutex<Vec<(ThreadId, Option<u64>)>>,
+}
+
+impl Fs for RecordingFs {
+    fn create(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        StdFs.create(path)
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn FsFile>> {
+        self.opens
+            .lock()
+            .unwrap()
+            .push((std::thread::current().id(), current_request_id()));
+        StdFs.open(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::io::Result<bool> {
+        StdFs.exists(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.create_dir_all(path)
+    }
+
+    fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        StdFs.list_files(path)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        StdFs.hard_link(src, dst)
+    }
+
+    fn rewrite_atomic(&self, path: &Path, content: &[u8]) -> std::io::Result<()> {
+        if path.ends_with("vlog_manifest") {
+            self.manifest_writes
+                .lock()
+                .unwrap()
+                .push((std::thread::current().id(), current_request_id()));
+        }
+        StdFs.rewrite_atomic(path, content)
+    }
+
+    fn sync_directory(&self, path: &Path) -> std::io::Result<()> {
+        StdFs.sync_directory(path)
+    }
+}
+
+#[test]
+fn request_context_scope() {
+    assert!(RequestContext::current().is_none());
+
+    RequestContext::new(RequestId(1)).scope(|| {
+        assert_eq!(Some(1), current_request_id());
+
+        RequestContext::new(RequestId(2)).scope(|| {
+            assert_eq!(Some(2), current_request_id());
+        });
+
+        assert_eq!(Some(1), current_request_id());
+        assert!(RequestContext::current()
+            .unwrap()
+            .downcast_ref::<String>()
+            .is_none());
+    });
+
+    assert!(RequestContext::current().is_none());
+}
+
+#[test]
+fn request_context_background_flush() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let fs = Arc::new(RecordingFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(fs.clone()),
+    )?;
+    value_log.start_background_flush()?;
+    fs.manifest_writes.lock().unwrap().clear();
+
+    RequestContext::new(RequestId(42)).scope(|| -> value_log::Result<()> {
+        let mut writer = value_log.get_writer()?;
+        writer.write("a", "a".repeat(1_000))?;
+        value_log.submit_writer(writer)
+    })?;
+
+    value_log.flush_barrier()?;
+    assert_eq!(1, value_log.segment_count());
+
+    let manifest_writes = fs.manifest_writes.lock().unwrap();
+    let (thread_id, request_id) = manifest_writes.first().unwrap();
+    assert_ne!(std::thread::current().id(), *thread_id);
+    assert_eq!(Some(42), *request_id);
+
+    Ok(())
+}
+
+#[test]
+fn request_context_par_scan() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let fs = Arc::new(RecordingFs::default());
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().fs(fs.clone()),
+    )?;
+
+    for key in ["a", "b", "c", "d"] {
+        let mut writer = value_log.get_writer()?;
+        writer.write(key, key.repeat(1_000))?;
+        value_log.register_writer(writer)?;
+    }
+
+    fs.opens.lock().unwrap().clear();
+
+    let mut count = 0;
+
+    RequestContext::new(RequestId(7)).scope(|| value_log.par_scan(2, |_, _, _| count += 1))?;
+    assert_eq!(4, count);
+
+    let opens = fs.opens.lock().unwrap();
+    assert!(!opens.is_empty());
+
+    for (thread_id, request_id) in opens.iter() {
+        assert_ne!(std::thread::current().id(), *thread_id);
+        assert_eq!(Some(7), *request_id);
+    }
+
+    Ok(())
+}
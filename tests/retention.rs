@@ -0,0 +1,193 @@
+use std::{sync::Arc, time::Duration};
+use test_log::test;
+use value_log::{
+    Compressor, Config, Error, IndexWriter, ManualClock, MockIndex, MockIndexWriter,
+    StaleThresholdStrategy, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn retention_drops_expired_segments() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().retention(Duration::from_millis(500)),
+    )?;
+
+    write_items(&value_log, &index, &["a", "b"])?;
+    let old_id = value_log.manifest.list_segment_ids()[0];
+
+    std::thread::sleep(Duration::from_millis(750));
+
+    write_items(&value_log, &index, &["c"])?;
+    assert_eq!(2, value_log.segment_count());
+
+    // NOTE: The old segment has no stale blobs, but is picked anyway
+    let strategy = StaleThresholdStrategy::new(0.5);
+    assert_eq!(vec![old_id], value_log.expired_segments());
+    assert_eq!(vec![old_id], value_log.pick_candidates(&strategy));
+
+    value_log.apply_gc_strategy(&strategy, &index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(1, value_log.segment_count());
+
+    let index = index.read().unwrap();
+
+    for key in ["a", "b"] {
+        let (vhandle, _) = index.get(key.as_bytes()).unwrap();
+        assert_eq!(old_id, vhandle.segment_id);
+        assert!(value_log.get(vhandle)?.is_none());
+    }
+
+    let (vhandle, _) = index.get("c".as_bytes()).unwrap();
+    assert_eq!(
+        "c".repeat(1_000).as_bytes(),
+        &*value_log.get(vhandle)?.unwrap()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn retention_expired_reads_before_gc() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(1_000_000));
+
+    let config = Config::<NoCompressor>::default()
+        .clock(clock.clone())
+        .retention(Duration::from_secs(60));
+
+    let value_log = ValueLog::open(folder.path(), config.clone())?;
+    write_items(&value_log, &index, &["a", "b"])?;
+
+    let (vhandle, _) = index.read().unwrap().get(b"a".as_slice()).cloned().unwrap();
+    assert!(value_log.get(&vhandle)?.is_some());
+
+    clock.advance(Duration::from_secs(60));
+
+    // NOTE: The blob is still cached and on disk, but is expired
+    let segment = value_log.manifest.get_segment(vhandle.segment_id).unwrap();
+    assert_eq!(0, segment.gc_stats.stale_items());
+
+    assert!(value_log.get(&vhandle)?.is_none());
+    assert!(!value_log.read_into(&vhandle, &mut vec![])?);
+    assert_eq!(1, value_log.segment_count());
+
+    // NOTE: Reads do not change the GC state
+    assert!(!segment.is_stale());
+    assert_eq!(0, segment.gc_stats.stale_items());
+    drop(segment);
+
+    drop(value_log);
+
+    let value_log = ValueLog::open(folder.path(), config.expired_reads_error(true))?;
+    assert!(matches!(
+        value_log.get(&vhandle),
+        Err(Error::Expired(id)) if id == vhandle.segment_id,
+    ));
+
+    let strategy = StaleThresholdStrategy::new(0.5);
+    value_log.apply_gc_strategy(&strategy, &index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+    assert_eq!(0, value_log.segment_count());
+
+    // NOTE: Once the segment is dropped, the blob does not exist anymore
+    assert!(value_log.get(&vhandle)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn retention_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    write_items(&value_log, &index, &["a", "b"])?;
+    std::thread::sleep(Duration::from_millis(10));
+
+    assert!(value_log.expired_segments().is_empty());
+    assert!(value_log
+        .pick_candidates(&StaleThresholdStrategy::new(0.5))
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn retention_relocation_keeps_age() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let config = Config::<NoCompressor>::default().retention(Duration::from_secs(3_600));
+
+    let created_at = {
+        let value_log = ValueLog::open(folder.path(), config.clone())?;
+
+        write_items(&value_log, &index, &["a", "b"])?;
+        write_items(&value_log, &index, &["c"])?;
+
+        let segments = value_log.manifest.list_segments();
+        let created_at = segments
+            .iter()
+            .map(|x| x.meta.created_at.unwrap())
+            .min()
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(10));
+
+        value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+        value_log.drop_stale_segments()?;
+        assert_eq!(1, value_log.segment_count());
+
+        let segment = value_log.manifest.list_segments().pop().unwrap();
+        assert_eq!(Some(created_at), segment.meta.created_at);
+        assert!(value_log.expired_segments().is_empty());
+
+        created_at
+    };
+
+    // NOTE: The creation time is persisted in the segment file
+    let value_log = ValueLog::open(folder.path(), config)?;
+    let segment = value_log.manifest.list_segments().pop().unwrap();
+    assert_eq!(Some(created_at), segment.meta.created_at);
+    assert_eq!(3, segment.len());
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn rollover_partitioner() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    // NOTE: Partition by the first byte of the key
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .rollover_partitioner(Arc::new(|key| u64::from(key.first().copied().unwrap_or(0)))),
+    )?;
+
+    write_items(&value_log, &index, &["b2", "a1", "c1", "b1"])?;
+    write_items(&value_log, &index, &["a2", "c2", "b3"])?;
+
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    assert_eq!(0, index.verify(&value_log)?);
+    assert_eq!(3, value_log.segment_count());
+
+    let mut segments = value_log
+        .manifest
+        .list_segments()
+        .into_iter()
+        .map(|segment| {
+            segment
+                .scan_meta()?
+                .map(|meta| Ok(String::from_utf8(meta?.key.to_vec()).unwrap()))
+                .collect::<value_log::Result<Vec<_>>>()
+        })
+        .collect::<value_log::Result<Vec<_>>>()?;
+    segments.sort();
+
+    assert_eq!(
+        vec![vec!["a1", "a2"], vec!["b1", "b2", "b3"], vec!["c1", "c2"],],
+        segments,
+    );
+
+    Ok(())
+}
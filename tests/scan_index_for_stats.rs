@@ -0,0 +1,93 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn scan_index_for_stats() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for value_len in [1_000, 2_000] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c"] {
+            let value = key.repeat(value_len);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    // NOTE: "c" is deleted
+    index.remove(b"c");
+
+    let expected = value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    let report = value_log.scan_index_for_stats(&index)?;
+
+    assert_eq!(expected.total_bytes, report.total_bytes);
+    assert_eq!(expected.stale_bytes, report.stale_bytes);
+    assert_eq!(expected.stale_blobs, report.stale_blobs);
+    assert_eq!(expected.stale_segment_count, report.stale_segment_count);
+
+    assert_eq!(9_000, report.total_bytes);
+    assert_eq!(5_000, report.stale_bytes);
+    assert_eq!(4, report.stale_blobs);
+
+    Ok(())
+}
+
+#[test]
+fn scan_index_for_stats_unsupported() -> value_log::Result<()> {
+    struct NoSizeIndex(MockIndex);
+
+    impl IndexReader for NoSizeIndex {
+        fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+            self.0.get(key)
+        }
+    }
+
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    index_writer.insert_indirect(b"a", vhandle, 3)?;
+    writer.write(b"a", b"abc")?;
+    value_log.register_writer(writer)?;
+
+    let err = value_log
+        .scan_index_for_stats(&NoSizeIndex(index))
+        .unwrap_err();
+    assert_eq!(
+        std::io::ErrorKind::Unsupported,
+        err.io_error().unwrap().kind()
+    );
+
+    Ok(())
+}
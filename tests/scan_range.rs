@@ -0,0 +1,83 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, key.len() as u32)?;
+
+        writer.write(key, key)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+fn collect_keys(
+    iter: impl Iterator<
+        Item = value_log::Result<(
+            value_log::UserKey,
+            value_log::ValueHandle,
+            value_log::UserValue,
+        )>,
+    >,
+) -> value_log::Result<Vec<String>> {
+    iter.map(|x| x.map(|(k, _, _)| String::from_utf8_lossy(&k).into_owned()))
+        .collect()
+}
+
+#[test]
+fn scan_range_pruned() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_batch(&value_log, &index, &["a", "b", "c"])?;
+    write_batch(&value_log, &index, &["d", "e", "f"])?;
+    write_batch(&value_log, &index, &["g", "h", "i"])?;
+
+    assert_eq!(
+        collect_keys(value_log.scan_range("b".."e")?)?,
+        ["b", "c", "d"],
+    );
+    assert_eq!(
+        collect_keys(value_log.scan_range("e"..="g")?)?,
+        ["e", "f", "g"],
+    );
+    assert_eq!(collect_keys(value_log.scan_range("h"..)?)?, ["h", "i"]);
+    assert_eq!(collect_keys(value_log.scan_range(.."b")?)?, ["a"]);
+    assert!(collect_keys(value_log.scan_range("x"..)?)?.is_empty());
+
+    // NOTE: Remove the first segment's file, it should not be touched
+    // because its key range does not overlap
+    let segment = value_log.manifest.get_segment(0).unwrap();
+    std::fs::remove_file(&segment.path)?;
+
+    assert_eq!(
+        collect_keys(value_log.scan_range("d"..="i")?)?,
+        ["d", "e", "f", "g", "h", "i"],
+    );
+    assert!(value_log.scan_range("a"..="i").is_err());
+
+    Ok(())
+}
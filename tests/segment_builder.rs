@@ -0,0 +1,57 @@
+use test_log::test;
+use value_log::{Compressor, Config, SegmentBuilder, ValueHandle, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_builder_ingest() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let mut builder = SegmentBuilder::<NoCompressor>::new(folder.path().join("prepared"))?;
+    assert!(builder.is_empty());
+
+    let mut offsets = vec![];
+
+    for key in ["a", "b", "c", "d"] {
+        offsets.push((key, builder.offset()));
+        builder.write(key, key.repeat(1_000))?;
+    }
+    assert_eq!(4, builder.len());
+
+    let path = builder.finish()?;
+
+    let value_log = ValueLog::open(
+        folder.path().join("vlog"),
+        Config::<NoCompressor>::default(),
+    )?;
+    let segment_id = value_log.ingest_segment(path)?;
+    assert_eq!(1, value_log.segment_count());
+
+    for (key, offset) in offsets {
+        let item = value_log.get(&ValueHandle { segment_id, offset })?.unwrap();
+        assert_eq!(&*item, key.repeat(1_000).as_bytes());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn segment_builder_empty() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let builder = SegmentBuilder::<NoCompressor>::new(folder.path().join("prepared"))?;
+    assert!(builder.finish().is_err());
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    keys: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in keys {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    Ok(())
+}
+
+#[test]
+fn segment_gc_report() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    assert!(value_log.manifest.gc_reports().is_empty());
+
+    write_items(&value_log, &index, &["a", "b", "c", "d"])?;
+    write_items(&value_log, &index, &["e", "f", "g", "h"])?;
+    write_items(&value_log, &index, &["a", "b", "c", "e"])?;
+
+    let ids = value_log.manifest.list_segment_ids();
+    assert_eq!(3, ids.len());
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let segment = value_log
+        .manifest
+        .get_segment(*ids.first().unwrap())
+        .unwrap();
+    let report = segment.gc_report();
+    assert_eq!(*ids.first().unwrap(), report.segment_id);
+    assert_eq!(4, report.item_count);
+    assert_eq!(3, report.stale_items);
+    assert_eq!(1, report.live_items);
+    assert_eq!(4_000, report.total_bytes);
+    assert_eq!(3_000, report.stale_bytes);
+    assert_eq!(1_000, report.live_bytes);
+    assert_eq!(4_000, report.compressed_bytes);
+    assert_eq!(3_000, report.reclaimable_bytes);
+    assert_eq!(0.75, report.stale_ratio);
+
+    // NOTE: Creation times are only recorded if an age-based feature is configured
+    assert!(report.age.is_none());
+
+    // NOTE: The report is a detached copy
+    drop(segment);
+    assert_eq!(3, report.stale_items);
+
+    let reports = value_log.manifest.gc_reports();
+    assert_eq!(
+        vec![
+            (*ids.first().unwrap(), 3_000),
+            (*ids.get(1).unwrap(), 1_000),
+            (*ids.get(2).unwrap(), 0),
+        ],
+        reports
+            .iter()
+            .map(|x| (x.segment_id, x.reclaimable_bytes))
+            .collect::<Vec<_>>(),
+    );
+
+    Ok(())
+}
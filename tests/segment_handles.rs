@@ -0,0 +1,70 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_handles() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for (idx, key) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            let value = key.repeat(10_000 * (idx + 1));
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    // Rebuild a secondary index from the segment alone
+    let rebuilt = MockIndex::default();
+    {
+        let mut index_writer = MockIndexWriter(rebuilt.clone());
+
+        for item in segment.handles()? {
+            let (key, vhandle, size) = item?;
+            index_writer.insert_indirect(&key, vhandle, size)?;
+        }
+
+        index_writer.finish()?;
+    }
+
+    assert_eq!(index.len(), rebuilt.len());
+    assert_eq!(index.range(..), rebuilt.range(..));
+
+    for (key, _, size) in rebuilt.range(..) {
+        let vhandle = rebuilt.get(&key)?.unwrap();
+        assert_eq!(0, vhandle.segment_id);
+
+        let value = value_log.get(&vhandle)?.unwrap();
+        assert_eq!(size as usize, value.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use test_log::test;
+use value_log::{Compressor, Config, ScanFilter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_scan_filtered() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["a", "b", "c", "d", "e", "f"] {
+        writer.write(key, key.repeat(10_000))?;
+    }
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    let mut visited = vec![];
+
+    let items = segment
+        .scan_filtered(|key| {
+            visited.push(key.to_vec());
+
+            match key {
+                b"b" | b"d" => ScanFilter::Yield,
+                b"e" => ScanFilter::Stop,
+                _ => ScanFilter::Skip,
+            }
+        })?
+        .collect::<value_log::Result<Vec<_>>>()?;
+
+    assert_eq!(
+        items
+            .iter()
+            .map(|(k, v, _)| (&**k, v.len()))
+            .collect::<Vec<_>>(),
+        [(&b"b"[..], 10_000), (b"d", 10_000)],
+    );
+    assert_eq!(&*items[1].1, "d".repeat(10_000).as_bytes());
+
+    // NOTE: "f" is never visited, because the scan stopped at "e"
+    assert_eq!(visited, [b"a", b"b", b"c", b"d", b"e"]);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_scan_keys() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c", "d", "e"] {
+            let value = key.repeat(10_000);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    index.remove(b"b");
+    index.remove(b"d");
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    // NOTE: Ownership check - which blobs are still referenced by the index?
+    let mut live = vec![];
+
+    for item in segment.scan_keys()? {
+        let (key, offset) = item?;
+
+        if let Some(vhandle) = index.get(&key)? {
+            if vhandle.segment_id == segment.id && vhandle.offset == offset {
+                live.push(key);
+            }
+        }
+    }
+
+    assert_eq!(live, [&b"a"[..], b"c", b"e"]);
+
+    Ok(())
+}
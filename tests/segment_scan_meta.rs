@@ -0,0 +1,60 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_scan_meta() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for (idx, key) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            let value = key.repeat(100_000 * idx);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    let metas = segment
+        .scan_meta()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(5, metas.len());
+
+    for (meta, item) in metas.iter().zip(segment.scan()?) {
+        let (key, value, checksum) = item?;
+
+        assert_eq!(meta.key, key);
+        assert_eq!(meta.value_size as usize, value.len());
+        assert_eq!(meta.checksum, checksum);
+
+        let (vhandle, _) = index.read().unwrap().get(&key).cloned().unwrap();
+        assert_eq!(vhandle.offset, meta.offset);
+    }
+
+    Ok(())
+}
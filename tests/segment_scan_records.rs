@@ -0,0 +1,60 @@
+use std::io::{Read, Seek, SeekFrom};
+use test_log::test;
+use value_log::{Compressor, Config, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_scan_records() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    for (idx, key) in ["a", "bb", "ccc", "dddd"].into_iter().enumerate() {
+        writer.write(key, key.repeat(1_000 * (idx + 1)))?;
+    }
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    let metas = segment
+        .scan_meta()?
+        .collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(4, metas.len());
+
+    let mut file = std::fs::File::open(&segment.path)?;
+    let mut expected_offset = 0;
+
+    for meta in &metas {
+        // NOTE: Records are laid out back to back
+        assert_eq!(expected_offset, meta.offset);
+        expected_offset += meta.record_len();
+
+        let mut record = vec![0; meta.record_len() as usize];
+        file.seek(SeekFrom::Start(meta.offset))?;
+        file.read_exact(&mut record)?;
+
+        assert!(record.starts_with(b"VLGBLOB\x01"));
+        assert!(record.ends_with(&meta.key.repeat(meta.value_size as usize / meta.key.len())));
+    }
+
+    // NOTE: The last record is followed by the segment metadata
+    let mut magic = [0; 8];
+    file.seek(SeekFrom::Start(expected_offset))?;
+    file.read_exact(&mut magic)?;
+    assert_ne!(b"VLGBLOB\x01", &magic);
+
+    Ok(())
+}
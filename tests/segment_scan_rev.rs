@@ -0,0 +1,39 @@
+use test_log::test;
+use value_log::{Compressor, Config, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_scan_rev() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    for key in ["a", "b", "c", "d", "e"] {
+        writer.write(key, key.repeat(1_000))?;
+    }
+    value_log.register_writer(writer)?;
+
+    let segment = value_log.manifest.get_segment(0).unwrap();
+
+    let forward = segment.scan()?.collect::<value_log::Result<Vec<_>>>()?;
+    let mut reverse = segment.scan_rev()?.collect::<value_log::Result<Vec<_>>>()?;
+    assert_eq!(5, reverse.len());
+
+    reverse.reverse();
+    assert_eq!(forward, reverse);
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::{
+    io::{Read, Seek},
+    path::PathBuf,
+    sync::Arc,
+};
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, SegmentSource, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Serves segments from a "backup" folder
+struct BackupSource(PathBuf);
+
+impl SegmentSource for BackupSource {
+    fn read_range(&self, segment_id: u64, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+        let mut file = std::fs::File::open(self.0.join(segment_id.to_string()))?;
+        file.seek(std::io::SeekFrom::Start(offset))?;
+
+        let mut buf = vec![];
+        file.take(len).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+#[test]
+fn segment_source_read_through() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path();
+
+    let backup_folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        vl_path,
+        Config::<NoCompressor>::default()
+            .segment_source(Arc::new(BackupSource(backup_folder.path().into()))),
+    )?;
+
+    {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in ["a", "b", "c", "d", "e"] {
+            let value = key.repeat(100_000);
+            let value = value.as_bytes();
+
+            let key = key.as_bytes();
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key, vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    // NOTE: Move segment into "backup"
+    for segment in value_log.manifest.list_segments() {
+        std::fs::rename(
+            &segment.path,
+            backup_folder.path().join(segment.id.to_string()),
+        )?;
+    }
+
+    for (key, (vhandle, _)) in index.read().unwrap().iter() {
+        let item = value_log.get(vhandle)?.unwrap();
+        assert_eq!(&*item, &*key.repeat(100_000));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn segment_source_missing_file_without_source() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let vl_path = folder.path();
+
+    let value_log = ValueLog::open(vl_path, Config::<NoCompressor>::default())?;
+
+    let mut writer = value_log.get_writer()?;
+    let vhandle = writer.get_next_value_handle();
+    writer.write(b"a", b"abc")?;
+    value_log.register_writer(writer)?;
+
+    for segment in value_log.manifest.list_segments() {
+        std::fs::remove_file(&segment.path)?;
+    }
+
+    let err = value_log.get(&vhandle).unwrap_err();
+    assert_eq!(std::io::ErrorKind::NotFound, err.io_error().unwrap().kind());
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use test_log::test;
+use value_log::{Compressor, Config, MockIndex, MockIndexWriter, SegmentTags, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_tags() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let config = Config::<NoCompressor>::default().segment_size_bytes(100);
+
+    let (flush_ids, cold_id) = {
+        let value_log = ValueLog::open(folder.path(), config.clone())?;
+
+        // NOTE: The writer rotates, so its tags are attached to every segment
+        let mut writer = value_log
+            .get_writer()?
+            .with_tag("source", "flush-42")
+            .with_tag("tier", "hot");
+
+        for key in ["a", "b", "c"] {
+            writer.write(key, key.repeat(1_000))?;
+        }
+        value_log.register_writer(writer)?;
+
+        let flush_ids = value_log.list_segments_with_tag("source", "flush-42");
+        assert_eq!(3, flush_ids.len());
+
+        let mut writer = value_log.get_writer()?.with_tag("tier", "cold");
+        writer.write("d", "hello")?;
+        value_log.register_writer(writer)?;
+
+        let mut writer = value_log.get_writer()?;
+        writer.write("e", "untagged")?;
+        value_log.register_writer(writer)?;
+
+        assert_eq!(5, value_log.segment_count());
+
+        let cold_ids = value_log.list_segments_with_tag("tier", "cold");
+        assert_eq!(1, cold_ids.len());
+        assert_eq!(flush_ids, value_log.list_segments_with_tag("tier", "hot"));
+        assert!(value_log
+            .list_segments_with_tag("source", "flush-43")
+            .is_empty());
+
+        (flush_ids, cold_ids[0])
+    };
+
+    // NOTE: Tags are persisted in the manifest
+    let value_log = ValueLog::open(folder.path(), config)?;
+    assert_eq!(
+        flush_ids,
+        value_log.list_segments_with_tag("source", "flush-42")
+    );
+
+    let expected = [("tier".to_string(), "cold".to_string())]
+        .into_iter()
+        .collect::<SegmentTags>();
+    assert_eq!(expected, value_log.segment_tags(cold_id));
+
+    // NOTE: Tags of dropped segments are removed, rewritten segments have no tags
+    let index = MockIndex::default();
+    value_log.major_compact(&index, MockIndexWriter(index.clone()))?;
+    value_log.drop_stale_segments()?;
+
+    assert!(value_log
+        .list_segments_with_tag("source", "flush-42")
+        .is_empty());
+    assert!(value_log.segment_tags(cold_id).is_empty());
+
+    Ok(())
+}
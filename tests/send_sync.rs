@@ -0,0 +1,78 @@
+use value_log::{
+    BlobCache, BlobIter, BlobMeta, Compressor, Config, Error, GcReport, KeyRange, MockIndex,
+    MockIndexWriter, Progress, Segment, SegmentBuilder, SegmentMetadata, SegmentReader,
+    SegmentWriter, ShardedValueLog, ShardedWriter, Slice, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn assert_send<T: Send>() {}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn assert_clone<T: Clone>() {}
+
+#[test]
+fn send_sync_shared_types() {
+    assert_send_sync::<ValueLog<NoCompressor>>();
+    assert_send_sync::<ShardedValueLog<NoCompressor>>();
+    assert_send_sync::<Config<NoCompressor>>();
+    assert_send_sync::<Segment<NoCompressor>>();
+    assert_send_sync::<BlobCache>();
+    assert_send_sync::<MockIndex>();
+
+    assert_send_sync::<ValueHandle>();
+    assert_send_sync::<Slice>();
+    assert_send_sync::<KeyRange>();
+    assert_send_sync::<SegmentMetadata>();
+    assert_send_sync::<BlobMeta>();
+    assert_send_sync::<GcReport>();
+    assert_send_sync::<Progress>();
+    assert_send_sync::<Error>();
+
+    assert_clone::<ValueLog<NoCompressor>>();
+    assert_clone::<ShardedValueLog<NoCompressor>>();
+    assert_clone::<Config<NoCompressor>>();
+}
+
+#[test]
+fn send_sync_writers_and_readers() {
+    assert_send::<SegmentWriter<NoCompressor>>();
+    assert_send::<ShardedWriter<NoCompressor>>();
+    assert_send::<SegmentBuilder<NoCompressor>>();
+    assert_send::<MockIndexWriter>();
+
+    assert_send::<SegmentReader<NoCompressor>>();
+    assert_send::<BlobIter<NoCompressor>>();
+}
+
+#[test]
+fn send_sync_clone_is_shared() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    let clone = value_log.clone();
+
+    std::thread::spawn(move || -> value_log::Result<()> {
+        let mut writer = clone.get_writer()?;
+        writer.write("a", "a")?;
+        clone.register_writer(writer)
+    })
+    .join()
+    .unwrap()?;
+
+    assert_eq!(1, value_log.segment_count());
+
+    Ok(())
+}
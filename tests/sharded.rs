@@ -0,0 +1,133 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexReader, IndexWriter, MockIndex, MockIndexWriter, ShardedValueLog,
+    StaleThresholdStrategy,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_all(
+    value_log: &ShardedValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[(String, String)],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for (key, value) in items {
+        let vhandle = writer.get_next_value_handle(key.as_bytes());
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)?;
+    index_writer.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn sharded_write_read() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ShardedValueLog::open(folder.path(), 4, Config::<NoCompressor>::default())?;
+    assert_eq!(4, value_log.shard_count());
+
+    let items = (0..1_000)
+        .map(|idx| (format!("key-{idx}"), format!("value-{idx}").repeat(10)))
+        .collect::<Vec<_>>();
+
+    write_all(&value_log, &index, &items)?;
+
+    // NOTE: Every shard received some of the keys
+    assert_eq!(4, value_log.segment_count());
+    for shard in value_log.shards() {
+        assert!(shard.manifest.list_segments()[0].len() > 0);
+    }
+
+    for (key, value) in &items {
+        let vhandle = index.get(key.as_bytes())?.unwrap();
+        let item = value_log.get(key.as_bytes(), &vhandle)?.unwrap();
+        assert_eq!(value.as_bytes(), &*item);
+    }
+
+    assert_eq!(1.0, value_log.space_amp());
+    assert!(value_log.gc_schedule().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn sharded_gc() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ShardedValueLog::open(folder.path(), 2, Config::<NoCompressor>::default())?;
+
+    let items = (0..100)
+        .map(|idx| (format!("key-{idx}"), "a".repeat(1_000)))
+        .collect::<Vec<_>>();
+
+    write_all(&value_log, &index, &items)?;
+    write_all(&value_log, &index, &items)?;
+    assert_eq!(4, value_log.segment_count());
+
+    // NOTE: Segment IDs are per shard, so only scan the handles of the shard's keys
+    for (idx, shard) in value_log.shards().iter().enumerate() {
+        let handles = index
+            .range(..)
+            .into_iter()
+            .filter(|(key, _, _)| value_log.shard_index(key) == idx)
+            .map(|(_, vhandle, size)| Ok((vhandle, size)))
+            .collect::<Vec<_>>();
+
+        shard.scan_for_stats(handles.into_iter())?;
+    }
+
+    assert_eq!(2.0, value_log.space_amp());
+    assert_eq!(2, value_log.gc_schedule().len());
+
+    for idx in value_log.gc_schedule() {
+        #[allow(clippy::indexing_slicing)]
+        let shard = &value_log.shards()[idx];
+
+        let strategy = StaleThresholdStrategy::new(0.5);
+        shard.apply_gc_strategy(&strategy, &index, MockIndexWriter(index.clone()))?;
+    }
+
+    value_log.drop_stale_segments()?;
+    assert_eq!(2, value_log.segment_count());
+    assert_eq!(0, value_log.stale_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn sharded_shard_count_mismatch() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let value_log = ShardedValueLog::open(folder.path(), 4, Config::<NoCompressor>::default())?;
+        assert_eq!(4, value_log.shards().len());
+    }
+
+    assert!(ShardedValueLog::open(folder.path(), 2, Config::<NoCompressor>::default()).is_err());
+    assert!(ShardedValueLog::open(folder.path(), 4, Config::<NoCompressor>::default()).is_ok());
+    assert!(ShardedValueLog::open(folder.path(), 0, Config::<NoCompressor>::default()).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "simulation")]
+
+use std::sync::Arc;
+use test_log::test;
+use value_log::{
+    sim::{self, SimFs},
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueHandle, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+/// Index writer that only applies its write batch when finished
+struct BatchIndexWriter(MockIndex, Vec<(Vec<u8>, ValueHandle, u32)>);
+
+impl IndexWriter for BatchIndexWriter {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        self.1.push((key.into(), vhandle, size));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        let mut index_writer = MockIndexWriter(self.0.clone());
+
+        for (key, vhandle, size) in std::mem::take(&mut self.1) {
+            index_writer.insert_indirect(&key, vhandle, size)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a batch, only committing the value handles to the index
+/// after the writer was registered successfully
+fn write_batch(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut handles = vec![];
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        handles.push((*key, writer.get_next_value_handle(), value.len() as u32));
+        writer.write(key.as_bytes(), value.as_bytes())?;
+    }
+
+    value_log.register_writer(writer)?;
+
+    let mut index_writer = BatchIndexWriter(index.clone(), vec![]);
+    for (key, vhandle, size) in handles {
+        index_writer.insert_indirect(key.as_bytes(), vhandle, size)?;
+    }
+    index_writer.finish()?;
+
+    Ok(())
+}
+
+fn workload(value_log: &ValueLog<NoCompressor>, index: &MockIndex) -> value_log::Result<()> {
+    write_batch(value_log, index, &["a", "b", "c"])?;
+    write_batch(value_log, index, &["c", "d", "e"])?;
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+    value_log.major_compact(index, BatchIndexWriter(index.clone(), vec![]))?;
+    value_log.drop_stale_segments()?;
+
+    write_batch(value_log, index, &["f", "g"])?;
+
+    Ok(())
+}
+
+#[test]
+fn simulation_crash_points() -> value_log::Result<()> {
+    for crash_point in (0..20_000).step_by(777) {
+        let folder = tempfile::tempdir()?;
+        let path = folder.path();
+
+        let fs = Arc::new(SimFs::new(crash_point));
+        let config = || Config::<NoCompressor>::default().fs(fs.clone());
+
+        let index = MockIndex::default();
+
+        let value_log = ValueLog::open(path, config())?;
+        fs.crash_after_bytes(crash_point);
+
+        let result = workload(&value_log, &index);
+        assert_eq!(result.is_err(), fs.is_crashed());
+        drop(value_log);
+
+        let value_log = sim::reopen(&fs, path, config())?;
+        sim::verify_invariants(&value_log, &index)?;
+
+        // NOTE: The value log needs to be writable again after recovery
+        write_batch(&value_log, &index, &["h"])?;
+        sim::verify_invariants(&value_log, &index)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn simulation_fsync_failure() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = folder.path();
+
+    let fs = Arc::new(SimFs::new(0));
+    let config = || Config::<NoCompressor>::default().fs(fs.clone());
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(path, config())?;
+    write_batch(&value_log, &index, &["a", "b", "c"])?;
+
+    fs.fail_next_fsyncs(1);
+    assert!(write_batch(&value_log, &index, &["d", "e"]).is_err());
+    assert_eq!(1, value_log.segment_count());
+
+    fs.crash();
+    drop(value_log);
+
+    let value_log = sim::reopen(&fs, path, config())?;
+    assert_eq!(1, value_log.segment_count());
+    sim::verify_invariants(&value_log, &index)?;
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use test_log::test;
+use value_log::{Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[String],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+#[test]
+fn split_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let keys = (b'a'..=b't')
+        .map(|x| char::from(x).to_string())
+        .collect::<Vec<_>>();
+    write_items(&value_log, &index, &keys)?;
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+
+    // NOTE: Overwritten blobs are not rewritten
+    write_items(&value_log, &index, &keys[..4])?;
+
+    let new_ids = value_log.split_segment(segment_id, 4, &index, MockIndexWriter(index.clone()))?;
+    assert!((3..=4).contains(&new_ids.len()), "{new_ids:?}");
+
+    let lens = new_ids
+        .iter()
+        .map(|id| value_log.manifest.get_segment(*id).unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(16, lens.iter().sum::<u64>());
+    assert!(lens.iter().all(|&len| len <= 5), "{lens:?}");
+
+    value_log.finish_gc(&[segment_id])?;
+    assert!(value_log.manifest.get_segment(segment_id).is_none());
+    assert_eq!(new_ids.len() + 1, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    assert!(value_log
+        .split_segment(segment_id, 4, &index, MockIndexWriter(index.clone()))
+        .is_err());
+
+    Ok(())
+}
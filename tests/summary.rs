@@ -0,0 +1,85 @@
+use test_log::test;
+use value_log::{
+    Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, SegmentSummary, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn vlog_summary() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    for keys in [&["a", "b"][..], &["a"]] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        for key in keys {
+            let value = key.repeat(100);
+
+            let vhandle = writer.get_next_value_handle();
+            index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+            writer.write(key, value)?;
+        }
+
+        value_log.register_writer(writer)?;
+    }
+
+    value_log.scan_for_stats(index.read().unwrap().values().cloned().map(Ok))?;
+
+    let summary = value_log.summary();
+    assert_eq!(2, summary.segments.len());
+    assert_eq!(3, summary.item_count);
+    assert_eq!(300, summary.total_bytes);
+    assert_eq!(1, summary.stale_items);
+    assert_eq!(100, summary.stale_bytes);
+    assert_eq!(0, summary.stale_segment_count());
+
+    let ids = summary.segments.iter().map(|x| x.id).collect::<Vec<_>>();
+
+    assert_eq!(
+        SegmentSummary {
+            id: ids[0],
+            item_count: 2,
+            compressed_bytes: 200,
+            total_bytes: 200,
+            stale_items: 1,
+            stale_bytes: 100,
+            key_version: None,
+            punched_bytes: 0,
+        },
+        summary.segments[0],
+    );
+
+    assert_eq!(
+        format!(
+            "vlog segments=2 stale_segments=0 items=3 compressed_bytes=300 total_bytes=300 stale_items=1 stale_bytes=100\n\
+             segment id={} items=2 compressed_bytes=200 total_bytes=200 stale_items=1 stale_bytes=100\n\
+             segment id={} items=1 compressed_bytes=100 total_bytes=100 stale_items=0 stale_bytes=0\n",
+            ids[0], ids[1],
+        ),
+        summary.to_string(),
+    );
+
+    let segment = value_log.manifest.get_segment(ids[1]).unwrap();
+    assert_eq!(summary.segments[1].to_string(), segment.to_string());
+    assert_eq!("stale_items=0 stale_bytes=0", segment.gc_stats.to_string());
+    assert!(format!("{:?}", segment.gc_stats).contains("stale_items: 0"));
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+use test_log::test;
+use value_log::{
+    BlobCache, Compressor, Config, IndexWriter, MockIndex, MockIndexWriter, StaleThresholdStrategy,
+    Temperature, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn segment_temperature() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    // NOTE: Disable the blob cache, so every read hits the segment
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default()
+            .blob_cache(std::sync::Arc::new(BlobCache::with_capacity_bytes(0)))
+            .read_rate_half_life(Duration::from_secs(1))
+            .temperature_thresholds(0.5, 20.0),
+    )?;
+
+    let mut handles = vec![];
+
+    for key in ["a", "b"] {
+        let mut index_writer = MockIndexWriter(index.clone());
+        let mut writer = value_log.get_writer()?;
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle.clone(), 5)?;
+        writer.write(key, "hello")?;
+
+        value_log.register_writer(writer)?;
+        handles.push(vhandle);
+    }
+
+    let hot_id = handles[0].segment_id;
+    let cold_id = handles[1].segment_id;
+
+    assert_eq!(Some(Temperature::Cold), value_log.temperature(hot_id));
+    assert_eq!(Some(Temperature::Cold), value_log.temperature(cold_id));
+    assert_eq!(None, value_log.temperature(1_000));
+
+    for _ in 0..100 {
+        value_log.get(&handles[0])?;
+    }
+    assert_eq!(Some(Temperature::Hot), value_log.temperature(hot_id));
+    assert_eq!(Some(Temperature::Cold), value_log.temperature(cold_id));
+
+    value_log.get(&handles[1])?;
+    assert_eq!(Some(Temperature::Warm), value_log.temperature(cold_id));
+
+    assert_eq!(
+        vec![hot_id],
+        value_log.list_segments_with_temperature(Temperature::Hot)
+    );
+    assert!(value_log
+        .list_segments_with_temperature(Temperature::Cold)
+        .is_empty());
+
+    // NOTE: Both segments are fully stale, colder segments are picked first
+    index.write().unwrap().clear();
+    value_log.scan_for_stats(std::iter::empty())?;
+    assert_eq!(
+        vec![cold_id, hot_id],
+        value_log.pick_candidates(&StaleThresholdStrategy::new(0.5))
+    );
+
+    // NOTE: Read rates decay over time
+    std::thread::sleep(Duration::from_secs(4));
+    assert_eq!(Some(Temperature::Warm), value_log.temperature(hot_id));
+    assert_eq!(Some(Temperature::Cold), value_log.temperature(cold_id));
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::{sync::Arc, time::Duration};
+use test_log::test;
+use value_log::{
+    BlobCache, Compressor, Config, IndexWriter, ManualClock, MockIndex, MockIndexWriter, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn write_items(
+    value_log: &ValueLog<NoCompressor>,
+    index: &MockIndex,
+    items: &[&str],
+) -> value_log::Result<()> {
+    let mut index_writer = MockIndexWriter(index.clone());
+    let mut writer = value_log.get_writer()?;
+
+    for key in items {
+        let value = key.repeat(1_000);
+
+        let vhandle = writer.get_next_value_handle();
+        index_writer.insert_indirect(key.as_bytes(), vhandle, value.len() as u32)?;
+
+        writer.write(key, value)?;
+    }
+
+    value_log.register_writer(writer)
+}
+
+fn config(clock: Arc<ManualClock>) -> Config<NoCompressor> {
+    Config::<NoCompressor>::default()
+        .blob_cache(Arc::new(BlobCache::with_capacity_bytes(0)))
+        .clock(clock)
+        .trash_window(Duration::from_secs(60))
+}
+
+#[test]
+fn trash_undrop_segment() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(1_000_000));
+
+    let value_log = ValueLog::open(folder.path(), config(clock.clone()))?;
+    write_items(&value_log, &index, &["a", "b"])?;
+
+    // NOTE: Simulate an index bug, so a segment that is still referenced is dropped
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    value_log.scan_for_stats(std::iter::empty())?;
+
+    let report = value_log.drop_stale_segments_with_report()?;
+    assert_eq!(vec![segment_id], report.segment_ids);
+    assert_eq!(0, value_log.segment_count());
+    assert_eq!(2, index.verify(&value_log)?);
+
+    let trashed = value_log.trashed_segments()?;
+    assert_eq!(1, trashed.len());
+    assert_eq!(segment_id, trashed[0].segment_id);
+    assert_eq!(1_000_000, trashed[0].dropped_at);
+
+    value_log.undrop_segment(segment_id)?;
+    assert_eq!(vec![segment_id], value_log.manifest.list_segment_ids());
+    assert_eq!(0, index.verify(&value_log)?);
+    assert!(value_log.trashed_segments()?.is_empty());
+
+    assert!(value_log.undrop_segment(segment_id).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn trash_purge_after_window() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(1_000_000));
+
+    let value_log = ValueLog::open(folder.path(), config(clock.clone()))?;
+    write_items(&value_log, &index, &["a"])?;
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    value_log.scan_for_stats(std::iter::empty())?;
+    value_log.drop_stale_segments()?;
+
+    clock.advance(Duration::from_secs(59));
+    assert_eq!(0, value_log.purge_trash()?);
+    assert_eq!(1, value_log.trashed_segments()?.len());
+
+    // NOTE: Dropping stale segments purges the trash, even if there is nothing to drop
+    clock.advance(Duration::from_secs(1));
+    value_log.drop_stale_segments()?;
+    assert!(value_log.trashed_segments()?.is_empty());
+    assert!(value_log.undrop_segment(segment_id).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn trash_undrop_after_reopen() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+    let clock = Arc::new(ManualClock::new(1_000_000));
+
+    let segment_id = {
+        let value_log = ValueLog::open(folder.path(), config(clock.clone()))?;
+        write_items(&value_log, &index, &["a", "b"])?;
+
+        let segment_id = value_log.manifest.list_segment_ids()[0];
+        value_log.scan_for_stats(std::iter::empty())?;
+        value_log.drop_stale_segments()?;
+
+        segment_id
+    };
+
+    let value_log = ValueLog::open(folder.path(), config(clock))?;
+    assert_eq!(0, value_log.segment_count());
+
+    // NOTE: The trashed segment's ID is not reused
+    write_items(&value_log, &index, &["c"])?;
+    assert!(value_log.manifest.list_segment_ids()[0] > segment_id);
+
+    value_log.undrop_segment(segment_id)?;
+    assert_eq!(2, value_log.segment_count());
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn trash_disabled() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+    write_items(&value_log, &index, &["a"])?;
+
+    let segment_id = value_log.manifest.list_segment_ids()[0];
+    value_log.scan_for_stats(std::iter::empty())?;
+    value_log.drop_stale_segments()?;
+
+    assert!(value_log.trashed_segments()?.is_empty());
+    assert!(value_log.undrop_segment(segment_id).is_err());
+    assert!(!folder.path().join("trash").exists());
+
+    Ok(())
+}
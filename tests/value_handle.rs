@@ -0,0 +1,79 @@
+use std::collections::BTreeSet;
+use value_log::{SizedValueHandle, ValueHandle};
+
+#[test]
+fn value_handle_order() {
+    let handles = [
+        ValueHandle {
+            segment_id: 1,
+            offset: 0,
+        },
+        ValueHandle {
+            segment_id: 0,
+            offset: 500,
+        },
+        ValueHandle {
+            segment_id: 0,
+            offset: 10,
+        },
+    ];
+
+    let sorted = handles.iter().cloned().collect::<BTreeSet<_>>();
+    assert_eq!(
+        sorted.into_iter().collect::<Vec<_>>(),
+        [handles[2].clone(), handles[1].clone(), handles[0].clone()],
+    );
+
+    let mut packed = handles.iter().map(ValueHandle::to_u128).collect::<Vec<_>>();
+    packed.sort_unstable();
+    assert_eq!(
+        packed
+            .into_iter()
+            .map(ValueHandle::from_u128)
+            .collect::<Vec<_>>(),
+        [handles[2].clone(), handles[1].clone(), handles[0].clone()],
+    );
+}
+
+#[test]
+fn value_handle_packed_roundtrip() {
+    for (segment_id, offset) in [
+        (0, 0),
+        (1, 2),
+        (u64::MAX, 0),
+        (0, u64::MAX),
+        (u64::MAX, u64::MAX),
+    ] {
+        let vhandle = ValueHandle { segment_id, offset };
+        assert_eq!(vhandle, ValueHandle::from_u128(vhandle.to_u128()));
+    }
+
+    assert_eq!(
+        (7u128 << 64) | 42,
+        ValueHandle {
+            segment_id: 7,
+            offset: 42
+        }
+        .to_u128(),
+    );
+}
+
+#[test]
+fn value_handle_display() {
+    let vhandle = ValueHandle {
+        segment_id: 7,
+        offset: 42,
+    };
+    assert_eq!("7:42", vhandle.to_string());
+
+    let sized = vhandle.clone().with_size(100);
+    assert_eq!("7:42 (100 bytes)", sized.to_string());
+    assert_eq!(
+        SizedValueHandle {
+            vhandle: vhandle.clone(),
+            size: 100
+        },
+        sized,
+    );
+    assert_eq!(vhandle, ValueHandle::from(sized));
+}
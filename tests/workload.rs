@@ -0,0 +1,95 @@
+#![cfg(feature = "workload")]
+
+use test_log::test;
+use value_log::{
+    workload::{ValueSize, Workload},
+    Compressor, Config, GcPolicy, MockIndex, ValueLog,
+};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+fn workload() -> Workload {
+    Workload::new(7)
+        .key_count(200)
+        .value_size(ValueSize::Uniform { min: 10, max: 500 })
+        .overwrite_ratio(0.8)
+        .key_skew(2.0)
+        .batch_size(50)
+        .maintenance_interval(2)
+}
+
+#[test]
+fn workload_without_gc() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(folder.path(), Config::<NoCompressor>::default())?;
+
+    let report = workload().run(&value_log, &index, 9)?;
+    assert_eq!(
+        vec![2, 4, 6, 8, 9],
+        report.samples.iter().map(|x| x.batch).collect::<Vec<_>>()
+    );
+
+    let last = report.samples.last().unwrap();
+    // NOTE: Without a GC policy, only fully stale segments are dropped
+    assert!(last.segment_count <= 9);
+    assert_eq!(last.user_bytes_written, last.bytes_written);
+    assert!(last.stored_bytes <= last.user_bytes_written);
+    assert!((last.write_amp - 1.0).abs() < f64::EPSILON);
+    assert!(last.space_amp > 1.0);
+    assert!(index.len() <= 200);
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn workload_with_gc() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let index = MockIndex::default();
+
+    let value_log = ValueLog::open(
+        folder.path(),
+        Config::<NoCompressor>::default().gc_policy(GcPolicy::default().stale_threshold(0.5)),
+    )?;
+
+    let report = workload().run(&value_log, &index, 20)?;
+
+    let last = report.samples.last().unwrap();
+    assert!(last.segment_count < 20);
+    assert!(last.write_amp > 1.0);
+    assert!(last.space_amp < 3.0);
+    assert_eq!(0, index.verify(&value_log)?);
+
+    Ok(())
+}
+
+#[test]
+fn workload_reproducible() -> value_log::Result<()> {
+    let run = || -> value_log::Result<_> {
+        let folder = tempfile::tempdir()?;
+
+        let value_log = ValueLog::open(
+            folder.path(),
+            Config::<NoCompressor>::default().gc_policy(GcPolicy::default().stale_threshold(0.5)),
+        )?;
+
+        workload().run(&value_log, &MockIndex::default(), 10)
+    };
+
+    assert_eq!(run()?, run()?);
+
+    Ok(())
+}
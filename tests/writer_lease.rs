@@ -0,0 +1,71 @@
+use test_log::test;
+use value_log::{Compressor, Config, Error, ValueLog};
+
+#[derive(Clone, Default)]
+struct NoCompressor;
+
+impl Compressor for NoCompressor {
+    fn compress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> value_log::Result<Vec<u8>> {
+        Ok(bytes.into())
+    }
+}
+
+#[test]
+fn writer_lease_foreign_value_log() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let a = ValueLog::open(folder.path().join("a"), Config::<NoCompressor>::default())?;
+    let b = ValueLog::open(folder.path().join("b"), Config::<NoCompressor>::default())?;
+
+    let mut writer = a.get_writer()?;
+    writer.write("a", "a")?;
+
+    assert!(matches!(b.register_writer(writer), Err(Error::StaleWriter)));
+    assert_eq!(0, a.segment_count());
+    assert_eq!(0, b.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn writer_lease_segment_list_replaced() -> value_log::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let empty = ValueLog::open(
+        folder.path().join("empty"),
+        Config::<NoCompressor>::default(),
+    )?;
+    let snapshot = empty.metadata_snapshot()?;
+
+    let vl_path = folder.path().join("vlog");
+    let value_log = ValueLog::open(&vl_path, Config::<NoCompressor>::default())?;
+
+    let mut stale_writer = value_log.get_writer()?;
+    stale_writer.write("a", "a")?;
+
+    value_log.apply_metadata_snapshot(&snapshot)?;
+
+    assert!(matches!(
+        value_log.register_writer(stale_writer),
+        Err(Error::StaleWriter),
+    ));
+    assert_eq!(0, value_log.segment_count());
+
+    // NOTE: Writers created after the segment list was replaced are fine
+    let mut writer = value_log.get_writer()?;
+    writer.write("b", "b")?;
+    value_log.register_writer(writer)?;
+    assert_eq!(1, value_log.segment_count());
+
+    // NOTE: The stale writer's segment file is cleaned up on recovery
+    drop(value_log);
+    let value_log = ValueLog::open(&vl_path, Config::<NoCompressor>::default())?;
+    assert_eq!(1, value_log.segment_count());
+    assert_eq!(1, std::fs::read_dir(vl_path.join("segments"))?.count());
+
+    Ok(())
+}